@@ -18,12 +18,15 @@ extern crate yaserde_derive;
 mod protocols;
 mod util;
 
-use akri_shared::akri::{metrics::run_metrics_server, API_NAMESPACE};
+use akri_shared::{
+    akri::{metrics::run_metrics_server, API_NAMESPACE},
+    os::{env_var::ActualEnvVarQuery, logging::init_logger},
+};
 use log::{info, trace};
-use prometheus::{HistogramVec, IntGaugeVec};
+use prometheus::{HistogramVec, IntCounterVec, IntGaugeVec};
 use std::time::Duration;
 use util::{
-    config_action, constants::SLOT_RECONCILIATION_SLOT_GRACE_PERIOD_SECS,
+    config_action, constants::SLOT_RECONCILIATION_SLOT_GRACE_PERIOD_SECS, debug_server,
     slot_reconciliation::periodic_slot_reconciliation,
 };
 
@@ -32,19 +35,36 @@ lazy_static! {
     pub static ref INSTANCE_COUNT_METRIC: IntGaugeVec = prometheus::register_int_gauge_vec!("akri_instance_count", "Akri Instance Count", &["configuration", "is_shared"]).unwrap();
     // Reports the time to get discovery results, grouped by Configuration
     pub static ref DISCOVERY_RESPONSE_TIME_METRIC: HistogramVec = prometheus::register_histogram_vec!("akri_discovery_response_time", "Akri Discovery Response Time", &["configuration"]).unwrap();
+    // Reports the number of discovery results returned by discovery handlers, grouped by Configuration
+    pub static ref DISCOVERY_RESULT_COUNT_METRIC: IntCounterVec = prometheus::register_int_counter_vec!("akri_discovery_result_count", "Akri Discovery Result Count", &["configuration"]).unwrap();
+    // Reports the number of running discovery handlers, grouped by protocol and whether their last discovery succeeded (Online), failed (Offline) or is paused after failing repeatedly (CircuitOpen)
+    pub static ref DISCOVERY_HANDLER_COUNT_METRIC: IntGaugeVec = prometheus::register_int_gauge_vec!("akri_discovery_handler_count", "Akri Discovery Handler Count", &["protocol", "connectivity_status"]).unwrap();
+    // Reports the number of times discovery was paused after failing repeatedly, grouped by protocol and Configuration
+    pub static ref DISCOVERY_CIRCUIT_OPEN_METRIC: IntCounterVec = prometheus::register_int_counter_vec!("akri_discovery_circuit_open_total", "Akri Discovery Circuit Open Count", &["protocol", "configuration"]).unwrap();
 }
 /// This is the entry point for the Akri Agent.
 /// It must be built on unix systems, since the underlying libraries for the `DevicePluginService` unix socket connection are unix only.
 #[cfg(unix)]
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    // `agent instances` prints the Instances tracked by the Agent running alongside it rather than starting an Agent
+    if std::env::args().nth(1).as_deref() == Some(debug_server::INSTANCES_SUBCOMMAND) {
+        let port = debug_server::get_debug_server_port(&ActualEnvVarQuery {}).ok_or_else(|| {
+            format!(
+                "the debug server is not enabled ... set {}=true on the Agent",
+                debug_server::DEBUG_SERVER_LABEL
+            )
+        })?;
+        return debug_server::print_instances(port).await;
+    }
+
     println!("{} Agent start", API_NAMESPACE);
 
     println!(
         "{} KUBERNETES_PORT found ... env_logger::init",
         API_NAMESPACE
     );
-    env_logger::try_init()?;
+    init_logger(&ActualEnvVarQuery {})?;
     trace!(
         "{} KUBERNETES_PORT found ... env_logger::init finished",
         API_NAMESPACE
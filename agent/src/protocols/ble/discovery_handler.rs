@@ -0,0 +1,61 @@
+use super::super::{validate_discovery_interval, DiscoveryHandler, DiscoveryResult};
+use super::{
+    ble_scanner_wrapper::BluezScanner,
+    discovery_impl::{do_scan, get_peripheral_properties},
+};
+use akri_shared::akri::configuration::BleDiscoveryHandlerConfig;
+use akri_shared::log_fields;
+use anyhow::Error;
+use async_trait::async_trait;
+use log::Level;
+use std::time::Duration;
+
+/// `BleDiscoveryHandler` discovers the BLE peripherals advertising near the node, filtered by
+/// `discovery_handler_config.service_uuids` and `discovery_handler_config.name_prefix`.
+/// The instances it discovers are always unshared, as each node only sees the peripherals in its radio range.
+#[derive(Debug)]
+pub struct BleDiscoveryHandler {
+    discovery_handler_config: BleDiscoveryHandlerConfig,
+}
+
+impl BleDiscoveryHandler {
+    pub fn new(discovery_handler_config: &BleDiscoveryHandlerConfig) -> Self {
+        BleDiscoveryHandler {
+            discovery_handler_config: discovery_handler_config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DiscoveryHandler for BleDiscoveryHandler {
+    async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error> {
+        let discovery_handler_config = self.discovery_handler_config.clone();
+        // Scanning blocks for the whole scan duration, so keep it off of the runtime's worker threads
+        let peripherals = tokio::task::spawn_blocking(move || {
+            do_scan(&BluezScanner {}, &discovery_handler_config)
+        })
+        .await??;
+        Ok(peripherals
+            .iter()
+            .map(|peripheral| {
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "ble", endpoint: peripheral.address },
+                    "discover - found BLE peripheral at address {}",
+                    peripheral.address
+                );
+                DiscoveryResult::new(
+                    &peripheral.address,
+                    get_peripheral_properties(peripheral),
+                    self.are_shared().unwrap(),
+                )
+            })
+            .collect::<Vec<DiscoveryResult>>())
+    }
+    fn are_shared(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        validate_discovery_interval(self.discovery_handler_config.discovery_interval_seconds)
+    }
+}
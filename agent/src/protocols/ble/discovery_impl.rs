@@ -0,0 +1,196 @@
+use super::{
+    ble_scanner_wrapper::{BlePeripheral, BleScanner},
+    BLE_ADDRESS_LABEL, BLE_NAME_LABEL, BLE_RSSI_LABEL, BLE_SERVICE_UUIDS_LABEL,
+};
+use akri_shared::akri::configuration::BleDiscoveryHandlerConfig;
+use anyhow::Error;
+use std::{collections::HashMap, time::Duration};
+
+/// Bluetooth Base UUID that 16-bit and 32-bit service UUIDs are short forms of
+const BLUETOOTH_BASE_UUID_SUFFIX: &str = "-0000-1000-8000-00805f9b34fb";
+
+/// Converts a service UUID to its lowercase 128-bit form, expanding 16-bit and 32-bit short forms
+/// such as `180d` using the Bluetooth Base UUID
+fn normalize_service_uuid(service_uuid: &str) -> String {
+    let service_uuid = service_uuid.trim().to_lowercase();
+    match service_uuid.len() {
+        4 => format!("0000{}{}", service_uuid, BLUETOOTH_BASE_UUID_SUFFIX),
+        8 => format!("{}{}", service_uuid, BLUETOOTH_BASE_UUID_SUFFIX),
+        _ => service_uuid,
+    }
+}
+
+/// Scans for peripherals with `scanner` and returns those that advertise one of the Configuration's
+/// service UUIDs, if any are listed, and whose local name starts with the Configuration's name prefix, if set
+pub fn do_scan(
+    scanner: &impl BleScanner,
+    discovery_handler_config: &BleDiscoveryHandlerConfig,
+) -> Result<Vec<BlePeripheral>, Error> {
+    let service_uuids: Vec<String> = discovery_handler_config
+        .service_uuids
+        .iter()
+        .map(|service_uuid| normalize_service_uuid(service_uuid))
+        .collect();
+    let peripherals = scanner.scan(Duration::from_secs(
+        discovery_handler_config.scan_duration_secs,
+    ))?;
+    trace!(
+        "do_scan - scan found {} peripherals before filtering",
+        peripherals.len()
+    );
+    Ok(peripherals
+        .into_iter()
+        .filter(|peripheral| {
+            service_uuids.is_empty()
+                || peripheral
+                    .service_uuids
+                    .iter()
+                    .any(|service_uuid| service_uuids.contains(&service_uuid.to_lowercase()))
+        })
+        .filter(
+            |peripheral| match discovery_handler_config.name_prefix.as_deref() {
+                Some(name_prefix) => peripheral
+                    .local_name
+                    .as_deref()
+                    .map_or(false, |name| name.starts_with(name_prefix)),
+                None => true,
+            },
+        )
+        .collect())
+}
+
+/// Returns the properties of a peripheral's Instance. Its name, RSSI and service UUIDs are only
+/// included if it advertised them.
+pub fn get_peripheral_properties(peripheral: &BlePeripheral) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    properties.insert(BLE_ADDRESS_LABEL.to_string(), peripheral.address.clone());
+    if let Some(local_name) = &peripheral.local_name {
+        properties.insert(BLE_NAME_LABEL.to_string(), local_name.clone());
+    }
+    if let Some(rssi) = peripheral.rssi {
+        properties.insert(BLE_RSSI_LABEL.to_string(), rssi.to_string());
+    }
+    if !peripheral.service_uuids.is_empty() {
+        properties.insert(
+            BLE_SERVICE_UUIDS_LABEL.to_string(),
+            peripheral.service_uuids.join(","),
+        );
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ble_scanner_wrapper::MockBleScanner;
+    use super::*;
+
+    const HEART_RATE_SERVICE_UUID: &str = "0000180d-0000-1000-8000-00805f9b34fb";
+
+    fn get_test_peripherals() -> Vec<BlePeripheral> {
+        vec![
+            BlePeripheral {
+                address: "AA:BB:CC:DD:EE:01".to_string(),
+                local_name: Some("Polar H10".to_string()),
+                rssi: Some(-60),
+                service_uuids: vec![HEART_RATE_SERVICE_UUID.to_string()],
+            },
+            BlePeripheral {
+                address: "AA:BB:CC:DD:EE:02".to_string(),
+                local_name: Some("Thermometer".to_string()),
+                rssi: None,
+                service_uuids: vec!["0000181a-0000-1000-8000-00805f9b34fb".to_string()],
+            },
+            BlePeripheral {
+                address: "AA:BB:CC:DD:EE:03".to_string(),
+                local_name: None,
+                rssi: Some(-80),
+                service_uuids: Vec::new(),
+            },
+        ]
+    }
+
+    fn get_test_config(
+        service_uuids: Vec<&str>,
+        name_prefix: Option<&str>,
+    ) -> BleDiscoveryHandlerConfig {
+        BleDiscoveryHandlerConfig {
+            service_uuids: service_uuids.into_iter().map(|u| u.to_string()).collect(),
+            name_prefix: name_prefix.map(|p| p.to_string()),
+            scan_duration_secs: 3,
+            discovery_interval_seconds: 10,
+        }
+    }
+
+    fn do_scan_addresses(discovery_handler_config: &BleDiscoveryHandlerConfig) -> Vec<String> {
+        let mut mock_scanner = MockBleScanner::new();
+        mock_scanner
+            .expect_scan()
+            .withf(|scan_duration| *scan_duration == Duration::from_secs(3))
+            .times(1)
+            .returning(|_| Ok(get_test_peripherals()));
+        do_scan(&mock_scanner, discovery_handler_config)
+            .unwrap()
+            .into_iter()
+            .map(|peripheral| peripheral.address)
+            .collect()
+    }
+
+    #[test]
+    fn test_do_scan_without_filters() {
+        assert_eq!(
+            3,
+            do_scan_addresses(&get_test_config(Vec::new(), None)).len()
+        );
+    }
+
+    #[test]
+    fn test_do_scan_filters_by_service_uuid() {
+        // Short and full forms of a service UUID match alike, regardless of case
+        for service_uuid in &["180d", "180D", HEART_RATE_SERVICE_UUID] {
+            assert_eq!(
+                vec!["AA:BB:CC:DD:EE:01"],
+                do_scan_addresses(&get_test_config(vec![*service_uuid], None))
+            );
+        }
+        assert_eq!(
+            vec!["AA:BB:CC:DD:EE:01", "AA:BB:CC:DD:EE:02"],
+            do_scan_addresses(&get_test_config(vec!["180d", "181a"], None))
+        );
+        assert!(do_scan_addresses(&get_test_config(vec!["1810"], None)).is_empty());
+    }
+
+    #[test]
+    fn test_do_scan_filters_by_name_prefix() {
+        assert_eq!(
+            vec!["AA:BB:CC:DD:EE:02"],
+            do_scan_addresses(&get_test_config(Vec::new(), Some("Therm")))
+        );
+        // Both filters must pass
+        assert!(do_scan_addresses(&get_test_config(vec!["180d"], Some("Therm"))).is_empty());
+    }
+
+    #[test]
+    fn test_do_scan_error() {
+        let mut mock_scanner = MockBleScanner::new();
+        mock_scanner
+            .expect_scan()
+            .times(1)
+            .returning(|_| Err(anyhow::format_err!("no Bluetooth adapter found")));
+        assert!(do_scan(&mock_scanner, &get_test_config(Vec::new(), None)).is_err());
+    }
+
+    #[test]
+    fn test_get_peripheral_properties() {
+        let peripherals = get_test_peripherals();
+        let properties = get_peripheral_properties(&peripherals[0]);
+        assert_eq!(4, properties.len());
+        assert_eq!("AA:BB:CC:DD:EE:01", properties[BLE_ADDRESS_LABEL]);
+        assert_eq!("Polar H10", properties[BLE_NAME_LABEL]);
+        assert_eq!("-60", properties[BLE_RSSI_LABEL]);
+        assert_eq!(HEART_RATE_SERVICE_UUID, properties[BLE_SERVICE_UUIDS_LABEL]);
+
+        let properties = get_peripheral_properties(&peripherals[2]);
+        assert_eq!(2, properties.len());
+        assert_eq!("-80", properties[BLE_RSSI_LABEL]);
+    }
+}
@@ -0,0 +1,75 @@
+mod discovery_handler;
+mod discovery_impl;
+pub use self::discovery_handler::BleDiscoveryHandler;
+
+/// Names of the properties set on the Instance of a discovered BLE peripheral
+pub const BLE_NAME_LABEL: &str = "AKRI_BLE_NAME";
+pub const BLE_ADDRESS_LABEL: &str = "AKRI_BLE_ADDRESS";
+pub const BLE_RSSI_LABEL: &str = "AKRI_BLE_RSSI";
+pub const BLE_SERVICE_UUIDS_LABEL: &str = "AKRI_BLE_SERVICE_UUIDS";
+
+/// Wrapper to enable mocking of BLE scans
+pub mod ble_scanner_wrapper {
+    use anyhow::Error;
+    use btleplug::api::{Central, Peripheral};
+    use btleplug::bluez::manager::Manager;
+    use mockall::predicate::*;
+    use mockall::*;
+    use std::time::Duration;
+
+    /// What a peripheral advertised during a scan
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct BlePeripheral {
+        /// MAC address, such as `AA:BB:CC:DD:EE:FF`
+        pub address: String,
+        pub local_name: Option<String>,
+        /// Signal strength of the peripheral's last advertisement in dBm
+        pub rssi: Option<i8>,
+        /// Advertised GATT service UUIDs in their lowercase 128-bit form
+        pub service_uuids: Vec<String>,
+    }
+
+    #[automock]
+    pub trait BleScanner {
+        /// Scans for advertising peripherals for `scan_duration`, blocking until the scan is done
+        fn scan(&self, scan_duration: Duration) -> Result<Vec<BlePeripheral>, Error>;
+    }
+
+    /// Scans with the first Bluetooth adapter BlueZ reports
+    pub struct BluezScanner {}
+
+    impl BleScanner for BluezScanner {
+        fn scan(&self, scan_duration: Duration) -> Result<Vec<BlePeripheral>, Error> {
+            let manager = Manager::new()?;
+            let adapter = manager
+                .adapters()?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::format_err!("no Bluetooth adapter found"))?;
+            adapter.start_scan()?;
+            std::thread::sleep(scan_duration);
+            let peripherals = adapter
+                .peripherals()
+                .iter()
+                .map(|peripheral| {
+                    let properties = peripheral.properties();
+                    BlePeripheral {
+                        address: properties.address.to_string(),
+                        local_name: properties.local_name,
+                        // btleplug stores the RSSI BlueZ reports in tx_power_level
+                        rssi: properties.tx_power_level,
+                        service_uuids: properties
+                            .services
+                            .iter()
+                            .map(|uuid| uuid.to_string())
+                            .collect(),
+                    }
+                })
+                .collect();
+            if let Err(e) = adapter.stop_scan() {
+                warn!("scan - failed to stop scanning: {}", e);
+            }
+            Ok(peripherals)
+        }
+    }
+}
@@ -1,22 +1,77 @@
-use super::super::{DiscoveryHandler, DiscoveryResult};
-use akri_shared::akri::configuration::DebugEchoDiscoveryHandlerConfig;
+use super::super::super::util::v1beta1;
+use super::super::{validate_discovery_interval, DiscoveryHandler, DiscoveryResult};
+use akri_shared::akri::configuration::{DebugEchoDevice, DebugEchoDiscoveryHandlerConfig};
+use akri_shared::log_fields;
 use anyhow::Error;
 use async_trait::async_trait;
-use std::{collections::HashMap, fs};
+use log::Level;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    time::Duration,
+};
 
 /// File acting as an environment variable for testing discovery.
 /// To mimic an instance going offline, kubectl exec into one of the akri-agent-daemonset pods
 /// and echo "OFFLINE" > /tmp/debug-echo-availability.txt
 /// To mimic a device coming back online, remove the word "OFFLINE" from the file
 /// ie: echo "" > /tmp/debug-echo-availability.txt
+/// To mimic only some instances going offline, write their ids to the file, one per line
+/// ie: echo "foo1" > /tmp/debug-echo-availability.txt
 pub const DEBUG_ECHO_AVAILABILITY_CHECK_PATH: &str = "/tmp/debug-echo-availability.txt";
 /// String to write into DEBUG_ECHO_AVAILABILITY_CHECK_PATH to make DebugEcho devices undiscoverable
 pub const OFFLINE: &str = "OFFLINE";
+/// Prefix of the per-device files that can take a single device offline.
+/// To mimic only device foo1 going offline: echo "OFFLINE" > /tmp/debug-echo-foo1.txt
+pub const DEBUG_ECHO_DEVICE_AVAILABILITY_CHECK_PATH_PREFIX: &str = "/tmp/debug-echo-";
+
+/// Returns the path of the file that can take the device with the given id offline
+pub fn get_device_availability_check_path(id: &str) -> String {
+    format!(
+        "{}{}.txt",
+        DEBUG_ECHO_DEVICE_AVAILABILITY_CHECK_PATH_PREFIX, id
+    )
+}
+
+/// Returns whether the device with the given id has been taken offline by writing "OFFLINE" to its own file
+fn is_device_offline(id: &str) -> bool {
+    fs::read_to_string(get_device_availability_check_path(id))
+        .map(|availability| availability.lines().any(|line| line.trim() == OFFLINE))
+        .unwrap_or(false)
+}
+
+/// Converts a DebugEcho device's `deviceSpecs` and `mounts` into those given to its brokers,
+/// so that scheduling of devices such as GPUs can be tested end-to-end
+fn get_device_specs_and_mounts(
+    device: &DebugEchoDevice,
+) -> (Vec<v1beta1::DeviceSpec>, Vec<v1beta1::Mount>) {
+    let device_specs = device
+        .device_specs
+        .iter()
+        .map(|device_spec| v1beta1::DeviceSpec {
+            container_path: device_spec.container_path.clone(),
+            host_path: device_spec.host_path.clone(),
+            permissions: device_spec.permissions.clone(),
+        })
+        .collect();
+    let mounts = device
+        .mounts
+        .iter()
+        .map(|mount| v1beta1::Mount {
+            container_path: mount.container_path.clone(),
+            host_path: mount.host_path.clone(),
+            read_only: mount.read_only,
+        })
+        .collect();
+    (device_specs, mounts)
+}
 
 /// `DebugEchoDiscoveryHandler` contains a `DebugEchoDiscoveryHandlerConfig` which has a
-/// list of mock instances (`discovery_handler_config.descriptions`) and their sharability.
-/// It mocks discovering the instances by inspecting the contents of the file at `DEBUG_ECHO_AVAILABILITY_CHECK_PATH`.
-/// If the file contains "OFFLINE", it won't discover any of the instances, else it discovers them all.
+/// list of mock instances (`discovery_handler_config.devices`) and their sharability.
+/// It mocks discovering the instances by inspecting the contents of the file at
+/// `discovery_handler_config.availability_check_path`, defaulting to `DEBUG_ECHO_AVAILABILITY_CHECK_PATH`.
+/// If the file contains "OFFLINE", it won't discover any of the instances, else it discovers all the instances
+/// whose ids are not listed in the file and whose own availability file does not contain "OFFLINE".
 #[derive(Debug)]
 pub struct DebugEchoDiscoveryHandler {
     discovery_handler_config: DebugEchoDiscoveryHandlerConfig,
@@ -33,22 +88,44 @@ impl DebugEchoDiscoveryHandler {
 #[async_trait]
 impl DiscoveryHandler for DebugEchoDiscoveryHandler {
     async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error> {
-        let availability =
-            fs::read_to_string(DEBUG_ECHO_AVAILABILITY_CHECK_PATH).unwrap_or_default();
-        trace!(
-            "discover -- DebugEcho capabilities visible? {}",
-            !availability.contains(OFFLINE)
+        let availability_check_path = self
+            .discovery_handler_config
+            .availability_check_path
+            .as_deref()
+            .unwrap_or(DEBUG_ECHO_AVAILABILITY_CHECK_PATH);
+        let availability = fs::read_to_string(availability_check_path).unwrap_or_default();
+        // Each line of the file names a device id to take offline, with "OFFLINE" taking them all offline
+        let offline_ids: HashSet<&str> = availability
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        log_fields!(
+            Level::Trace,
+            { protocol: "debugEcho" },
+            "discover -- DebugEcho offline device ids {:?}",
+            offline_ids
         );
         // If the device is offline, return an empty list of instance info
-        if availability.contains(OFFLINE) {
+        if offline_ids.contains(OFFLINE) {
             Ok(Vec::new())
         } else {
+            let shared = self.are_shared()?;
             Ok(self
                 .discovery_handler_config
-                .descriptions
+                .devices
                 .iter()
-                .map(|description| {
-                    DiscoveryResult::new(description, HashMap::new(), self.are_shared().unwrap())
+                .filter(|device| {
+                    !offline_ids.contains(device.id.as_str()) && !is_device_offline(&device.id)
+                })
+                .map(|device| {
+                    let (device_specs, mounts) = get_device_specs_and_mounts(device);
+                    DiscoveryResult::new(
+                        &device.id,
+                        device.properties.clone(),
+                        device.shared.unwrap_or(shared),
+                    )
+                    .with_devices(device_specs, mounts)
                 })
                 .collect::<Vec<DiscoveryResult>>())
         }
@@ -56,4 +133,276 @@ impl DiscoveryHandler for DebugEchoDiscoveryHandler {
     fn are_shared(&self) -> Result<bool, Error> {
         Ok(self.discovery_handler_config.shared)
     }
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        validate_discovery_interval(self.discovery_handler_config.discovery_interval_seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use akri_shared::akri::configuration::{KubeAkriConfig, ProtocolHandler};
+
+    fn build_debug_echo_config(
+        ids: Vec<&str>,
+        availability_check_path: &std::path::Path,
+    ) -> DebugEchoDiscoveryHandlerConfig {
+        DebugEchoDiscoveryHandlerConfig {
+            devices: ids
+                .iter()
+                .map(|id| DebugEchoDevice {
+                    id: id.to_string(),
+                    properties: HashMap::new(),
+                    shared: None,
+                    device_specs: Vec::new(),
+                    mounts: Vec::new(),
+                })
+                .collect(),
+            shared: false,
+            discovery_interval_seconds: 10,
+            availability_check_path: Some(availability_check_path.to_str().unwrap().to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover_uses_configured_availability_check_path() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        std::env::set_var("AGENT_NODE_NAME", "node-a");
+        let online_file = tempfile::NamedTempFile::new().unwrap();
+        let offline_file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(offline_file.path(), OFFLINE).unwrap();
+
+        let online_handler = DebugEchoDiscoveryHandler::new(&build_debug_echo_config(
+            vec!["foo0", "foo1"],
+            online_file.path(),
+        ));
+        let offline_handler = DebugEchoDiscoveryHandler::new(&build_debug_echo_config(
+            vec!["bar0"],
+            offline_file.path(),
+        ));
+        assert_eq!(2, online_handler.discover().await.unwrap().len());
+        assert!(offline_handler.discover().await.unwrap().is_empty());
+
+        // Bringing the second device online should not affect the first
+        fs::write(offline_file.path(), "ONLINE").unwrap();
+        fs::write(online_file.path(), OFFLINE).unwrap();
+        assert!(online_handler.discover().await.unwrap().is_empty());
+        assert_eq!(1, offline_handler.discover().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn test_discover_takes_listed_ids_offline() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        std::env::set_var("AGENT_NODE_NAME", "node-a");
+        let availability_file = tempfile::NamedTempFile::new().unwrap();
+        let handler = DebugEchoDiscoveryHandler::new(&build_debug_echo_config(
+            vec!["foo0", "foo1", "foo2"],
+            availability_file.path(),
+        ));
+        let expected_digests = |ids: Vec<&str>| {
+            ids.into_iter()
+                .map(|d| DiscoveryResult::new(&d.to_string(), HashMap::new(), false).digest)
+                .collect::<Vec<String>>()
+        };
+        let discovered_digests = |results: Vec<DiscoveryResult>| {
+            results
+                .into_iter()
+                .map(|r| r.digest)
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            expected_digests(vec!["foo0", "foo1", "foo2"]),
+            discovered_digests(handler.discover().await.unwrap())
+        );
+
+        fs::write(availability_file.path(), "foo1\n").unwrap();
+        assert_eq!(
+            expected_digests(vec!["foo0", "foo2"]),
+            discovered_digests(handler.discover().await.unwrap())
+        );
+
+        fs::write(availability_file.path(), "foo0\n foo2 \nunknown\n").unwrap();
+        assert_eq!(
+            expected_digests(vec!["foo1"]),
+            discovered_digests(handler.discover().await.unwrap())
+        );
+
+        fs::write(availability_file.path(), "foo0\nOFFLINE\n").unwrap();
+        assert!(handler.discover().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_discover_takes_devices_with_offline_files_offline() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        std::env::set_var("AGENT_NODE_NAME", "node-a");
+        let availability_file = tempfile::NamedTempFile::new().unwrap();
+        // Use ids unique to this test since the per-device files are shared by all tests
+        let ids = vec!["per-device-foo0", "per-device-foo1", "per-device-foo2"];
+        let handler = DebugEchoDiscoveryHandler::new(&build_debug_echo_config(
+            ids.clone(),
+            availability_file.path(),
+        ));
+        let discovered_digests = |results: Vec<DiscoveryResult>| {
+            results
+                .into_iter()
+                .map(|r| r.digest)
+                .collect::<Vec<String>>()
+        };
+        let expected_digests = |ids: Vec<&str>| {
+            ids.into_iter()
+                .map(|d| DiscoveryResult::new(d, HashMap::new(), false).digest)
+                .collect::<Vec<String>>()
+        };
+
+        fs::write(
+            get_device_availability_check_path("per-device-foo1"),
+            OFFLINE,
+        )
+        .unwrap();
+        fs::write(
+            get_device_availability_check_path("per-device-foo2"),
+            "ONLINE",
+        )
+        .unwrap();
+        assert_eq!(
+            expected_digests(vec!["per-device-foo0", "per-device-foo2"]),
+            discovered_digests(handler.discover().await.unwrap())
+        );
+
+        // The global file still takes every device offline
+        fs::write(availability_file.path(), OFFLINE).unwrap();
+        assert!(handler.discover().await.unwrap().is_empty());
+
+        fs::write(availability_file.path(), "").unwrap();
+        fs::write(get_device_availability_check_path("per-device-foo1"), "").unwrap();
+        assert_eq!(
+            expected_digests(ids.clone()),
+            discovered_digests(handler.discover().await.unwrap())
+        );
+
+        for id in ids {
+            let _ = fs::remove_file(get_device_availability_check_path(id));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover_reports_device_properties() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        std::env::set_var("AGENT_NODE_NAME", "node-a");
+        let availability_file = tempfile::NamedTempFile::new().unwrap();
+        let mut config = build_debug_echo_config(vec!["foo0"], availability_file.path());
+        let properties: HashMap<String, String> = vec![("VENDOR".to_string(), "acme".to_string())]
+            .into_iter()
+            .collect();
+        config.devices.push(DebugEchoDevice {
+            id: "foo1".to_string(),
+            properties: properties.clone(),
+            shared: Some(true),
+            device_specs: Vec::new(),
+            mounts: Vec::new(),
+        });
+        let results = DebugEchoDiscoveryHandler::new(&config)
+            .discover()
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                DiscoveryResult::new("foo0", HashMap::new(), false),
+                DiscoveryResult::new("foo1", properties, true)
+            ],
+            results
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discover_prefers_device_shared() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        std::env::set_var("AGENT_NODE_NAME", "node-a");
+        let availability_file = tempfile::NamedTempFile::new().unwrap();
+        let mut config = build_debug_echo_config(vec!["foo0"], availability_file.path());
+        config.shared = true;
+        config.devices.push(DebugEchoDevice {
+            id: "foo1".to_string(),
+            properties: HashMap::new(),
+            shared: Some(false),
+            device_specs: Vec::new(),
+            mounts: Vec::new(),
+        });
+        let results = DebugEchoDiscoveryHandler::new(&config)
+            .discover()
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                DiscoveryResult::new("foo0", HashMap::new(), true),
+                DiscoveryResult::new("foo1", HashMap::new(), false)
+            ],
+            results
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discover_reports_device_specs_and_mounts() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        std::env::set_var("AGENT_NODE_NAME", "node-a");
+        let availability_file = tempfile::NamedTempFile::new().unwrap();
+        let yaml = fs::read_to_string("../test/yaml/akri-debug-echo-gpu.yaml").unwrap();
+        let config: KubeAkriConfig = serde_yaml::from_str(&yaml).unwrap();
+        let mut discovery_handler_config = match config.spec.protocol {
+            ProtocolHandler::debugEcho(discovery_handler_config) => discovery_handler_config,
+            _ => panic!("protocol should be debugEcho"),
+        };
+        discovery_handler_config.availability_check_path =
+            Some(availability_file.path().to_str().unwrap().to_string());
+        let results = DebugEchoDiscoveryHandler::new(&discovery_handler_config)
+            .discover()
+            .await
+            .unwrap();
+        assert_eq!(1, results.len());
+        assert_eq!(
+            vec![
+                v1beta1::DeviceSpec {
+                    container_path: "/dev/nvidia0".to_string(),
+                    host_path: "/dev/nvidia0".to_string(),
+                    permissions: "rw".to_string(),
+                },
+                v1beta1::DeviceSpec {
+                    container_path: "/dev/nvidiactl".to_string(),
+                    host_path: "/dev/nvidiactl".to_string(),
+                    permissions: "r".to_string(),
+                }
+            ],
+            results[0].device_specs
+        );
+        assert_eq!(
+            vec![v1beta1::Mount {
+                container_path: "/usr/local/nvidia".to_string(),
+                host_path: "/usr/lib/nvidia".to_string(),
+                read_only: true,
+            }],
+            results[0].mounts
+        );
+    }
+
+    #[test]
+    fn test_deserialize_debug_echo_config_defaults() {
+        let json = r#"{"descriptions":["foo0"]}"#;
+        let config: DebugEchoDiscoveryHandlerConfig = serde_json::from_str(json).unwrap();
+        assert_eq!("foo0", config.devices[0].id);
+        assert!(!config.shared);
+        assert_eq!(None, config.availability_check_path);
+        assert!(!DebugEchoDiscoveryHandler::new(&config)
+            .are_shared()
+            .unwrap());
+
+        let json =
+            r#"{"descriptions":["foo0"],"shared":true,"availabilityCheckPath":"/tmp/foo0.txt"}"#;
+        let config: DebugEchoDiscoveryHandlerConfig = serde_json::from_str(json).unwrap();
+        assert!(config.shared);
+        assert_eq!(
+            Some("/tmp/foo0.txt".to_string()),
+            config.availability_check_path
+        );
+    }
 }
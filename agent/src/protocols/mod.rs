@@ -1,3 +1,4 @@
+use super::util::{constants::DISCOVERY_DELAY_SECS, v1beta1};
 use akri_shared::{
     akri::configuration::ProtocolHandler,
     os::env_var::{ActualEnvVarQuery, EnvVarQuery},
@@ -6,36 +7,168 @@ use anyhow::Error;
 use async_trait::async_trait;
 use blake2::digest::{Input, VariableOutput};
 use blake2::VarBlake2b;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, time::Duration};
+
+/// Name of the environment variable that sets the number of bytes in an instance digest
+pub const AKRI_DIGEST_LENGTH: &str = "AKRI_DIGEST_LENGTH";
+/// Name of the environment variable that selects the instance digest algorithm (`blake2b` or `sha256`)
+pub const AKRI_DIGEST_ALGORITHM: &str = "AKRI_DIGEST_ALGORITHM";
+/// Default number of bytes in an instance digest
+const DEFAULT_DIGEST_LENGTH: usize = 3;
+/// Maximum number of bytes in an instance digest
+const MAX_DIGEST_LENGTH: usize = 32;
+/// Property a discovery handler can set to `Unhealthy` on a `DiscoveryResult` to keep its device from being
+/// scheduled to while it is still discoverable, such as an OPC UA server under maintenance
+pub const AKRI_DEVICE_HEALTH_LABEL_ID: &str = "AKRI_DEVICE_HEALTH";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DiscoveryResult {
+    /// Id of the device that `digest` was generated from
+    pub id: String,
     pub digest: String,
     pub properties: HashMap<String, String>,
+    /// Whether the device can be used by multiple nodes
+    pub shared: bool,
+    /// Number of usage slots the device supports, overriding the Configuration's capacity when set
+    pub capacity: Option<i32>,
+    /// Host devices given to the brokers of the device
+    pub device_specs: Vec<v1beta1::DeviceSpec>,
+    /// Host paths mounted into the brokers of the device
+    pub mounts: Vec<v1beta1::Mount>,
 }
 impl DiscoveryResult {
     fn new(id_to_digest: &str, properties: HashMap<String, String>, shared: bool) -> Self {
-        let mut id_to_digest = id_to_digest.to_string();
-        // For unshared devices, include node hostname in id_to_digest so instances have unique names
-        if !shared {
-            id_to_digest = format!(
-                "{}{}",
-                &id_to_digest,
-                std::env::var("AGENT_NODE_NAME").unwrap()
-            );
+        let digest = generate_instance_digest(id_to_digest, shared);
+        DiscoveryResult {
+            id: id_to_digest.to_string(),
+            digest,
+            properties,
+            shared,
+            capacity: None,
+            device_specs: Vec::new(),
+            mounts: Vec::new(),
         }
-        let mut hasher = VarBlake2b::new(3).unwrap();
-        hasher.input(id_to_digest);
-        let digest = hasher
-            .vec_result()
-            .iter()
-            .map(|num| format!("{:02x}", num))
-            .collect::<Vec<String>>()
-            .join("");
-        DiscoveryResult { digest, properties }
+    }
+
+    fn with_capacity(mut self, capacity: Option<i32>) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    fn with_devices(
+        mut self,
+        device_specs: Vec<v1beta1::DeviceSpec>,
+        mounts: Vec<v1beta1::Mount>,
+    ) -> Self {
+        self.device_specs = device_specs;
+        self.mounts = mounts;
+        self
+    }
+}
+
+/// Generates the digest used to name an Instance, as configured by `AKRI_DIGEST_LENGTH` and `AKRI_DIGEST_ALGORITHM`
+pub fn generate_instance_digest(id_to_digest: &str, shared: bool) -> String {
+    let query = ActualEnvVarQuery {};
+    inner_generate_instance_digest(id_to_digest, shared, &query)
+}
+
+/// Generates a digest `extra_bytes` longer than `generate_instance_digest` would, up to the maximum digest length,
+/// so that a device whose Instance name is already taken by another device can be given a distinct one
+pub fn generate_extended_instance_digest(
+    id_to_digest: &str,
+    shared: bool,
+    extra_bytes: usize,
+) -> Option<String> {
+    let query = ActualEnvVarQuery {};
+    inner_generate_extended_instance_digest(id_to_digest, shared, extra_bytes, &query)
+}
+
+fn inner_generate_instance_digest(
+    id_to_digest: &str,
+    shared: bool,
+    query: &impl EnvVarQuery,
+) -> String {
+    generate_digest_of_length(id_to_digest, shared, get_digest_length(query), query)
+}
+
+fn inner_generate_extended_instance_digest(
+    id_to_digest: &str,
+    shared: bool,
+    extra_bytes: usize,
+    query: &impl EnvVarQuery,
+) -> Option<String> {
+    let digest_length = get_digest_length(query) + extra_bytes;
+    if digest_length > MAX_DIGEST_LENGTH {
+        return None;
+    }
+    Some(generate_digest_of_length(
+        id_to_digest,
+        shared,
+        digest_length,
+        query,
+    ))
+}
+
+fn get_digest_length(query: &impl EnvVarQuery) -> usize {
+    match query.get_env_var(AKRI_DIGEST_LENGTH) {
+        Ok(length) => match length.parse::<usize>() {
+            Ok(length) if length > 0 && length <= MAX_DIGEST_LENGTH => length,
+            _ => {
+                error!(
+                    "get_digest_length - {} must be between 1 and {} but was {} ... using {}",
+                    AKRI_DIGEST_LENGTH, MAX_DIGEST_LENGTH, length, DEFAULT_DIGEST_LENGTH
+                );
+                DEFAULT_DIGEST_LENGTH
+            }
+        },
+        Err(_) => DEFAULT_DIGEST_LENGTH,
     }
 }
 
+fn generate_digest_of_length(
+    id_to_digest: &str,
+    shared: bool,
+    digest_length: usize,
+    query: &impl EnvVarQuery,
+) -> String {
+    let mut id_to_digest = id_to_digest.to_string();
+    // For unshared devices, include node hostname in id_to_digest so instances have unique names
+    if !shared {
+        id_to_digest = format!(
+            "{}{}",
+            &id_to_digest,
+            query.get_env_var("AGENT_NODE_NAME").unwrap()
+        );
+    }
+    let digest_bytes: Vec<u8> = match query.get_env_var(AKRI_DIGEST_ALGORITHM).as_deref() {
+        Ok("sha256") => Sha256::digest(id_to_digest.as_bytes())
+            .iter()
+            .take(digest_length)
+            .cloned()
+            .collect(),
+        Ok("blake2b") | Err(_) => blake2b_digest(&id_to_digest, digest_length),
+        Ok(algorithm) => {
+            error!(
+                "generate_digest_of_length - unknown {} {} ... using blake2b",
+                AKRI_DIGEST_ALGORITHM, algorithm
+            );
+            blake2b_digest(&id_to_digest, digest_length)
+        }
+    };
+    digest_bytes
+        .iter()
+        .map(|num| format!("{:02x}", num))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+fn blake2b_digest(id_to_digest: &str, digest_length: usize) -> Vec<u8> {
+    let mut hasher = VarBlake2b::new(digest_length).unwrap();
+    hasher.input(id_to_digest);
+    hasher.vec_result()
+}
+
 /// DiscoveryHandler describes anything that can find available instances and define
 /// whether they are shared.
 ///
@@ -61,13 +194,42 @@ impl DiscoveryResult {
 pub trait DiscoveryHandler {
     async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error>;
     fn are_shared(&self) -> Result<bool, Error>;
+    /// How long to wait between calls to `discover`
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        Ok(Duration::from_secs(DISCOVERY_DELAY_SECS))
+    }
+    /// Waits until the set of available instances may have changed or `max_wait` has elapsed,
+    /// whichever comes first. Handlers that cannot be notified of changes simply wait out `max_wait`.
+    async fn wait_for_change(&self, max_wait: Duration) {
+        tokio::time::delay_for(max_wait).await
+    }
+}
+
+/// Converts a Configuration's `discoveryIntervalSeconds` into a `Duration`,
+/// rejecting intervals of less than one second
+fn validate_discovery_interval(discovery_interval_seconds: i32) -> Result<Duration, Error> {
+    if discovery_interval_seconds < 1 {
+        return Err(anyhow::format_err!(
+            "invalid argument: discoveryIntervalSeconds must be at least 1 but was {}",
+            discovery_interval_seconds
+        ));
+    }
+    Ok(Duration::from_secs(discovery_interval_seconds as u64))
 }
 
+#[cfg(feature = "ble-feat")]
+mod ble;
 pub mod debug_echo;
+#[cfg(feature = "mqtt-feat")]
+mod mqtt;
 #[cfg(feature = "onvif-feat")]
 mod onvif;
 #[cfg(feature = "opcua-feat")]
 mod opcua;
+#[cfg(feature = "serial-feat")]
+mod serial;
+#[cfg(feature = "snmp-feat")]
+mod snmp;
 #[cfg(feature = "udev-feat")]
 mod udev;
 
@@ -78,6 +240,20 @@ pub fn get_discovery_handler(
     inner_get_discovery_handler(discovery_handler_config, &query_var_set)
 }
 
+/// Returns the name a protocol is configured by, such as "onvif"
+pub fn get_protocol_name(discovery_handler_config: &ProtocolHandler) -> &'static str {
+    match discovery_handler_config {
+        ProtocolHandler::onvif(_) => "onvif",
+        ProtocolHandler::udev(_) => "udev",
+        ProtocolHandler::opcua(_) => "opcua",
+        ProtocolHandler::debugEcho(_) => "debugEcho",
+        ProtocolHandler::ble(_) => "ble",
+        ProtocolHandler::snmp(_) => "snmp",
+        ProtocolHandler::mqtt(_) => "mqtt",
+        ProtocolHandler::serial(_) => "serial",
+    }
+}
+
 fn inner_get_discovery_handler(
     discovery_handler_config: &ProtocolHandler,
     query: &impl EnvVarQuery,
@@ -89,6 +265,16 @@ fn inner_get_discovery_handler(
         ProtocolHandler::udev(udev) => Ok(Box::new(udev::UdevDiscoveryHandler::new(&udev))),
         #[cfg(feature = "opcua-feat")]
         ProtocolHandler::opcua(opcua) => Ok(Box::new(opcua::OpcuaDiscoveryHandler::new(&opcua))),
+        #[cfg(feature = "ble-feat")]
+        ProtocolHandler::ble(ble) => Ok(Box::new(ble::BleDiscoveryHandler::new(&ble))),
+        #[cfg(feature = "snmp-feat")]
+        ProtocolHandler::snmp(snmp) => Ok(Box::new(snmp::SnmpDiscoveryHandler::new(&snmp))),
+        #[cfg(feature = "mqtt-feat")]
+        ProtocolHandler::mqtt(mqtt) => Ok(Box::new(mqtt::MqttDiscoveryHandler::new(&mqtt))),
+        #[cfg(feature = "serial-feat")]
+        ProtocolHandler::serial(serial) => {
+            Ok(Box::new(serial::SerialDiscoveryHandler::new(&serial)))
+        }
         ProtocolHandler::debugEcho(dbg) => match query.get_env_var("ENABLE_DEBUG_ECHO") {
             Ok(_) => Ok(Box::new(debug_echo::DebugEchoDiscoveryHandler::new(dbg))),
             _ => Err(anyhow::format_err!("No protocol configured")),
@@ -136,6 +322,64 @@ mod test {
         assert!(serde_json::from_str::<Configuration>(json).is_err());
     }
 
+    #[test]
+    fn test_get_protocol_name() {
+        let protocols = vec![
+            (r#"{"onvif":{}}"#, "onvif"),
+            (r#"{"udev":{"udevRules":[]}}"#, "udev"),
+            (
+                r#"{"opcua":{"opcuaDiscoveryMethod":{"standard":{}}}}"#,
+                "opcua",
+            ),
+            (r#"{"debugEcho":{"descriptions":["foo1"]}}"#, "debugEcho"),
+            (r#"{"ble":{}}"#, "ble"),
+            (r#"{"snmp":{"subnets":["10.0.0.0/24"]}}"#, "snmp"),
+            (
+                r#"{"mqtt":{"brokerUrl":"mqtt://broker","topicPattern":"devices/#"}}"#,
+                "mqtt",
+            ),
+            (r#"{"serial":{"portPattern":"/dev/ttyUSB*"}}"#, "serial"),
+        ];
+        for (json, expected_name) in protocols {
+            let deserialized: ProtocolHandler = serde_json::from_str(json).unwrap();
+            assert_eq!(expected_name, get_protocol_name(&deserialized));
+        }
+    }
+
+    #[test]
+    fn test_get_discovery_interval() {
+        let mut mock_query = MockEnvVarQuery::new();
+        mock_query
+            .expect_get_env_var()
+            .returning(|_| Ok("1".to_string()));
+
+        let json = r#"{"debugEcho":{"descriptions":["foo1"]}}"#;
+        let deserialized: ProtocolHandler = serde_json::from_str(json).unwrap();
+        let discovery_handler = inner_get_discovery_handler(&deserialized, &mock_query).unwrap();
+        assert_eq!(
+            Duration::from_secs(10),
+            discovery_handler.get_discovery_interval().unwrap()
+        );
+
+        let json = r#"{"debugEcho":{"descriptions":["foo1"],"discoveryIntervalSeconds":3}}"#;
+        let deserialized: ProtocolHandler = serde_json::from_str(json).unwrap();
+        let discovery_handler = inner_get_discovery_handler(&deserialized, &mock_query).unwrap();
+        assert_eq!(
+            Duration::from_secs(3),
+            discovery_handler.get_discovery_interval().unwrap()
+        );
+
+        let json = r#"{"debugEcho":{"descriptions":["foo1"],"discoveryIntervalSeconds":0}}"#;
+        let deserialized: ProtocolHandler = serde_json::from_str(json).unwrap();
+        let discovery_handler = inner_get_discovery_handler(&deserialized, &mock_query).unwrap();
+        assert!(discovery_handler.get_discovery_interval().is_err());
+
+        let json = r#"{"onvif":{"discoveryIntervalSeconds":-1}}"#;
+        let deserialized: ProtocolHandler = serde_json::from_str(json).unwrap();
+        let discovery_handler = inner_get_discovery_handler(&deserialized, &mock_query).unwrap();
+        assert!(discovery_handler.get_discovery_interval().is_err());
+    }
+
     #[tokio::test]
     async fn test_udev_discover_no_rules() {
         let mock_query = MockEnvVarQuery::new();
@@ -184,6 +428,110 @@ mod test {
         );
     }
 
+    fn configure_digest_env_vars(
+        mock_query: &mut MockEnvVarQuery,
+        digest_length: Option<&'static str>,
+        digest_algorithm: Option<&'static str>,
+    ) {
+        mock_query
+            .expect_get_env_var()
+            .returning(move |name| match name {
+                "AGENT_NODE_NAME" => Ok("node-a".to_string()),
+                AKRI_DIGEST_LENGTH => digest_length
+                    .map(|length| length.to_string())
+                    .ok_or(VarError::NotPresent),
+                AKRI_DIGEST_ALGORITHM => digest_algorithm
+                    .map(|algorithm| algorithm.to_string())
+                    .ok_or(VarError::NotPresent),
+                _ => Err(VarError::NotPresent),
+            });
+    }
+
+    #[test]
+    fn test_inner_generate_instance_digest() {
+        // Defaults to a 3 byte Blake2b digest
+        let mut mock_query = MockEnvVarQuery::new();
+        configure_digest_env_vars(&mut mock_query, None, None);
+        let mut hasher = VarBlake2b::new(3).unwrap();
+        hasher.input("foo1");
+        let expected = hasher
+            .vec_result()
+            .iter()
+            .map(|num| format!("{:02x}", num))
+            .collect::<Vec<String>>()
+            .join("");
+        assert_eq!(
+            expected,
+            inner_generate_instance_digest("foo1", true, &mock_query)
+        );
+        assert_ne!(
+            expected,
+            inner_generate_instance_digest("foo1", false, &mock_query)
+        );
+
+        for algorithm in &["blake2b", "sha256"] {
+            let mut mock_query = MockEnvVarQuery::new();
+            configure_digest_env_vars(&mut mock_query, Some("8"), Some(algorithm));
+            assert_eq!(
+                16,
+                inner_generate_instance_digest("foo1", true, &mock_query).len()
+            );
+        }
+
+        let mut mock_query = MockEnvVarQuery::new();
+        configure_digest_env_vars(&mut mock_query, Some("32"), Some("sha256"));
+        assert_eq!(
+            64,
+            inner_generate_instance_digest("foo1", true, &mock_query).len()
+        );
+
+        // Invalid lengths fall back to the default
+        for length in &["0", "33", "three"] {
+            let mut mock_query = MockEnvVarQuery::new();
+            configure_digest_env_vars(&mut mock_query, Some(length), None);
+            assert_eq!(
+                expected,
+                inner_generate_instance_digest("foo1", true, &mock_query)
+            );
+        }
+    }
+
+    #[test]
+    fn test_inner_generate_instance_digest_uniqueness() {
+        for algorithm in &["blake2b", "sha256"] {
+            let mut mock_query = MockEnvVarQuery::new();
+            configure_digest_env_vars(&mut mock_query, Some("8"), Some(algorithm));
+            let digests: std::collections::HashSet<String> = (0..1000)
+                .map(|i| {
+                    inner_generate_instance_digest(&format!("device-{}", i), true, &mock_query)
+                })
+                .collect();
+            assert_eq!(1000, digests.len());
+        }
+    }
+
+    #[test]
+    fn test_inner_generate_extended_instance_digest() {
+        let mut mock_query = MockEnvVarQuery::new();
+        configure_digest_env_vars(&mut mock_query, None, None);
+        let digest = inner_generate_instance_digest("foo1", true, &mock_query);
+        let extended_digest =
+            inner_generate_extended_instance_digest("foo1", true, 1, &mock_query).unwrap();
+        assert_eq!(8, extended_digest.len());
+        assert_ne!(digest, extended_digest);
+        assert_eq!(
+            extended_digest,
+            inner_generate_extended_instance_digest("foo1", true, 1, &mock_query).unwrap()
+        );
+        assert_eq!(
+            64,
+            inner_generate_extended_instance_digest("foo1", true, 29, &mock_query)
+                .unwrap()
+                .len()
+        );
+        assert!(inner_generate_extended_instance_digest("foo1", true, 30, &mock_query).is_none());
+    }
+
     #[tokio::test]
     async fn test_discovery_result_partialeq() {
         let left = DiscoveryResult::new(&"foo1".to_string(), HashMap::new(), true);
@@ -0,0 +1,294 @@
+use super::super::{validate_discovery_interval, DiscoveryHandler, DiscoveryResult};
+use super::discovery_impl::{apply_message, get_devices, MqttDevice};
+use akri_shared::akri::configuration::MqttDiscoveryHandlerConfig;
+use akri_shared::log_fields;
+use anyhow::Error;
+use async_trait::async_trait;
+use log::Level;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{delay_for, timeout},
+};
+
+/// Port brokers listen on for unencrypted connections by default
+const DEFAULT_MQTT_PORT: u16 = 1883;
+
+/// How long to wait before reconnecting after the connection to the broker fails
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How long to let a burst of announcements, such as the retained ones sent upon subscribing, settle
+/// before discovering again
+const ANNOUNCEMENT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// `MqttDiscoveryHandler` discovers the devices announced on the topics matching
+/// `discovery_handler_config.topic_pattern`. The instances it discovers are always shared, as every node
+/// subscribes to the same broker.
+/// A subscription is started on the first discovery, and it re-discovers as soon as an announcement changes the
+/// set of devices.
+#[derive(Debug)]
+pub struct MqttDiscoveryHandler {
+    discovery_handler_config: MqttDiscoveryHandlerConfig,
+    /// Devices announced so far, keyed by the topic they were announced on
+    devices: Arc<Mutex<HashMap<String, MqttDevice>>>,
+    /// Notified when the announced devices change once the subscription has been started
+    change_receiver: tokio::sync::Mutex<Option<mpsc::Receiver<()>>>,
+    /// Dropped along with the handler to end the subscription
+    stop_sender: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl MqttDiscoveryHandler {
+    pub fn new(discovery_handler_config: &MqttDiscoveryHandlerConfig) -> Self {
+        MqttDiscoveryHandler {
+            discovery_handler_config: discovery_handler_config.clone(),
+            devices: Arc::new(Mutex::new(HashMap::new())),
+            change_receiver: tokio::sync::Mutex::new(None),
+            stop_sender: Mutex::new(None),
+        }
+    }
+
+    /// Creates the options the Agent connects to the broker with. Each node connects with its own client id,
+    /// as a broker disconnects a client when another one connects with the same id.
+    fn get_mqtt_options(&self) -> Result<MqttOptions, Error> {
+        let broker_url = url::Url::parse(&self.discovery_handler_config.broker_url)?;
+        if broker_url.scheme() != "mqtt" && broker_url.scheme() != "tcp" {
+            return Err(anyhow::format_err!(
+                "invalid argument: brokerUrl {} must use the mqtt or tcp scheme",
+                self.discovery_handler_config.broker_url
+            ));
+        }
+        let host = broker_url.host_str().ok_or_else(|| {
+            anyhow::format_err!(
+                "invalid argument: brokerUrl {} has no host",
+                self.discovery_handler_config.broker_url
+            )
+        })?;
+        let node_name =
+            std::env::var("AGENT_NODE_NAME").unwrap_or_else(|_| uuid::Uuid::new_v4().to_string());
+        let mut mqtt_options = MqttOptions::new(
+            format!("{}-{}", self.discovery_handler_config.client_id, node_name),
+            host,
+            broker_url.port().unwrap_or(DEFAULT_MQTT_PORT),
+        );
+        if let Some(username) = &self.discovery_handler_config.username {
+            mqtt_options.set_credentials(
+                username.clone(),
+                self.discovery_handler_config
+                    .password
+                    .clone()
+                    .unwrap_or_default(),
+            );
+        }
+        Ok(mqtt_options)
+    }
+
+    /// Starts the subscription if it is not running yet
+    async fn start_subscription(&self) -> Result<(), Error> {
+        let mut change_receiver = self.change_receiver.lock().await;
+        if change_receiver.is_some() {
+            return Ok(());
+        }
+        let qos = rumqttc::qos(self.discovery_handler_config.qos)
+            .map_err(|e| anyhow::format_err!("invalid argument: qos: {:?}", e))?;
+        let (client, event_loop) = AsyncClient::new(self.get_mqtt_options()?, 10);
+        let (change_sender, receiver) = mpsc::channel(1);
+        let (stop_sender, stop_receiver) = oneshot::channel();
+        log_fields!(
+            Level::Trace,
+            { protocol: "mqtt", endpoint: self.discovery_handler_config.broker_url },
+            "start_subscription - subscribing to {} on {}",
+            self.discovery_handler_config.topic_pattern,
+            self.discovery_handler_config.broker_url
+        );
+        tokio::spawn(subscribe(
+            client,
+            event_loop,
+            qos,
+            self.discovery_handler_config.clone(),
+            self.devices.clone(),
+            change_sender,
+            stop_receiver,
+        ));
+        *change_receiver = Some(receiver);
+        *self.stop_sender.lock().unwrap() = Some(stop_sender);
+        Ok(())
+    }
+}
+
+/// Subscribes to the Configuration's topic pattern, applying each message to `devices` and notifying
+/// `change_sender` when they change, until `stop_receiver` is dropped. The subscription is renewed whenever
+/// the client reconnects.
+async fn subscribe(
+    client: AsyncClient,
+    mut event_loop: EventLoop,
+    qos: rumqttc::QoS,
+    discovery_handler_config: MqttDiscoveryHandlerConfig,
+    devices: Arc<Mutex<HashMap<String, MqttDevice>>>,
+    mut change_sender: mpsc::Sender<()>,
+    mut stop_receiver: oneshot::Receiver<()>,
+) {
+    loop {
+        let event = tokio::select! {
+            _ = &mut stop_receiver => {
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "mqtt", endpoint: discovery_handler_config.broker_url },
+                    "subscribe - discovery handler dropped ... ending subscription"
+                );
+                return;
+            }
+            event = event_loop.poll() => event,
+        };
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "mqtt", endpoint: discovery_handler_config.broker_url },
+                    "subscribe - connected to {}",
+                    discovery_handler_config.broker_url
+                );
+                if let Err(e) = client
+                    .subscribe(discovery_handler_config.topic_pattern.clone(), qos)
+                    .await
+                {
+                    log_fields!(
+                        Level::Error,
+                        { protocol: "mqtt", endpoint: discovery_handler_config.broker_url },
+                        "subscribe - failed to subscribe with error {:?}",
+                        e
+                    );
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let changed = apply_message(
+                    &mut devices.lock().unwrap(),
+                    &publish.topic,
+                    &publish.payload,
+                    &discovery_handler_config,
+                );
+                match changed {
+                    Ok(true) => {
+                        log_fields!(
+                            Level::Trace,
+                            { protocol: "mqtt", endpoint: discovery_handler_config.broker_url },
+                            "subscribe - devices changed by message on {}",
+                            publish.topic
+                        );
+                        // A full channel already holds an unhandled notification
+                        let _ = change_sender.try_send(());
+                    }
+                    Ok(false) => {}
+                    Err(e) => log_fields!(
+                        Level::Trace,
+                        { protocol: "mqtt", endpoint: discovery_handler_config.broker_url },
+                        "subscribe - ignoring message on {} with error {}",
+                        publish.topic,
+                        e
+                    ),
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log_fields!(
+                    Level::Warn,
+                    { protocol: "mqtt", endpoint: discovery_handler_config.broker_url },
+                    "subscribe - connection to {} failed with error {:?} ... reconnecting in {:?}",
+                    discovery_handler_config.broker_url, e, RECONNECT_DELAY
+                );
+                delay_for(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DiscoveryHandler for MqttDiscoveryHandler {
+    async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error> {
+        self.start_subscription().await?;
+        let devices = get_devices(&self.devices.lock().unwrap());
+        Ok(devices
+            .into_iter()
+            .map(|device| {
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "mqtt", endpoint: self.discovery_handler_config.broker_url },
+                    "discover - found MQTT device {}",
+                    device.id
+                );
+                DiscoveryResult::new(&device.id, device.properties, self.are_shared().unwrap())
+            })
+            .collect::<Vec<DiscoveryResult>>())
+    }
+    fn are_shared(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        validate_discovery_interval(self.discovery_handler_config.discovery_interval_seconds)
+    }
+    async fn wait_for_change(&self, max_wait: Duration) {
+        let mut change_receiver = self.change_receiver.lock().await;
+        match change_receiver.as_mut() {
+            Some(change_receiver) => {
+                if let Ok(Some(())) = timeout(max_wait, change_receiver.recv()).await {
+                    delay_for(ANNOUNCEMENT_DEBOUNCE).await;
+                    while change_receiver.try_recv().is_ok() {}
+                    log_fields!(
+                        Level::Trace,
+                        { protocol: "mqtt", endpoint: self.discovery_handler_config.broker_url },
+                        "wait_for_change - announced devices changed ... discovering again"
+                    );
+                }
+            }
+            None => delay_for(max_wait).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_handler(broker_url: &str) -> MqttDiscoveryHandler {
+        MqttDiscoveryHandler::new(
+            &serde_json::from_str(&format!(
+                r#"{{"brokerUrl":"{}","topicPattern":"devices/#","clientId":"akri-test"}}"#,
+                broker_url
+            ))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_get_mqtt_options() {
+        std::env::set_var("AGENT_NODE_NAME", "node-a");
+        let mqtt_options = create_handler("mqtt://broker.local")
+            .get_mqtt_options()
+            .unwrap();
+        assert_eq!(
+            ("broker.local".to_string(), DEFAULT_MQTT_PORT),
+            mqtt_options.broker_address()
+        );
+        assert_eq!("akri-test-node-a", mqtt_options.client_id());
+        assert!(mqtt_options.credentials().is_none());
+
+        let mqtt_options = create_handler("tcp://10.0.0.1:1884")
+            .get_mqtt_options()
+            .unwrap();
+        assert_eq!(
+            ("10.0.0.1".to_string(), 1884),
+            mqtt_options.broker_address()
+        );
+
+        assert!(create_handler("mqtts://broker.local")
+            .get_mqtt_options()
+            .is_err());
+        assert!(create_handler("broker.local:1883")
+            .get_mqtt_options()
+            .is_err());
+    }
+}
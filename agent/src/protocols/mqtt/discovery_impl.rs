@@ -0,0 +1,288 @@
+use super::{MQTT_ID_LABEL, MQTT_PROPERTY_LABEL_PREFIX, MQTT_TOPIC_LABEL};
+use akri_shared::akri::configuration::MqttDiscoveryHandlerConfig;
+use anyhow::Error;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A device, as announced on a topic
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttDevice {
+    pub id: String,
+    pub properties: HashMap<String, String>,
+}
+
+/// Converts a JSON path of object fields and array indices, such as `$.device.identifiers[0]`,
+/// into the equivalent JSON pointer, such as `/device/identifiers/0`
+pub fn json_path_to_pointer(json_path: &str) -> Result<String, Error> {
+    let json_path = json_path.trim();
+    let json_path = json_path
+        .strip_prefix('$')
+        .map(|rest| rest.strip_prefix('.').unwrap_or(rest))
+        .unwrap_or(json_path);
+    let mut pointer = String::new();
+    if json_path.is_empty() {
+        return Ok(pointer);
+    }
+    for segment in json_path.split('.') {
+        let (field, indices) = match segment.find('[') {
+            Some(index) => segment.split_at(index),
+            None => (segment, ""),
+        };
+        if field.is_empty() && indices.is_empty() {
+            return Err(anyhow::format_err!(
+                "invalid argument: JSON path {} has an empty field name",
+                json_path
+            ));
+        }
+        if !field.is_empty() {
+            pointer.push('/');
+            pointer.push_str(&field.replace('~', "~0").replace('/', "~1"));
+        }
+        let mut indices = indices;
+        while !indices.is_empty() {
+            let end = indices.find(']').ok_or_else(|| {
+                anyhow::format_err!(
+                    "invalid argument: JSON path {} has an unclosed [",
+                    json_path
+                )
+            })?;
+            let index = indices[1..end].parse::<usize>().map_err(|_| {
+                anyhow::format_err!(
+                    "invalid argument: JSON path {} has an index that is not a number",
+                    json_path
+                )
+            })?;
+            pointer.push_str(&format!("/{}", index));
+            indices = &indices[end + 1..];
+            if !indices.is_empty() && !indices.starts_with('[') {
+                return Err(anyhow::format_err!(
+                    "invalid argument: JSON path {} has characters after an index",
+                    json_path
+                ));
+            }
+        }
+    }
+    Ok(pointer)
+}
+
+/// Turns a property name into the name of an environment variable, such as `firmware-version` into
+/// `AKRI_MQTT_FIRMWARE_VERSION`, as properties are passed on to brokers' environments
+fn to_property_label(name: &str) -> String {
+    format!(
+        "{}{}",
+        MQTT_PROPERTY_LABEL_PREFIX,
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            })
+            .collect::<String>()
+    )
+}
+
+/// Strings are used as is, and other values as their JSON
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Extracts the device an announcement describes. The announcement must have a string or number at the
+/// id path, while the object at the properties path is optional.
+pub fn parse_announcement(
+    topic: &str,
+    payload: &str,
+    discovery_handler_config: &MqttDiscoveryHandlerConfig,
+) -> Result<MqttDevice, Error> {
+    let announcement: Value = serde_json::from_str(payload)?;
+    let id = match announcement.pointer(&json_path_to_pointer(&discovery_handler_config.id_path)?) {
+        Some(Value::String(id)) if !id.is_empty() => id.clone(),
+        Some(Value::Number(id)) => id.to_string(),
+        _ => {
+            return Err(anyhow::format_err!(
+                "announcement on {} has no string or number at {}",
+                topic,
+                discovery_handler_config.id_path
+            ))
+        }
+    };
+    let mut properties = HashMap::new();
+    if let Some(Value::Object(fields)) = announcement.pointer(&json_path_to_pointer(
+        &discovery_handler_config.properties_path,
+    )?) {
+        for (name, value) in fields.iter().filter(|(_, value)| !value.is_null()) {
+            properties.insert(to_property_label(name), value_to_string(value));
+        }
+    }
+    properties.insert(MQTT_ID_LABEL.to_string(), id.clone());
+    properties.insert(MQTT_TOPIC_LABEL.to_string(), topic.to_string());
+    Ok(MqttDevice { id, properties })
+}
+
+/// Applies a message received on `topic` to the devices announced so far, keyed by topic. An empty payload or
+/// the Configuration's offline payload removes the device announced on the topic, and any other payload
+/// announces one. Returns whether the devices changed.
+pub fn apply_message(
+    devices: &mut HashMap<String, MqttDevice>,
+    topic: &str,
+    payload: &[u8],
+    discovery_handler_config: &MqttDiscoveryHandlerConfig,
+) -> Result<bool, Error> {
+    let payload = String::from_utf8_lossy(payload);
+    let payload = payload.trim();
+    if payload.is_empty()
+        || discovery_handler_config
+            .offline_payload
+            .as_deref()
+            .map_or(false, |offline_payload| offline_payload.trim() == payload)
+    {
+        return Ok(devices.remove(topic).is_some());
+    }
+    let device = parse_announcement(topic, payload, discovery_handler_config)?;
+    Ok(devices.insert(topic.to_string(), device.clone()) != Some(device))
+}
+
+/// Returns the announced devices, keeping only the device on the first topic when a device is announced on
+/// more than one
+pub fn get_devices(devices: &HashMap<String, MqttDevice>) -> Vec<MqttDevice> {
+    let devices: BTreeMap<&String, &MqttDevice> = devices.iter().collect();
+    let mut ids = HashSet::new();
+    devices
+        .into_iter()
+        .filter(|(_, device)| ids.insert(device.id.clone()))
+        .map(|(_, device)| device.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_config(offline_payload: Option<&str>) -> MqttDiscoveryHandlerConfig {
+        let mut discovery_handler_config: MqttDiscoveryHandlerConfig = serde_json::from_str(
+            r#"{"brokerUrl":"mqtt://broker","topicPattern":"devices/+/announce"}"#,
+        )
+        .unwrap();
+        discovery_handler_config.offline_payload = offline_payload.map(|p| p.to_string());
+        discovery_handler_config
+    }
+
+    #[test]
+    fn test_json_path_to_pointer() {
+        assert_eq!("/id", json_path_to_pointer("$.id").unwrap());
+        assert_eq!("/id", json_path_to_pointer("id").unwrap());
+        assert_eq!("", json_path_to_pointer("$").unwrap());
+        assert_eq!(
+            "/device/identifiers/0",
+            json_path_to_pointer("$.device.identifiers[0]").unwrap()
+        );
+        assert_eq!("/a/1/2", json_path_to_pointer("$.a[1][2]").unwrap());
+        assert_eq!("/a~1b/c~0d", json_path_to_pointer("$.a/b.c~d").unwrap());
+        assert!(json_path_to_pointer("$.a..b").is_err());
+        assert!(json_path_to_pointer("$.a[0").is_err());
+        assert!(json_path_to_pointer("$.a[x]").is_err());
+        assert!(json_path_to_pointer("$.a[0]b").is_err());
+    }
+
+    #[test]
+    fn test_parse_announcement() {
+        let device = parse_announcement(
+            "devices/sensor-1/announce",
+            r#"{"id":"sensor-1","properties":{"model":"TH-2","firmware-version":"1.2","battery":87,"tags":["a"],"unset":null}}"#,
+            &get_test_config(None),
+        )
+        .unwrap();
+        assert_eq!("sensor-1", device.id);
+        assert_eq!(6, device.properties.len());
+        assert_eq!("TH-2", device.properties["AKRI_MQTT_MODEL"]);
+        assert_eq!("1.2", device.properties["AKRI_MQTT_FIRMWARE_VERSION"]);
+        assert_eq!("87", device.properties["AKRI_MQTT_BATTERY"]);
+        assert_eq!(r#"["a"]"#, device.properties["AKRI_MQTT_TAGS"]);
+        assert_eq!("sensor-1", device.properties[MQTT_ID_LABEL]);
+        assert_eq!(
+            "devices/sensor-1/announce",
+            device.properties[MQTT_TOPIC_LABEL]
+        );
+
+        // Ids can be numbers and properties are optional
+        let mut discovery_handler_config = get_test_config(None);
+        discovery_handler_config.id_path = "$.device.identifiers[0]".to_string();
+        let device = parse_announcement(
+            "homeassistant/sensor/1/config",
+            r#"{"device":{"identifiers":[42]}}"#,
+            &discovery_handler_config,
+        )
+        .unwrap();
+        assert_eq!("42", device.id);
+        assert_eq!(2, device.properties.len());
+
+        assert!(parse_announcement("t", r#"{"name":"no id"}"#, &get_test_config(None)).is_err());
+        assert!(parse_announcement("t", r#"{"id":""}"#, &get_test_config(None)).is_err());
+        assert!(parse_announcement("t", "not json", &get_test_config(None)).is_err());
+    }
+
+    #[test]
+    fn test_apply_message() {
+        let discovery_handler_config = get_test_config(Some("offline"));
+        let mut devices = HashMap::new();
+        let topic = "devices/sensor-1/announce";
+        let announcement = br#"{"id":"sensor-1","properties":{"model":"TH-2"}}"#;
+
+        assert!(
+            apply_message(&mut devices, topic, announcement, &discovery_handler_config).unwrap()
+        );
+        // Repeated announcements do not change the devices, while changed properties do
+        assert!(
+            !apply_message(&mut devices, topic, announcement, &discovery_handler_config).unwrap()
+        );
+        assert!(apply_message(
+            &mut devices,
+            topic,
+            br#"{"id":"sensor-1","properties":{"model":"TH-3"}}"#,
+            &discovery_handler_config
+        )
+        .unwrap());
+        assert_eq!(1, devices.len());
+
+        // The offline payload and an empty payload remove the device announced on the topic
+        assert!(apply_message(&mut devices, topic, b"offline", &discovery_handler_config).unwrap());
+        assert!(devices.is_empty());
+        assert!(!apply_message(&mut devices, topic, b"", &discovery_handler_config).unwrap());
+        assert!(
+            apply_message(&mut devices, topic, announcement, &discovery_handler_config).unwrap()
+        );
+        assert!(apply_message(&mut devices, topic, b"", &discovery_handler_config).unwrap());
+        assert!(devices.is_empty());
+
+        // Without an offline payload, an offline message is an invalid announcement
+        assert!(apply_message(&mut devices, topic, b"offline", &get_test_config(None)).is_err());
+    }
+
+    #[test]
+    fn test_get_devices() {
+        let discovery_handler_config = get_test_config(None);
+        let mut devices = HashMap::new();
+        for (topic, payload) in &[
+            ("devices/b/announce", r#"{"id":"sensor-1"}"#),
+            ("devices/a/announce", r#"{"id":"sensor-1"}"#),
+            ("devices/c/announce", r#"{"id":"sensor-2"}"#),
+        ] {
+            apply_message(
+                &mut devices,
+                topic,
+                payload.as_bytes(),
+                &discovery_handler_config,
+            )
+            .unwrap();
+        }
+        let devices = get_devices(&devices);
+        assert_eq!(2, devices.len());
+        assert_eq!(
+            "devices/a/announce",
+            devices[0].properties[MQTT_TOPIC_LABEL]
+        );
+        assert_eq!("sensor-2", devices[1].id);
+    }
+}
@@ -0,0 +1,9 @@
+mod discovery_handler;
+mod discovery_impl;
+pub use self::discovery_handler::MqttDiscoveryHandler;
+
+/// Names of the properties set on the Instance of a discovered MQTT device
+pub const MQTT_ID_LABEL: &str = "AKRI_MQTT_ID";
+pub const MQTT_TOPIC_LABEL: &str = "AKRI_MQTT_TOPIC";
+/// Prefix of the properties taken from the fields of a device's announcement
+pub const MQTT_PROPERTY_LABEL_PREFIX: &str = "AKRI_MQTT_";
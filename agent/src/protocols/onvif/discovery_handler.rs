@@ -1,69 +1,284 @@
-use super::super::{DiscoveryHandler, DiscoveryResult};
+use super::super::{validate_discovery_interval, DiscoveryHandler, DiscoveryResult};
 use super::discovery_impl::util;
-use akri_shared::akri::configuration::{FilterList, FilterType, OnvifDiscoveryHandlerConfig};
+use akri_shared::akri::configuration::{
+    should_include_values, FilterList, OnvifDiscoveryHandlerConfig,
+};
+use akri_shared::log_fields;
+#[cfg(feature = "ptz-metadata")]
+use akri_shared::onvif::device_info::ONVIF_DEVICE_PTZ_SUPPORT_LABEL_ID;
 use akri_shared::onvif::device_info::{
-    OnvifQuery, OnvifQueryImpl, ONVIF_DEVICE_IP_ADDRESS_LABEL_ID,
-    ONVIF_DEVICE_MAC_ADDRESS_LABEL_ID, ONVIF_DEVICE_SERVICE_URL_LABEL_ID,
+    CachingOnvifQuery, Credentials, NotAuthorizedError, OnvifQuery, OnvifQueryCache,
+    OnvifQueryImpl, MEDIA_WSDL, ONVIF_DEVICE_FIRMWARE_VERSION_LABEL_ID,
+    ONVIF_DEVICE_GROUP_LABEL_ID, ONVIF_DEVICE_IP_ADDRESS_LABEL_ID,
+    ONVIF_DEVICE_MAC_ADDRESS_LABEL_ID, ONVIF_DEVICE_MANUFACTURER_LABEL_ID,
+    ONVIF_DEVICE_MODEL_LABEL_ID, ONVIF_DEVICE_RTSP_URI_LABEL_ID, ONVIF_DEVICE_SERVICE_URL_LABEL_ID,
 };
 use anyhow::Error;
 use async_trait::async_trait;
-use std::{collections::HashMap, time::Duration};
+use ipnet::Ipv4Net;
+use log::Level;
+use std::{collections::HashMap, fs, net::Ipv4Addr, time::Duration};
+
+/// Prefix shared by the names of the properties of an ONVIF camera
+const ONVIF_DEVICE_LABEL_PREFIX: &str = "ONVIF_DEVICE_";
+
+/// Fewest seconds `discoveryTimeoutSeconds` can be set to, since searching for less time finds no cameras
+pub const MIN_DISCOVERY_TIMEOUT_SECONDS: i32 = 1;
+/// Most seconds `discoveryTimeoutSeconds` can be set to, since discovery is blocked while searching
+pub const MAX_DISCOVERY_TIMEOUT_SECONDS: i32 = 60;
+
+/// Converts a Configuration's `discoveryTimeoutSeconds` into a `Duration`, rejecting timeouts outside
+/// of [`MIN_DISCOVERY_TIMEOUT_SECONDS`, `MAX_DISCOVERY_TIMEOUT_SECONDS`]
+fn validate_discovery_timeout(discovery_timeout_seconds: i32) -> Result<Duration, Error> {
+    if discovery_timeout_seconds < MIN_DISCOVERY_TIMEOUT_SECONDS
+        || discovery_timeout_seconds > MAX_DISCOVERY_TIMEOUT_SECONDS
+    {
+        return Err(anyhow::format_err!(
+            "invalid argument: discoveryTimeoutSeconds must be between {} and {} but was {}",
+            MIN_DISCOVERY_TIMEOUT_SECONDS,
+            MAX_DISCOVERY_TIMEOUT_SECONDS,
+            discovery_timeout_seconds
+        ));
+    }
+    Ok(Duration::from_secs(discovery_timeout_seconds as u64))
+}
 
 /// `OnvifDiscoveryHandler` discovers the onvif instances as described by the filters `discover_handler_config.ip_addresses`,
 /// `discover_handler_config.mac_addresses`, and `discover_handler_config.scopes`.
 /// The instances it discovers are always shared.
+/// Camera responses are cached for `discover_handler_config.query_cache_ttl_seconds` across discovery iterations.
 #[derive(Debug)]
 pub struct OnvifDiscoveryHandler {
     discovery_handler_config: OnvifDiscoveryHandlerConfig,
+    query_cache: OnvifQueryCache,
 }
 
 impl OnvifDiscoveryHandler {
     pub fn new(discovery_handler_config: &OnvifDiscoveryHandlerConfig) -> Self {
+        let query_cache_ttl =
+            Duration::from_secs(discovery_handler_config.query_cache_ttl_seconds.max(0) as u64);
         OnvifDiscoveryHandler {
             discovery_handler_config: discovery_handler_config.clone(),
+            query_cache: OnvifQueryCache::new(query_cache_ttl),
         }
     }
 
-    fn execute_filter(filter_list: Option<&FilterList>, filter_against: &[String]) -> bool {
-        if filter_list.is_none() {
-            return false;
+    /// Resolves the configured credentials, reading the password from `password_file` if one is specified
+    fn get_credentials(&self) -> Result<Option<Credentials>, Error> {
+        match &self.discovery_handler_config.credentials {
+            Some(credentials) => {
+                let password = match (&credentials.password, &credentials.password_file) {
+                    (_, Some(password_file)) => fs::read_to_string(password_file)
+                        .map_err(|e| {
+                            anyhow::format_err!(
+                                "could not read ONVIF password file {}: {}",
+                                password_file,
+                                e
+                            )
+                        })?
+                        .trim_end()
+                        .to_string(),
+                    (Some(password), None) => password.clone(),
+                    (None, None) => {
+                        return Err(anyhow::format_err!(
+                            "ONVIF credentials for {} must specify a password or passwordFile",
+                            credentials.username
+                        ))
+                    }
+                };
+                Ok(Some(Credentials {
+                    username: credentials.username.clone(),
+                    password,
+                }))
+            }
+            None => Ok(None),
         }
-        let filter_action = filter_list.as_ref().unwrap().action.clone();
-        let filter_count = filter_list
-            .unwrap()
-            .items
+    }
+
+    /// Logs an error returned by an ONVIF query, calling out cameras that rejected the request as not authorized
+    fn log_query_error(query: &str, device_service_url: &str, e: &Error) {
+        if e.downcast_ref::<NotAuthorizedError>().is_some() {
+            log_fields!(
+                Level::Error,
+                { protocol: "onvif", endpoint: device_service_url },
+                "apply_filters - camera {} rejected credentials when getting {} ... excluding it",
+                device_service_url, query
+            );
+        } else {
+            log_fields!(
+                Level::Error,
+                { protocol: "onvif", endpoint: device_service_url },
+                "apply_filters - error getting {}: {}",
+                query, e
+            );
+        }
+    }
+
+    /// Gets the stream uri of the first media profile of the camera at `device_service_url`
+    async fn get_stream_uri(
+        onvif_query: &impl OnvifQuery,
+        device_service_url: &str,
+    ) -> Result<String, anyhow::Error> {
+        let media_service_url = onvif_query
+            .get_device_service_uri(device_service_url, MEDIA_WSDL)
+            .await?;
+        let profiles = onvif_query.get_device_profiles(&media_service_url).await?;
+        let profile_token = profiles.first().ok_or_else(|| {
+            anyhow::format_err!("camera {} has no media profiles", device_service_url)
+        })?;
+        onvif_query
+            .get_device_profile_streaming_uri(&media_service_url, profile_token)
+            .await
+    }
+
+    /// Returns true if the camera described by `filter_against` should be filtered out.
+    /// Items match any value containing them unless the filter list sets another match type
+    fn execute_filter(filter_list: Option<&FilterList>, filter_against: &[String]) -> bool {
+        !should_include_values(filter_list, filter_against, |item, value| {
+            value.contains(item)
+        })
+    }
+
+    /// Parses `preferredNetworks`, rejecting entries that are not IPv4 networks in CIDR notation
+    fn get_preferred_networks(&self) -> Result<Vec<Ipv4Net>, Error> {
+        self.discovery_handler_config
+            .preferred_networks
             .iter()
-            .filter(|pattern| {
-                filter_against
-                    .iter()
-                    .filter(|filter_against_item| filter_against_item.contains(*pattern))
-                    .count()
-                    > 0
+            .map(|network| {
+                network.parse::<Ipv4Net>().map_err(|e| {
+                    anyhow::format_err!(
+                        "invalid argument: preferredNetworks entry {} is not an IPv4 network in CIDR notation: {}",
+                        network,
+                        e
+                    )
+                })
             })
-            .count();
+            .collect()
+    }
 
-        if FilterType::Include == filter_action {
-            filter_count == 0
-        } else {
-            filter_count != 0
+    /// Groups the (device service url, ip address, mac address) of each camera response by mac address, keeping
+    /// the order cameras were first seen in. Within a group, urls whose ip address is in one of `preferred_networks`
+    /// come first; the order is otherwise kept.
+    fn group_by_mac_address(
+        cameras: Vec<(String, String, String)>,
+        preferred_networks: &[Ipv4Net],
+        group_by_scope: None,
+    ) -> Vec<(String, Vec<(String, String)>)> {
+        let mut groups: Vec<(String, Vec<(String, String)>)> = Vec::new();
+        for (device_service_url, ip_address, mac_address) in cameras {
+            match groups.iter_mut().find(|(mac, _)| *mac == mac_address) {
+                Some((_, urls)) => {
+                    if !urls.iter().any(|(url, _)| *url == device_service_url) {
+                        urls.push((device_service_url, ip_address))
+                    }
+                }
+                None => groups.push((mac_address, vec![(device_service_url, ip_address)])),
+            }
+        }
+        let is_preferred = |ip_address: &str| match ip_address.parse::<Ipv4Addr>() {
+            Ok(ip_address) => preferred_networks
+                .iter()
+                .any(|network| network.contains(&ip_address)),
+            Err(_) => false,
+        };
+        for (_, urls) in groups.iter_mut() {
+            // Stable sort, so urls that are equally preferred keep their order
+            urls.sort_by_key(|(_, ip_address)| !is_preferred(ip_address));
+        }
+        groups
+    }
+
+    /// Aggregates the cameras whose scopes include one starting with `group_scope_prefix` into a single result per
+    /// group, identified by the rest of the scope. The properties of each camera of a group are added to the group's
+    /// result with the camera's position in the group, in order of ids, after `ONVIF_DEVICE_`, such as
+    /// `ONVIF_DEVICE_0_SERVICE_URL`. Cameras without a group scope are returned unchanged. Results are in the order
+    /// their first camera was given in.
+    fn apply_grouping(
+        cameras: Vec<(DiscoveryResult, Vec<String>)>,
+        group_scope_prefix: &str,
+        shared: bool,
+    ) -> Vec<DiscoveryResult> {
+        // Each entry is either a group's name and cameras or a camera without a group
+        let mut groups: Vec<(Option<String>, Vec<DiscoveryResult>)> = Vec::new();
+        for (camera, scopes) in cameras {
+            let group = scopes
+                .iter()
+                .find_map(|scope| scope.strip_prefix(group_scope_prefix))
+                .map(|group| group.to_string());
+            match group {
+                Some(group) => match groups
+                    .iter_mut()
+                    .find(|(name, _)| name.as_ref() == Some(&group))
+                {
+                    Some((_, members)) => members.push(camera),
+                    None => groups.push((Some(group), vec![camera])),
+                },
+                None => groups.push((None, vec![camera])),
+            }
         }
+        groups
+            .into_iter()
+            .flat_map(|(group, mut members)| match group {
+                Some(group) => {
+                    // Order members by id so that each keeps its position however the cameras responded
+                    members.sort_by(|a, b| a.id.cmp(&b.id));
+                    trace!(
+                        "apply_grouping - group {} has cameras {:?}",
+                        &group,
+                        members.iter().map(|member| &member.id).collect::<Vec<_>>()
+                    );
+                    let mut properties = HashMap::new();
+                    properties.insert(ONVIF_DEVICE_GROUP_LABEL_ID.to_string(), group.clone());
+                    for (i, member) in members.into_iter().enumerate() {
+                        let member_prefix = format!("{}{}_", ONVIF_DEVICE_LABEL_PREFIX, i);
+                        for (key, value) in member.properties {
+                            let key = match key.strip_prefix(ONVIF_DEVICE_LABEL_PREFIX) {
+                                Some(name) => format!("{}{}", member_prefix, name),
+                                None => format!("{}{}", member_prefix, key),
+                            };
+                            properties.insert(key, value);
+                        }
+                    }
+                    vec![DiscoveryResult::new(&group, properties, shared)]
+                }
+                None => members,
+            })
+            .collect()
     }
 
+    /// Filters the cameras at `device_service_uris`, collapsing cameras that answered on more than one
+    /// address (and so share a mac address) into a single result for their preferred address.
+    /// If `group_by_scope` is set, cameras in the same group are then reported as a single result.
     async fn apply_filters(
         &self,
         device_service_uris: Vec<String>,
         onvif_query: &impl OnvifQuery,
     ) -> Result<Vec<DiscoveryResult>, anyhow::Error> {
-        let mut result = Vec::new();
+        let preferred_networks = self.get_preferred_networks()?;
+        if self.discovery_handler_config.group_by_scope.as_deref() == Some("") {
+            return Err(anyhow::format_err!(
+                "invalid argument: groupByScope must not be empty"
+            ));
+        }
+        let mut cameras = Vec::new();
         for device_service_url in device_service_uris.iter() {
-            trace!("apply_filters - device service url {}", &device_service_url);
+            log_fields!(
+                Level::Trace,
+                { protocol: "onvif", endpoint: device_service_url },
+                "apply_filters - device service url {}",
+                &device_service_url
+            );
             let (ip_address, mac_address) = match onvif_query
                 .get_device_ip_and_mac_address(&device_service_url)
                 .await
             {
                 Ok(ip_and_mac) => ip_and_mac,
                 Err(e) => {
-                    error!("apply_filters - error getting ip and mac address: {}", e);
+                    OnvifDiscoveryHandler::log_query_error(
+                        "ip and mac address",
+                        &device_service_url,
+                        &e,
+                    );
                     continue;
                 }
             };
@@ -86,13 +301,35 @@ impl OnvifDiscoveryHandler {
                 continue;
             }
 
-            let ip_and_mac_joined = format!("{}-{}", &ip_address, &mac_address);
+            cameras.push((device_service_url.clone(), ip_address, mac_address));
+        }
+
+        let mut result = Vec::new();
+        for (mac_address, device_service_urls) in
+            OnvifDiscoveryHandler::group_by_mac_address(cameras, &preferred_networks)
+        {
+            let (device_service_url, ip_address) = device_service_urls[0].clone();
+            // A camera that answered on several addresses is identified by its mac address alone,
+            // so that it keeps the same Instance whichever address is preferred
+            let id = if device_service_urls.len() > 1 {
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "onvif", endpoint: device_service_url },
+                    "apply_filters - camera {} answered at {:?} ... using {}",
+                    &mac_address,
+                    &device_service_urls,
+                    &device_service_url
+                );
+                mac_address.clone()
+            } else {
+                format!("{}-{}", &ip_address, &mac_address)
+            };
 
             // Evaluate camera scopes against scopes filter if provided
             let device_scopes = match onvif_query.get_device_scopes(&device_service_url).await {
                 Ok(scopes) => scopes,
                 Err(e) => {
-                    error!("apply_filters - error getting scopes: {}", e);
+                    OnvifDiscoveryHandler::log_query_error("scopes", &device_service_url, &e);
                     continue;
                 }
             };
@@ -110,48 +347,152 @@ impl OnvifDiscoveryHandler {
             );
             properties.insert(ONVIF_DEVICE_IP_ADDRESS_LABEL_ID.into(), ip_address);
             properties.insert(ONVIF_DEVICE_MAC_ADDRESS_LABEL_ID.into(), mac_address);
+            // Give brokers every address the camera answered on, in order of preference, to fail over to
+            if device_service_urls.len() > 1 {
+                for (i, (url, _)) in device_service_urls.iter().enumerate() {
+                    properties.insert(
+                        format!("{}_{}", ONVIF_DEVICE_SERVICE_URL_LABEL_ID, i),
+                        url.clone(),
+                    );
+                }
+            }
+            #[cfg(feature = "ptz-metadata")]
+            match onvif_query
+                .get_device_capabilities(&device_service_url)
+                .await
+            {
+                Ok(capabilities) => {
+                    properties.insert(
+                        ONVIF_DEVICE_PTZ_SUPPORT_LABEL_ID.into(),
+                        capabilities.ptz.to_string(),
+                    );
+                }
+                Err(e) => {
+                    OnvifDiscoveryHandler::log_query_error("capabilities", &device_service_url, &e)
+                }
+            }
+            if self.discovery_handler_config.include_stream_uri {
+                match OnvifDiscoveryHandler::get_stream_uri(onvif_query, &device_service_url).await
+                {
+                    Ok(stream_uri) => {
+                        properties.insert(ONVIF_DEVICE_RTSP_URI_LABEL_ID.into(), stream_uri);
+                    }
+                    Err(e) => OnvifDiscoveryHandler::log_query_error(
+                        "stream uri",
+                        &device_service_url,
+                        &e,
+                    ),
+                }
+            }
+            if self.discovery_handler_config.include_device_info {
+                match onvif_query
+                    .get_device_information(&device_service_url)
+                    .await
+                {
+                    Ok(device_information) => {
+                        properties.insert(
+                            ONVIF_DEVICE_MANUFACTURER_LABEL_ID.into(),
+                            device_information.manufacturer,
+                        );
+                        properties
+                            .insert(ONVIF_DEVICE_MODEL_LABEL_ID.into(), device_information.model);
+                        properties.insert(
+                            ONVIF_DEVICE_FIRMWARE_VERSION_LABEL_ID.into(),
+                            device_information.firmware_version,
+                        );
+                    }
+                    Err(e) => OnvifDiscoveryHandler::log_query_error(
+                        "device information",
+                        &device_service_url,
+                        &e,
+                    ),
+                }
+            }
 
-            trace!(
-                "apply_filters - returns DiscoveryResult ip/mac: {:?}, props: {:?}",
-                &ip_and_mac_joined,
+            log_fields!(
+                Level::Trace,
+                { protocol: "onvif", endpoint: device_service_url },
+                "apply_filters - returns DiscoveryResult id: {:?}, props: {:?}",
+                &id,
                 &properties
             );
-            result.push(DiscoveryResult::new(
-                &ip_and_mac_joined,
-                properties,
-                self.are_shared().unwrap(),
+            result.push((
+                DiscoveryResult::new(&id, properties, self.are_shared().unwrap()),
+                device_scopes,
             ))
         }
-        Ok(result)
+        Ok(match &self.discovery_handler_config.group_by_scope {
+            Some(group_scope_prefix) => OnvifDiscoveryHandler::apply_grouping(
+                result,
+                group_scope_prefix,
+                self.are_shared().unwrap(),
+            ),
+            None => result.into_iter().map(|(camera, _)| camera).collect(),
+        })
     }
 }
 
 #[async_trait]
 impl DiscoveryHandler for OnvifDiscoveryHandler {
     async fn discover(&self) -> Result<Vec<DiscoveryResult>, anyhow::Error> {
-        let onvif_query = OnvifQueryImpl {};
+        let discovery_timeout =
+            validate_discovery_timeout(self.discovery_handler_config.discovery_timeout_seconds)?;
+        let onvif_query = CachingOnvifQuery::new(
+            OnvifQueryImpl {
+                credentials: self.get_credentials()?,
+            },
+            &self.query_cache,
+        );
 
-        info!("discover - filters:{:?}", &self.discovery_handler_config,);
-        let discovered_onvif_cameras = util::simple_onvif_discover(Duration::from_secs(
-            self.discovery_handler_config.discovery_timeout_seconds as u64,
-        ))
+        log_fields!(
+            Level::Info,
+            { protocol: "onvif" },
+            "discover - filters:{:?}",
+            &self.discovery_handler_config
+        );
+        let discovered_onvif_cameras = util::simple_onvif_discover(
+            discovery_timeout,
+            self.discovery_handler_config.max_cameras,
+            Duration::from_millis(self.discovery_handler_config.probe_delay_ms),
+            self.discovery_handler_config.network_interface.as_deref(),
+            self.discovery_handler_config.multicast_ttl,
+        )
         .await?;
-        info!("discover - discovered:{:?}", &discovered_onvif_cameras,);
+        log_fields!(
+            Level::Info,
+            { protocol: "onvif" },
+            "discover - discovered:{:?}",
+            &discovered_onvif_cameras
+        );
         let filtered_onvif_cameras = self
             .apply_filters(discovered_onvif_cameras, &onvif_query)
             .await;
-        info!("discover - filtered:{:?}", &filtered_onvif_cameras);
+        log_fields!(
+            Level::Info,
+            { protocol: "onvif" },
+            "discover - filtered:{:?}",
+            &filtered_onvif_cameras
+        );
         filtered_onvif_cameras
     }
     fn are_shared(&self) -> Result<bool, Error> {
         Ok(true)
     }
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        validate_discovery_interval(self.discovery_handler_config.discovery_interval_seconds)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use akri_shared::onvif::device_info::MockOnvifQuery;
+    #[cfg(feature = "ptz-metadata")]
+    use akri_shared::onvif::device_info::DeviceCapabilities;
+    use akri_shared::{
+        akri::configuration::{FilterType, MatchType, OnvifCredentials},
+        onvif::device_info::{DeviceInformation, MockOnvifQuery},
+    };
+    use proptest::prelude::*;
 
     struct IpAndMac {
         mock_uri: &'static str,
@@ -228,6 +569,302 @@ mod tests {
             mac_addresses: None,
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
+        });
+        let instances = onvif
+            .apply_filters(vec![mock_uri.to_string()], &mock)
+            .await
+            .unwrap();
+
+        assert_eq!(1, instances.len());
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_deduplicates_mac_address() {
+        let mut mock = MockOnvifQuery::new();
+        // The camera answers on its wireless address first, then on its wired address
+        configure_get_device_ip_and_mac_address(
+            &mut mock,
+            "wireless_uri",
+            "192.168.1.5",
+            "mock:mac",
+        );
+        configure_get_device_ip_and_mac_address(&mut mock, "wired_uri", "10.0.0.5", "mock:mac");
+        configure_get_device_ip_and_mac_address(&mut mock, "other_uri", "192.168.1.6", "other:mac");
+        configure_get_device_scopes(&mut mock, "wired_uri", "mock.scope");
+        configure_get_device_scopes(&mut mock, "other_uri", "mock.scope");
+
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: vec!["10.0.0.0/8".to_string()],
+            group_by_scope: None,
+        });
+        let instances = onvif
+            .apply_filters(
+                vec![
+                    "wireless_uri".to_string(),
+                    "wired_uri".to_string(),
+                    "other_uri".to_string(),
+                ],
+                &mock,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(2, instances.len());
+        // The camera is found once, at the address in the preferred network, with both addresses to fail over to
+        let camera = &instances[0];
+        assert_eq!("mock:mac", camera.id);
+        assert_eq!(
+            Some(&"wired_uri".to_string()),
+            camera.properties.get(ONVIF_DEVICE_SERVICE_URL_LABEL_ID)
+        );
+        assert_eq!(
+            Some(&"10.0.0.5".to_string()),
+            camera.properties.get(ONVIF_DEVICE_IP_ADDRESS_LABEL_ID)
+        );
+        assert_eq!(
+            Some(&"wired_uri".to_string()),
+            camera
+                .properties
+                .get(&format!("{}_0", ONVIF_DEVICE_SERVICE_URL_LABEL_ID))
+        );
+        assert_eq!(
+            Some(&"wireless_uri".to_string()),
+            camera
+                .properties
+                .get(&format!("{}_1", ONVIF_DEVICE_SERVICE_URL_LABEL_ID))
+        );
+        // Cameras that answered once keep their ip-mac id and single service url
+        let other_camera = &instances[1];
+        assert_eq!("192.168.1.6-other:mac", other_camera.id);
+        assert_eq!(
+            None,
+            other_camera
+                .properties
+                .get(&format!("{}_0", ONVIF_DEVICE_SERVICE_URL_LABEL_ID))
+        );
+    }
+
+    fn create_camera(
+        ip_address: &str,
+        mac_address: &str,
+        scopes: Vec<&str>,
+    ) -> (DiscoveryResult, Vec<String>) {
+        let mut properties = HashMap::new();
+        properties.insert(
+            ONVIF_DEVICE_SERVICE_URL_LABEL_ID.to_string(),
+            format!("http://{}/onvif/device_service", ip_address),
+        );
+        properties.insert(
+            ONVIF_DEVICE_IP_ADDRESS_LABEL_ID.to_string(),
+            ip_address.to_string(),
+        );
+        properties.insert(
+            ONVIF_DEVICE_MAC_ADDRESS_LABEL_ID.to_string(),
+            mac_address.to_string(),
+        );
+        (
+            DiscoveryResult::new(&format!("{}-{}", ip_address, mac_address), properties, true),
+            scopes.into_iter().map(|scope| scope.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_apply_grouping() {
+        let group_scope_prefix = "onvif://www.onvif.org/group/";
+        let cameras = vec![
+            create_camera(
+                "10.0.0.2",
+                "right:mac",
+                vec![
+                    "onvif://www.onvif.org/name/right",
+                    "onvif://www.onvif.org/group/stereo-1",
+                ],
+            ),
+            create_camera(
+                "10.0.0.3",
+                "solo:mac",
+                vec!["onvif://www.onvif.org/name/solo"],
+            ),
+            create_camera(
+                "10.0.0.1",
+                "left:mac",
+                vec!["onvif://www.onvif.org/group/stereo-1"],
+            ),
+            create_camera(
+                "10.0.0.4",
+                "other:mac",
+                vec!["onvif://www.onvif.org/group/stereo-2"],
+            ),
+        ];
+
+        let results = OnvifDiscoveryHandler::apply_grouping(cameras, group_scope_prefix, true);
+        let ids: Vec<&str> = results.iter().map(|result| result.id.as_str()).collect();
+        assert_eq!(vec!["stereo-1", "10.0.0.3-solo:mac", "stereo-2"], ids);
+
+        // The cameras of a group are ordered by id, whatever order they responded in
+        let stereo = &results[0];
+        assert_eq!(
+            DiscoveryResult::new("stereo-1", HashMap::new(), true).digest,
+            stereo.digest
+        );
+        assert_eq!(
+            Some(&"stereo-1".to_string()),
+            stereo.properties.get(ONVIF_DEVICE_GROUP_LABEL_ID)
+        );
+        for (i, (ip_address, mac_address)) in
+            vec![("10.0.0.1", "left:mac"), ("10.0.0.2", "right:mac")]
+                .into_iter()
+                .enumerate()
+        {
+            assert_eq!(
+                Some(&format!("http://{}/onvif/device_service", ip_address)),
+                stereo
+                    .properties
+                    .get(&format!("ONVIF_DEVICE_{}_SERVICE_URL", i))
+            );
+            assert_eq!(
+                Some(&ip_address.to_string()),
+                stereo
+                    .properties
+                    .get(&format!("ONVIF_DEVICE_{}_IP_ADDRESS", i))
+            );
+            assert_eq!(
+                Some(&mac_address.to_string()),
+                stereo
+                    .properties
+                    .get(&format!("ONVIF_DEVICE_{}_MAC_ADDRESS", i))
+            );
+        }
+        assert_eq!(7, stereo.properties.len());
+
+        // A camera without a group scope is left as it was
+        let solo = &results[1];
+        assert_eq!(
+            Some(&"10.0.0.3".to_string()),
+            solo.properties.get(ONVIF_DEVICE_IP_ADDRESS_LABEL_ID)
+        );
+        assert_eq!(None, solo.properties.get(ONVIF_DEVICE_GROUP_LABEL_ID));
+
+        // A group of one camera is still reported as its group
+        assert_eq!(
+            Some(&"other:mac".to_string()),
+            results[2].properties.get("ONVIF_DEVICE_0_MAC_ADDRESS")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_empty_group_by_scope() {
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: Some("".to_string()),
+        });
+        assert!(onvif
+            .apply_filters(vec!["device_uri".to_string()], &MockOnvifQuery::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_invalid_preferred_networks() {
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: vec!["10.0.0.0".to_string()],
+            group_by_scope: None,
+        });
+        assert!(onvif
+            .apply_filters(vec!["device_uri".to_string()], &MockOnvifQuery::new())
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "ptz-metadata")]
+    #[tokio::test]
+    async fn test_apply_filters_ptz_support() {
+        let mock_uri = "device_uri";
+
+        let mut mock = MockOnvifQuery::new();
+        configure_scenario(
+            &mut mock,
+            Some(IpAndMac {
+                mock_uri: "device_uri",
+                mock_ip: "mock.ip",
+                mock_mac: "mock:mac",
+            }),
+            Some(Scope {
+                mock_uri: "device_uri",
+                mock_scope: "mock.scope",
+            }),
+        );
+        mock.expect_get_device_capabilities()
+            .times(1)
+            .withf(move |u| u == mock_uri)
+            .returning(|_| Ok(DeviceCapabilities { ptz: true }));
+
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -235,6 +872,513 @@ mod tests {
             .unwrap();
 
         assert_eq!(1, instances.len());
+        assert_eq!(
+            &"true".to_string(),
+            instances[0]
+                .properties
+                .get(ONVIF_DEVICE_PTZ_SUPPORT_LABEL_ID)
+                .unwrap()
+        );
+    }
+
+    fn configure_get_stream_uri(
+        mock: &mut MockOnvifQuery,
+        uri: &'static str,
+        profiles: Vec<String>,
+        stream_uri: &'static str,
+    ) {
+        let media_uri = "media_uri";
+        mock.expect_get_device_service_uri()
+            .times(1)
+            .withf(move |u, service| u == uri && service == MEDIA_WSDL)
+            .returning(move |_, _| Ok(media_uri.to_string()));
+        mock.expect_get_device_profiles()
+            .times(1)
+            .withf(move |u| u == media_uri)
+            .returning(move |_| Ok(profiles.clone()));
+        mock.expect_get_device_profile_streaming_uri()
+            .withf(move |u, profile_token| u == media_uri && profile_token == "profile_1")
+            .returning(move |_, _| Ok(stream_uri.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_include_stream_uri() {
+        let mock_uri = "device_uri";
+        let mock_stream_uri = "rtsp://mock.ip:554/stream1";
+
+        let mut mock = MockOnvifQuery::new();
+        configure_scenario(
+            &mut mock,
+            Some(IpAndMac {
+                mock_uri,
+                mock_ip: "mock.ip",
+                mock_mac: "mock:mac",
+            }),
+            Some(Scope {
+                mock_uri,
+                mock_scope: "mock.scope",
+            }),
+        );
+        configure_get_stream_uri(
+            &mut mock,
+            mock_uri,
+            vec!["profile_1".to_string(), "profile_2".to_string()],
+            mock_stream_uri,
+        );
+        #[cfg(feature = "ptz-metadata")]
+        mock.expect_get_device_capabilities()
+            .returning(|_| Ok(DeviceCapabilities::default()));
+
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: true,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
+        });
+        let instances = onvif
+            .apply_filters(vec![mock_uri.to_string()], &mock)
+            .await
+            .unwrap();
+
+        assert_eq!(1, instances.len());
+        assert_eq!(
+            &mock_stream_uri.to_string(),
+            instances[0]
+                .properties
+                .get(ONVIF_DEVICE_RTSP_URI_LABEL_ID)
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_include_stream_uri_no_profiles() {
+        let mock_uri = "device_uri";
+
+        let mut mock = MockOnvifQuery::new();
+        configure_scenario(
+            &mut mock,
+            Some(IpAndMac {
+                mock_uri,
+                mock_ip: "mock.ip",
+                mock_mac: "mock:mac",
+            }),
+            Some(Scope {
+                mock_uri,
+                mock_scope: "mock.scope",
+            }),
+        );
+        configure_get_stream_uri(&mut mock, mock_uri, Vec::new(), "unused");
+        #[cfg(feature = "ptz-metadata")]
+        mock.expect_get_device_capabilities()
+            .returning(|_| Ok(DeviceCapabilities::default()));
+
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: true,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
+        });
+        let instances = onvif
+            .apply_filters(vec![mock_uri.to_string()], &mock)
+            .await
+            .unwrap();
+
+        // A camera whose stream uri cannot be found is still discovered, just without the property
+        assert_eq!(1, instances.len());
+        assert!(instances[0]
+            .properties
+            .get(ONVIF_DEVICE_RTSP_URI_LABEL_ID)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_include_device_info() {
+        let mock_uri = "device_uri";
+
+        let mut mock = MockOnvifQuery::new();
+        configure_scenario(
+            &mut mock,
+            Some(IpAndMac {
+                mock_uri,
+                mock_ip: "mock.ip",
+                mock_mac: "mock:mac",
+            }),
+            Some(Scope {
+                mock_uri,
+                mock_scope: "mock.scope",
+            }),
+        );
+        mock.expect_get_device_information()
+            .times(1)
+            .withf(move |u| u == mock_uri)
+            .returning(|_| {
+                Ok(DeviceInformation {
+                    manufacturer: "Contoso".to_string(),
+                    model: "IPC-100".to_string(),
+                    firmware_version: "2.4.1".to_string(),
+                    serial_number: "SN0001".to_string(),
+                })
+            });
+        #[cfg(feature = "ptz-metadata")]
+        mock.expect_get_device_capabilities()
+            .returning(|_| Ok(DeviceCapabilities::default()));
+
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: true,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
+        });
+        let instances = onvif
+            .apply_filters(vec![mock_uri.to_string()], &mock)
+            .await
+            .unwrap();
+
+        assert_eq!(1, instances.len());
+        let properties = &instances[0].properties;
+        assert_eq!(
+            Some(&"Contoso".to_string()),
+            properties.get(ONVIF_DEVICE_MANUFACTURER_LABEL_ID)
+        );
+        assert_eq!(
+            Some(&"IPC-100".to_string()),
+            properties.get(ONVIF_DEVICE_MODEL_LABEL_ID)
+        );
+        assert_eq!(
+            Some(&"2.4.1".to_string()),
+            properties.get(ONVIF_DEVICE_FIRMWARE_VERSION_LABEL_ID)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_filters_not_authorized() {
+        let mock_uri = "device_uri";
+
+        let mut mock = MockOnvifQuery::new();
+        mock.expect_get_device_ip_and_mac_address()
+            .times(1)
+            .withf(move |u| u == mock_uri)
+            .returning(move |u| Err(NotAuthorizedError { url: u.to_string() }.into()));
+
+        let onvif = OnvifDiscoveryHandler::new(&OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
+        });
+        let instances = onvif
+            .apply_filters(vec![mock_uri.to_string()], &mock)
+            .await
+            .unwrap();
+
+        assert_eq!(0, instances.len());
+    }
+
+    #[test]
+    fn test_validate_discovery_timeout() {
+        assert!(validate_discovery_timeout(0).is_err());
+        assert_eq!(
+            Duration::from_secs(1),
+            validate_discovery_timeout(MIN_DISCOVERY_TIMEOUT_SECONDS).unwrap()
+        );
+        assert_eq!(
+            Duration::from_secs(60),
+            validate_discovery_timeout(MAX_DISCOVERY_TIMEOUT_SECONDS).unwrap()
+        );
+        assert!(validate_discovery_timeout(3600).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_validate_discovery_timeout_range(discovery_timeout_seconds in any::<i32>()) {
+            let in_range = (MIN_DISCOVERY_TIMEOUT_SECONDS..=MAX_DISCOVERY_TIMEOUT_SECONDS)
+                .contains(&discovery_timeout_seconds);
+            prop_assert_eq!(in_range, validate_discovery_timeout(discovery_timeout_seconds).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_get_credentials() {
+        let mut config = OnvifDiscoveryHandlerConfig {
+            ip_addresses: None,
+            mac_addresses: None,
+            scopes: None,
+            discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
+        };
+        assert_eq!(
+            None,
+            OnvifDiscoveryHandler::new(&config)
+                .get_credentials()
+                .unwrap()
+        );
+
+        let password_file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(password_file.path(), "filepassword\n").unwrap();
+        config.credentials = Some(OnvifCredentials {
+            username: "admin".to_string(),
+            password: Some("literalpassword".to_string()),
+            password_file: Some(password_file.path().to_str().unwrap().to_string()),
+        });
+        assert_eq!(
+            Some(Credentials {
+                username: "admin".to_string(),
+                password: "filepassword".to_string(),
+            }),
+            OnvifDiscoveryHandler::new(&config)
+                .get_credentials()
+                .unwrap()
+        );
+
+        config.credentials = Some(OnvifCredentials {
+            username: "admin".to_string(),
+            password: None,
+            password_file: None,
+        });
+        assert!(OnvifDiscoveryHandler::new(&config)
+            .get_credentials()
+            .is_err());
+    }
+
+    #[test]
+    fn test_execute_filter_glob() {
+        let glob_filter = |action: FilterType, items: Vec<&str>| FilterList {
+            items: items.into_iter().map(|item| item.to_string()).collect(),
+            action,
+            match_type: MatchType::Glob,
+        };
+        let ip_addresses = |ip: &str| vec![ip.to_string()];
+
+        // `*` matches any characters, so every camera at .10 is included across subnets
+        let filter = glob_filter(FilterType::Include, vec!["192.168.*.10"]);
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &ip_addresses("192.168.1.10")
+        ));
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &ip_addresses("192.168.20.10")
+        ));
+        assert!(OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &ip_addresses("192.168.1.100")
+        ));
+        // Unlike literal items, patterns must match the whole value
+        assert!(OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &ip_addresses("10.192.168.1.10")
+        ));
+
+        // `?` matches a single character
+        let filter = glob_filter(FilterType::Exclude, vec!["10.0.0.?"]);
+        assert!(OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &ip_addresses("10.0.0.5")
+        ));
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &ip_addresses("10.0.0.50")
+        ));
+
+        // Character classes match one of a set of characters
+        let filter = glob_filter(
+            FilterType::Include,
+            vec!["onvif://www.onvif.org/location/building_[ab]*"],
+        );
+        let scopes = vec![
+            "onvif://www.onvif.org/type/video_encoder".to_string(),
+            "onvif://www.onvif.org/location/building_b/floor_2".to_string(),
+        ];
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &scopes
+        ));
+        let scopes = vec!["onvif://www.onvif.org/location/building_c/floor_2".to_string()];
+        assert!(OnvifDiscoveryHandler::execute_filter(
+            Some(&filter),
+            &scopes
+        ));
+    }
+
+    #[test]
+    fn test_execute_filter_exact_and_regex() {
+        let filter = |action: FilterType, match_type: MatchType, item: &str| FilterList {
+            items: vec![item.to_string()],
+            action,
+            match_type,
+        };
+        let ip_addresses = |ip: &str| vec![ip.to_string()];
+
+        // Literal items match any address containing them, so 192.168.1.100 is included along with 192.168.1.10
+        let literal = filter(FilterType::Include, MatchType::Literal, "192.168.1.10");
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&literal),
+            &ip_addresses("192.168.1.100")
+        ));
+
+        // Exact items only match the whole address
+        let exact = filter(FilterType::Include, MatchType::Exact, "192.168.1.10");
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&exact),
+            &ip_addresses("192.168.1.10")
+        ));
+        assert!(OnvifDiscoveryHandler::execute_filter(
+            Some(&exact),
+            &ip_addresses("192.168.1.100")
+        ));
+        let exact = filter(FilterType::Exclude, MatchType::Exact, "192.168.1.10");
+        assert!(OnvifDiscoveryHandler::execute_filter(
+            Some(&exact),
+            &ip_addresses("192.168.1.10")
+        ));
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&exact),
+            &ip_addresses("192.168.1.100")
+        ));
+
+        // Anchored regexes only match the whole address
+        let regex = filter(
+            FilterType::Include,
+            MatchType::Regex,
+            r"^192\.168\.[0-9]+\.10$",
+        );
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&regex),
+            &ip_addresses("192.168.7.10")
+        ));
+        assert!(OnvifDiscoveryHandler::execute_filter(
+            Some(&regex),
+            &ip_addresses("192.168.7.100")
+        ));
+        // Unanchored regexes match any part of the value, such as one of a camera's scopes
+        let regex = filter(FilterType::Exclude, MatchType::Regex, "location/lab_[0-9]+");
+        let scopes = vec![
+            "onvif://www.onvif.org/type/video_encoder".to_string(),
+            "onvif://www.onvif.org/location/lab_2".to_string(),
+        ];
+        assert!(OnvifDiscoveryHandler::execute_filter(Some(&regex), &scopes));
+        let scopes = vec!["onvif://www.onvif.org/location/office".to_string()];
+        assert!(!OnvifDiscoveryHandler::execute_filter(
+            Some(&regex),
+            &scopes
+        ));
+    }
+
+    #[test]
+    fn test_execute_filter_empty_and_missing_values() {
+        let filter = |action: FilterType, items: &[&str]| FilterList {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            action,
+            match_type: MatchType::Literal,
+        };
+        let scopes = vec![
+            "onvif://www.onvif.org/type/video_encoder".to_string(),
+            "onvif://www.onvif.org/location/lab_2".to_string(),
+        ];
+        // (filter list, values filtered against, whether the camera is filtered out)
+        let cases = vec![
+            (None, scopes.clone(), false),
+            (None, Vec::new(), false),
+            // An empty Include list includes nothing, an empty Exclude list excludes nothing
+            (Some(filter(FilterType::Include, &[])), scopes.clone(), true),
+            (Some(filter(FilterType::Include, &[])), Vec::new(), true),
+            (
+                Some(filter(FilterType::Exclude, &[])),
+                scopes.clone(),
+                false,
+            ),
+            (Some(filter(FilterType::Exclude, &[])), Vec::new(), false),
+            // A camera without scopes matches no items
+            (
+                Some(filter(FilterType::Include, &["location/lab"])),
+                Vec::new(),
+                true,
+            ),
+            (
+                Some(filter(FilterType::Exclude, &["location/lab"])),
+                Vec::new(),
+                false,
+            ),
+            (
+                Some(filter(
+                    FilterType::Include,
+                    &["location/office", "location/lab"],
+                )),
+                scopes.clone(),
+                false,
+            ),
+            (
+                Some(filter(
+                    FilterType::Exclude,
+                    &["location/office", "location/lab"],
+                )),
+                scopes.clone(),
+                true,
+            ),
+        ];
+        for (filter_list, filter_against, filtered_out) in cases {
+            assert_eq!(
+                filtered_out,
+                OnvifDiscoveryHandler::execute_filter(filter_list.as_ref(), &filter_against),
+                "filter list {:?} with values {:?}",
+                filter_list,
+                filter_against
+            );
+        }
     }
 
     #[tokio::test]
@@ -260,10 +1404,22 @@ mod tests {
             ip_addresses: Some(FilterList {
                 action: FilterType::Include,
                 items: vec![mock_ip.to_string()],
+                match_type: MatchType::Literal,
             }),
             mac_addresses: None,
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -292,10 +1448,22 @@ mod tests {
             ip_addresses: Some(FilterList {
                 action: FilterType::Include,
                 items: vec!["nonexist.ip".to_string()],
+                match_type: MatchType::Literal,
             }),
             mac_addresses: None,
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -327,10 +1495,22 @@ mod tests {
             ip_addresses: Some(FilterList {
                 action: FilterType::Exclude,
                 items: vec!["nonexist.ip".to_string()],
+                match_type: MatchType::Literal,
             }),
             mac_addresses: None,
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -360,10 +1540,22 @@ mod tests {
             ip_addresses: Some(FilterList {
                 action: FilterType::Exclude,
                 items: vec![mock_ip.to_string()],
+                match_type: MatchType::Literal,
             }),
             mac_addresses: None,
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -397,9 +1589,21 @@ mod tests {
             mac_addresses: Some(FilterList {
                 action: FilterType::Include,
                 items: vec![mock_mac.to_string()],
+                match_type: MatchType::Literal,
             }),
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -429,9 +1633,21 @@ mod tests {
             mac_addresses: Some(FilterList {
                 action: FilterType::Include,
                 items: vec!["nonexist:mac".to_string()],
+                match_type: MatchType::Literal,
             }),
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -464,9 +1680,21 @@ mod tests {
             mac_addresses: Some(FilterList {
                 action: FilterType::Exclude,
                 items: vec!["nonexist:mac".to_string()],
+                match_type: MatchType::Literal,
             }),
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
@@ -497,9 +1725,21 @@ mod tests {
             mac_addresses: Some(FilterList {
                 action: FilterType::Exclude,
                 items: vec![mock_mac.to_string()],
+                match_type: MatchType::Literal,
             }),
             scopes: None,
             discovery_timeout_seconds: 1,
+            discovery_interval_seconds: 10,
+            query_cache_ttl_seconds: 60,
+            credentials: None,
+            include_stream_uri: false,
+            include_device_info: false,
+            max_cameras: None,
+            probe_delay_ms: 0,
+            network_interface: None,
+            multicast_ttl: None,
+            preferred_networks: Vec::new(),
+            group_by_scope: None,
         });
         let instances = onvif
             .apply_filters(vec![mock_uri.to_string()], &mock)
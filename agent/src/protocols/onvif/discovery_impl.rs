@@ -208,6 +208,15 @@ pub mod util {
             .collect::<Vec<String>>()
     }
 
+    /// Creates a WS-Discovery ProbeMatches response listing `xaddrs`, as sent by a camera
+    #[cfg(test)]
+    fn create_discovery_response(xaddrs: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:SOAP-ENC=\"http://www.w3.org/2003/05/soap-encoding\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xs=\"http://www.w3.org/2000/10/XMLSchema\" xmlns:wsse=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd\" xmlns:wsa5=\"http://www.w3.org/2005/08/addressing\" xmlns:xop=\"http://www.w3.org/2004/08/xop/include\" xmlns:wsa=\"http://schemas.xmlsoap.org/ws/2004/08/addressing\" xmlns:tt=\"http://www.onvif.org/ver10/schema\" xmlns:ns1=\"http://www.w3.org/2005/05/xmlmime\" xmlns:wstop=\"http://docs.oasis-open.org/wsn/t-1\" xmlns:ns7=\"http://docs.oasis-open.org/wsrf/r-2\" xmlns:ns2=\"http://docs.oasis-open.org/wsrf/bf-2\" xmlns:dndl=\"http://www.onvif.org/ver10/network/wsdl/DiscoveryLookupBinding\" xmlns:dnrd=\"http://www.onvif.org/ver10/network/wsdl/RemoteDiscoveryBinding\" xmlns:d=\"http://schemas.xmlsoap.org/ws/2005/04/discovery\" xmlns:dn=\"http://www.onvif.org/ver10/network/wsdl\" xmlns:ns10=\"http://www.onvif.org/ver10/replay/wsdl\" xmlns:ns11=\"http://www.onvif.org/ver10/search/wsdl\" xmlns:ns13=\"http://www.onvif.org/ver20/analytics/wsdl/RuleEngineBinding\" xmlns:ns14=\"http://www.onvif.org/ver20/analytics/wsdl/AnalyticsEngineBinding\" xmlns:tan=\"http://www.onvif.org/ver20/analytics/wsdl\" xmlns:ns15=\"http://www.onvif.org/ver10/events/wsdl/PullPointSubscriptionBinding\" xmlns:ns16=\"http://www.onvif.org/ver10/events/wsdl/EventBinding\" xmlns:tev=\"http://www.onvif.org/ver10/events/wsdl\" xmlns:ns17=\"http://www.onvif.org/ver10/events/wsdl/SubscriptionManagerBinding\" xmlns:ns18=\"http://www.onvif.org/ver10/events/wsdl/NotificationProducerBinding\" xmlns:ns19=\"http://www.onvif.org/ver10/events/wsdl/NotificationConsumerBinding\" xmlns:ns20=\"http://www.onvif.org/ver10/events/wsdl/PullPointBinding\" xmlns:ns21=\"http://www.onvif.org/ver10/events/wsdl/CreatePullPointBinding\" xmlns:ns22=\"http://www.onvif.org/ver10/events/wsdl/PausableSubscriptionManagerBinding\" xmlns:wsnt=\"http://docs.oasis-open.org/wsn/b-2\" xmlns:ns3=\"http://www.onvif.org/ver10/analyticsdevice/wsdl\" xmlns:ns4=\"http://www.onvif.org/ver10/deviceIO/wsdl\" xmlns:ns5=\"http://www.onvif.org/ver10/display/wsdl\" xmlns:ns8=\"http://www.onvif.org/ver10/receiver/wsdl\" xmlns:ns9=\"http://www.onvif.org/ver10/recording/wsdl\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\" xmlns:timg=\"http://www.onvif.org/ver20/imaging/wsdl\" xmlns:tptz=\"http://www.onvif.org/ver20/ptz/wsdl\" xmlns:trt=\"http://www.onvif.org/ver10/media/wsdl\" xmlns:trt2=\"http://www.onvif.org/ver20/media/wsdl\" xmlns:ter=\"http://www.onvif.org/ver10/error\" xmlns:tns1=\"http://www.onvif.org/ver10/topics\" xmlns:tnsn=\"http://www.eventextension.com/2011/event/topics\"><SOAP-ENV:Header><wsa:MessageID>urn:uuid:2bc6f06c-5566-7788-99ac-0012414fb745</wsa:MessageID><wsa:RelatesTo>uuid:7b1d26aa-b02e-4ad2-8aab-4c928298ee0c</wsa:RelatesTo><wsa:To SOAP-ENV:mustUnderstand=\"true\">http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</wsa:To><wsa:Action SOAP-ENV:mustUnderstand=\"true\">http://schemas.xmlsoap.org/ws/2005/04/discovery/ProbeMatches</wsa:Action></SOAP-ENV:Header><SOAP-ENV:Body><d:ProbeMatches><d:ProbeMatch><wsa:EndpointReference><wsa:Address>urn:uuid:10919da4-5566-7788-99aa-0012414fb745</wsa:Address></wsa:EndpointReference><d:Types>dn:NetworkVideoTransmitter</d:Types><d:Scopes>onvif://www.onvif.org/type/video_encoder onvif://www.onvif.org/type/audio_encoder onvif://www.onvif.org/hardware/IPC-model onvif://www.onvif.org/location/country/china onvif://www.onvif.org/name/NVT onvif://www.onvif.org/Profile/Streaming </d:Scopes><d:XAddrs>{}</d:XAddrs><d:MetadataVersion>10</d:MetadataVersion></d:ProbeMatch></d:ProbeMatches></SOAP-ENV:Body></SOAP-ENV:Envelope>",
+            xaddrs
+        )
+    }
+
     #[cfg(test)]
     mod deserialize_tests {
         use super::*;
@@ -217,15 +226,162 @@ pub mod util {
             let _ = env_logger::builder().is_test(true).try_init();
 
             let uris = vec!["uri_one".to_string(), "uri_two".to_string()];
-            let response = format!(
-                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:SOAP-ENC=\"http://www.w3.org/2003/05/soap-encoding\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xs=\"http://www.w3.org/2000/10/XMLSchema\" xmlns:wsse=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd\" xmlns:wsa5=\"http://www.w3.org/2005/08/addressing\" xmlns:xop=\"http://www.w3.org/2004/08/xop/include\" xmlns:wsa=\"http://schemas.xmlsoap.org/ws/2004/08/addressing\" xmlns:tt=\"http://www.onvif.org/ver10/schema\" xmlns:ns1=\"http://www.w3.org/2005/05/xmlmime\" xmlns:wstop=\"http://docs.oasis-open.org/wsn/t-1\" xmlns:ns7=\"http://docs.oasis-open.org/wsrf/r-2\" xmlns:ns2=\"http://docs.oasis-open.org/wsrf/bf-2\" xmlns:dndl=\"http://www.onvif.org/ver10/network/wsdl/DiscoveryLookupBinding\" xmlns:dnrd=\"http://www.onvif.org/ver10/network/wsdl/RemoteDiscoveryBinding\" xmlns:d=\"http://schemas.xmlsoap.org/ws/2005/04/discovery\" xmlns:dn=\"http://www.onvif.org/ver10/network/wsdl\" xmlns:ns10=\"http://www.onvif.org/ver10/replay/wsdl\" xmlns:ns11=\"http://www.onvif.org/ver10/search/wsdl\" xmlns:ns13=\"http://www.onvif.org/ver20/analytics/wsdl/RuleEngineBinding\" xmlns:ns14=\"http://www.onvif.org/ver20/analytics/wsdl/AnalyticsEngineBinding\" xmlns:tan=\"http://www.onvif.org/ver20/analytics/wsdl\" xmlns:ns15=\"http://www.onvif.org/ver10/events/wsdl/PullPointSubscriptionBinding\" xmlns:ns16=\"http://www.onvif.org/ver10/events/wsdl/EventBinding\" xmlns:tev=\"http://www.onvif.org/ver10/events/wsdl\" xmlns:ns17=\"http://www.onvif.org/ver10/events/wsdl/SubscriptionManagerBinding\" xmlns:ns18=\"http://www.onvif.org/ver10/events/wsdl/NotificationProducerBinding\" xmlns:ns19=\"http://www.onvif.org/ver10/events/wsdl/NotificationConsumerBinding\" xmlns:ns20=\"http://www.onvif.org/ver10/events/wsdl/PullPointBinding\" xmlns:ns21=\"http://www.onvif.org/ver10/events/wsdl/CreatePullPointBinding\" xmlns:ns22=\"http://www.onvif.org/ver10/events/wsdl/PausableSubscriptionManagerBinding\" xmlns:wsnt=\"http://docs.oasis-open.org/wsn/b-2\" xmlns:ns3=\"http://www.onvif.org/ver10/analyticsdevice/wsdl\" xmlns:ns4=\"http://www.onvif.org/ver10/deviceIO/wsdl\" xmlns:ns5=\"http://www.onvif.org/ver10/display/wsdl\" xmlns:ns8=\"http://www.onvif.org/ver10/receiver/wsdl\" xmlns:ns9=\"http://www.onvif.org/ver10/recording/wsdl\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\" xmlns:timg=\"http://www.onvif.org/ver20/imaging/wsdl\" xmlns:tptz=\"http://www.onvif.org/ver20/ptz/wsdl\" xmlns:trt=\"http://www.onvif.org/ver10/media/wsdl\" xmlns:trt2=\"http://www.onvif.org/ver20/media/wsdl\" xmlns:ter=\"http://www.onvif.org/ver10/error\" xmlns:tns1=\"http://www.onvif.org/ver10/topics\" xmlns:tnsn=\"http://www.eventextension.com/2011/event/topics\"><SOAP-ENV:Header><wsa:MessageID>urn:uuid:2bc6f06c-5566-7788-99ac-0012414fb745</wsa:MessageID><wsa:RelatesTo>uuid:7b1d26aa-b02e-4ad2-8aab-4c928298ee0c</wsa:RelatesTo><wsa:To SOAP-ENV:mustUnderstand=\"true\">http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</wsa:To><wsa:Action SOAP-ENV:mustUnderstand=\"true\">http://schemas.xmlsoap.org/ws/2005/04/discovery/ProbeMatches</wsa:Action></SOAP-ENV:Header><SOAP-ENV:Body><d:ProbeMatches><d:ProbeMatch><wsa:EndpointReference><wsa:Address>urn:uuid:10919da4-5566-7788-99aa-0012414fb745</wsa:Address></wsa:EndpointReference><d:Types>dn:NetworkVideoTransmitter</d:Types><d:Scopes>onvif://www.onvif.org/type/video_encoder onvif://www.onvif.org/type/audio_encoder onvif://www.onvif.org/hardware/IPC-model onvif://www.onvif.org/location/country/china onvif://www.onvif.org/name/NVT onvif://www.onvif.org/Profile/Streaming </d:Scopes><d:XAddrs>{}</d:XAddrs><d:MetadataVersion>10</d:MetadataVersion></d:ProbeMatch></d:ProbeMatches></SOAP-ENV:Body></SOAP-ENV:Envelope>",
-                &uris.join(" ")
-            );
+            let response = create_discovery_response(&uris.join(" "));
             assert_eq!(uris, get_device_uris_from_discovery_response(&response));
         }
     }
 
-    pub async fn simple_onvif_discover(timeout: Duration) -> Result<Vec<String>, anyhow::Error> {
+    /// Collects the device uris in WS-Discovery responses received on `socket` into `devices` until either
+    /// `max_cameras` uris have been collected or a read times out after cancellation was signalled
+    fn receive_discovery_responses(
+        socket: &UdpSocket,
+        devices: &Mutex<Vec<String>>,
+        max_cameras: Option<usize>,
+        discovery_cancel_rx: &mut mpsc::Receiver<()>,
+    ) {
+        loop {
+            let mut buf = vec![0; 16 * 1024];
+            match socket.recv_from(&mut buf) {
+                Ok((len, _)) => {
+                    let broadcast_response_as_string =
+                        String::from_utf8_lossy(&buf[..len]).to_string();
+                    trace!(
+                        "receive_discovery_responses - response: {:?}",
+                        broadcast_response_as_string
+                    );
+
+                    let mut devices = devices.lock().unwrap();
+                    get_device_uris_from_discovery_response(&broadcast_response_as_string)
+                        .iter()
+                        .for_each(|device_uri| {
+                            trace!(
+                                "receive_discovery_responses - device_uri parsed from response: {:?}",
+                                device_uri
+                            );
+                            devices.push(device_uri.to_string());
+                        });
+                    trace!("receive_discovery_responses - devices: {:?}", devices);
+                    if let Some(max_cameras) = max_cameras {
+                        if devices.len() >= max_cameras {
+                            devices.truncate(max_cameras);
+                            info!(
+                                "receive_discovery_responses - reached maximum of {} cameras ... ignoring further responses",
+                                max_cameras
+                            );
+                            return;
+                        }
+                    }
+                }
+                Err(e) => match e.kind() {
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                        match discovery_cancel_rx.try_recv() {
+                            Err(TryRecvError::Closed) | Ok(_) => {
+                                trace!("receive_discovery_responses - recv_from error ... timeout signalled/disconnected (stop collecting responses): {:?}", e);
+                                return;
+                            }
+                            Err(TryRecvError::Empty) => {
+                                trace!("receive_discovery_responses - recv_from error ... no timeout (continue collecting responses): {:?}", e);
+                                // continue looping
+                            }
+                        }
+                    }
+                    e => {
+                        error!("receive_discovery_responses - recv_from error: {:?}", e);
+                        Err(e).unwrap()
+                    }
+                },
+            }
+        }
+    }
+
+    /// Selects the local address to send the WS-Discovery probe from, given the name or IPv4 address of a
+    /// network interface and the IPv4 addresses of this host's interfaces (as interface name and address).
+    /// Without a network interface, the unspecified address is used so that the operating system picks one.
+    fn select_probe_address(
+        network_interface: Option<&str>,
+        local_interfaces: &[(String, Ipv4Addr)],
+    ) -> Result<Ipv4Addr, anyhow::Error> {
+        let network_interface = match network_interface {
+            Some(network_interface) => network_interface,
+            None => return Ok(Ipv4Addr::UNSPECIFIED),
+        };
+        let selected = match network_interface.parse::<Ipv4Addr>() {
+            Ok(address) => local_interfaces
+                .iter()
+                .find(|(_, local_address)| *local_address == address),
+            Err(_) => local_interfaces
+                .iter()
+                .find(|(name, _)| name == network_interface),
+        };
+        match selected {
+            Some((_, address)) => Ok(*address),
+            None => Err(anyhow::format_err!(
+                "invalid networkInterface {} ... no local network interface has that name or IPv4 address",
+                network_interface
+            )),
+        }
+    }
+
+    #[cfg(test)]
+    mod probe_address_tests {
+        use super::*;
+
+        #[test]
+        fn test_select_probe_address() {
+            let local_interfaces = vec![
+                ("lo".to_string(), Ipv4Addr::new(127, 0, 0, 1)),
+                ("eth0".to_string(), Ipv4Addr::new(10, 0, 0, 5)),
+                ("eth1".to_string(), Ipv4Addr::new(192, 168, 1, 5)),
+            ];
+            assert_eq!(
+                Ipv4Addr::UNSPECIFIED,
+                select_probe_address(None, &local_interfaces).unwrap()
+            );
+            assert_eq!(
+                Ipv4Addr::new(192, 168, 1, 5),
+                select_probe_address(Some("eth1"), &local_interfaces).unwrap()
+            );
+            assert_eq!(
+                Ipv4Addr::new(10, 0, 0, 5),
+                select_probe_address(Some("10.0.0.5"), &local_interfaces).unwrap()
+            );
+            assert!(select_probe_address(Some("wlan0"), &local_interfaces).is_err());
+            assert!(select_probe_address(Some("10.0.0.6"), &local_interfaces).is_err());
+        }
+    }
+
+    /// Returns the name and IPv4 address of each of this host's network interfaces
+    fn get_local_ipv4_interfaces() -> Result<Vec<(String, Ipv4Addr)>, anyhow::Error> {
+        Ok(get_if_addrs::get_if_addrs()?
+            .into_iter()
+            .filter_map(|interface| match interface.ip() {
+                IpAddr::V4(address) => Some((interface.name, address)),
+                IpAddr::V6(_) => None,
+            })
+            .collect())
+    }
+
+    /// Sends a WS-Discovery probe, after waiting `probe_delay`, and returns the uris of the cameras that respond
+    /// within `timeout`, up to `max_cameras` of them. The probe is sent from `network_interface`, if given,
+    /// with a time to live of `multicast_ttl`, if given.
+    pub async fn simple_onvif_discover(
+        timeout: Duration,
+        max_cameras: Option<usize>,
+        probe_delay: Duration,
+        network_interface: Option<&str>,
+        multicast_ttl: Option<u32>,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let probe_address = match network_interface {
+            Some(_) => select_probe_address(network_interface, &get_local_ipv4_interfaces()?)?,
+            None => Ipv4Addr::UNSPECIFIED,
+        };
+        if probe_delay > Duration::from_millis(0) {
+            trace!(
+                "simple_onvif_discover - waiting {:?} before probing",
+                probe_delay
+            );
+            time::delay_for(probe_delay).await;
+        }
         let (mut discovery_timeout_tx, mut discovery_timeout_rx) = mpsc::channel(2);
         let (mut discovery_cancel_tx, mut discovery_cancel_rx) = mpsc::channel(2);
         let shared_devices = Arc::new(Mutex::new(Vec::new()));
@@ -240,9 +396,9 @@ pub mod util {
                 &uuid_str
             );
 
-            const LOCAL_IPV4_ADDR: Ipv4Addr = Ipv4Addr::UNSPECIFIED;
+            // Binding to the address of a specific interface makes the probe go out of that interface
             const LOCAL_PORT: u16 = 0;
-            let local_socket_addr = SocketAddr::new(IpAddr::V4(LOCAL_IPV4_ADDR), LOCAL_PORT);
+            let local_socket_addr = SocketAddr::new(IpAddr::V4(probe_address), LOCAL_PORT);
 
             // WS-Discovery multicast ip and port selected from available standard
             // options.  See https://en.wikipedia.org/wiki/WS-Discovery
@@ -261,66 +417,26 @@ pub mod util {
             socket
                 .set_read_timeout(Some(Duration::from_millis(200)))
                 .unwrap();
+            if let Some(multicast_ttl) = multicast_ttl {
+                socket.set_multicast_ttl_v4(multicast_ttl).unwrap();
+            }
             trace!(
                 "simple_onvif_discover - joining multicast: {:?} {:?}",
                 &MULTI_IPV4_ADDR,
-                &LOCAL_IPV4_ADDR
+                &probe_address
             );
             socket
-                .join_multicast_v4(&MULTI_IPV4_ADDR, &LOCAL_IPV4_ADDR)
+                .join_multicast_v4(&MULTI_IPV4_ADDR, &probe_address)
                 .unwrap();
 
             let envelope_as_string = create_onvif_discovery_message(&uuid_str);
             match socket.send_to(&envelope_as_string.as_bytes(), multi_socket_addr) {
-                Ok(_) => {
-                    loop {
-                        let mut buf = vec![0; 16 * 1024];
-                        match socket.recv_from(&mut buf) {
-                            Ok((len, _)) => {
-                                let broadcast_response_as_string =
-                                    String::from_utf8_lossy(&buf[..len]).to_string();
-                                trace!(
-                                    "simple_onvif_discover - response: {:?}",
-                                    broadcast_response_as_string
-                                );
-
-                                get_device_uris_from_discovery_response(
-                                    &broadcast_response_as_string,
-                                )
-                                .iter()
-                                .for_each(|device_uri| {
-                                    trace!(
-                                        "simple_onvif_discover - device_uri parsed from response: {:?}",
-                                        device_uri
-                                    );
-                                    thread_devices.lock().unwrap().push(device_uri.to_string());
-                                    trace!(
-                                        "simple_onvif_discover - thread_devices: {:?}",
-                                        thread_devices.lock().unwrap()
-                                    );
-                                });
-                            }
-                            Err(e) => match e.kind() {
-                                ErrorKind::WouldBlock | ErrorKind::TimedOut => {
-                                    match discovery_cancel_rx.try_recv() {
-                                        Err(TryRecvError::Closed) | Ok(_) => {
-                                            trace!("simple_onvif_discover - recv_from error ... timeout signalled/disconnected (stop collecting responses): {:?}", e);
-                                            break;
-                                        }
-                                        Err(TryRecvError::Empty) => {
-                                            trace!("simple_onvif_discover - recv_from error ... no timeout (continue collecting responses): {:?}", e);
-                                            // continue looping
-                                        }
-                                    }
-                                }
-                                e => {
-                                    error!("simple_onvif_discover - recv_from error: {:?}", e);
-                                    Err(e).unwrap()
-                                }
-                            },
-                        }
-                    }
-                }
+                Ok(_) => receive_discovery_responses(
+                    &socket,
+                    &thread_devices,
+                    max_cameras,
+                    &mut discovery_cancel_rx,
+                ),
                 Err(e) => match e.kind() {
                     ErrorKind::WouldBlock | ErrorKind::TimedOut => {
                         trace!("simple_onvif_discover - send_to timeout: {:?}", e);
@@ -363,6 +479,53 @@ pub mod util {
             time::{Duration, SystemTime},
         };
 
+        /// Returns a socket that a mock camera socket has sent one WS-Discovery response to for each of `camera_count` cameras
+        fn create_socket_with_responses(camera_count: usize) -> UdpSocket {
+            let agent_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            agent_socket
+                .set_read_timeout(Some(Duration::from_millis(200)))
+                .unwrap();
+            let camera_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            for i in 0..camera_count {
+                let response = create_discovery_response(&format!("http://10.0.0.{}:5357/svc", i));
+                camera_socket
+                    .send_to(response.as_bytes(), agent_socket.local_addr().unwrap())
+                    .unwrap();
+            }
+            agent_socket
+        }
+
+        #[test]
+        fn test_receive_discovery_responses() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            let agent_socket = create_socket_with_responses(5);
+            let devices = Mutex::new(Vec::new());
+            let (mut discovery_cancel_tx, mut discovery_cancel_rx) = mpsc::channel(2);
+            discovery_cancel_tx.try_send(()).unwrap();
+            receive_discovery_responses(&agent_socket, &devices, None, &mut discovery_cancel_rx);
+            assert_eq!(5, devices.lock().unwrap().len());
+        }
+
+        #[test]
+        fn test_receive_discovery_responses_max_cameras() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            // Without being cancelled, collecting responses stops once the maximum is reached
+            let agent_socket = create_socket_with_responses(5);
+            let devices = Mutex::new(Vec::new());
+            let (_discovery_cancel_tx, mut discovery_cancel_rx) = mpsc::channel(2);
+            receive_discovery_responses(&agent_socket, &devices, Some(3), &mut discovery_cancel_rx);
+            assert_eq!(
+                vec![
+                    "http://10.0.0.0:5357/svc".to_string(),
+                    "http://10.0.0.1:5357/svc".to_string(),
+                    "http://10.0.0.2:5357/svc".to_string()
+                ],
+                *devices.lock().unwrap()
+            );
+        }
+
         #[tokio::test(core_threads = 2)]
         async fn test_timeout_for_simple_onvif_discover() {
             let _ = env_logger::builder().is_test(true).try_init();
@@ -373,7 +536,10 @@ pub mod util {
             let thread_duration = duration.clone();
             tokio::spawn(async move {
                 let start = SystemTime::now();
-                let _ignore = simple_onvif_discover(timeout).await.unwrap();
+                let _ignore =
+                    simple_onvif_discover(timeout, None, Duration::from_millis(0), None, None)
+                        .await
+                        .unwrap();
                 let end = SystemTime::now();
                 let mut inner_duration = thread_duration.lock().unwrap();
                 *inner_duration = end.duration_since(start).unwrap();
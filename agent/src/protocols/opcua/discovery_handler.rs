@@ -1,20 +1,34 @@
-use super::super::{DiscoveryHandler, DiscoveryResult};
-use super::{discovery_impl::do_standard_discovery, OPCUA_DISCOVERY_URL_LABEL};
+use super::super::{validate_discovery_interval, DiscoveryHandler, DiscoveryResult};
+use super::{
+    discovery_impl::{do_get_server_status_properties, do_standard_discovery},
+    opcua_client_wrapper::{get_client_credentials, ClientCredentials},
+    OPCUA_DISCOVERY_URL_LABEL,
+};
 use akri_shared::akri::configuration::{OpcuaDiscoveryHandlerConfig, OpcuaDiscoveryMethod};
+use akri_shared::log_fields;
 use anyhow::Error;
 use async_trait::async_trait;
+use log::Level;
+use std::time::Duration;
 
 /// `OpcuaDiscoveryHandler` discovers the OPC UA server instances as described by the `discovery_handler_config.opcua_discovery_method`
 /// and the filter `discover_handler_config.application_names`. The instances it discovers are always shared.
 #[derive(Debug)]
 pub struct OpcuaDiscoveryHandler {
     discovery_handler_config: OpcuaDiscoveryHandlerConfig,
+    /// Credentials the discovery clients authenticate with, if configured and loadable
+    client_credentials: Option<ClientCredentials>,
 }
 
 impl OpcuaDiscoveryHandler {
     pub fn new(discovery_handler_config: &OpcuaDiscoveryHandlerConfig) -> Self {
+        let client_credentials = get_client_credentials(
+            discovery_handler_config.certificate_path.as_deref(),
+            discovery_handler_config.private_key_path.as_deref(),
+        );
         OpcuaDiscoveryHandler {
             discovery_handler_config: discovery_handler_config.clone(),
+            client_credentials,
         }
     }
 }
@@ -22,30 +36,65 @@ impl OpcuaDiscoveryHandler {
 #[async_trait]
 impl DiscoveryHandler for OpcuaDiscoveryHandler {
     async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error> {
-        let discovery_urls: Vec<String> =
+        let (discovery_urls, discovery_url_timeout) =
             match &self.discovery_handler_config.opcua_discovery_method {
-                OpcuaDiscoveryMethod::standard(standard_opcua_discovery) => do_standard_discovery(
-                    standard_opcua_discovery.discovery_urls.clone(),
-                    self.discovery_handler_config.application_names.clone(),
-                ),
-                // No other discovery methods implemented yet
+                OpcuaDiscoveryMethod::standard(standard_opcua_discovery) => {
+                    let discovery_url_timeout = Duration::from_secs(
+                        standard_opcua_discovery
+                            .discovery_url_timeout_seconds
+                            .max(0) as u64,
+                    );
+                    let discovery_urls = do_standard_discovery(
+                        standard_opcua_discovery.discovery_urls.clone(),
+                        self.discovery_handler_config.application_names.clone(),
+                        discovery_url_timeout,
+                        self.client_credentials.clone(),
+                    )
+                    .await;
+                    (discovery_urls, discovery_url_timeout)
+                } // No other discovery methods implemented yet
             };
 
+        // Reading each server's status takes another round-trip, so only do so if asked
+        let mut server_status_properties = if self.discovery_handler_config.include_server_status {
+            do_get_server_status_properties(
+                discovery_urls.clone(),
+                discovery_url_timeout,
+                self.client_credentials.clone(),
+            )
+            .await
+        } else {
+            std::collections::HashMap::new()
+        };
+
         // Build DiscoveryResult for each server discovered
         Ok(discovery_urls
             .into_iter()
             .map(|discovery_url| {
-                let mut properties = std::collections::HashMap::new();
-                trace!(
+                let mut properties = server_status_properties
+                    .remove(&discovery_url)
+                    .unwrap_or_default();
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "opcua", endpoint: discovery_url },
                     "discover - found OPC UA server at DiscoveryURL {}",
                     discovery_url
                 );
                 properties.insert(OPCUA_DISCOVERY_URL_LABEL.to_string(), discovery_url.clone());
+                let capacity = self
+                    .discovery_handler_config
+                    .server_capacities
+                    .get(&discovery_url)
+                    .copied();
                 DiscoveryResult::new(&discovery_url, properties, self.are_shared().unwrap())
+                    .with_capacity(capacity)
             })
             .collect::<Vec<DiscoveryResult>>())
     }
     fn are_shared(&self) -> Result<bool, Error> {
         Ok(true)
     }
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        validate_discovery_interval(self.discovery_handler_config.discovery_interval_seconds)
+    }
 }
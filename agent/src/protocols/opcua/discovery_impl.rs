@@ -1,12 +1,21 @@
-use super::opcua_client_wrapper::{create_opcua_discovery_client, OpcuaClient};
+use super::opcua_client_wrapper::{
+    create_opcua_discovery_client, ClientCredentials, OpcuaClient, ServerStatus,
+};
 use super::tcp_stream_wrapper::{TcpStream, TcpStreamImpl};
+use super::{
+    OPCUA_BUILD_NUMBER_LABEL, OPCUA_MANUFACTURER_NAME_LABEL, OPCUA_PRODUCT_NAME_LABEL,
+    OPCUA_PRODUCT_URI_LABEL,
+};
 use ::url::Url;
 use akri_shared::akri::configuration::{should_include, FilterList};
 use opcua_client::prelude::*;
 use std::{
+    collections::HashMap,
     net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
     time::Duration,
 };
+use tokio::time::timeout;
 
 /// Timeout for testing TCP connection to OPC UA Server or LDS DiscoveryEndpoint
 /// Used when testing TCP connection before calling FindServers on the endpoint
@@ -20,77 +29,209 @@ const TCP_CONNECTION_TEST_TIMEOUT_SECS: u64 = 3;
 /// A DiscoveryServer is "an Application that maintains a list of OPC UA Servers that are available on the network and
 /// provides mechanisms for Clients to obtain this list" (OPC UA Specification 12). A LocalDiscoveryServer is an implementation
 /// of an OPC UA DiscoveryServer.
-/// `do_standard_discovery` creates an OPC UA Discovery Client and calls get_discovery_urls, passing in the DiscoveryURLs provided
-/// in the OPC UA Configuration.
-pub fn do_standard_discovery(
+/// `do_standard_discovery` calls get_discovery_urls, passing in the DiscoveryURLs provided in the OPC UA Configuration
+/// and creating an OPC UA Discovery Client for each of them. The Clients authenticate with `client_credentials` if provided.
+pub async fn do_standard_discovery(
     discovery_urls: Vec<String>,
     filter_list: Option<FilterList>,
+    discovery_url_timeout: Duration,
+    client_credentials: Option<ClientCredentials>,
 ) -> Vec<String> {
     trace!(
         "do_standard_discovery - for DiscoveryUrls {:?}",
         discovery_urls
     );
-    let mut discovery_client = create_opcua_discovery_client();
-    let tcp_stream = TcpStreamImpl {};
     get_discovery_urls(
-        &mut discovery_client,
+        Arc::new(move |_: &str| create_opcua_discovery_client(client_credentials.as_ref())),
         discovery_urls,
         filter_list,
-        tcp_stream,
+        Arc::new(TcpStreamImpl {}),
+        discovery_url_timeout,
+    )
+    .await
+}
+
+/// Reads the ServerStatus of each discovered Server, returning its build information as properties keyed by DiscoveryURL.
+/// The Clients authenticate with `client_credentials` if provided.
+pub async fn do_get_server_status_properties(
+    discovery_urls: Vec<String>,
+    discovery_url_timeout: Duration,
+    client_credentials: Option<ClientCredentials>,
+) -> HashMap<String, HashMap<String, String>> {
+    get_server_status_properties(
+        Arc::new(move |_: &str| create_opcua_discovery_client(client_credentials.as_ref())),
+        discovery_urls,
+        discovery_url_timeout,
     )
+    .await
+}
+
+/// Reads the ServerStatus of each Server concurrently, each with its own client. A Server whose status cannot be read
+/// within `discovery_url_timeout` is left out, so that it is still discovered, just without its build information.
+async fn get_server_status_properties<C: OpcuaClient + 'static>(
+    create_client: Arc<dyn Fn(&str) -> C + Send + Sync>,
+    discovery_urls: Vec<String>,
+    discovery_url_timeout: Duration,
+) -> HashMap<String, HashMap<String, String>> {
+    let queries = discovery_urls.into_iter().map(|url| {
+        let create_client = create_client.clone();
+        async move {
+            let query_url = url.clone();
+            // Reading the ServerStatus blocks, so query each Server on its own thread
+            let query = tokio::task::spawn_blocking(move || {
+                create_client(&query_url).get_server_status(&query_url)
+            });
+            match timeout(discovery_url_timeout, query).await {
+                Ok(Ok(Ok(server_status))) => {
+                    Some((url, server_status_to_properties(server_status)))
+                }
+                Ok(Ok(Err(e))) => {
+                    trace!(
+                        "get_server_status_properties - failed to read ServerStatus of {} with error {:?}",
+                        url,
+                        e
+                    );
+                    None
+                }
+                Ok(Err(e)) => {
+                    trace!(
+                        "get_server_status_properties - read of ServerStatus of {} did not complete: {:?}",
+                        url,
+                        e
+                    );
+                    None
+                }
+                Err(_) => {
+                    trace!(
+                        "get_server_status_properties - Server {} did not respond within {:?}",
+                        url,
+                        discovery_url_timeout
+                    );
+                    None
+                }
+            }
+        }
+    });
+    futures::future::join_all(queries)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn server_status_to_properties(server_status: ServerStatus) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    properties.insert(
+        OPCUA_PRODUCT_URI_LABEL.to_string(),
+        server_status.product_uri,
+    );
+    properties.insert(
+        OPCUA_MANUFACTURER_NAME_LABEL.to_string(),
+        server_status.manufacturer_name,
+    );
+    properties.insert(
+        OPCUA_PRODUCT_NAME_LABEL.to_string(),
+        server_status.product_name,
+    );
+    properties.insert(
+        OPCUA_BUILD_NUMBER_LABEL.to_string(),
+        server_status.build_number,
+    );
+    properties
 }
 
 /// This calls FindServers on each DiscoveryURL provided in order to
 /// (1) verify the DiscoveryURL
 /// (2) discover other servers registered with a Local Discovery Server in the case that the DiscoveryURL is for an LDS
 /// (3) determine whether the application at that URL should be included according to `ApplicationType` and the `application_names` filter
-fn get_discovery_urls(
-    discovery_client: &mut impl OpcuaClient,
+/// The DiscoveryURLs are queried concurrently, each with its own client. A DiscoveryURL that errors or does not respond
+/// within `discovery_url_timeout` is skipped, so one unreachable LDS does not hold up discovery of the others.
+async fn get_discovery_urls<C: OpcuaClient + 'static>(
+    create_discovery_client: Arc<dyn Fn(&str) -> C + Send + Sync>,
     lds_urls: Vec<String>,
     filter_list: Option<FilterList>,
-    tcp_stream: impl TcpStream,
+    tcp_stream: Arc<impl TcpStream + Send + Sync + 'static>,
+    discovery_url_timeout: Duration,
 ) -> Vec<String> {
-    let mut discovery_urls: Vec<String> = Vec::new();
-    lds_urls.iter().for_each(|url| {
-        if let Err(e) = test_tcp_connection(url, &tcp_stream) {
-            trace!(
-                "get_discovery_urls - failed to make tcp connection with url {} with error {:?}",
-                url,
-                e
-            );
-        } else {
-            match discovery_client.find_servers(url) {
-                Ok(applications) => {
+    let queries = lds_urls.into_iter().map(|url| {
+        let create_discovery_client = create_discovery_client.clone();
+        let filter_list = filter_list.clone();
+        let tcp_stream = tcp_stream.clone();
+        async move {
+            let query_url = url.clone();
+            // FindServers blocks, so query each DiscoveryURL on its own thread
+            let query = tokio::task::spawn_blocking(move || {
+                let mut discovery_client = create_discovery_client(&query_url);
+                find_discovery_urls(
+                    &mut discovery_client,
+                    &query_url,
+                    filter_list.as_ref(),
+                    tcp_stream.as_ref(),
+                )
+            });
+            match timeout(discovery_url_timeout, query).await {
+                Ok(Ok(Ok(servers_discovery_urls))) => servers_discovery_urls,
+                Ok(Ok(Err(e))) => {
                     trace!(
-                        "get_discovery_urls - Server at {} responded with {} Applications",
+                        "get_discovery_urls - failed to find servers at DiscoveryURL {} with error {:?}",
                         url,
-                        applications.len()
+                        e
                     );
-                    let mut servers_discovery_urls: Vec<String> = applications
-                        .iter()
-                        .filter_map(|application| {
-                            get_discovery_url_from_application_description(
-                                application,
-                                filter_list.as_ref(),
-                            )
-                        })
-                        .collect::<Vec<String>>();
-                    discovery_urls.append(&mut servers_discovery_urls);
+                    Vec::new()
                 }
-                Err(err) => {
+                Ok(Err(e)) => {
                     trace!(
-                        "get_discovery_urls - cannot find servers on discovery server. Error {:?}",
-                        err
+                        "get_discovery_urls - query of DiscoveryURL {} did not complete: {:?}",
+                        url,
+                        e
                     );
+                    Vec::new()
                 }
-            };
+                Err(_) => {
+                    trace!(
+                        "get_discovery_urls - DiscoveryURL {} did not respond within {:?}",
+                        url,
+                        discovery_url_timeout
+                    );
+                    Vec::new()
+                }
+            }
         }
     });
+    let mut discovery_urls: Vec<String> = futures::future::join_all(queries)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
     // Remove duplicates in the case that a server was registered with more than one LDS
     discovery_urls.dedup();
     discovery_urls
 }
 
+/// Calls FindServers on a single DiscoveryURL, returning the DiscoveryURLs of the servers that should be included
+fn find_discovery_urls(
+    discovery_client: &mut impl OpcuaClient,
+    url: &str,
+    filter_list: Option<&FilterList>,
+    tcp_stream: &impl TcpStream,
+) -> Result<Vec<String>, anyhow::Error> {
+    test_tcp_connection(url, tcp_stream)?;
+    let applications = discovery_client
+        .find_servers(url)
+        .map_err(|e| anyhow::format_err!("cannot find servers on discovery server: {:?}", e))?;
+    trace!(
+        "find_discovery_urls - Server at {} responded with {} Applications",
+        url,
+        applications.len()
+    );
+    Ok(applications
+        .iter()
+        .filter_map(|application| {
+            get_discovery_url_from_application_description(application, filter_list)
+        })
+        .collect::<Vec<String>>())
+}
+
 /// The Rust OPC UA implementation of FindServers does not use a timeout when connecting with a Server over TCP
 /// So, an unsuccessful attempt can take over 2 minutes.
 /// Therefore, this tests the connection using a timeout before calling FindServers on the DiscoveryURL.
@@ -183,7 +324,10 @@ mod tests {
     use super::super::opcua_client_wrapper::MockOpcuaClient;
     use super::super::tcp_stream_wrapper::MockTcpStream;
     use super::*;
-    use mockall::Sequence;
+    use akri_shared::akri::configuration::{FilterType, MatchType};
+    use std::sync::Mutex;
+
+    const DISCOVERY_URL_TIMEOUT: Duration = Duration::from_secs(5);
 
     pub fn create_application_description(
         application_uri: &str,
@@ -202,46 +346,62 @@ mod tests {
         }
     }
 
-    fn set_up_mock_tcp_stream(
-        discovery_url: &'static str,
-        discovery_url2: &'static str,
-    ) -> MockTcpStream {
+    fn set_up_mock_tcp_stream(discovery_urls: Vec<&'static str>) -> MockTcpStream {
         let mut mock_tcp_stream = MockTcpStream::new();
-        let mut tcp_stream_seq = Sequence::new();
         let tcp_timeout_duration = Duration::from_secs(TCP_CONNECTION_TEST_TIMEOUT_SECS);
-        let discovery_url_socket_addr = get_socket_addr(discovery_url).unwrap();
+        for discovery_url in discovery_urls {
+            let discovery_url_socket_addr = get_socket_addr(discovery_url).unwrap();
+            mock_tcp_stream
+                .expect_connect_timeout()
+                .times(1)
+                .withf(move |addr: &SocketAddr, timeout: &Duration| {
+                    addr == &discovery_url_socket_addr && timeout == &tcp_timeout_duration
+                })
+                .returning(|_, _| Ok(()));
+        }
         mock_tcp_stream
-            .expect_connect_timeout()
-            .times(1)
-            .withf(move |addr: &SocketAddr, timeout: &Duration| {
-                addr == &discovery_url_socket_addr && timeout == &tcp_timeout_duration
-            })
-            .return_once(move |_, _| Ok(()))
-            .in_sequence(&mut tcp_stream_seq);
+    }
 
-        let discovery_url_socket_addr2 = get_socket_addr(discovery_url2).unwrap();
-        mock_tcp_stream
-            .expect_connect_timeout()
+    /// Creates a client that expects FindServers to be called once on `url`
+    fn set_up_mock_client(
+        url: &'static str,
+        result: Result<Vec<ApplicationDescription>, StatusCode>,
+    ) -> (&'static str, MockOpcuaClient) {
+        let mut mock_client = MockOpcuaClient::new();
+        mock_client
+            .expect_find_servers()
             .times(1)
-            .withf(move |addr: &SocketAddr, timeout: &Duration| {
-                addr == &discovery_url_socket_addr2 && timeout == &tcp_timeout_duration
-            })
-            .return_once(move |_, _| Ok(()))
-            .in_sequence(&mut tcp_stream_seq);
+            .withf(move |actual_url: &str| actual_url == url)
+            .return_once(move |_| result);
+        (url, mock_client)
+    }
 
-        mock_tcp_stream
+    /// Hands out each DiscoveryURL's mock client, or a client that expects no calls if there is none
+    fn mock_client_factory(
+        mock_clients: Vec<(&'static str, MockOpcuaClient)>,
+    ) -> Arc<dyn Fn(&str) -> MockOpcuaClient + Send + Sync> {
+        let mock_clients: Mutex<HashMap<String, MockOpcuaClient>> = Mutex::new(
+            mock_clients
+                .into_iter()
+                .map(|(url, mock_client)| (url.to_string(), mock_client))
+                .collect(),
+        );
+        Arc::new(move |url: &str| {
+            mock_clients
+                .lock()
+                .unwrap()
+                .remove(url)
+                .unwrap_or_else(MockOpcuaClient::new)
+        })
     }
 
-    #[test]
-    fn test_get_discovery_urls() {
+    #[tokio::test]
+    async fn test_get_discovery_urls() {
         let lds_url = "opc.tcp://127.0.0.1:4840/";
         let lds_url2 = "opc.tcp://10.0.0.1:4840/";
         let discovery_url = "opc.tcp://127.0.0.1:4855/";
         let discovery_url2 = "opc.tcp://127.0.0.1:4866/";
-        let mut mock_client = MockOpcuaClient::new();
-        let mut find_servers_seq = Sequence::new();
-
-        let mock_tcp_stream = set_up_mock_tcp_stream(lds_url, lds_url2);
+        let mock_tcp_stream = set_up_mock_tcp_stream(vec![lds_url, lds_url2]);
 
         let server_application_description = create_application_description(
             "urn:Mock OPC UA Server",
@@ -255,38 +415,28 @@ mod tests {
             ApplicationType::Server,
             discovery_url2,
         );
-
-        mock_client
-            .expect_find_servers()
-            .times(1)
-            .withf(move |url: &str| url == lds_url)
-            .return_once(move |_| Ok(vec![server_application_description]))
-            .in_sequence(&mut find_servers_seq);
-
-        mock_client
-            .expect_find_servers()
-            .times(1)
-            .withf(move |url: &str| url == lds_url2)
-            .return_once(move |_| Ok(vec![server_application_description2]))
-            .in_sequence(&mut find_servers_seq);
+        let create_discovery_client = mock_client_factory(vec![
+            set_up_mock_client(lds_url, Ok(vec![server_application_description])),
+            set_up_mock_client(lds_url2, Ok(vec![server_application_description2])),
+        ]);
 
         let discovery_urls = get_discovery_urls(
-            &mut mock_client,
+            create_discovery_client,
             vec![lds_url.to_string(), lds_url2.to_string()],
             None,
-            mock_tcp_stream,
-        );
+            Arc::new(mock_tcp_stream),
+            DISCOVERY_URL_TIMEOUT,
+        )
+        .await;
         assert_eq!(discovery_urls.len(), 2);
         assert_eq!(&discovery_urls[0], discovery_url);
     }
 
-    #[test]
-    fn test_get_discovery_urls_server_not_found() {
+    #[tokio::test]
+    async fn test_get_discovery_urls_server_not_found() {
         let discovery_url = "opc.tcp://127.0.0.1:4855/";
         let discovery_url2 = "opc.tcp://127.0.0.1:4866/";
-        let mut mock_client = MockOpcuaClient::new();
-        let mut find_servers_seq = Sequence::new();
-        let mock_tcp_stream = set_up_mock_tcp_stream(discovery_url, discovery_url2);
+        let mock_tcp_stream = set_up_mock_tcp_stream(vec![discovery_url, discovery_url2]);
 
         let server_application_description2 = create_application_description(
             "urn:Mock OPC UA Server2",
@@ -294,39 +444,66 @@ mod tests {
             ApplicationType::Server,
             discovery_url2,
         );
+        let create_discovery_client = mock_client_factory(vec![
+            set_up_mock_client(discovery_url, Err(StatusCode::BadResourceUnavailable)),
+            set_up_mock_client(discovery_url2, Ok(vec![server_application_description2])),
+        ]);
 
-        mock_client
-            .expect_find_servers()
-            .times(1)
-            .withf(move |url: &str| url == discovery_url)
-            .return_once(move |_| Err(StatusCode::BadResourceUnavailable))
-            .in_sequence(&mut find_servers_seq);
+        let discovery_urls = get_discovery_urls(
+            create_discovery_client,
+            vec![discovery_url.to_string(), discovery_url2.to_string()],
+            None,
+            Arc::new(mock_tcp_stream),
+            DISCOVERY_URL_TIMEOUT,
+        )
+        .await;
+        assert_eq!(discovery_urls.len(), 1);
+        assert_eq!(&discovery_urls[0], discovery_url2);
+    }
 
-        mock_client
+    #[tokio::test]
+    async fn test_get_discovery_urls_skips_unresponsive_lds() {
+        let lds_url = "opc.tcp://127.0.0.1:4840/";
+        let unresponsive_lds_url = "opc.tcp://10.0.0.1:4840/";
+        let discovery_url = "opc.tcp://127.0.0.1:4855/";
+        let mock_tcp_stream = set_up_mock_tcp_stream(vec![lds_url, unresponsive_lds_url]);
+
+        let server_application_description = create_application_description(
+            "urn:Mock OPC UA Server",
+            "Mock OPC UA Server",
+            ApplicationType::Server,
+            discovery_url,
+        );
+        let mut unresponsive_client = MockOpcuaClient::new();
+        unresponsive_client
             .expect_find_servers()
             .times(1)
-            .withf(move |url: &str| url == discovery_url2)
-            .return_once(move |_| Ok(vec![server_application_description2]))
-            .in_sequence(&mut find_servers_seq);
+            .returning(|_| {
+                std::thread::sleep(Duration::from_millis(500));
+                Ok(Vec::new())
+            });
+        let create_discovery_client = mock_client_factory(vec![
+            set_up_mock_client(lds_url, Ok(vec![server_application_description])),
+            (unresponsive_lds_url, unresponsive_client),
+        ]);
 
         let discovery_urls = get_discovery_urls(
-            &mut mock_client,
-            vec![discovery_url.to_string(), discovery_url2.to_string()],
+            create_discovery_client,
+            vec![unresponsive_lds_url.to_string(), lds_url.to_string()],
             None,
-            mock_tcp_stream,
-        );
-        assert_eq!(discovery_urls.len(), 1);
-        assert_eq!(&discovery_urls[0], discovery_url2);
+            Arc::new(mock_tcp_stream),
+            Duration::from_millis(100),
+        )
+        .await;
+        assert_eq!(vec![discovery_url.to_string()], discovery_urls);
     }
 
-    #[test]
-    fn test_get_discovery_urls_removes_duplicates() {
+    #[tokio::test]
+    async fn test_get_discovery_urls_removes_duplicates() {
         let lds_url = "opc.tcp://127.0.0.1:4840/";
         let lds_url2 = "opc.tcp://10.0.0.1:4840/";
         let discovery_url = "opc.tcp://10.123.456.7:4855/";
-        let mut mock_client = MockOpcuaClient::new();
-        let mut find_servers_seq = Sequence::new();
-        let mock_tcp_stream = set_up_mock_tcp_stream(lds_url, lds_url2);
+        let mock_tcp_stream = set_up_mock_tcp_stream(vec![lds_url, lds_url2]);
 
         let server_application_description = create_application_description(
             "urn:Mock OPC UA Server",
@@ -340,51 +517,42 @@ mod tests {
             ApplicationType::Server,
             discovery_url,
         );
-
-        mock_client
-            .expect_find_servers()
-            .times(1)
-            .withf(move |url: &str| url == lds_url)
-            .return_once(move |_| Ok(vec![server_application_description]))
-            .in_sequence(&mut find_servers_seq);
-
-        mock_client
-            .expect_find_servers()
-            .times(1)
-            .withf(move |url: &str| url == lds_url2)
-            .return_once(move |_| Ok(vec![server_application_description2]))
-            .in_sequence(&mut find_servers_seq);
+        let create_discovery_client = mock_client_factory(vec![
+            set_up_mock_client(lds_url, Ok(vec![server_application_description])),
+            set_up_mock_client(lds_url2, Ok(vec![server_application_description2])),
+        ]);
 
         let discovery_urls = get_discovery_urls(
-            &mut mock_client,
+            create_discovery_client,
             vec![lds_url.to_string(), lds_url2.to_string()],
             None,
-            mock_tcp_stream,
-        );
+            Arc::new(mock_tcp_stream),
+            DISCOVERY_URL_TIMEOUT,
+        )
+        .await;
         assert_eq!(discovery_urls.len(), 1);
     }
 
-    #[test]
+    #[tokio::test]
     // Test that find servers isn't called on invalid DiscoveryURL (missing opc)
-    fn test_get_server_endpoints_invalid_url() {
-        let mut mock_client = MockOpcuaClient::new();
+    async fn test_get_server_endpoints_invalid_url() {
         let mock_tcp_stream = MockTcpStream::new();
         assert!(get_discovery_urls(
-            &mut mock_client,
+            mock_client_factory(Vec::new()),
             vec!["tcp://127.0.0.1:4855/".to_string()],
             None,
-            mock_tcp_stream
+            Arc::new(mock_tcp_stream),
+            DISCOVERY_URL_TIMEOUT,
         )
+        .await
         .is_empty())
     }
 
-    #[test]
+    #[tokio::test]
     // Test that it filters out DiscoveryServers
-    fn test_get_server_endpoints_filter_out_lds() {
+    async fn test_get_server_endpoints_filter_out_lds() {
         let discovery_url = "opc.tcp://127.0.0.1:4840/";
-        let mut mock_client = MockOpcuaClient::new();
-        let mut mock_tcp_stream = MockTcpStream::new();
-        let tcp_timeout_duration = Duration::from_secs(TCP_CONNECTION_TEST_TIMEOUT_SECS);
+        let mock_tcp_stream = set_up_mock_tcp_stream(vec![discovery_url]);
 
         let discovery_server_application_description = create_application_description(
             "urn:Mock OPC UA Server",
@@ -392,26 +560,106 @@ mod tests {
             ApplicationType::DiscoveryServer,
             discovery_url,
         );
-        mock_client
-            .expect_find_servers()
-            .times(1)
-            .withf(move |url: &str| url == discovery_url)
-            .return_once(move |_| Ok(vec![discovery_server_application_description]));
-        let discovery_url_socket_addr = get_socket_addr(discovery_url).unwrap();
-        mock_tcp_stream
-            .expect_connect_timeout()
-            .times(1)
-            .withf(move |addr: &SocketAddr, timeout: &Duration| {
-                addr == &discovery_url_socket_addr && timeout == &tcp_timeout_duration
-            })
-            .return_once(move |_, _| Ok(()));
+        let create_discovery_client = mock_client_factory(vec![set_up_mock_client(
+            discovery_url,
+            Ok(vec![discovery_server_application_description]),
+        )]);
 
         let discovery_urls = get_discovery_urls(
-            &mut mock_client,
+            create_discovery_client,
             vec![discovery_url.to_string()],
             None,
-            mock_tcp_stream,
-        );
+            Arc::new(mock_tcp_stream),
+            DISCOVERY_URL_TIMEOUT,
+        )
+        .await;
         assert!(discovery_urls.is_empty());
     }
+
+    #[test]
+    fn test_get_discovery_url_from_application_description_filters_application_names() {
+        let discovery_url = "opc.tcp://127.0.0.1:4840/";
+        let server = create_application_description(
+            "urn:Mock OPC UA Server",
+            "Mock OPC UA Server",
+            ApplicationType::Server,
+            discovery_url,
+        );
+        let filter_list = |action: FilterType, items: &[&str]| FilterList {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            action,
+            match_type: MatchType::Literal,
+        };
+        // (filter list, whether the server is discovered)
+        let cases = vec![
+            (None, true),
+            (Some(filter_list(FilterType::Include, &[])), false),
+            (Some(filter_list(FilterType::Exclude, &[])), true),
+            (
+                Some(filter_list(FilterType::Include, &["Mock OPC UA Server"])),
+                true,
+            ),
+            (Some(filter_list(FilterType::Include, &["Mock"])), false),
+            (
+                Some(filter_list(FilterType::Exclude, &["Mock OPC UA Server"])),
+                false,
+            ),
+            (
+                Some(filter_list(FilterType::Exclude, &["Other Server"])),
+                true,
+            ),
+        ];
+        for (filter_list, discovered) in cases {
+            assert_eq!(
+                discovered,
+                get_discovery_url_from_application_description(&server, filter_list.as_ref())
+                    .is_some(),
+                "filter list {:?}",
+                filter_list
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_server_status_properties() {
+        let discovery_url = "opc.tcp://127.0.0.1:4855/";
+        let discovery_url2 = "opc.tcp://127.0.0.1:4866/";
+        let mut mock_client = MockOpcuaClient::new();
+        mock_client
+            .expect_get_server_status()
+            .times(1)
+            .withf(move |actual_url: &str| actual_url == discovery_url)
+            .return_once(|_| {
+                Ok(ServerStatus {
+                    product_uri: "urn:acme:plc".to_string(),
+                    manufacturer_name: "Acme".to_string(),
+                    product_name: "Acme PLC".to_string(),
+                    build_number: "1.2.3".to_string(),
+                })
+            });
+        // A Server whose status cannot be read is left out
+        let mut mock_client2 = MockOpcuaClient::new();
+        mock_client2
+            .expect_get_server_status()
+            .times(1)
+            .return_once(|_| Err(StatusCode::BadNotConnected));
+        let create_client = mock_client_factory(vec![
+            (discovery_url, mock_client),
+            (discovery_url2, mock_client2),
+        ]);
+
+        let properties = get_server_status_properties(
+            create_client,
+            vec![discovery_url.to_string(), discovery_url2.to_string()],
+            DISCOVERY_URL_TIMEOUT,
+        )
+        .await;
+        assert_eq!(1, properties.len());
+        let server_properties = properties.get(discovery_url).unwrap();
+        assert_eq!(4, server_properties.len());
+        assert_eq!("urn:acme:plc", server_properties[OPCUA_PRODUCT_URI_LABEL]);
+        assert_eq!("Acme", server_properties[OPCUA_MANUFACTURER_NAME_LABEL]);
+        assert_eq!("Acme PLC", server_properties[OPCUA_PRODUCT_NAME_LABEL]);
+        assert_eq!("1.2.3", server_properties[OPCUA_BUILD_NUMBER_LABEL]);
+    }
 }
@@ -6,11 +6,18 @@ pub use self::discovery_handler::OpcuaDiscoveryHandler;
 /// Holds the DiscoveryURL for the OPC UA Server the broker is to connect to.
 pub const OPCUA_DISCOVERY_URL_LABEL: &str = "OPCUA_DISCOVERY_URL";
 
+/// Names of the properties holding a Server's build information, set when `includeServerStatus` is enabled
+pub const OPCUA_PRODUCT_URI_LABEL: &str = "AKRI_OPCUA_PRODUCT_URI";
+pub const OPCUA_MANUFACTURER_NAME_LABEL: &str = "AKRI_OPCUA_MANUFACTURER_NAME";
+pub const OPCUA_PRODUCT_NAME_LABEL: &str = "AKRI_OPCUA_PRODUCT_NAME";
+pub const OPCUA_BUILD_NUMBER_LABEL: &str = "AKRI_OPCUA_BUILD_NUMBER";
+
 /// Wrapper to enable mocking of OPC UA Client
 pub mod opcua_client_wrapper {
     use mockall::predicate::*;
     use mockall::*;
     use opcua_client::prelude::*;
+    use std::path::{Path, PathBuf};
 
     #[automock]
     pub trait OpcuaClient {
@@ -18,6 +25,63 @@ pub mod opcua_client_wrapper {
             &mut self,
             discovery_endpoint_url: &str,
         ) -> Result<Vec<ApplicationDescription>, StatusCode>;
+        fn get_server_status(&mut self, endpoint_url: &str) -> Result<ServerStatus, StatusCode>;
+    }
+
+    /// Build information a Server reports in its ServerStatus
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct ServerStatus {
+        pub product_uri: String,
+        pub manufacturer_name: String,
+        pub product_name: String,
+        pub build_number: String,
+    }
+
+    /// Certificate and private key that the discovery client authenticates with
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ClientCredentials {
+        pub certificate_path: PathBuf,
+        pub private_key_path: PathBuf,
+    }
+
+    /// Checks that the certificate and private key at the given paths can be loaded, returning them as
+    /// `ClientCredentials`. Returns None, so that discovery falls back to connecting without security,
+    /// if either path is not set or cannot be loaded.
+    pub fn get_client_credentials(
+        certificate_path: Option<&str>,
+        private_key_path: Option<&str>,
+    ) -> Option<ClientCredentials> {
+        let (certificate_path, private_key_path) = match (certificate_path, private_key_path) {
+            (Some(certificate_path), Some(private_key_path)) => {
+                (Path::new(certificate_path), Path::new(private_key_path))
+            }
+            (None, None) => {
+                warn!("get_client_credentials - no certificate or private key specified ... discovering without security");
+                return None;
+            }
+            _ => {
+                warn!("get_client_credentials - certificatePath and privateKeyPath must be specified together ... discovering without security");
+                return None;
+            }
+        };
+        if let Err(e) = CertificateStore::read_cert(certificate_path) {
+            warn!(
+                "get_client_credentials - could not load certificate {:?} with error {} ... discovering without security",
+                certificate_path, e
+            );
+            return None;
+        }
+        if let Err(e) = CertificateStore::read_pkey(private_key_path) {
+            warn!(
+                "get_client_credentials - could not load private key {:?} with error {} ... discovering without security",
+                private_key_path, e
+            );
+            return None;
+        }
+        Some(ClientCredentials {
+            certificate_path: certificate_path.to_path_buf(),
+            private_key_path: private_key_path.to_path_buf(),
+        })
     }
 
     pub struct OpcuaClientImpl {
@@ -30,15 +94,20 @@ pub mod opcua_client_wrapper {
             application_uri: &str,
             create_sample_keypair: bool,
             session_retry_limit: i32,
+            credentials: Option<&ClientCredentials>,
         ) -> Self {
+            let mut client_builder = ClientBuilder::new()
+                .application_name(application_name)
+                .application_uri(application_uri)
+                .create_sample_keypair(create_sample_keypair)
+                .session_retry_limit(session_retry_limit);
+            if let Some(credentials) = credentials {
+                client_builder = client_builder
+                    .certificate_path(&credentials.certificate_path)
+                    .private_key_path(&credentials.private_key_path);
+            }
             OpcuaClientImpl {
-                inner_opcua_client: ClientBuilder::new()
-                    .application_name(application_name)
-                    .application_uri(application_uri)
-                    .create_sample_keypair(create_sample_keypair)
-                    .session_retry_limit(session_retry_limit)
-                    .client()
-                    .unwrap(),
+                inner_opcua_client: client_builder.client().unwrap(),
             }
         }
     }
@@ -50,10 +119,62 @@ pub mod opcua_client_wrapper {
         ) -> Result<Vec<ApplicationDescription>, StatusCode> {
             self.inner_opcua_client.find_servers(discovery_endpoint_url)
         }
+
+        /// Opens an anonymous session without security to the Server at `endpoint_url` and reads
+        /// the BuildInfo of its ServerStatus
+        fn get_server_status(&mut self, endpoint_url: &str) -> Result<ServerStatus, StatusCode> {
+            let session = self.inner_opcua_client.connect_to_endpoint(
+                (
+                    endpoint_url,
+                    SecurityPolicy::None.to_str(),
+                    MessageSecurityMode::None,
+                    UserTokenPolicy::anonymous(),
+                ),
+                IdentityToken::Anonymous,
+            )?;
+            let nodes_to_read: Vec<ReadValueId> = vec![
+                VariableId::Server_ServerStatus_BuildInfo_ProductUri,
+                VariableId::Server_ServerStatus_BuildInfo_ManufacturerName,
+                VariableId::Server_ServerStatus_BuildInfo_ProductName,
+                VariableId::Server_ServerStatus_BuildInfo_BuildNumber,
+            ]
+            .into_iter()
+            .map(|variable_id| ReadValueId {
+                node_id: NodeId::new(0, variable_id as u32),
+                attribute_id: AttributeId::Value as u32,
+                index_range: UAString::null(),
+                data_encoding: QualifiedName::null(),
+            })
+            .collect();
+            let mut session = session.write().unwrap();
+            let result = session.read(&nodes_to_read, TimestampsToReturn::Neither, 0.0);
+            session.disconnect();
+            let values: Vec<String> = result?
+                .into_iter()
+                .map(|data_value| match data_value.value {
+                    Some(Variant::String(value)) => value.as_ref().to_string(),
+                    _ => String::new(),
+                })
+                .collect();
+            if values.len() != nodes_to_read.len() {
+                return Err(StatusCode::BadUnexpectedError);
+            }
+            Ok(ServerStatus {
+                product_uri: values[0].clone(),
+                manufacturer_name: values[1].clone(),
+                product_name: values[2].clone(),
+                build_number: values[3].clone(),
+            })
+        }
     }
-    /// Returns an OPC UA Client that will only be used to connect to OPC UA Server and Local Discovery Servers' DiscoveryEndpoints
-    pub fn create_opcua_discovery_client() -> impl OpcuaClient {
-        // No security is needed to connect to these DisoveryEndpoints; therefore, creating a keypair for the Client is unneccessary.
+
+    /// Returns an OPC UA Client that will only be used to connect to OPC UA Server and Local Discovery Servers' DiscoveryEndpoints,
+    /// and to read the ServerStatus of discovered Servers.
+    /// The Client authenticates with `credentials` if they are provided.
+    pub fn create_opcua_discovery_client(
+        credentials: Option<&ClientCredentials>,
+    ) -> impl OpcuaClient {
+        // The Client's keypair, if any, comes from the provided credentials; therefore, creating a sample keypair is unneccessary.
         let create_sample_keypair = false;
         // Do not try to create a session again
         let session_retry_limit = 0;
@@ -62,8 +183,46 @@ pub mod opcua_client_wrapper {
             "urn:DiscoveryClient",
             create_sample_keypair,
             session_retry_limit,
+            credentials,
         )
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const CERTIFICATE_PATH: &str = "../test/opcua/cert.der";
+        const PRIVATE_KEY_PATH: &str = "../test/opcua/private.pem";
+
+        #[test]
+        fn test_get_client_credentials() {
+            let _ = env_logger::builder().is_test(true).try_init();
+            assert_eq!(
+                get_client_credentials(Some(CERTIFICATE_PATH), Some(PRIVATE_KEY_PATH)),
+                Some(ClientCredentials {
+                    certificate_path: PathBuf::from(CERTIFICATE_PATH),
+                    private_key_path: PathBuf::from(PRIVATE_KEY_PATH),
+                })
+            );
+        }
+
+        #[test]
+        fn test_get_client_credentials_falls_back() {
+            let _ = env_logger::builder().is_test(true).try_init();
+            assert_eq!(get_client_credentials(None, None), None);
+            assert_eq!(get_client_credentials(Some(CERTIFICATE_PATH), None), None);
+            assert_eq!(get_client_credentials(None, Some(PRIVATE_KEY_PATH)), None);
+            assert_eq!(
+                get_client_credentials(Some("../test/opcua/missing.der"), Some(PRIVATE_KEY_PATH)),
+                None
+            );
+            // A private key is not a certificate
+            assert_eq!(
+                get_client_credentials(Some(PRIVATE_KEY_PATH), Some(PRIVATE_KEY_PATH)),
+                None
+            );
+        }
+    }
 }
 pub mod tcp_stream_wrapper {
     use mockall::predicate::*;
@@ -0,0 +1,62 @@
+use super::super::{validate_discovery_interval, DiscoveryHandler, DiscoveryResult};
+use super::{
+    discovery_impl::{do_scan, get_device_properties},
+    serial_port_wrapper::SystemSerialPortProber,
+};
+use akri_shared::akri::configuration::SerialDiscoveryHandlerConfig;
+use akri_shared::log_fields;
+use anyhow::Error;
+use async_trait::async_trait;
+use log::Level;
+use std::time::Duration;
+
+/// `SerialDiscoveryHandler` discovers the serial ports matching `discovery_handler_config.port_pattern`,
+/// probing them with `discovery_handler_config.probe_command` if it is set.
+/// The instances it discovers are always unshared, as ports are attached to a single node.
+#[derive(Debug)]
+pub struct SerialDiscoveryHandler {
+    discovery_handler_config: SerialDiscoveryHandlerConfig,
+}
+
+impl SerialDiscoveryHandler {
+    pub fn new(discovery_handler_config: &SerialDiscoveryHandlerConfig) -> Self {
+        SerialDiscoveryHandler {
+            discovery_handler_config: discovery_handler_config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DiscoveryHandler for SerialDiscoveryHandler {
+    async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error> {
+        let discovery_handler_config = self.discovery_handler_config.clone();
+        // Probing blocks while waiting on each port, so keep it off of the runtime's worker threads
+        let devices = tokio::task::spawn_blocking(move || {
+            do_scan(&SystemSerialPortProber {}, &discovery_handler_config)
+        })
+        .await??;
+        Ok(devices
+            .iter()
+            .map(|device| {
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "serial", endpoint: device.path },
+                    "discover - found serial port {} at {} baud",
+                    device.path,
+                    device.baud_rate
+                );
+                DiscoveryResult::new(
+                    &device.path,
+                    get_device_properties(device),
+                    self.are_shared().unwrap(),
+                )
+            })
+            .collect::<Vec<DiscoveryResult>>())
+    }
+    fn are_shared(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        validate_discovery_interval(self.discovery_handler_config.discovery_interval_seconds)
+    }
+}
@@ -0,0 +1,243 @@
+use super::{
+    serial_port_wrapper::SerialPortProber, SERIAL_BAUD_LABEL, SERIAL_PORT_LABEL,
+    SERIAL_RESPONSE_LABEL,
+};
+use akri_shared::akri::configuration::SerialDiscoveryHandlerConfig;
+use anyhow::Error;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    time::Duration,
+};
+
+/// A serial port that was found, along with the baud rate it responded at and its response, if probed
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerialDevice {
+    /// Canonical path of the port, with symlinks such as `/dev/serial/by-id/...` resolved
+    pub path: String,
+    pub baud_rate: u32,
+    pub response: Option<String>,
+}
+
+/// Returns the canonical paths of the ports matching `port_pattern`, so that a port matched through several
+/// symlinks is only listed once
+pub fn get_port_paths(port_pattern: &str) -> Result<Vec<String>, Error> {
+    let mut paths = BTreeSet::new();
+    for entry in glob::glob(port_pattern)? {
+        let path = match entry {
+            Ok(path) => path,
+            Err(e) => {
+                trace!("get_port_paths - skipping unreadable path {}", e);
+                continue;
+            }
+        };
+        match fs::canonicalize(&path) {
+            Ok(canonical_path) => {
+                paths.insert(canonical_path.to_string_lossy().to_string());
+            }
+            Err(e) => trace!(
+                "get_port_paths - skipping {:?}, which could not be resolved: {}",
+                path,
+                e
+            ),
+        }
+    }
+    Ok(paths.into_iter().collect())
+}
+
+/// Returns whether a response to the probe command identifies the device the Configuration is looking for
+fn is_expected_response(response: &str, response_contains: Option<&str>) -> bool {
+    match response_contains {
+        Some(response_contains) => response.contains(response_contains),
+        None => !response.trim().is_empty(),
+    }
+}
+
+/// Probes a port at each of the Configuration's baud rates in turn, returning the device at the first rate
+/// that opens the port and, if there is a probe command, gets the expected response to it
+pub fn probe_port(
+    prober: &impl SerialPortProber,
+    path: &str,
+    discovery_handler_config: &SerialDiscoveryHandlerConfig,
+) -> Option<SerialDevice> {
+    let probe_timeout = Duration::from_millis(discovery_handler_config.probe_timeout_ms);
+    for baud_rate in &discovery_handler_config.baud_rates {
+        match prober.probe(
+            path,
+            *baud_rate,
+            discovery_handler_config.probe_command.clone(),
+            probe_timeout,
+        ) {
+            Ok(None) => {
+                return Some(SerialDevice {
+                    path: path.to_string(),
+                    baud_rate: *baud_rate,
+                    response: None,
+                })
+            }
+            Ok(Some(response))
+                if is_expected_response(
+                    &response,
+                    discovery_handler_config.response_contains.as_deref(),
+                ) =>
+            {
+                return Some(SerialDevice {
+                    path: path.to_string(),
+                    baud_rate: *baud_rate,
+                    response: Some(response.trim().to_string()),
+                })
+            }
+            Ok(Some(response)) => trace!(
+                "probe_port - unexpected response {:?} from {} at {} baud",
+                response,
+                path,
+                baud_rate
+            ),
+            Err(e) => {
+                // A port that cannot be opened will not open at another baud rate either
+                trace!("probe_port - could not probe {}: {}", path, e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// Finds the ports matching the Configuration's port pattern and probes each one
+pub fn do_scan(
+    prober: &impl SerialPortProber,
+    discovery_handler_config: &SerialDiscoveryHandlerConfig,
+) -> Result<Vec<SerialDevice>, Error> {
+    Ok(get_port_paths(&discovery_handler_config.port_pattern)?
+        .iter()
+        .filter_map(|path| probe_port(prober, path, discovery_handler_config))
+        .collect())
+}
+
+/// Returns the properties of a port's Instance. Its response is only included if it was probed.
+pub fn get_device_properties(device: &SerialDevice) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    properties.insert(SERIAL_PORT_LABEL.to_string(), device.path.clone());
+    properties.insert(SERIAL_BAUD_LABEL.to_string(), device.baud_rate.to_string());
+    if let Some(response) = &device.response {
+        properties.insert(SERIAL_RESPONSE_LABEL.to_string(), response.clone());
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::serial_port_wrapper::MockSerialPortProber;
+    use super::*;
+
+    fn get_test_config(
+        probe_command: Option<&str>,
+        response_contains: Option<&str>,
+    ) -> SerialDiscoveryHandlerConfig {
+        SerialDiscoveryHandlerConfig {
+            port_pattern: "/dev/ttyUSB*".to_string(),
+            baud_rates: vec![115200, 9600],
+            probe_command: probe_command.map(|c| c.to_string()),
+            response_contains: response_contains.map(|r| r.to_string()),
+            probe_timeout_ms: 200,
+            discovery_interval_seconds: 10,
+        }
+    }
+
+    #[test]
+    fn test_get_port_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let port = dir.path().join("ttyUSB0");
+        fs::write(&port, "").unwrap();
+        fs::write(dir.path().join("ttyUSB1"), "").unwrap();
+        fs::write(dir.path().join("ttyS0"), "").unwrap();
+        std::os::unix::fs::symlink(&port, dir.path().join("ttyUSB-link")).unwrap();
+        let canonical_dir = fs::canonicalize(dir.path()).unwrap();
+
+        let paths = get_port_paths(&format!("{}/ttyUSB*", dir.path().display())).unwrap();
+        // The symlink resolves to a port that is already listed
+        assert_eq!(
+            vec![
+                canonical_dir.join("ttyUSB0").to_string_lossy().to_string(),
+                canonical_dir.join("ttyUSB1").to_string_lossy().to_string(),
+            ],
+            paths
+        );
+        assert!(get_port_paths("/dev/[").is_err());
+    }
+
+    #[test]
+    fn test_probe_port_without_probe_command() {
+        let mut mock_prober = MockSerialPortProber::new();
+        mock_prober
+            .expect_probe()
+            .withf(|path, baud_rate, probe_command, probe_timeout| {
+                path == "/dev/ttyUSB0"
+                    && *baud_rate == 115200
+                    && probe_command.is_none()
+                    && *probe_timeout == Duration::from_millis(200)
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(None));
+        let device =
+            probe_port(&mock_prober, "/dev/ttyUSB0", &get_test_config(None, None)).unwrap();
+        assert_eq!(115200, device.baud_rate);
+        assert_eq!(2, get_device_properties(&device).len());
+    }
+
+    #[test]
+    fn test_probe_port_tries_each_baud_rate() {
+        let mut mock_prober = MockSerialPortProber::new();
+        mock_prober
+            .expect_probe()
+            .times(2)
+            .returning(|_, baud_rate, probe_command, _| {
+                assert_eq!(Some("*IDN?\r\n"), probe_command.as_deref());
+                match baud_rate {
+                    115200 => Ok(Some("\u{fffd}\u{fffd}".to_string())),
+                    _ => Ok(Some("ACME,PLC-1,1234\r\n".to_string())),
+                }
+            });
+        let device = probe_port(
+            &mock_prober,
+            "/dev/ttyUSB0",
+            &get_test_config(Some("*IDN?\r\n"), Some("ACME")),
+        )
+        .unwrap();
+        assert_eq!(9600, device.baud_rate);
+        let properties = get_device_properties(&device);
+        assert_eq!(3, properties.len());
+        assert_eq!("/dev/ttyUSB0", properties[SERIAL_PORT_LABEL]);
+        assert_eq!("9600", properties[SERIAL_BAUD_LABEL]);
+        assert_eq!("ACME,PLC-1,1234", properties[SERIAL_RESPONSE_LABEL]);
+    }
+
+    #[test]
+    fn test_probe_port_without_expected_response() {
+        // Silent ports are not discovered when a probe command is set, nor are ports with the wrong response
+        for (response, response_contains) in &[("", None), ("OTHER,1", Some("ACME"))] {
+            let mut mock_prober = MockSerialPortProber::new();
+            let response = response.to_string();
+            mock_prober
+                .expect_probe()
+                .times(2)
+                .returning(move |_, _, _, _| Ok(Some(response.clone())));
+            assert!(probe_port(
+                &mock_prober,
+                "/dev/ttyUSB0",
+                &get_test_config(Some("*IDN?\r\n"), *response_contains)
+            )
+            .is_none());
+        }
+    }
+
+    #[test]
+    fn test_probe_port_open_error() {
+        let mut mock_prober = MockSerialPortProber::new();
+        mock_prober
+            .expect_probe()
+            .times(1)
+            .returning(|_, _, _, _| Err(anyhow::format_err!("Permission denied")));
+        assert!(probe_port(&mock_prober, "/dev/ttyUSB0", &get_test_config(None, None)).is_none());
+    }
+}
@@ -0,0 +1,73 @@
+mod discovery_handler;
+mod discovery_impl;
+pub use self::discovery_handler::SerialDiscoveryHandler;
+
+/// Names of the properties set on the Instance of a discovered serial port
+pub const SERIAL_PORT_LABEL: &str = "AKRI_SERIAL_PORT";
+pub const SERIAL_BAUD_LABEL: &str = "AKRI_SERIAL_BAUD";
+pub const SERIAL_RESPONSE_LABEL: &str = "AKRI_SERIAL_RESPONSE";
+
+/// Wrapper to enable mocking of serial port access
+pub mod serial_port_wrapper {
+    use anyhow::Error;
+    use mockall::predicate::*;
+    use mockall::*;
+    use serialport::ClearBuffer;
+    use std::{
+        io::{ErrorKind, Read, Write},
+        time::Duration,
+    };
+
+    /// Most bytes of a response that are read, so that a port that keeps sending data does not hold up discovery
+    const MAX_RESPONSE_BYTES: usize = 1024;
+
+    #[automock]
+    pub trait SerialPortProber {
+        /// Opens the port at `path` with `baud_rate`, failing if it cannot be opened. If a probe command is
+        /// given, it is written to the port and whatever the port sends back until it has been quiet for
+        /// `probe_timeout` is returned.
+        fn probe(
+            &self,
+            path: &str,
+            baud_rate: u32,
+            probe_command: Option<String>,
+            probe_timeout: Duration,
+        ) -> Result<Option<String>, Error>;
+    }
+
+    pub struct SystemSerialPortProber {}
+
+    impl SerialPortProber for SystemSerialPortProber {
+        fn probe(
+            &self,
+            path: &str,
+            baud_rate: u32,
+            probe_command: Option<String>,
+            probe_timeout: Duration,
+        ) -> Result<Option<String>, Error> {
+            let mut port = serialport::new(path, baud_rate)
+                .timeout(probe_timeout)
+                .open()?;
+            let probe_command = match probe_command {
+                Some(probe_command) => probe_command,
+                None => return Ok(None),
+            };
+            // Drop anything the device sent before it was probed
+            port.clear(ClearBuffer::Input)?;
+            port.write_all(probe_command.as_bytes())?;
+            port.flush()?;
+            let mut response = Vec::new();
+            let mut buf = [0; 256];
+            while response.len() < MAX_RESPONSE_BYTES {
+                match port.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(read) => response.extend_from_slice(&buf[..read]),
+                    Err(e) if e.kind() == ErrorKind::TimedOut => break,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            response.truncate(MAX_RESPONSE_BYTES);
+            Ok(Some(String::from_utf8_lossy(&response).to_string()))
+        }
+    }
+}
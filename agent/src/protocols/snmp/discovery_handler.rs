@@ -0,0 +1,71 @@
+use super::super::{validate_discovery_interval, DiscoveryHandler, DiscoveryResult};
+use super::{
+    discovery_impl::{get_addresses, get_agent_properties, query_agents},
+    snmp_client_wrapper::SyncSnmpClient,
+};
+use akri_shared::akri::configuration::{SnmpDiscoveryHandlerConfig, SnmpVersion};
+use akri_shared::log_fields;
+use anyhow::Error;
+use async_trait::async_trait;
+use log::Level;
+use std::{sync::Arc, time::Duration};
+
+/// `SnmpDiscoveryHandler` discovers the SNMP agents in `discovery_handler_config.subnets` by reading their
+/// system group, keeping those whose sysObjectID is under `discovery_handler_config.oid_filter` if it is set.
+/// The instances it discovers are always shared, as agents are reachable from every node.
+#[derive(Debug)]
+pub struct SnmpDiscoveryHandler {
+    discovery_handler_config: SnmpDiscoveryHandlerConfig,
+}
+
+impl SnmpDiscoveryHandler {
+    pub fn new(discovery_handler_config: &SnmpDiscoveryHandlerConfig) -> Self {
+        SnmpDiscoveryHandler {
+            discovery_handler_config: discovery_handler_config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DiscoveryHandler for SnmpDiscoveryHandler {
+    async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error> {
+        let addresses = get_addresses(
+            &self.discovery_handler_config.subnets,
+            self.discovery_handler_config.port,
+        )?;
+        let community = match &self.discovery_handler_config.version {
+            SnmpVersion::v2c(security) => &security.community,
+        };
+        let agents = query_agents(
+            Arc::new(SyncSnmpClient {}),
+            addresses,
+            community,
+            Duration::from_secs(self.discovery_handler_config.request_timeout_seconds),
+            self.discovery_handler_config.oid_filter.as_deref(),
+        )
+        .await;
+        Ok(agents
+            .iter()
+            .map(|(address, system_info)| {
+                log_fields!(
+                    Level::Trace,
+                    { protocol: "snmp", endpoint: address },
+                    "discover - found SNMP agent {} at {}",
+                    system_info.sys_descr,
+                    address
+                );
+                DiscoveryResult::new(
+                    &address.ip().to_string(),
+                    get_agent_properties(address, system_info),
+                    self.are_shared().unwrap(),
+                )
+            })
+            .collect::<Vec<DiscoveryResult>>())
+    }
+    fn are_shared(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+    fn get_discovery_interval(&self) -> Result<Duration, Error> {
+        validate_discovery_interval(self.discovery_handler_config.discovery_interval_seconds)
+    }
+}
@@ -0,0 +1,259 @@
+use super::{
+    snmp_client_wrapper::{SnmpClient, SystemInfo},
+    SNMP_ADDR_LABEL, SNMP_SYS_DESCR_LABEL, SNMP_SYS_LOCATION_LABEL, SNMP_SYS_NAME_LABEL,
+    SNMP_SYS_OBJECT_ID_LABEL,
+};
+use anyhow::Error;
+use futures::stream::{self, StreamExt};
+use ipnet::IpNet;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Maximum number of addresses queried in one discovery, which is the size of a /20 IPv4 subnet
+const MAX_SNMP_ADDRESSES: usize = 4096;
+
+/// Maximum number of agents queried at once
+const MAX_CONCURRENT_QUERIES: usize = 64;
+
+/// Expands the Configuration's subnets into the addresses of the agents to query. Entries may be single IP
+/// addresses or subnets in CIDR notation, whose network and broadcast addresses are skipped.
+pub fn get_addresses(subnets: &[String], port: u16) -> Result<Vec<SocketAddr>, Error> {
+    let mut addresses = Vec::new();
+    for subnet in subnets {
+        if let Ok(ip_address) = subnet.parse::<IpAddr>() {
+            addresses.push(SocketAddr::new(ip_address, port));
+            continue;
+        }
+        let network = subnet.parse::<IpNet>().map_err(|e| {
+            anyhow::format_err!(
+                "invalid argument: subnets entry {} is neither an IP address nor a subnet in CIDR notation: {}",
+                subnet,
+                e
+            )
+        })?;
+        addresses.extend(
+            network
+                .hosts()
+                .take(MAX_SNMP_ADDRESSES + 1)
+                .map(|ip_address| SocketAddr::new(ip_address, port)),
+        );
+        if addresses.len() > MAX_SNMP_ADDRESSES {
+            break;
+        }
+    }
+    if addresses.len() > MAX_SNMP_ADDRESSES {
+        return Err(anyhow::format_err!(
+            "invalid argument: subnets contain more than {} addresses",
+            MAX_SNMP_ADDRESSES
+        ));
+    }
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
+
+/// Returns whether `sys_object_id` is `oid_filter` or an OID under it. OIDs may be written with or without
+/// a leading dot.
+pub fn matches_oid_filter(sys_object_id: &str, oid_filter: Option<&str>) -> bool {
+    match oid_filter {
+        Some(oid_filter) => {
+            let oid_filter = oid_filter.trim().trim_start_matches('.');
+            let sys_object_id = sys_object_id.trim_start_matches('.');
+            sys_object_id == oid_filter || sys_object_id.starts_with(&format!("{}.", oid_filter))
+        }
+        None => true,
+    }
+}
+
+/// Queries the system group of each address concurrently, returning the agents that responded and whose
+/// sysObjectID passes `oid_filter`. Addresses that do not respond within `request_timeout` are left out.
+pub async fn query_agents(
+    snmp_client: Arc<dyn SnmpClient + Send + Sync>,
+    addresses: Vec<SocketAddr>,
+    community: &str,
+    request_timeout: Duration,
+    oid_filter: Option<&str>,
+) -> Vec<(SocketAddr, SystemInfo)> {
+    stream::iter(addresses)
+        .map(|address| {
+            let snmp_client = snmp_client.clone();
+            let community = community.to_string();
+            async move {
+                // Requests block until the agent responds or times out, so query each agent on its own thread
+                let query = tokio::task::spawn_blocking(move || {
+                    snmp_client.get_system_info(address, &community, request_timeout)
+                })
+                .await;
+                (address, query)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_QUERIES)
+        .filter_map(|(address, query)| async move {
+            match query {
+                Ok(Ok(system_info)) => Some((address, system_info)),
+                Ok(Err(e)) => {
+                    trace!("query_agents - no SNMP agent at {}: {}", address, e);
+                    None
+                }
+                Err(e) => {
+                    trace!(
+                        "query_agents - query of {} did not complete: {:?}",
+                        address,
+                        e
+                    );
+                    None
+                }
+            }
+        })
+        .filter(|(address, system_info)| {
+            let matches = matches_oid_filter(&system_info.sys_object_id, oid_filter);
+            if !matches {
+                trace!(
+                    "query_agents - agent at {} with sysObjectID {} filtered out",
+                    address,
+                    system_info.sys_object_id
+                );
+            }
+            futures::future::ready(matches)
+        })
+        .collect()
+        .await
+}
+
+/// Returns the properties of an agent's Instance. Its sysName and sysLocation are only included if set.
+pub fn get_agent_properties(
+    address: &SocketAddr,
+    system_info: &SystemInfo,
+) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    properties.insert(SNMP_ADDR_LABEL.to_string(), address.ip().to_string());
+    properties.insert(
+        SNMP_SYS_DESCR_LABEL.to_string(),
+        system_info.sys_descr.clone(),
+    );
+    properties.insert(
+        SNMP_SYS_OBJECT_ID_LABEL.to_string(),
+        system_info.sys_object_id.clone(),
+    );
+    if let Some(sys_name) = system_info.sys_name.as_ref().filter(|n| !n.is_empty()) {
+        properties.insert(SNMP_SYS_NAME_LABEL.to_string(), sys_name.clone());
+    }
+    if let Some(sys_location) = system_info.sys_location.as_ref().filter(|l| !l.is_empty()) {
+        properties.insert(SNMP_SYS_LOCATION_LABEL.to_string(), sys_location.clone());
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::snmp_client_wrapper::MockSnmpClient;
+    use super::*;
+
+    #[test]
+    fn test_get_addresses() {
+        let addresses = get_addresses(
+            &[
+                "10.0.0.0/30".to_string(),
+                "10.0.0.1".to_string(),
+                "192.168.1.5".to_string(),
+            ],
+            161,
+        )
+        .unwrap();
+        // The network and broadcast addresses of the /30 are skipped and the duplicate is removed
+        assert_eq!(
+            vec![
+                "10.0.0.1:161".parse::<SocketAddr>().unwrap(),
+                "10.0.0.2:161".parse().unwrap(),
+                "192.168.1.5:161".parse().unwrap(),
+            ],
+            addresses
+        );
+
+        assert!(get_addresses(&["10.0.0.0/33".to_string()], 161).is_err());
+        assert!(get_addresses(&["not-an-address".to_string()], 161).is_err());
+        // A /20 fits, but a /19 does not
+        assert_eq!(
+            4094,
+            get_addresses(&["10.0.0.0/20".to_string()], 161)
+                .unwrap()
+                .len()
+        );
+        assert!(get_addresses(&["10.0.0.0/19".to_string()], 161).is_err());
+    }
+
+    #[test]
+    fn test_matches_oid_filter() {
+        assert!(matches_oid_filter("1.3.6.1.4.1.9.1.1", None));
+        assert!(matches_oid_filter(
+            "1.3.6.1.4.1.9.1.1",
+            Some("1.3.6.1.4.1.9")
+        ));
+        assert!(matches_oid_filter("1.3.6.1.4.1.9", Some(".1.3.6.1.4.1.9")));
+        // Only whole sub-identifiers match
+        assert!(!matches_oid_filter(
+            "1.3.6.1.4.1.99.1",
+            Some("1.3.6.1.4.1.9")
+        ));
+        assert!(!matches_oid_filter(
+            "1.3.6.1.4.1.2636.1",
+            Some("1.3.6.1.4.1.9")
+        ));
+    }
+
+    fn get_test_system_info(sys_object_id: &str) -> SystemInfo {
+        SystemInfo {
+            sys_descr: "Test switch".to_string(),
+            sys_object_id: sys_object_id.to_string(),
+            sys_name: Some("switch-1".to_string()),
+            sys_location: Some(String::new()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_agents() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut mock_snmp_client = MockSnmpClient::new();
+        mock_snmp_client
+            .expect_get_system_info()
+            .withf(|_, community, request_timeout| {
+                community == "public" && *request_timeout == Duration::from_secs(1)
+            })
+            .times(3)
+            .returning(|address, _, _| match address.ip().to_string().as_str() {
+                "10.0.0.1" => Ok(get_test_system_info("1.3.6.1.4.1.9.1.1")),
+                "10.0.0.2" => Ok(get_test_system_info("1.3.6.1.4.1.2636.1.1")),
+                _ => Err(anyhow::format_err!("request timed out")),
+            });
+        let addresses =
+            get_addresses(&["10.0.0.0/30".to_string(), "10.0.0.3".to_string()], 161).unwrap();
+        let agents = query_agents(
+            Arc::new(mock_snmp_client),
+            addresses,
+            "public",
+            Duration::from_secs(1),
+            Some("1.3.6.1.4.1.9"),
+        )
+        .await;
+        assert_eq!(1, agents.len());
+        assert_eq!("10.0.0.1:161".parse::<SocketAddr>().unwrap(), agents[0].0);
+    }
+
+    #[test]
+    fn test_get_agent_properties() {
+        let properties = get_agent_properties(
+            &"10.0.0.1:161".parse().unwrap(),
+            &get_test_system_info("1.3.6.1.4.1.9.1.1"),
+        );
+        // The empty sysLocation is left out
+        assert_eq!(4, properties.len());
+        assert_eq!("10.0.0.1", properties[SNMP_ADDR_LABEL]);
+        assert_eq!("Test switch", properties[SNMP_SYS_DESCR_LABEL]);
+        assert_eq!("1.3.6.1.4.1.9.1.1", properties[SNMP_SYS_OBJECT_ID_LABEL]);
+        assert_eq!("switch-1", properties[SNMP_SYS_NAME_LABEL]);
+    }
+}
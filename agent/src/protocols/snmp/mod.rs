@@ -0,0 +1,104 @@
+mod discovery_handler;
+mod discovery_impl;
+pub use self::discovery_handler::SnmpDiscoveryHandler;
+
+/// Names of the properties set on the Instance of a discovered SNMP agent
+pub const SNMP_ADDR_LABEL: &str = "AKRI_SNMP_ADDR";
+pub const SNMP_SYS_DESCR_LABEL: &str = "AKRI_SNMP_SYS_DESCR";
+pub const SNMP_SYS_OBJECT_ID_LABEL: &str = "AKRI_SNMP_SYS_OBJECT_ID";
+pub const SNMP_SYS_NAME_LABEL: &str = "AKRI_SNMP_SYS_NAME";
+pub const SNMP_SYS_LOCATION_LABEL: &str = "AKRI_SNMP_SYS_LOCATION";
+
+/// Wrapper to enable mocking of SNMP requests
+pub mod snmp_client_wrapper {
+    use anyhow::Error;
+    use mockall::predicate::*;
+    use mockall::*;
+    use snmp::{ObjIdBuf, SyncSession, Value};
+    use std::{net::SocketAddr, time::Duration};
+
+    /// OIDs of the scalars of the system group (RFC 1213) read from each agent
+    const SYS_DESCR_OID: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+    const SYS_OBJECT_ID_OID: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 2, 0];
+    const SYS_NAME_OID: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 5, 0];
+    const SYS_LOCATION_OID: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 6, 0];
+
+    /// The system group of an SNMP agent
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct SystemInfo {
+        pub sys_descr: String,
+        /// sysObjectID in dotted notation, such as `1.3.6.1.4.1.9.1.1`
+        pub sys_object_id: String,
+        pub sys_name: Option<String>,
+        pub sys_location: Option<String>,
+    }
+
+    #[automock]
+    pub trait SnmpClient {
+        /// Reads the system group of the agent at `address` with SNMP v2c, blocking until it responds or
+        /// `request_timeout` elapses for one of the requests
+        fn get_system_info(
+            &self,
+            address: SocketAddr,
+            community: &str,
+            request_timeout: Duration,
+        ) -> Result<SystemInfo, Error>;
+    }
+
+    /// Queries agents with the synchronous client of the snmp crate
+    pub struct SyncSnmpClient {}
+
+    impl SnmpClient for SyncSnmpClient {
+        fn get_system_info(
+            &self,
+            address: SocketAddr,
+            community: &str,
+            request_timeout: Duration,
+        ) -> Result<SystemInfo, Error> {
+            let mut session =
+                SyncSession::new(address, community.as_bytes(), Some(request_timeout), 0)?;
+            let sys_descr = get_scalar(&mut session, SYS_DESCR_OID)?.ok_or_else(|| {
+                anyhow::format_err!("agent at {} did not return sysDescr", address)
+            })?;
+            let sys_object_id = get_scalar(&mut session, SYS_OBJECT_ID_OID)?.ok_or_else(|| {
+                anyhow::format_err!("agent at {} did not return sysObjectID", address)
+            })?;
+            Ok(SystemInfo {
+                sys_descr,
+                sys_object_id,
+                sys_name: get_scalar(&mut session, SYS_NAME_OID)?,
+                sys_location: get_scalar(&mut session, SYS_LOCATION_OID)?,
+            })
+        }
+    }
+
+    /// Gets a string or OID scalar, returning None if the agent does not have it
+    fn get_scalar(session: &mut SyncSession, oid: &[u32]) -> Result<Option<String>, Error> {
+        // SnmpError does not implement std::error::Error, so it is converted by hand
+        let mut response = session
+            .get(oid)
+            .map_err(|e| anyhow::format_err!("SNMP get failed: {:?}", e))?;
+        if response.error_status != 0 {
+            return Ok(None);
+        }
+        Ok(match response.varbinds.next() {
+            Some((_, Value::OctetString(value))) => {
+                Some(String::from_utf8_lossy(value).trim().to_string())
+            }
+            Some((_, Value::ObjectIdentifier(value))) => {
+                let mut buf: ObjIdBuf = [0; 128];
+                let name = value
+                    .read_name(&mut buf)
+                    .map_err(|e| anyhow::format_err!("invalid OID in response: {:?}", e))?;
+                Some(
+                    name.iter()
+                        .map(|subid| subid.to_string())
+                        .collect::<Vec<String>>()
+                        .join("."),
+                )
+            }
+            // noSuchObject and noSuchInstance exceptions, or a value of an unexpected type
+            _ => None,
+        })
+    }
+}
@@ -1,30 +1,75 @@
 use super::super::{DiscoveryHandler, DiscoveryResult};
-use super::{discovery_impl, udev_enumerator, UDEV_DEVNODE_LABEL_ID};
+use super::{discovery_impl, udev_enumerator, udev_monitor, UDEV_DEVNODE_LABEL_ID};
 use akri_shared::akri::configuration::UdevDiscoveryHandlerConfig;
+use akri_shared::log_fields;
 use anyhow::Error;
 use async_trait::async_trait;
-use std::collections::HashSet;
+use log::Level;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{mpsc, Mutex};
+
+/// How long to let a burst of udev events settle before re-enumerating devices
+const UDEV_EVENT_DEBOUNCE: Duration = Duration::from_millis(500);
 
 /// `UdevDiscoveryHandler` discovers udev instances by parsing the udev rules in `discovery_handler_config.udev_rules`.
 /// The instances it discovers are always unshared.
+/// Besides discovering periodically, it re-discovers as soon as udev reports a device being added or removed
+/// in one of the subsystems the udev rules match on.
 #[derive(Debug)]
 pub struct UdevDiscoveryHandler {
     discovery_handler_config: UdevDiscoveryHandlerConfig,
+    /// Notified of udev add and remove events once the udev monitor has been started
+    change_receiver: Mutex<Option<mpsc::Receiver<()>>>,
+    /// Set when the handler is dropped to stop the udev monitor
+    stop_monitor: Arc<AtomicBool>,
 }
 
 impl UdevDiscoveryHandler {
     pub fn new(discovery_handler_config: &UdevDiscoveryHandlerConfig) -> Self {
         UdevDiscoveryHandler {
             discovery_handler_config: discovery_handler_config.clone(),
+            change_receiver: Mutex::new(None),
+            stop_monitor: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    /// Returns the subsystems to watch for udev events, or an empty set to watch all subsystems
+    /// if any udev rule can match devices outside of a specific subsystem
+    fn get_monitored_subsystems(&self) -> HashSet<String> {
+        let mut subsystems = HashSet::new();
+        for rule in &self.discovery_handler_config.udev_rules {
+            match discovery_impl::get_matched_subsystems(rule) {
+                Ok(Some(rule_subsystems)) => subsystems.extend(rule_subsystems),
+                _ => return HashSet::new(),
+            }
+        }
+        subsystems
+    }
+}
+
+impl Drop for UdevDiscoveryHandler {
+    fn drop(&mut self) {
+        self.stop_monitor.store(true, Ordering::SeqCst);
+    }
 }
 
 #[async_trait]
 impl DiscoveryHandler for UdevDiscoveryHandler {
     async fn discover(&self) -> Result<Vec<DiscoveryResult>, Error> {
         let udev_rules = self.discovery_handler_config.udev_rules.clone();
-        trace!("discover - for udev rules {:?}", udev_rules);
+        log_fields!(
+            Level::Trace,
+            { protocol: "udev" },
+            "discover - for udev rules {:?}",
+            udev_rules
+        );
         let mut devpaths: HashSet<String> = HashSet::new();
         udev_rules
             .iter()
@@ -37,7 +82,9 @@ impl DiscoveryHandler for UdevDiscoveryHandler {
                 Ok(())
             })
             .collect::<Result<(), Error>>()?;
-        trace!(
+        log_fields!(
+            Level::Trace,
+            { protocol: "udev" },
             "discover - mapping and returning devices at devpaths {:?}",
             devpaths
         );
@@ -54,4 +101,67 @@ impl DiscoveryHandler for UdevDiscoveryHandler {
     fn are_shared(&self) -> Result<bool, Error> {
         Ok(false)
     }
+
+    async fn wait_for_change(&self, max_wait: Duration) {
+        let mut change_receiver = self.change_receiver.lock().await;
+        if change_receiver.is_none() {
+            let subsystems = self.get_monitored_subsystems();
+            log_fields!(
+                Level::Trace,
+                { protocol: "udev" },
+                "wait_for_change - starting udev monitor for subsystems {:?}",
+                subsystems
+            );
+            *change_receiver = Some(udev_monitor::start_watching(
+                subsystems,
+                self.stop_monitor.clone(),
+            ));
+        }
+        if udev_monitor::wait_for_change(
+            change_receiver.as_mut().unwrap(),
+            max_wait,
+            UDEV_EVENT_DEBOUNCE,
+        )
+        .await
+        {
+            log_fields!(
+                Level::Trace,
+                { protocol: "udev" },
+                "wait_for_change - udev reported a device change ... discovering again"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_handler(udev_rules: Vec<&str>) -> UdevDiscoveryHandler {
+        UdevDiscoveryHandler::new(&UdevDiscoveryHandlerConfig {
+            udev_rules: udev_rules
+                .into_iter()
+                .map(|rule| rule.to_string())
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn test_get_monitored_subsystems() {
+        let handler = create_handler(vec![
+            "KERNEL==\"video[0-9]*\", SUBSYSTEM==\"video4linux\"",
+            "SUBSYSTEM==\"sound\"",
+        ]);
+        let expected: HashSet<String> = vec!["video4linux".to_string(), "sound".to_string()]
+            .into_iter()
+            .collect();
+        assert_eq!(handler.get_monitored_subsystems(), expected);
+
+        // A rule without a SUBSYSTEM match means all subsystems must be watched
+        let handler = create_handler(vec![
+            "SUBSYSTEM==\"video4linux\"",
+            "KERNEL==\"video[0-9]*\"",
+        ]);
+        assert!(handler.get_monitored_subsystems().is_empty());
+    }
 }
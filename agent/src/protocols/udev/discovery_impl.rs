@@ -8,8 +8,17 @@ use super::udev_enumerator::Enumerator;
 use pest::iterators::Pair;
 use pest::Parser;
 use regex::Regex;
+use std::ffi::OsStr;
 
 const TAGS: &str = "TAGS";
+/// Fields that match against a device or any of its ancestors
+const HIERARCHY_FIELDS: [Rule; 5] = [
+    Rule::attributes,
+    Rule::drivers,
+    Rule::kernels,
+    Rule::subsystems,
+    Rule::tags,
+];
 
 #[derive(Parser)]
 #[grammar = "protocols/udev/udev_rule_grammar.pest"]
@@ -36,6 +45,26 @@ pub fn do_parse_and_find(
     Ok(devpaths)
 }
 
+/// Returns the subsystems a udev rule matches on with `SUBSYSTEM==`, or None if the rule can
+/// match devices from any subsystem
+pub fn get_matched_subsystems(
+    udev_rule_string: &str,
+) -> Result<Option<Vec<String>>, anyhow::Error> {
+    let subsystems: Vec<String> = parse_udev_rule(udev_rule_string)?
+        .into_iter()
+        .filter(|udev_filter| {
+            udev_filter.field.as_rule() == Rule::subsystem
+                && udev_filter.operation == Rule::equality
+        })
+        .map(|udev_filter| udev_filter.value)
+        .collect();
+    if subsystems.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(subsystems))
+    }
+}
+
 /// This parses a udev rule and returns a list of UdevFilter objects that specify which devices to search for.
 /// This returns an error if the udev rule parameter does not fit the format specified in udev
 /// man pages/wiki and therefore does not match the grammar specified in udev_rule_grammar.pest
@@ -53,7 +82,14 @@ fn parse_udev_rule(udev_rule_string: &str) -> Result<Vec<UdevFilter>, anyhow::Er
 
     // So long as parse succeeds, subsequent unwraps will not fails, since they are following the
     // format specified in the grammar
-    let udev_rule = UdevRuleParser::parse(Rule::udev_rule, udev_rule_string)?
+    let udev_rule = UdevRuleParser::parse(Rule::udev_rule, udev_rule_string)
+        .map_err(|e| {
+            anyhow::format_err!(
+                "invalid argument: could not parse udev rule [{}]: {}",
+                udev_rule_string,
+                e
+            )
+        })?
         .next() // move to first rule within udev_rule aka inner_rule
         .unwrap() // does not panic because udev_rule always has inner_rule
         .into_inner() // go into inner_rule which has format { udev_filter ~ ("," ~ udev_filter)* }
@@ -70,7 +106,8 @@ fn parse_udev_rule(udev_rule_string: &str) -> Result<Vec<UdevFilter>, anyhow::Er
         let inner_field = field_pair.into_inner().next().unwrap();
         if inner_field.as_rule() == Rule::unsupported_field {
             return Err(anyhow::format_err!(
-                "parse_udev_rule - unsupported field {}",
+                "invalid argument: udev rule [{}] uses unsupported field {}",
+                udev_rule_string,
                 inner_field.into_inner().next().unwrap().as_str()
             ));
         }
@@ -91,8 +128,8 @@ fn parse_udev_rule(udev_rule_string: &str) -> Result<Vec<UdevFilter>, anyhow::Er
                 value: value.to_string(),
             });
         } else {
-            return Err(anyhow::format_err!("parse_udev_rule - unsupported action operation for rule with field [{}], operation [{:?}], and value[{}]",
-            inner_field.into_inner().as_str(), operation, value));
+            return Err(anyhow::format_err!("invalid argument: udev rule [{}] uses unsupported action operation for field [{}], operation [{:?}], and value[{}]",
+            udev_rule_string, inner_field.into_inner().as_str(), operation, value));
         }
     }
     Ok(udev_filters)
@@ -265,7 +302,24 @@ fn filter_by_remaining_udev_filters(
         "filter_by_remaining_udev_filters - enter with udev_filters {:?}",
         udev_filters
     );
-    let mut mutable_devices = devices;
+    // As in udev, all hierarchy fields that must match have to match on the same device,
+    // either the device itself or one of its ancestors
+    let (hierarchy_udev_filters, udev_filters): (Vec<&UdevFilter>, Vec<&UdevFilter>) =
+        udev_filters.into_iter().partition(|udev_filter| {
+            udev_filter.operation == Rule::equality
+                && HIERARCHY_FIELDS.contains(&udev_filter.field.as_rule())
+        });
+    let hierarchy_udev_filters: Vec<(&UdevFilter, Regex)> = hierarchy_udev_filters
+        .into_iter()
+        .map(|udev_filter| (udev_filter, Regex::new(&udev_filter.value).unwrap()))
+        .collect();
+    let mut mutable_devices: Vec<_> = devices
+        .into_iter()
+        .filter(|device| {
+            hierarchy_udev_filters.is_empty()
+                || device_or_parents_match_all(device, &hierarchy_udev_filters)
+        })
+        .collect();
     for udev_filter in udev_filters {
         let value_regex = Regex::new(&udev_filter.value).unwrap();
         let is_equality = udev_filter.operation == Rule::equality;
@@ -437,6 +491,67 @@ fn is_regex_match(test_value: &str, value_regex: &Regex) -> bool {
     }
 }
 
+/// Recursively look up a device's hierarchy to see if it or one of its ancestors matches all of the hierarchy filters.
+fn device_or_parents_match_all(
+    device: &impl DeviceExt,
+    hierarchy_udev_filters: &[(&UdevFilter, Regex)],
+) -> bool {
+    let device_matches_all = hierarchy_udev_filters
+        .iter()
+        .all(|(udev_filter, value_regex)| device_has_field(device, udev_filter, value_regex));
+    if device_matches_all {
+        true
+    } else {
+        match get_parent(device) {
+            Some(parent) => device_or_parents_match_all(&parent, hierarchy_udev_filters),
+            None => false,
+        }
+    }
+}
+
+/// Check whether a device itself, ignoring its ancestors, matches a hierarchy field
+/// (ATTRS, DRIVERS, KERNELS, SUBSYSTEMS or TAGS).
+fn device_has_field(
+    device: &impl DeviceExt,
+    udev_filter: &UdevFilter,
+    value_regex: &Regex,
+) -> bool {
+    let is_value_match = |value: Option<&OsStr>| match value {
+        Some(value) => is_regex_match(value.to_str().unwrap(), value_regex),
+        None => false,
+    };
+    match udev_filter.field.as_rule() {
+        Rule::attributes => {
+            let key = udev_filter
+                .field
+                .clone()
+                .into_inner()
+                .next()
+                .unwrap()
+                .into_inner()
+                .next()
+                .unwrap()
+                .as_str();
+            is_value_match(get_attribute_value(device, key))
+        }
+        Rule::drivers => is_value_match(get_driver(device)),
+        Rule::kernels => is_value_match(Some(get_sysname(device))),
+        Rule::subsystems => is_value_match(get_subsystem(device)),
+        Rule::tags => match get_property_value(device, TAGS) {
+            Some(tags) => tags
+                .to_str()
+                .unwrap()
+                .split(':')
+                .any(|tag| is_regex_match(tag, value_regex)),
+            None => false,
+        },
+        _ => {
+            error!("device_has_field - encountered non-hierarchy field");
+            false
+        }
+    }
+}
+
 /// Recursively look up a device's hierarchy to see if it or one of its ancestors has a specified subsystem.
 fn device_or_parents_have_subsystem(device: &impl DeviceExt, value_regex: &Regex) -> bool {
     match get_subsystem(device) {
@@ -654,6 +769,22 @@ mod discovery_tests {
         assert!(parse_udev_rule(rule).is_err());
     }
 
+    #[test]
+    fn test_parse_udev_rule_invalid_argument() {
+        let rule = "SUBSYSTEM==\"video4linux\", ACTION==\"add\"";
+        let error = parse_udev_rule(rule).unwrap_err().to_string();
+        assert!(error.starts_with("invalid argument"));
+        assert!(error.contains("unsupported field ACTION"));
+
+        let rule = "SUBSYSTEM=\"video4linux\"";
+        let error = parse_udev_rule(rule).unwrap_err().to_string();
+        assert!(error.starts_with("invalid argument"));
+
+        let rule = "TYPO==\"blah\"";
+        let error = parse_udev_rule(rule).unwrap_err().to_string();
+        assert!(error.starts_with("invalid argument: could not parse udev rule"));
+    }
+
     #[test]
     fn test_parse_udev_rule_empty() {
         // Assert that doesn't throw error on empty rules
@@ -894,6 +1025,83 @@ mod discovery_tests {
         assert_eq!(filtered_devices.len(), 0);
     }
 
+    // Test that hierarchy fields that must match are matched on the same device, as udev does
+    #[test]
+    fn test_filter_by_hierarchy_fields_on_same_device() {
+        let mut usb_attributes = std::collections::HashMap::new();
+        usb_attributes.insert("idVendor".to_string(), "05a9".to_string());
+        // Camera whose USB parent has the vendor id
+        let mock_usb_parent = create_mock_device(
+            "/devices/path",
+            "/dev/node",
+            "1-1",
+            HashMap::new(),
+            usb_attributes.clone(),
+            None,
+            Some(OsStr::new("usb")),
+            None,
+        );
+        let mock_usb_camera = create_mock_device(
+            "/devices/path",
+            "/dev/video0",
+            "video0",
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            Some(OsStr::new("video4linux")),
+            Some(mock_usb_parent),
+        );
+        // Camera with a USB parent, but whose vendor id is on a non-USB grandparent
+        let mock_pci_grandparent = create_mock_device(
+            "/devices/path",
+            "/dev/node",
+            "0000:00:14.0",
+            HashMap::new(),
+            usb_attributes,
+            None,
+            Some(OsStr::new("pci")),
+            None,
+        );
+        let mock_other_usb_parent = create_mock_device(
+            "/devices/path",
+            "/dev/node",
+            "2-1",
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            Some(OsStr::new("usb")),
+            Some(mock_pci_grandparent),
+        );
+        let mock_other_camera = create_mock_device(
+            "/devices/path",
+            "/dev/video1",
+            "video1",
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            Some(OsStr::new("video4linux")),
+            Some(mock_other_usb_parent),
+        );
+        let devices = vec![mock_usb_camera, mock_other_camera];
+
+        let rule = "SUBSYSTEMS==\"usb\", ATTRS{idVendor}==\"05a9\"";
+        let udev_filters = parse_udev_rule(rule).unwrap();
+        let udev_filters: Vec<&UdevFilter> = udev_filters.iter().collect();
+        let filtered_devices = filter_by_remaining_udev_filters(devices.clone(), udev_filters);
+        assert_eq!(filtered_devices.len(), 1);
+        assert_eq!(
+            get_sysname(&filtered_devices[0]).to_str().unwrap(),
+            "video0"
+        );
+
+        // Negated hierarchy fields exclude devices with any ancestor that matches
+        let rule = "SUBSYSTEMS==\"usb\", ATTRS{idVendor}!=\"05a9\"";
+        let udev_filters = parse_udev_rule(rule).unwrap();
+        let udev_filters: Vec<&UdevFilter> = udev_filters.iter().collect();
+        let filtered_devices = filter_by_remaining_udev_filters(devices, udev_filters);
+        assert_eq!(filtered_devices.len(), 0);
+    }
+
     #[test]
     fn test_filter_by_subsystems() {
         let rule = "SUBSYSTEMS==\"usb\"";
@@ -1286,6 +1494,21 @@ mod discovery_tests {
         );
     }
 
+    #[test]
+    fn test_get_matched_subsystems() {
+        let rule = "KERNEL==\"video[0-9]*\", SUBSYSTEM==\"video4linux\"";
+        assert_eq!(
+            get_matched_subsystems(rule).unwrap(),
+            Some(vec!["video4linux".to_string()])
+        );
+
+        let rule = "KERNEL==\"video[0-9]*\", SUBSYSTEM!=\"usb\"";
+        assert_eq!(get_matched_subsystems(rule).unwrap(), None);
+
+        let rule = "TYPO==\"blah\"";
+        assert!(get_matched_subsystems(rule).is_err());
+    }
+
     // Only tests that proper match calls were made
     #[test]
     fn test_do_parse_and_find() {
@@ -152,3 +152,321 @@ pub mod udev_enumerator {
         }
     }
 }
+
+pub mod udev_monitor {
+    extern crate udev;
+    use mockall::*;
+    use std::{
+        collections::HashSet,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::{
+        sync::mpsc::{self, error::TrySendError},
+        time::{delay_for, timeout, Instant},
+    };
+
+    /// How often the monitor is polled when udev has no pending events
+    const MONITOR_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum UdevEventKind {
+        Add,
+        Remove,
+        Other,
+    }
+
+    /// The parts of a udev event that decide whether devices should be re-enumerated
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct UdevEvent {
+        pub kind: UdevEventKind,
+        pub subsystem: Option<String>,
+    }
+
+    /// Wrap udev::MonitorSocket in a trait to enable injecting events for testing.
+    #[automock]
+    pub trait UdevMonitor {
+        /// Returns the next pending event, if any, without blocking
+        fn next_event(&mut self) -> Option<UdevEvent>;
+    }
+
+    pub struct UdevMonitorImpl {
+        inner_monitor: udev::MonitorSocket,
+    }
+
+    impl UdevMonitorImpl {
+        /// Listens for udev events from the given subsystems, or from all subsystems if none are given
+        pub fn new(subsystems: &HashSet<String>) -> std::io::Result<Self> {
+            let mut builder = udev::MonitorBuilder::new()?;
+            for subsystem in subsystems {
+                builder = builder.match_subsystem(subsystem)?;
+            }
+            Ok(UdevMonitorImpl {
+                inner_monitor: builder.listen()?,
+            })
+        }
+    }
+
+    impl UdevMonitor for UdevMonitorImpl {
+        fn next_event(&mut self) -> Option<UdevEvent> {
+            self.inner_monitor.next().map(|event| UdevEvent {
+                kind: match event.event_type() {
+                    udev::EventType::Add => UdevEventKind::Add,
+                    udev::EventType::Remove => UdevEventKind::Remove,
+                    _ => UdevEventKind::Other,
+                },
+                subsystem: event
+                    .subsystem()
+                    .map(|subsystem| subsystem.to_string_lossy().to_string()),
+            })
+        }
+    }
+
+    /// Returns whether an event may have changed which devices match the udev rules
+    fn is_relevant_event(event: &UdevEvent, subsystems: &HashSet<String>) -> bool {
+        if event.kind == UdevEventKind::Other {
+            return false;
+        }
+        if subsystems.is_empty() {
+            return true;
+        }
+        match &event.subsystem {
+            Some(subsystem) => subsystems.contains(subsystem),
+            None => false,
+        }
+    }
+
+    /// Polls the monitor until `stop` is set, notifying `change_sender` of every add or remove event
+    /// from one of `subsystems` (or from any subsystem if `subsystems` is empty).
+    /// Notifications that arrive while one is already pending are coalesced.
+    pub fn watch_for_changes(
+        mut monitor: impl UdevMonitor,
+        subsystems: &HashSet<String>,
+        mut change_sender: mpsc::Sender<()>,
+        stop: &AtomicBool,
+    ) {
+        while !stop.load(Ordering::SeqCst) {
+            match monitor.next_event() {
+                Some(event) => {
+                    if !is_relevant_event(&event, subsystems) {
+                        continue;
+                    }
+                    trace!("watch_for_changes - received udev event {:?}", event);
+                    if let Err(TrySendError::Closed(_)) = change_sender.try_send(()) {
+                        trace!("watch_for_changes - change receiver dropped ... returning");
+                        return;
+                    }
+                }
+                None => std::thread::sleep(MONITOR_POLL_INTERVAL),
+            }
+        }
+    }
+
+    /// Starts a thread that watches udev for add and remove events from `subsystems`,
+    /// returning the receiver that it notifies of them. The thread exits once `stop` is set.
+    pub fn start_watching(
+        subsystems: HashSet<String>,
+        stop: Arc<AtomicBool>,
+    ) -> mpsc::Receiver<()> {
+        let (change_sender, change_receiver) = mpsc::channel(1);
+        std::thread::spawn(move || {
+            match UdevMonitorImpl::new(&subsystems) {
+            Ok(monitor) => watch_for_changes(monitor, &subsystems, change_sender, &stop),
+            Err(e) => error!(
+                "start_watching - could not create udev monitor {} ... relying on periodic discovery",
+                e
+            ),
+        }
+        });
+        change_receiver
+    }
+
+    /// Waits up to `max_wait` for a change to be reported on `change_receiver`.
+    /// Once one arrives, waits a further `debounce` so that a burst of events
+    /// (such as a device and its interfaces being added) results in a single re-enumeration.
+    /// Returns whether a change was reported.
+    pub async fn wait_for_change(
+        change_receiver: &mut mpsc::Receiver<()>,
+        max_wait: Duration,
+        debounce: Duration,
+    ) -> bool {
+        let start = Instant::now();
+        match timeout(max_wait, change_receiver.recv()).await {
+            Ok(Some(())) => {
+                delay_for(debounce).await;
+                while change_receiver.try_recv().is_ok() {}
+                true
+            }
+            Ok(None) => {
+                // The watcher has stopped, so fall back to periodic discovery
+                if let Some(remaining) = max_wait.checked_sub(start.elapsed()) {
+                    delay_for(remaining).await;
+                }
+                false
+            }
+            Err(_) => false,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn event(kind: UdevEventKind, subsystem: &str) -> UdevEvent {
+            UdevEvent {
+                kind,
+                subsystem: Some(subsystem.to_string()),
+            }
+        }
+
+        /// Creates a monitor that reports `events` and then sets `stop`
+        fn mock_monitor(events: Vec<UdevEvent>, stop: Arc<AtomicBool>) -> MockUdevMonitor {
+            let mut events = events.into_iter();
+            let mut mock_monitor = MockUdevMonitor::new();
+            mock_monitor.expect_next_event().returning(move || {
+                let next = events.next();
+                if next.is_none() {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                next
+            });
+            mock_monitor
+        }
+
+        #[test]
+        fn test_is_relevant_event() {
+            let video: HashSet<String> = vec!["video4linux".to_string()].into_iter().collect();
+            assert!(is_relevant_event(
+                &event(UdevEventKind::Add, "video4linux"),
+                &video
+            ));
+            assert!(is_relevant_event(
+                &event(UdevEventKind::Remove, "video4linux"),
+                &video
+            ));
+            assert!(!is_relevant_event(
+                &event(UdevEventKind::Other, "video4linux"),
+                &video
+            ));
+            assert!(!is_relevant_event(
+                &event(UdevEventKind::Add, "usb"),
+                &video
+            ));
+            assert!(!is_relevant_event(
+                &UdevEvent {
+                    kind: UdevEventKind::Add,
+                    subsystem: None
+                },
+                &video
+            ));
+            assert!(is_relevant_event(
+                &event(UdevEventKind::Add, "usb"),
+                &HashSet::new()
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_watch_for_changes_reports_matching_events() {
+            let _ = env_logger::builder().is_test(true).try_init();
+            let stop = Arc::new(AtomicBool::new(false));
+            let monitor = mock_monitor(
+                vec![
+                    event(UdevEventKind::Other, "video4linux"),
+                    event(UdevEventKind::Add, "usb"),
+                    event(UdevEventKind::Add, "video4linux"),
+                ],
+                stop.clone(),
+            );
+            let subsystems: HashSet<String> = vec!["video4linux".to_string()].into_iter().collect();
+            let (change_sender, mut change_receiver) = mpsc::channel(1);
+            watch_for_changes(monitor, &subsystems, change_sender, &stop);
+            assert_eq!(Some(()), change_receiver.recv().await);
+            // The watcher has returned and dropped its sender
+            assert_eq!(None, change_receiver.recv().await);
+        }
+
+        #[tokio::test]
+        async fn test_watch_for_changes_ignores_other_events() {
+            let _ = env_logger::builder().is_test(true).try_init();
+            let stop = Arc::new(AtomicBool::new(false));
+            let monitor = mock_monitor(
+                vec![
+                    event(UdevEventKind::Other, "video4linux"),
+                    event(UdevEventKind::Remove, "usb"),
+                ],
+                stop.clone(),
+            );
+            let subsystems: HashSet<String> = vec!["video4linux".to_string()].into_iter().collect();
+            let (change_sender, mut change_receiver) = mpsc::channel(1);
+            watch_for_changes(monitor, &subsystems, change_sender, &stop);
+            assert_eq!(None, change_receiver.recv().await);
+        }
+
+        #[tokio::test]
+        async fn test_watch_for_changes_coalesces_bursts() {
+            let _ = env_logger::builder().is_test(true).try_init();
+            let stop = Arc::new(AtomicBool::new(false));
+            let monitor = mock_monitor(
+                vec![
+                    event(UdevEventKind::Add, "video4linux"),
+                    event(UdevEventKind::Add, "video4linux"),
+                    event(UdevEventKind::Remove, "video4linux"),
+                ],
+                stop.clone(),
+            );
+            let (change_sender, mut change_receiver) = mpsc::channel(1);
+            watch_for_changes(monitor, &HashSet::new(), change_sender, &stop);
+            assert_eq!(Some(()), change_receiver.recv().await);
+            assert_eq!(None, change_receiver.recv().await);
+        }
+
+        #[tokio::test]
+        async fn test_wait_for_change() {
+            let _ = env_logger::builder().is_test(true).try_init();
+            let (mut change_sender, mut change_receiver) = mpsc::channel(1);
+
+            // No events within max_wait
+            assert!(
+                !wait_for_change(
+                    &mut change_receiver,
+                    Duration::from_millis(10),
+                    Duration::from_millis(10)
+                )
+                .await
+            );
+
+            // An event is reported and any that arrive during the debounce are drained
+            change_sender.try_send(()).unwrap();
+            let mut burst_sender = change_sender.clone();
+            tokio::spawn(async move {
+                delay_for(Duration::from_millis(10)).await;
+                burst_sender.try_send(()).unwrap();
+            });
+            assert!(
+                wait_for_change(
+                    &mut change_receiver,
+                    Duration::from_secs(5),
+                    Duration::from_millis(100)
+                )
+                .await
+            );
+            assert!(change_receiver.try_recv().is_err());
+
+            // A stopped watcher falls back to waiting out max_wait
+            drop(change_sender);
+            let start = Instant::now();
+            assert!(
+                !wait_for_change(
+                    &mut change_receiver,
+                    Duration::from_millis(50),
+                    Duration::from_millis(10)
+                )
+                .await
+            );
+            assert!(start.elapsed() >= Duration::from_millis(50));
+        }
+    }
+}
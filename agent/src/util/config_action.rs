@@ -1,41 +1,192 @@
-use super::super::{protocols, DISCOVERY_RESPONSE_TIME_METRIC, INSTANCE_COUNT_METRIC};
+use super::super::{
+    protocols, DISCOVERY_CIRCUIT_OPEN_METRIC, DISCOVERY_HANDLER_COUNT_METRIC,
+    DISCOVERY_RESPONSE_TIME_METRIC, DISCOVERY_RESULT_COUNT_METRIC, INSTANCE_COUNT_METRIC,
+};
 use super::{
     constants::{
-        DEVICE_PLUGIN_PATH, DISCOVERY_DELAY_SECS, SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS,
+        BUILD_DEVICE_PLUGIN_TIMEOUT_SECS, CIRCUIT_BREAKER_FAILURE_THRESHOLD,
+        CIRCUIT_BREAKER_RESET_SECS, CIRCUIT_BREAKER_WINDOW_SECS,
+        DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS, DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS,
+        DEVICE_PLUGIN_PATH, KUBELET_SOCKET, KUBELET_SOCKET_CHECK_DELAY_SECS,
+        SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS,
     },
-    device_plugin_service,
+    debug_server, device_plugin_service,
     device_plugin_service::{
         get_device_instance_name, ConnectivityStatus, InstanceInfo, InstanceMap,
+        ListAndWatchMessageKind,
+    },
+    event_recorder::{
+        get_configuration_reference, EventRecorder, INSTANCE_BACK_ONLINE_REASON,
+        INSTANCE_CAPACITY_NOT_CHANGED_REASON, INSTANCE_CREATED_REASON,
+        INSTANCE_DELETED_GRACE_PERIOD_EXPIRED, INSTANCE_DELETED_LOCAL_DISAPPEARED,
+        INSTANCE_DELETED_REASON, INSTANCE_OFFLINE_REASON,
     },
 };
 use akri_shared::{
     akri::{
-        configuration::{Configuration, KubeAkriConfig, ProtocolHandler},
+        configuration::{
+            Configuration, ConfigurationCondition, KubeAkriConfig, ProtocolHandler,
+            DISCOVERY_ERROR_CONDITION,
+        },
+        instance::{get_devices_patch, KubeAkriInstance},
         API_CONFIGURATIONS, API_NAMESPACE, API_VERSION,
     },
     k8s,
     k8s::KubeInterface,
+    log_fields,
+    os::env_var::{ActualEnvVarQuery, EnvVarQuery},
 };
-use futures::StreamExt;
+use chrono::{SecondsFormat, Utc};
+use futures::{stream, Future, StreamExt};
 use kube::api::{Informer, RawApi, WatchEvent};
-use log::{info, trace};
+use log::{info, trace, Level};
 use std::{
-    collections::HashMap,
-    sync::Arc,
+    collections::{BTreeMap, HashMap, VecDeque},
+    env,
+    os::unix::fs::MetadataExt,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
-use tokio::{
-    sync::{broadcast, mpsc, Mutex},
-    time::timeout,
-};
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+pub type ConfigMap = Arc<Mutex<HashMap<String, ConfigInfo>>>;
+
+/// Labels of this Agent's node, looked up the first time a Configuration with a node selector is seen
+pub type NodeLabels = Arc<Mutex<Option<BTreeMap<String, String>>>>;
+
+/// Name of the environment variable that limits how many device plugins are built at once for a Configuration
+pub const MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS_LABEL: &str =
+    "AKRI_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS";
+
+/// Name of the environment variable that sets how long a discovery handler may take to respond before it is considered hung
+pub const DISCOVERY_STALL_TIMEOUT_LABEL: &str = "AKRI_DISCOVERY_STALL_TIMEOUT_SECS";
+
+/// `connectivity_status` of discovery handlers whose last discovery succeeded
+const DISCOVERY_HANDLER_ONLINE: &str = "Online";
+/// `connectivity_status` of discovery handlers whose last discovery failed
+const DISCOVERY_HANDLER_OFFLINE: &str = "Offline";
+/// `connectivity_status` of discovery handlers whose discovery is paused after failing repeatedly
+const DISCOVERY_HANDLER_CIRCUIT_OPEN: &str = "CircuitOpen";
+
+/// Counts a running discovery handler in `DISCOVERY_HANDLER_COUNT_METRIC` under its current
+/// connectivity status, removing it from the count when dropped.
+struct DiscoveryHandlerStatusMetric {
+    protocol: &'static str,
+    connectivity_status: Option<&'static str>,
+}
+
+impl DiscoveryHandlerStatusMetric {
+    fn new(protocol: &'static str) -> Self {
+        DiscoveryHandlerStatusMetric {
+            protocol,
+            connectivity_status: None,
+        }
+    }
+
+    fn set(&mut self, connectivity_status: &'static str) {
+        if self.connectivity_status == Some(connectivity_status) {
+            return;
+        }
+        if let Some(previous_status) = self.connectivity_status {
+            DISCOVERY_HANDLER_COUNT_METRIC
+                .with_label_values(&[self.protocol, previous_status])
+                .dec();
+        }
+        DISCOVERY_HANDLER_COUNT_METRIC
+            .with_label_values(&[self.protocol, connectivity_status])
+            .inc();
+        self.connectivity_status = Some(connectivity_status);
+    }
+}
+
+impl Drop for DiscoveryHandlerStatusMetric {
+    fn drop(&mut self) {
+        if let Some(connectivity_status) = self.connectivity_status {
+            DISCOVERY_HANDLER_COUNT_METRIC
+                .with_label_values(&[self.protocol, connectivity_status])
+                .dec();
+        }
+    }
+}
+
+/// Pauses discovery for a Configuration whose discovery handler keeps failing, such as one given a wrong address
+/// or bad credentials, rather than retrying it every interval. The circuit opens once `failure_threshold`
+/// consecutive discoveries fail within `window`. After `reset`, it half-opens to let one discovery through:
+/// success closes the circuit and failure opens it again.
+struct DiscoveryCircuitBreaker {
+    failure_threshold: usize,
+    window: Duration,
+    reset: Duration,
+    /// Times of the most recent consecutive failures, oldest first
+    failures: VecDeque<Instant>,
+    /// When the circuit last opened, or None if it is closed
+    opened_at: Option<Instant>,
+}
+
+impl DiscoveryCircuitBreaker {
+    fn new(failure_threshold: usize, window: Duration, reset: Duration) -> Self {
+        DiscoveryCircuitBreaker {
+            failure_threshold,
+            window,
+            reset,
+            failures: VecDeque::new(),
+            opened_at: None,
+        }
+    }
+
+    /// Returns how much longer discovery is paused, or None if discovery may be attempted
+    fn remaining_pause(&self, now: Instant) -> Option<Duration> {
+        let reopens_at = self.opened_at? + self.reset;
+        if now < reopens_at {
+            Some(reopens_at - now)
+        } else {
+            None
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.failures.clear();
+        self.opened_at = None;
+    }
 
-type ConfigMap = Arc<Mutex<HashMap<String, ConfigInfo>>>;
+    /// Records a failed discovery, returning whether it opened the circuit
+    fn record_failure(&mut self, now: Instant) -> bool {
+        if self.opened_at.is_some() {
+            // The attempt allowed through while half-open failed
+            self.opened_at = Some(now);
+            return true;
+        }
+        self.failures.push_back(now);
+        if self.failures.len() > self.failure_threshold {
+            self.failures.pop_front();
+        }
+        match self.failures.front() {
+            Some(first_failure)
+                if self.failures.len() == self.failure_threshold
+                    && now.duration_since(*first_failure) < self.window =>
+            {
+                self.failures.clear();
+                self.opened_at = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+}
 
 /// Information for managing a Configuration, such as all applied Instances of that Configuration
 /// and senders for ceasing to discover instances upon Configuration deletion.
 #[derive(Debug)]
 pub struct ConfigInfo {
     instance_map: InstanceMap,
+    /// Spec the Configuration's discovery was started with, or last updated to by a change of capacity
+    config_spec: Configuration,
+    /// Capacity of the Configuration, shared with its periodic discovery so that a change of capacity
+    /// applies to Instances discovered afterwards
+    capacity: Arc<AtomicI32>,
     stop_discovery_sender: mpsc::Sender<()>,
     finished_discovery_sender: broadcast::Sender<()>,
 }
@@ -47,9 +198,36 @@ pub async fn do_config_watch() -> Result<(), Box<dyn std::error::Error + Send +
     let kube_interface = k8s::create_kube_interface();
     let mut tasks = Vec::new();
 
+    // Serve the state of each Configuration's Instances if requested
+    if let Some(port) = debug_server::get_debug_server_port(&ActualEnvVarQuery {}) {
+        let config_map = config_map.clone();
+        tasks.push(tokio::spawn(async move {
+            debug_server::run_debug_server(config_map, port)
+                .await
+                .unwrap();
+        }));
+    }
+
+    // Re-register device plugins whenever kubelet restarts and forgets them
+    {
+        let config_map = config_map.clone();
+        tasks.push(tokio::spawn(async move {
+            watch_kubelet_socket(
+                config_map,
+                KUBELET_SOCKET,
+                Duration::from_secs(KUBELET_SOCKET_CHECK_DELAY_SECS),
+            )
+            .await;
+        }));
+    }
+
     // Handle pre-existing configs
+    let node_labels: NodeLabels = Arc::new(Mutex::new(None));
     let pre_existing_configs = kube_interface.get_configurations().await?;
     for config in pre_existing_configs {
+        if !should_discover_on_node(&kube_interface, &config, &node_labels).await {
+            continue;
+        }
         let config_map = config_map.clone();
         tasks.push(tokio::spawn(async move {
             handle_config_add(&config, config_map).await.unwrap();
@@ -58,7 +236,7 @@ pub async fn do_config_watch() -> Result<(), Box<dyn std::error::Error + Send +
 
     // Watch for new configs and changes
     tasks.push(tokio::spawn(async move {
-        watch_for_config_changes(&kube_interface, config_map)
+        watch_for_config_changes(&kube_interface, config_map, node_labels)
             .await
             .unwrap();
     }));
@@ -68,10 +246,79 @@ pub async fn do_config_watch() -> Result<(), Box<dyn std::error::Error + Send +
     Ok(())
 }
 
+/// Returns each Configuration's name and `InstanceMap`, without holding the `ConfigMap` lock
+/// while the `InstanceMap`s are read
+pub async fn get_instance_maps(config_map: &ConfigMap) -> Vec<(String, InstanceMap)> {
+    config_map
+        .lock()
+        .await
+        .iter()
+        .map(|(config_name, config_info)| (config_name.clone(), config_info.instance_map.clone()))
+        .collect()
+}
+
+/// Returns the inode of the kubelet registration socket, or None if kubelet is not serving it
+fn get_kubelet_socket_inode(kubelet_socket: &str) -> Option<u64> {
+    std::fs::metadata(kubelet_socket)
+        .map(|metadata| metadata.ino())
+        .ok()
+}
+
+/// Returns whether kubelet has restarted given the inode of its socket when it was last seen and now.
+/// Kubelet recreates its socket when it starts, so a socket with a new inode belongs to a new kubelet.
+/// The socket going missing is not a restart until it comes back.
+fn has_kubelet_restarted(last_inode: &mut Option<u64>, inode: Option<u64>) -> bool {
+    match (*last_inode, inode) {
+        (_, None) => false,
+        (last, Some(inode)) => {
+            *last_inode = Some(inode);
+            last.map_or(false, |last| last != inode)
+        }
+    }
+}
+
+/// Checks every `check_delay` whether kubelet has restarted. A restarted kubelet forgets every device plugin
+/// that registered with it and deletes their sockets, so each Configuration's DevicePluginServices are then
+/// served on new sockets and registered with the new kubelet.
+async fn watch_kubelet_socket(config_map: ConfigMap, kubelet_socket: &str, check_delay: Duration) {
+    let mut last_inode = get_kubelet_socket_inode(kubelet_socket);
+    loop {
+        tokio::time::delay_for(check_delay).await;
+        if has_kubelet_restarted(&mut last_inode, get_kubelet_socket_inode(kubelet_socket)) {
+            info!(
+                "watch_kubelet_socket - kubelet socket {} was recreated ... re-registering device plugins",
+                kubelet_socket
+            );
+            reregister_device_plugins(&config_map).await;
+        }
+    }
+}
+
+/// Tells the DevicePluginService of every Instance of every Configuration to serve itself on a new socket and
+/// register with kubelet again. The Instances stay in their InstanceMaps.
+async fn reregister_device_plugins(config_map: &ConfigMap) {
+    for (config_name, instance_map) in get_instance_maps(config_map).await {
+        for (instance_name, instance_info) in instance_map.lock().await.iter() {
+            log_fields!(
+                Level::Trace,
+                { configuration: config_name, instance: instance_name },
+                "reregister_device_plugins - re-registering device plugin for Instance {} of config {}",
+                instance_name,
+                config_name
+            );
+            instance_info
+                .list_and_watch_message_sender
+                .send(ListAndWatchMessageKind::Reregister)
+                .unwrap_or(0);
+        }
+    }
+}
+
 /// This watches for Configuration events
 async fn watch_for_config_changes(
     kube_interface: &impl KubeInterface,
     config_map: ConfigMap,
+    node_labels: NodeLabels,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
     trace!("watch_for_config_changes - start");
     let akri_config_type = RawApi::customResource(API_CONFIGURATIONS)
@@ -86,7 +333,7 @@ async fn watch_for_config_changes(
         // Currently, this does not handle None except to break the
         // while.
         while let Some(event) = configs.next().await {
-            handle_config(kube_interface, event?, config_map.clone()).await?
+            handle_config(kube_interface, event?, config_map.clone(), &node_labels).await?
         }
     }
 }
@@ -97,21 +344,29 @@ async fn handle_config(
     kube_interface: &impl KubeInterface,
     event: WatchEvent<KubeAkriConfig>,
     config_map: ConfigMap,
+    node_labels: &NodeLabels,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
     trace!("handle_config - something happened to a configuration");
     match event {
         WatchEvent::Added(config) => {
-            info!(
+            log_fields!(
+                Level::Info,
+                { configuration: config.metadata.name },
                 "handle_config - added Configuration {}",
                 config.metadata.name
             );
+            if !should_discover_on_node(kube_interface, &config, node_labels).await {
+                return Ok(());
+            }
             tokio::spawn(async move {
                 handle_config_add(&config, config_map).await.unwrap();
             });
             Ok(())
         }
         WatchEvent::Deleted(config) => {
-            info!(
+            log_fields!(
+                Level::Info,
+                { configuration: config.metadata.name },
                 "handle_config - deleted Configuration {}",
                 config.metadata.name,
             );
@@ -120,11 +375,37 @@ async fn handle_config(
         }
         // If a config is updated, delete all associated instances and device plugins and then recreate them to reflect updated config
         WatchEvent::Modified(config) => {
-            info!(
+            // Updates to the Configuration's status, such as those written by Agents, do not change how it is discovered
+            if is_spec_unchanged(&config, &config_map).await {
+                log_fields!(
+                    Level::Trace,
+                    { configuration: config.metadata.name },
+                    "handle_config - ignoring modification to status of Configuration {}",
+                    config.metadata.name
+                );
+                return Ok(());
+            }
+            // A new capacity is applied to the existing Instances rather than rediscovering them
+            if is_only_capacity_changed(&config, &config_map).await {
+                log_fields!(
+                    Level::Info,
+                    { configuration: config.metadata.name },
+                    "handle_config - capacity of Configuration {} changed to {}",
+                    config.metadata.name, config.spec.capacity
+                );
+                handle_config_capacity_change(kube_interface, &config, config_map).await;
+                return Ok(());
+            }
+            log_fields!(
+                Level::Info,
+                { configuration: config.metadata.name },
                 "handle_config - modified Configuration {}",
                 config.metadata.name,
             );
             handle_config_delete(kube_interface, &config, config_map.clone()).await?;
+            if !should_discover_on_node(kube_interface, &config, node_labels).await {
+                return Ok(());
+            }
             tokio::spawn(async move {
                 handle_config_add(&config, config_map).await.unwrap();
             });
@@ -137,6 +418,142 @@ async fn handle_config(
     }
 }
 
+/// Returns whether this node's labels satisfy the Configuration's node selector.
+/// The node's labels are looked up once and cached in `node_labels`, so label changes
+/// are picked up when the Agent restarts. Nodes that cannot be looked up are treated
+/// as not selected.
+async fn should_discover_on_node(
+    kube_interface: &impl KubeInterface,
+    config: &KubeAkriConfig,
+    node_labels: &NodeLabels,
+) -> bool {
+    let node_selector = &config.spec.node_selector;
+    if node_selector.is_empty() {
+        return true;
+    }
+    let node_name = match env::var("AGENT_NODE_NAME") {
+        Ok(node_name) => node_name,
+        Err(e) => {
+            error!(
+                "should_discover_on_node - could not get AGENT_NODE_NAME: {}",
+                e
+            );
+            return false;
+        }
+    };
+    let mut node_labels = node_labels.lock().await;
+    if node_labels.is_none() {
+        match kube_interface.find_node(&node_name).await {
+            Ok(node) => *node_labels = Some(node.metadata.labels),
+            Err(e) => {
+                log_fields!(
+                    Level::Error,
+                    { configuration: config.metadata.name },
+                    "should_discover_on_node - error {} finding node {} ... skipping Configuration {}",
+                    e, node_name, config.metadata.name
+                );
+                return false;
+            }
+        }
+    }
+    let labels = node_labels.as_ref().unwrap();
+    let selected = node_selector
+        .iter()
+        .all(|(key, value)| labels.get(key) == Some(value));
+    if !selected {
+        log_fields!(
+            Level::Info,
+            { configuration: config.metadata.name },
+            "should_discover_on_node - node {} does not match the node selector of Configuration {} ... skipping",
+            node_name, config.metadata.name
+        );
+    }
+    selected
+}
+
+/// Returns whether discovery is already running for a Configuration with the same spec
+async fn is_spec_unchanged(config: &KubeAkriConfig, config_map: &ConfigMap) -> bool {
+    match config_map.lock().await.get(&config.metadata.name) {
+        Some(config_info) => {
+            serde_json::to_value(&config_info.config_spec).ok()
+                == serde_json::to_value(&config.spec).ok()
+        }
+        None => false,
+    }
+}
+
+/// Returns whether discovery is already running for a Configuration whose spec differs only in its capacity
+async fn is_only_capacity_changed(config: &KubeAkriConfig, config_map: &ConfigMap) -> bool {
+    match config_map.lock().await.get(&config.metadata.name) {
+        Some(config_info) if config_info.config_spec.capacity != config.spec.capacity => {
+            let mut config_spec = config.spec.clone();
+            config_spec.capacity = config_info.config_spec.capacity;
+            serde_json::to_value(&config_info.config_spec).ok()
+                == serde_json::to_value(&config_spec).ok()
+        }
+        _ => false,
+    }
+}
+
+/// This handles a change to a Configuration's capacity by changing the number of usage slots of each of its
+/// Instances and having their DevicePluginServices advertise the new number to kubelet. Instances discovered
+/// afterwards are created with the new capacity. An Instance cannot be given fewer slots than are allocated,
+/// in which case it keeps its slots and an Event is recorded on the Configuration.
+async fn handle_config_capacity_change(
+    kube_interface: &impl KubeInterface,
+    config: &KubeAkriConfig,
+    config_map: ConfigMap,
+) {
+    let (instance_map, previous_capacity) =
+        match config_map.lock().await.get_mut(&config.metadata.name) {
+            Some(config_info) => {
+                let previous_capacity = config_info.config_spec.capacity;
+                config_info.config_spec = config.spec.clone();
+                config_info
+                    .capacity
+                    .store(config.spec.capacity, Ordering::SeqCst);
+                (config_info.instance_map.clone(), previous_capacity)
+            }
+            None => return,
+        };
+    let namespace = config.metadata.namespace.as_ref().unwrap();
+    let configuration = get_configuration_reference(
+        &config.metadata.name,
+        config.metadata.uid.as_deref().unwrap_or_default(),
+        namespace,
+    );
+    let event_recorder = EventRecorder::new();
+    let instances = instance_map.lock().await.clone();
+    for (instance_name, instance_info) in instances {
+        if let Err(e) = device_plugin_service::update_instance_capacity(
+            &instance_name,
+            namespace,
+            previous_capacity,
+            config.spec.capacity,
+            &instance_info,
+            kube_interface,
+        )
+        .await
+        {
+            log_fields!(
+                Level::Error,
+                { configuration: config.metadata.name, instance: instance_name },
+                "handle_config_capacity_change - could not change capacity of Instance {}: {}",
+                instance_name, e
+            );
+            event_recorder
+                .record(
+                    kube_interface,
+                    &configuration,
+                    &instance_name,
+                    INSTANCE_CAPACITY_NOT_CHANGED_REASON,
+                    e.to_string(),
+                )
+                .await;
+        }
+    }
+}
+
 /// This handles added Configuration by creating a new ConfigInfo for it and adding it to the ConfigMap.
 /// Then calls a function to continually observe the availability of instances associated with the Configuration.
 async fn handle_config_add(
@@ -145,13 +562,17 @@ async fn handle_config_add(
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
     let config_protocol = config.spec.protocol.clone();
     let discovery_handler = protocols::get_discovery_handler(&config_protocol)?;
-    let discovery_results = discovery_handler.discover().await?;
+    // Reject an invalid discovery interval before doing any discovery.
+    // Errors from discovery itself are reported on the Configuration's status by do_periodic_discovery.
+    discovery_handler.get_discovery_interval()?;
     let config_name = config.metadata.name.clone();
     let config_uid = config.metadata.uid.as_ref().unwrap().clone();
     let config_namespace = config.metadata.namespace.as_ref().unwrap().clone();
-    info!(
-        "handle_config_add - entered for Configuration {} with visible_instances={:?}",
-        config.metadata.name, &discovery_results
+    log_fields!(
+        Level::Info,
+        { configuration: config.metadata.name },
+        "handle_config_add - entered for Configuration {}",
+        config.metadata.name
     );
     // Create a new instance map for this config and add it to the config map
     let instance_map: InstanceMap = Arc::new(Mutex::new(HashMap::new()));
@@ -159,8 +580,11 @@ async fn handle_config_add(
     let (stop_discovery_sender, stop_discovery_receiver) = mpsc::channel(1);
     // Channel capacity: should only ever be sent once upon receiving stop watching message
     let (finished_discovery_sender, _) = broadcast::channel(1);
+    let capacity = Arc::new(AtomicI32::new(config.spec.capacity));
     let config_info = ConfigInfo {
         instance_map: instance_map.clone(),
+        config_spec: config.spec.clone(),
+        capacity: capacity.clone(),
         stop_discovery_sender,
         finished_discovery_sender: finished_discovery_sender.clone(),
     };
@@ -178,8 +602,10 @@ async fn handle_config_add(
             config_uid,
             config_namespace,
             config_spec,
+            capacity,
             config_protocol,
             instance_map,
+            event_recorder: EventRecorder::new(),
         };
         periodic_discovery
             .do_periodic_discovery(
@@ -203,7 +629,19 @@ pub async fn handle_config_delete(
     config: &KubeAkriConfig,
     config_map: ConfigMap,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    trace!(
+    // Configurations whose node selector does not match this node were never discovered
+    if !config_map.lock().await.contains_key(&config.metadata.name) {
+        log_fields!(
+            Level::Trace,
+            { configuration: config.metadata.name },
+            "handle_config_delete - config {} is not being discovered on this node",
+            config.metadata.name
+        );
+        return Ok(());
+    }
+    log_fields!(
+        Level::Trace,
+        { configuration: config.metadata.name },
         "handle_config_delete - for config {} telling do_periodic_discovery to end",
         config.metadata.name
     );
@@ -227,12 +665,16 @@ pub async fn handle_config_delete(
             .finished_discovery_sender
             .subscribe();
         finished_discovery_receiver.recv().await.unwrap();
-        trace!(
+        log_fields!(
+            Level::Trace,
+            { configuration: config.metadata.name },
             "handle_config_delete - for config {} received message that do_periodic_discovery ended",
             config.metadata.name
         );
     } else {
-        trace!(
+        log_fields!(
+            Level::Trace,
+            { configuration: config.metadata.name },
             "handle_config_delete - for config {} do_periodic_discovery receiver has been dropped",
             config.metadata.name
         );
@@ -255,7 +697,9 @@ pub async fn handle_config_delete(
     let instances_to_delete_map = instance_map_locked.clone();
     let namespace = config.metadata.namespace.as_ref().unwrap();
     for (instance_name, instance_info) in instances_to_delete_map {
-        trace!(
+        log_fields!(
+            Level::Trace,
+            { configuration: config.metadata.name, instance: instance_name },
             "handle_config_delete - found Instance {} associated with deleted config {} ... sending message to end list_and_watch",
             instance_name,
             config.metadata.name
@@ -282,7 +726,12 @@ async fn try_delete_instance(
         .await
     {
         Ok(()) => {
-            trace!("delete_instance - deleted Instance {}", instance_name);
+            log_fields!(
+                Level::Trace,
+                { instance: instance_name },
+                "delete_instance - deleted Instance {}",
+                instance_name
+            );
             Ok(())
         }
         Err(e) => {
@@ -291,7 +740,9 @@ async fn try_delete_instance(
                 .find_instance(&instance_name, &instance_namespace)
                 .await
             {
-                trace!(
+                log_fields!(
+                    Level::Trace,
+                    { instance: instance_name },
                     "delete_instance - discovered Instance {} already deleted",
                     instance_name
                 );
@@ -303,23 +754,280 @@ async fn try_delete_instance(
     }
 }
 
+/// Waits up to `timeout` for a DevicePluginService to be built, so that a slow API server cannot stall discovery.
+/// A build that times out is abandoned and reported as an error.
+async fn build_device_plugin_with_timeout(
+    instance_name: &str,
+    timeout: Duration,
+    build: impl Future<
+        Output = Result<
+            broadcast::Sender<ListAndWatchMessageKind>,
+            Box<dyn std::error::Error + Send + Sync + 'static>,
+        >,
+    >,
+) -> Result<
+    broadcast::Sender<ListAndWatchMessageKind>,
+    Box<dyn std::error::Error + Send + Sync + 'static>,
+> {
+    match tokio::time::timeout(timeout, build).await {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "timed out after {} seconds building device plugin for instance {}",
+            timeout.as_secs(),
+            instance_name
+        )
+        .into()),
+    }
+}
+
+/// Returns how many device plugins may be built at once, as set by `AKRI_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS`
+fn get_max_concurrent_device_plugin_builds(query: &impl EnvVarQuery) -> usize {
+    match query.get_env_var(MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS_LABEL) {
+        Ok(max) => match max.parse::<usize>() {
+            Ok(max) if max > 0 => max,
+            _ => {
+                error!(
+                    "get_max_concurrent_device_plugin_builds - {} must be a positive integer but was {} ... using {}",
+                    MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS_LABEL, max, DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS
+                );
+                DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS
+            }
+        },
+        Err(_) => DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS,
+    }
+}
+
+/// Returns how long a discovery handler may take to respond, as set by `AKRI_DISCOVERY_STALL_TIMEOUT_SECS`
+fn get_discovery_stall_timeout(query: &impl EnvVarQuery) -> Duration {
+    match query.get_env_var(DISCOVERY_STALL_TIMEOUT_LABEL) {
+        Ok(secs) => match secs.parse::<u64>() {
+            Ok(secs) if secs > 0 => Duration::from_secs(secs),
+            _ => {
+                error!(
+                    "get_discovery_stall_timeout - {} must be a positive integer but was {} ... using {}",
+                    DISCOVERY_STALL_TIMEOUT_LABEL, secs, DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS
+                );
+                Duration::from_secs(DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS)
+            }
+        },
+        Err(_) => Duration::from_secs(DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS),
+    }
+}
+
+/// Builds the device plugins for newly discovered instances concurrently, running at most `max_concurrent`
+/// of the `(instance name, build)` pairs at a time so that kubelet is not flooded with registrations.
+/// Each build is given `timeout` to finish. Returns the names of the instances whose device plugins
+/// could not be built, which are tried again on the next discovery.
+async fn build_device_plugins(
+    builds: Vec<(
+        String,
+        impl Future<
+            Output = Result<
+                broadcast::Sender<ListAndWatchMessageKind>,
+                Box<dyn std::error::Error + Send + Sync + 'static>,
+            >,
+        >,
+    )>,
+    max_concurrent: usize,
+    timeout: Duration,
+) -> Vec<String> {
+    stream::iter(builds)
+        .map(|(instance_name, build)| async move {
+            let result = build_device_plugin_with_timeout(&instance_name, timeout, build).await;
+            (instance_name, result)
+        })
+        .buffer_unordered(max_concurrent)
+        .filter_map(|(instance_name, result)| async move {
+            match result {
+                Ok(_) => None,
+                Err(e) => {
+                    log_fields!(
+                        Level::Error,
+                        { instance: instance_name },
+                        "build_device_plugins - error {} building device plugin for instance {} ... trying again on next iteration",
+                        e, instance_name
+                    );
+                    Some(instance_name)
+                }
+            }
+        })
+        .collect()
+        .await
+}
+
+/// Keeps only as many newly discovered instances as fit under a Configuration's `maxInstances`,
+/// given the number of instances already being tracked
+fn limit_new_discovery_results(
+    config_name: &str,
+    mut new_discovery_results: Vec<protocols::DiscoveryResult>,
+    max_instances: Option<usize>,
+    current_instance_count: usize,
+) -> Vec<protocols::DiscoveryResult> {
+    if let Some(max_instances) = max_instances {
+        let available = max_instances.saturating_sub(current_instance_count);
+        if new_discovery_results.len() > available {
+            log_fields!(
+                Level::Warn,
+                { configuration: config_name },
+                "limit_new_discovery_results - config {} is limited to {} instances ... ignoring {} of {} newly discovered instances",
+                config_name,
+                max_instances,
+                new_discovery_results.len() - available,
+                new_discovery_results.len()
+            );
+            new_discovery_results.truncate(available);
+        }
+    }
+    new_discovery_results
+}
+
+/// Two devices whose digests, and so Instance names, collided
+#[derive(Debug, PartialEq)]
+struct DigestCollision {
+    digest: String,
+    device_id: String,
+    other_device_id: String,
+    /// The longer digest `device_id` was given instead
+    extended_digest: String,
+}
+
+impl std::fmt::Display for DigestCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "device {} has the same digest {} as device {} ... using digest {} for it instead",
+            self.device_id, self.digest, self.other_device_id, self.extended_digest
+        )
+    }
+}
+
+/// Maps each discovered device to the name of its Instance. If a device's digest collides with that of a
+/// different device, the digest of whichever device does not already own the Instance name (per `instance_ids`,
+/// which maps Instance names to device ids) is extended until it is unique, rather than merging the two devices
+/// into one Instance. Devices that do not yet own a name claim them in order of id, so the outcome is deterministic.
+/// Collisions are also returned the first time a device is given an extended digest, so they can be reported once
+/// rather than on every discovery round.
+fn get_currently_visible_instances(
+    config_name: &str,
+    discovery_results: &[protocols::DiscoveryResult],
+    instance_ids: &HashMap<String, String>,
+) -> (
+    HashMap<String, protocols::DiscoveryResult>,
+    Vec<DigestCollision>,
+) {
+    let mut discovery_results = discovery_results.to_vec();
+    discovery_results.sort_by_key(|discovery_result| {
+        let owns_instance_name = instance_ids.get(&get_device_instance_name(
+            &discovery_result.digest,
+            config_name,
+        )) == Some(&discovery_result.id);
+        (!owns_instance_name, discovery_result.id.clone())
+    });
+    let mut currently_visible_instances: HashMap<String, protocols::DiscoveryResult> =
+        HashMap::new();
+    let mut digest_collisions = Vec::new();
+    'discovery_results: for mut discovery_result in discovery_results {
+        let mut instance_name = get_device_instance_name(&discovery_result.digest, config_name);
+        let digest = discovery_result.digest.clone();
+        let mut other_device_id: Option<String> = None;
+        let mut extra_bytes = 0;
+        while let Some(id) = currently_visible_instances
+            .get(&instance_name)
+            .map(|r| &r.id)
+            .or_else(|| instance_ids.get(&instance_name))
+            .filter(|id| *id != &discovery_result.id)
+        {
+            if other_device_id.is_none() {
+                other_device_id = Some(id.clone());
+            }
+            extra_bytes += 1;
+            match protocols::generate_extended_instance_digest(
+                &discovery_result.id,
+                discovery_result.shared,
+                extra_bytes,
+            ) {
+                Some(digest) => {
+                    log_fields!(
+                        Level::Trace,
+                        { configuration: config_name, instance: instance_name },
+                        "get_currently_visible_instances - instance name {} of device {} is taken ... extending its digest to {}",
+                        instance_name, discovery_result.id, digest
+                    );
+                    instance_name = get_device_instance_name(&digest, config_name);
+                    discovery_result.digest = digest;
+                }
+                None => {
+                    log_fields!(
+                        Level::Error,
+                        { configuration: config_name },
+                        "get_currently_visible_instances - could not find a unique instance name for device {} of config {} ... ignoring it",
+                        discovery_result.id, config_name
+                    );
+                    continue 'discovery_results;
+                }
+            }
+        }
+        if let Some(other_device_id) = other_device_id {
+            if instance_ids.get(&instance_name) != Some(&discovery_result.id) {
+                digest_collisions.push(DigestCollision {
+                    digest,
+                    device_id: discovery_result.id.clone(),
+                    other_device_id,
+                    extended_digest: discovery_result.digest.clone(),
+                });
+            }
+        }
+        currently_visible_instances.insert(instance_name, discovery_result);
+    }
+    (currently_visible_instances, digest_collisions)
+}
+
 /// Information required for periodic discovery
 struct PeriodicDiscovery {
     config_name: String,
     config_uid: String,
     config_namespace: String,
     config_spec: Configuration,
+    /// Capacity of the Configuration, which may have been changed since discovery started
+    capacity: Arc<AtomicI32>,
     config_protocol: ProtocolHandler,
     instance_map: InstanceMap,
+    /// Records Events about the Configuration's Instances
+    event_recorder: EventRecorder,
 }
 
 impl PeriodicDiscovery {
+    /// Returns the Configuration's spec with its current capacity
+    fn get_config_spec(&self) -> Configuration {
+        let mut config_spec = self.config_spec.clone();
+        config_spec.capacity = self.capacity.load(Ordering::SeqCst);
+        config_spec
+    }
+
     /// This is spawned as a task for each Configuration and continues to periodically run
     /// until the Config is deleted, at which point, this function is signaled to stop.
     /// Looks up which instances are currently visible to the node. Passes this list to a function that
     /// updates the ConnectivityStatus of the Configuration's Instances or deletes Instance CRDs if needed.
     /// If a new instance becomes visible that isn't in the Configuration's InstanceMap,
     /// a DevicePluginService and Instance CRD are created for it, and it is added to the InstanceMap.
+    /// Discovery errors, such as an invalid udev rule, are reported on the Configuration's status.
+    #[cfg_attr(
+        feature = "tracing-feat",
+        tracing::instrument(
+            level = "trace",
+            skip(
+                self,
+                kube_interface,
+                stop_discovery_receiver,
+                finished_discovery_sender,
+                device_plugin_path
+            ),
+            fields(
+                config = %self.config_name,
+                protocol = protocols::get_protocol_name(&self.config_protocol)
+            )
+        )
+    )]
     async fn do_periodic_discovery(
         &self,
         kube_interface: &impl KubeInterface,
@@ -327,135 +1035,685 @@ impl PeriodicDiscovery {
         finished_discovery_sender: broadcast::Sender<()>,
         device_plugin_path: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-        trace!(
+        let protocol_name = protocols::get_protocol_name(&self.config_protocol);
+        log_fields!(
+            Level::Trace,
+            { configuration: self.config_name, protocol: protocol_name },
             "do_periodic_discovery - start for config {}",
             self.config_name
         );
         let protocol = protocols::get_discovery_handler(&self.config_protocol)?;
         let shared = protocol.are_shared()?;
+        let discovery_interval = protocol.get_discovery_interval()?;
+        let max_concurrent_device_plugin_builds =
+            get_max_concurrent_device_plugin_builds(&ActualEnvVarQuery {});
+        let discovery_stall_timeout = get_discovery_stall_timeout(&ActualEnvVarQuery {});
+        self.adopt_pre_existing_instances(kube_interface, device_plugin_path)
+            .await;
+        // Name of this node, which identifies the DiscoveryError condition reported by this Agent
+        let node_name = env::var("AGENT_NODE_NAME")?;
+        // Error last written to this node's DiscoveryError condition, or None until the condition is first written
+        let mut reported_discovery_error: Option<Option<String>> = None;
+        let mut discovery_handler_status = DiscoveryHandlerStatusMetric::new(protocol_name);
+        // Id of the device each Instance name was given to, used to keep devices whose digests collide apart
+        let mut instance_ids: HashMap<String, String> = HashMap::new();
+        let mut circuit_breaker = DiscoveryCircuitBreaker::new(
+            CIRCUIT_BREAKER_FAILURE_THRESHOLD,
+            Duration::from_secs(CIRCUIT_BREAKER_WINDOW_SECS),
+            Duration::from_secs(CIRCUIT_BREAKER_RESET_SECS),
+        );
         loop {
-            trace!(
+            log_fields!(
+                Level::Trace,
+                { configuration: self.config_name, protocol: protocol_name },
                 "do_periodic_discovery - loop iteration for config {}",
                 &self.config_name
             );
             let config_name = self.config_name.clone();
+            // While discovery is paused, wait out the pause instead of discovering
+            if let Some(remaining_pause) = circuit_breaker.remaining_pause(Instant::now()) {
+                log_fields!(
+                    Level::Trace,
+                    { configuration: config_name, protocol: protocol_name },
+                    "do_periodic_discovery - discovery for config {} is paused for {:?}",
+                    config_name,
+                    remaining_pause
+                );
+                let stop = tokio::select! {
+                    _ = stop_discovery_receiver.recv() => true,
+                    _ = tokio::time::delay_for(remaining_pause) => false,
+                };
+                if stop {
+                    log_fields!(
+                        Level::Trace,
+                        { configuration: config_name, protocol: protocol_name },
+                        "do_periodic_discovery - for config {} received message to end while paused ... sending message that finished and returning Ok",
+                        config_name
+                    );
+                    finished_discovery_sender.send(()).unwrap();
+                    return Ok(());
+                }
+                continue;
+            }
             let timer = DISCOVERY_RESPONSE_TIME_METRIC
                 .with_label_values(&[&config_name])
                 .start_timer();
-            let discovery_results = protocol.discover().await?;
+            let discovery_results = self
+                .discover(protocol.as_ref(), discovery_stall_timeout)
+                .await;
             timer.observe_duration();
-            let currently_visible_instances: HashMap<String, protocols::DiscoveryResult> =
-                discovery_results
-                    .iter()
-                    .map(|discovery_result| {
-                        let instance_name =
-                            get_device_instance_name(&discovery_result.digest, &config_name);
-                        (instance_name, discovery_result.clone())
-                    })
-                    .collect();
-            INSTANCE_COUNT_METRIC
-                .with_label_values(&[&config_name, &shared.to_string()])
-                .set(currently_visible_instances.len() as i64);
-            // Update the connectivity status of instances and return list of visible instances that don't have Instance CRs
-            let new_discovery_results = self
-                .update_connectivity_status(kube_interface, &currently_visible_instances, shared)
-                .await?;
-
-            // If there are newly visible instances associated with a Config, make a device plugin and Instance CR for them
-            if !new_discovery_results.is_empty() {
-                for discovery_result in new_discovery_results {
-                    let config_name = config_name.clone();
-                    let instance_name =
-                        get_device_instance_name(&discovery_result.digest, &config_name);
-                    trace!(
-                        "do_periodic_discovery - new instance {} came online",
-                        instance_name
+            let discovery_error = match &discovery_results {
+                Ok(_) => {
+                    circuit_breaker.record_success();
+                    discovery_handler_status.set(DISCOVERY_HANDLER_ONLINE);
+                    None
+                }
+                Err(e) => {
+                    if circuit_breaker.record_failure(Instant::now()) {
+                        discovery_handler_status.set(DISCOVERY_HANDLER_CIRCUIT_OPEN);
+                        DISCOVERY_CIRCUIT_OPEN_METRIC
+                            .with_label_values(&[protocol_name, &config_name])
+                            .inc();
+                        log_fields!(
+                            Level::Error,
+                            { configuration: config_name, protocol: protocol_name },
+                            "do_periodic_discovery - circuit open: discovery for config {} (protocol {}) failed with error {} ... pausing discovery for {} seconds",
+                            config_name, protocol_name, e, CIRCUIT_BREAKER_RESET_SECS
+                        );
+                    } else {
+                        discovery_handler_status.set(DISCOVERY_HANDLER_OFFLINE);
+                        log_fields!(
+                            Level::Error,
+                            { configuration: config_name, protocol: protocol_name },
+                            "do_periodic_discovery - discovery for config {} failed with error {} ... trying again on next iteration",
+                            config_name, e
+                        );
+                    }
+                    Some(e.to_string())
+                }
+            };
+            self.report_discovery_error(
+                kube_interface,
+                &node_name,
+                &mut reported_discovery_error,
+                discovery_error,
+            )
+            .await;
+            if let Ok(discovery_results) = discovery_results {
+                {
+                    let instance_map = self.instance_map.lock().await;
+                    instance_ids
+                        .retain(|instance_name, _| instance_map.contains_key(instance_name));
+                }
+                let known_instance_ids = self
+                    .get_known_instance_ids(kube_interface, &discovery_results, &instance_ids)
+                    .await;
+                let (currently_visible_instances, digest_collisions) =
+                    get_currently_visible_instances(
+                        &config_name,
+                        &discovery_results,
+                        &known_instance_ids,
                     );
-                    let instance_properties = discovery_result.properties.clone();
-                    let config_spec = self.config_spec.clone();
-                    let instance_map = self.instance_map.clone();
-                    if let Err(e) = device_plugin_service::build_device_plugin(
-                        instance_name,
-                        config_name,
-                        self.config_uid.clone(),
-                        self.config_namespace.clone(),
-                        config_spec,
-                        shared,
-                        instance_properties,
-                        instance_map,
+                for digest_collision in digest_collisions {
+                    log_fields!(
+                        Level::Warn,
+                        { configuration: config_name, protocol: protocol_name },
+                        "do_periodic_discovery - config {} {}",
+                        config_name, digest_collision
+                    );
+                }
+                for (instance_name, discovery_result) in &currently_visible_instances {
+                    instance_ids.insert(instance_name.clone(), discovery_result.id.clone());
+                }
+                INSTANCE_COUNT_METRIC
+                    .with_label_values(&[&config_name, &shared.to_string()])
+                    .set(currently_visible_instances.len() as i64);
+                // Update the connectivity status of instances and return list of visible instances that don't have Instance CRs
+                let new_discovery_results = self
+                    .update_connectivity_status(
+                        kube_interface,
+                        &currently_visible_instances,
                         device_plugin_path,
                     )
-                    .await
+                    .await?;
+                let new_discovery_results = limit_new_discovery_results(
+                    &config_name,
+                    new_discovery_results,
+                    self.config_spec.max_instances,
+                    self.instance_map.lock().await.len(),
+                );
+
+                // If there are newly visible instances associated with a Config, make a device plugin and Instance CR for them
+                if !new_discovery_results.is_empty() {
+                    let builds: Vec<_> = new_discovery_results
+                        .into_iter()
+                        .map(|discovery_result| {
+                            let instance_name =
+                                get_device_instance_name(&discovery_result.digest, &config_name);
+                            log_fields!(
+                                Level::Trace,
+                                {
+                                    configuration: config_name,
+                                    protocol: protocol_name,
+                                    instance: instance_name,
+                                },
+                                "do_periodic_discovery - new instance {} came online",
+                                instance_name
+                            );
+                            let build = device_plugin_service::build_device_plugin(
+                                instance_name.clone(),
+                                Some(discovery_result.id),
+                                config_name.clone(),
+                                self.config_uid.clone(),
+                                self.config_namespace.clone(),
+                                self.get_config_spec(),
+                                discovery_result.shared,
+                                discovery_result.properties,
+                                Arc::new(AtomicI32::new(
+                                    discovery_result
+                                        .capacity
+                                        .unwrap_or_else(|| self.capacity.load(Ordering::SeqCst)),
+                                )),
+                                discovery_result.device_specs,
+                                discovery_result.mounts,
+                                self.instance_map.clone(),
+                                device_plugin_path,
+                            );
+                            (instance_name, build)
+                        })
+                        .collect();
+                    let built_instances: Vec<String> = builds
+                        .iter()
+                        .map(|(instance_name, _)| instance_name.clone())
+                        .collect();
+                    let failed_instances = build_device_plugins(
+                        builds,
+                        max_concurrent_device_plugin_builds,
+                        Duration::from_secs(BUILD_DEVICE_PLUGIN_TIMEOUT_SECS),
+                    )
+                    .await;
+                    for instance_name in built_instances
+                        .iter()
+                        .filter(|instance_name| !failed_instances.contains(instance_name))
                     {
-                        error!("do_periodic_discovery - error {} building device plugin ... trying again on next iteration", e);
+                        self.record_instance_event(
+                            kube_interface,
+                            instance_name,
+                            INSTANCE_CREATED_REASON,
+                            format!("Discovered device and created Instance {}", instance_name),
+                        )
+                        .await;
                     }
                 }
             }
-            if timeout(
-                Duration::from_secs(DISCOVERY_DELAY_SECS),
-                stop_discovery_receiver.recv(),
-            )
-            .await
-            .is_ok()
-            {
-                trace!("do_periodic_discovery - for config {} received message to end ... sending message that finished and returning Ok", config_name);
+            // Discover again once the interval elapses or the discovery handler sees a change, unless told to stop
+            let stop = tokio::select! {
+                _ = stop_discovery_receiver.recv() => true,
+                _ = protocol.wait_for_change(discovery_interval) => false,
+            };
+            if stop {
+                log_fields!(
+                    Level::Trace,
+                    { configuration: config_name, protocol: protocol_name },
+                    "do_periodic_discovery - for config {} received message to end ... sending message that finished and returning Ok",
+                    config_name
+                );
                 finished_discovery_sender.send(()).unwrap();
                 return Ok(());
             };
         }
     }
 
-    /// Takes in a list of currently visible instances and either updates an Instance's ConnectivityStatus or deletes an Instance.
-    /// If an instance is no longer visible then it's ConnectivityStatus is changed to Offline(time now).
-    /// The associated DevicePluginService checks its ConnectivityStatus before sending a response back to kubelet
-    /// and will send all unhealthy devices if its status is Offline, preventing kubelet from allocating any more pods to it.
-    /// An Instance CRD is deleted and it's DevicePluginService shutdown if its:
-    /// (A) shared instance is still not visible after 5 minutes or (B) unshared instance is still not visible on the next visibility check.
-    /// An unshared instance will be offline for between DISCOVERY_DELAY_SECS - 2 x DISCOVERY_DELAY_SECS
-    async fn update_connectivity_status(
+    /// Writes this node's DiscoveryError condition on the Configuration when discovery starts failing, fails with a
+    /// different error, or recovers. The Configuration's status is shared by every node, so the current status is read
+    /// and only this node's condition is changed, and the write fails if another node updated the status in between.
+    /// `reported_discovery_error` tracks what was last written so that the status is only updated when it changes.
+    /// Failing to write the status is logged and retried on the next call.
+    async fn report_discovery_error(
         &self,
         kube_interface: &impl KubeInterface,
-        currently_visible_instances: &HashMap<String, protocols::DiscoveryResult>,
-        shared: bool,
-    ) -> Result<Vec<protocols::DiscoveryResult>, Box<dyn std::error::Error + Send + Sync + 'static>>
-    {
-        let instance_map_clone = self.instance_map.lock().await.clone();
-        // Find all visible instances that do not have Instance CRDs yet
-        let new_discovery_results: Vec<protocols::DiscoveryResult> = currently_visible_instances
-            .iter()
-            .filter(|(name, _)| !instance_map_clone.contains_key(*name))
-            .map(|(_, p)| p.clone())
-            .collect();
-
-        for (instance, instance_info) in instance_map_clone {
-            if currently_visible_instances.contains_key(&instance) {
-                let connectivity_status = instance_info.connectivity_status;
-                // If instance is visible, make sure connectivity status is (updated to be) Online
-                if let ConnectivityStatus::Offline(_instant) = connectivity_status {
-                    trace!(
-                        "update_connectivity_status - instance {} that was temporarily offline is back online",
-                        instance
-                    );
-                    let list_and_watch_message_sender = instance_info.list_and_watch_message_sender;
-                    let updated_instance_info = InstanceInfo {
-                        connectivity_status: ConnectivityStatus::Online,
-                        list_and_watch_message_sender: list_and_watch_message_sender.clone(),
-                    };
-                    self.instance_map
-                        .lock()
+        node_name: &str,
+        reported_discovery_error: &mut Option<Option<String>>,
+        discovery_error: Option<String>,
+    ) {
+        if reported_discovery_error.as_ref() == Some(&discovery_error) {
+            return;
+        }
+        let result = match kube_interface
+            .find_configuration(&self.config_name, &self.config_namespace)
+            .await
+        {
+            Ok(config) => {
+                let mut status = config.status.unwrap_or_default();
+                let condition = ConfigurationCondition {
+                    condition_type: DISCOVERY_ERROR_CONDITION.to_string(),
+                    status: if discovery_error.is_some() {
+                        "True".to_string()
+                    } else {
+                        "False".to_string()
+                    },
+                    message: discovery_error.clone().unwrap_or_default(),
+                    last_transition_time: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                    node_name: Some(node_name.to_string()),
+                };
+                if status.set_node_condition(condition) {
+                    kube_interface
+                        .update_configuration_status(
+                            &status,
+                            &config.metadata.resourceVersion.unwrap_or_default(),
+                            &self.config_name,
+                            &self.config_namespace,
+                        )
                         .await
-                        .insert(instance.clone(), updated_instance_info);
-                    list_and_watch_message_sender
-                        .send(device_plugin_service::ListAndWatchMessageKind::Continue)
-                        .unwrap();
+                } else {
+                    Ok(())
                 }
-                trace!(
-                    "update_connectivity_status - instance {} still online",
-                    instance
-                );
-            } else {
-                // If the instance is not visible:
-                // // If the instance has not already been labeled offline, label it
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => *reported_discovery_error = Some(discovery_error),
+            Err(e) => log_fields!(
+                Level::Error,
+                {
+                    configuration: self.config_name,
+                    protocol: protocols::get_protocol_name(&self.config_protocol),
+                },
+                "report_discovery_error - error {} updating status of config {}",
+                e, self.config_name
+            ),
+        }
+    }
+
+    /// Runs a single round of discovery with the Configuration's discovery handler. A handler that has not
+    /// responded within `stall_timeout` is considered hung, and the round fails so that it is reported and retried.
+    #[cfg_attr(
+        feature = "tracing-feat",
+        tracing::instrument(
+            level = "trace",
+            skip(self, protocol),
+            fields(
+                config = %self.config_name,
+                protocol = protocols::get_protocol_name(&self.config_protocol)
+            )
+        )
+    )]
+    async fn discover(
+        &self,
+        protocol: &(dyn protocols::DiscoveryHandler + Sync + Send),
+        stall_timeout: Duration,
+    ) -> Result<Vec<protocols::DiscoveryResult>, anyhow::Error> {
+        let discovery_results = tokio::time::timeout(stall_timeout, protocol.discover())
+            .await
+            .map_err(|_| {
+                anyhow::format_err!(
+                    "discovery handler did not respond within {} seconds",
+                    stall_timeout.as_secs()
+                )
+            })??;
+        DISCOVERY_RESULT_COUNT_METRIC
+            .with_label_values(&[&self.config_name])
+            .inc_by(discovery_results.len() as u64);
+        #[cfg(feature = "tracing-feat")]
+        tracing::trace!(
+            instance_count = discovery_results.len(),
+            "discover - finished"
+        );
+        Ok(discovery_results)
+    }
+
+    /// Returns `instance_ids`, which maps the Instance names this node has given to device ids, along with the
+    /// device ids recorded in this Configuration's existing Instances, including those created by other nodes.
+    /// These are only looked up when a device has not been given an Instance name by this node yet, so that it
+    /// is not given a name another node already gave a different device with the same digest. If the lookup
+    /// fails, only the names this node has given are returned.
+    async fn get_known_instance_ids(
+        &self,
+        kube_interface: &impl KubeInterface,
+        discovery_results: &[protocols::DiscoveryResult],
+        instance_ids: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let mut known_instance_ids = instance_ids.clone();
+        if discovery_results.iter().all(|discovery_result| {
+            instance_ids
+                .values()
+                .any(|device_id| device_id == &discovery_result.id)
+        }) {
+            return known_instance_ids;
+        }
+        match kube_interface.get_instances().await {
+            Ok(instances) => {
+                for instance in instances.items.into_iter().filter(|instance| {
+                    instance.spec.configuration_name == self.config_name
+                        && instance.metadata.namespace.as_ref() == Some(&self.config_namespace)
+                }) {
+                    if let Some(device_id) = instance.spec.device_id {
+                        known_instance_ids
+                            .entry(instance.metadata.name)
+                            .or_insert(device_id);
+                    }
+                }
+            }
+            Err(e) => log_fields!(
+                Level::Error,
+                { configuration: self.config_name },
+                "get_known_instance_ids - error {} getting Instances of config {} ... naming new devices from this node's Instances only",
+                e, self.config_name
+            ),
+        }
+        known_instance_ids
+    }
+
+    /// Finds the Instances of this Configuration that already exist and include this node,
+    /// such as those left behind when the Agent restarted.
+    async fn get_instances_to_adopt(
+        &self,
+        kube_interface: &impl KubeInterface,
+        node_name: &str,
+    ) -> Result<Vec<KubeAkriInstance>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        Ok(kube_interface
+            .get_instances()
+            .await?
+            .items
+            .into_iter()
+            .filter(|instance| {
+                instance.spec.configuration_name == self.config_name
+                    && instance.metadata.namespace.as_ref() == Some(&self.config_namespace)
+                    && instance.spec.nodes.contains(&node_name.to_string())
+            })
+            .collect())
+    }
+
+    /// Rebuilds the InstanceMap from Instances that already exist for this node, creating a DevicePluginService
+    /// for each rather than treating them as newly discovered. Adopted Instances start Online and follow the
+    /// normal offline path in `update_connectivity_status` if they are not rediscovered.
+    /// If the existing Instances cannot be looked up, none are adopted and discovery handles them as newly discovered.
+    async fn adopt_pre_existing_instances(
+        &self,
+        kube_interface: &impl KubeInterface,
+        device_plugin_path: &str,
+    ) {
+        let node_name = match env::var("AGENT_NODE_NAME") {
+            Ok(node_name) => node_name,
+            Err(e) => {
+                log_fields!(
+                    Level::Error,
+                    { configuration: self.config_name },
+                    "adopt_pre_existing_instances - could not get AGENT_NODE_NAME for config {}: {} ... not adopting Instances",
+                    self.config_name, e
+                );
+                return;
+            }
+        };
+        let instances_to_adopt = match self
+            .get_instances_to_adopt(kube_interface, &node_name)
+            .await
+        {
+            Ok(instances_to_adopt) => instances_to_adopt,
+            Err(e) => {
+                log_fields!(
+                    Level::Error,
+                    { configuration: self.config_name },
+                    "adopt_pre_existing_instances - error {} getting Instances of config {} ... not adopting Instances",
+                    e, self.config_name
+                );
+                return;
+            }
+        };
+        for instance in instances_to_adopt {
+            let instance_name = instance.metadata.name;
+            // Keep the number of usage slots the Instance was created with
+            let capacity = Arc::new(AtomicI32::new(match instance.spec.device_usage.len() {
+                0 => self.capacity.load(Ordering::SeqCst),
+                slots => slots as i32,
+            }));
+            log_fields!(
+                Level::Trace,
+                { configuration: self.config_name, instance: instance_name },
+                "adopt_pre_existing_instances - adopting Instance {} for config {}",
+                instance_name,
+                self.config_name
+            );
+            let instance_properties = instance.spec.metadata;
+            let device_specs =
+                device_plugin_service::from_instance_device_specs(&instance.spec.device_specs);
+            let mounts = device_plugin_service::from_instance_mounts(&instance.spec.mounts);
+            match build_device_plugin_with_timeout(
+                &instance_name,
+                Duration::from_secs(BUILD_DEVICE_PLUGIN_TIMEOUT_SECS),
+                device_plugin_service::build_device_plugin(
+                    instance_name.clone(),
+                    instance.spec.device_id.clone(),
+                    self.config_name.clone(),
+                    self.config_uid.clone(),
+                    self.config_namespace.clone(),
+                    self.get_config_spec(),
+                    instance.spec.shared,
+                    instance_properties.clone(),
+                    capacity.clone(),
+                    device_specs.clone(),
+                    mounts.clone(),
+                    self.instance_map.clone(),
+                    device_plugin_path,
+                ),
+            )
+            .await
+            {
+                Ok(list_and_watch_message_sender) => {
+                    self.instance_map.lock().await.insert(
+                        instance_name,
+                        InstanceInfo {
+                            list_and_watch_message_sender,
+                            connectivity_status: ConnectivityStatus::Online,
+                            properties: instance_properties,
+                            shared: instance.spec.shared,
+                            device_specs,
+                            mounts,
+                            capacity,
+                        },
+                    );
+                }
+                Err(e) => log_fields!(
+                    Level::Error,
+                    { configuration: self.config_name, instance: instance_name },
+                    "adopt_pre_existing_instances - error {} building device plugin for Instance {} ... will be handled as newly discovered",
+                    e, instance_name
+                ),
+            }
+        }
+    }
+
+    /// Records an Event about one of the Configuration's Instances on the Configuration
+    async fn record_instance_event(
+        &self,
+        kube_interface: &impl KubeInterface,
+        instance_name: &str,
+        reason: &'static str,
+        message: String,
+    ) {
+        let configuration = get_configuration_reference(
+            &self.config_name,
+            &self.config_uid,
+            &self.config_namespace,
+        );
+        self.event_recorder
+            .record(
+                kube_interface,
+                &configuration,
+                instance_name,
+                reason,
+                message,
+            )
+            .await;
+    }
+
+    /// Takes in a list of currently visible instances and either updates an Instance's ConnectivityStatus or deletes an Instance.
+    /// If a visible instance's properties, device specs or mounts have changed, such as its reported health, they are written to its Instance CRD
+    /// and stored in its `InstanceInfo`, and its DevicePluginService is told to send kubelet a new list of virtual Devices.
+    /// If an instance is no longer visible then it's ConnectivityStatus is changed to Offline(time now).
+    /// The associated DevicePluginService checks its ConnectivityStatus before sending a response back to kubelet
+    /// and will send all unhealthy devices if its status is Offline, preventing kubelet from allocating any more pods to it.
+    /// An Instance CRD is deleted and it's DevicePluginService shutdown if its:
+    /// (A) shared instance is still not visible after 5 minutes or (B) unshared instance is still not visible on the next visibility check.
+    /// An unshared instance will be offline for between one and two discovery intervals
+    /// An Event is recorded on the Configuration when an instance goes offline, comes back online, or is deleted.
+    #[cfg_attr(
+        feature = "tracing-feat",
+        tracing::instrument(
+            level = "trace",
+            skip(self, kube_interface, currently_visible_instances),
+            fields(
+                config = %self.config_name,
+                instance_count = currently_visible_instances.len()
+            )
+        )
+    )]
+    async fn update_connectivity_status(
+        &self,
+        kube_interface: &impl KubeInterface,
+        currently_visible_instances: &HashMap<String, protocols::DiscoveryResult>,
+        device_plugin_path: &str,
+    ) -> Result<Vec<protocols::DiscoveryResult>, Box<dyn std::error::Error + Send + Sync + 'static>>
+    {
+        let instance_map_clone = self.instance_map.lock().await.clone();
+        // Find all visible instances that do not have Instance CRDs yet
+        let new_discovery_results: Vec<protocols::DiscoveryResult> = currently_visible_instances
+            .iter()
+            .filter(|(name, _)| !instance_map_clone.contains_key(*name))
+            .map(|(_, p)| p.clone())
+            .collect();
+
+        for (instance, instance_info) in instance_map_clone {
+            if let Some(discovery_result) = currently_visible_instances.get(&instance) {
+                let came_back_online = instance_info.connectivity_status.is_offline();
+                // If the instance's properties changed, update its Instance CRD before storing them.
+                // If the update fails, the change is picked up again on the next iteration.
+                let mut properties_changed = false;
+                if instance_info.properties != discovery_result.properties {
+                    log_fields!(
+                        Level::Trace,
+                        { configuration: self.config_name, instance: instance },
+                        "update_connectivity_status - properties of instance {} changed",
+                        instance
+                    );
+                    match kube_interface
+                        .update_instance_properties(
+                            &discovery_result.properties,
+                            &instance,
+                            &self.config_namespace,
+                        )
+                        .await
+                    {
+                        Ok(()) => properties_changed = true,
+                        Err(e) => log_fields!(
+                            Level::Error,
+                            { configuration: self.config_name, instance: instance },
+                            "update_connectivity_status - error {} updating properties of Instance {} ... trying again on next iteration",
+                            e, instance
+                        ),
+                    }
+                }
+                // Device specs and mounts are handled the same way, so that brokers allocated the
+                // Instance after a change are given the host devices and paths now reported for it
+                let mut devices_changed = false;
+                if instance_info.device_specs != discovery_result.device_specs
+                    || instance_info.mounts != discovery_result.mounts
+                {
+                    log_fields!(
+                        Level::Trace,
+                        { configuration: self.config_name, instance: instance },
+                        "update_connectivity_status - device specs or mounts of instance {} changed",
+                        instance
+                    );
+                    match kube_interface
+                        .patch_instance(
+                            &get_devices_patch(
+                                &device_plugin_service::to_instance_device_specs(
+                                    &discovery_result.device_specs,
+                                ),
+                                &device_plugin_service::to_instance_mounts(
+                                    &discovery_result.mounts,
+                                ),
+                            ),
+                            &instance,
+                            &self.config_namespace,
+                        )
+                        .await
+                    {
+                        Ok(()) => devices_changed = true,
+                        Err(e) => log_fields!(
+                            Level::Error,
+                            { configuration: self.config_name, instance: instance },
+                            "update_connectivity_status - error {} updating device specs and mounts of Instance {} ... trying again on next iteration",
+                            e, instance
+                        ),
+                    }
+                }
+                // If instance is visible, make sure connectivity status is (updated to be) Online
+                // and that its properties, device specs and mounts are up to date
+                if came_back_online || properties_changed || devices_changed {
+                    if let ConnectivityStatus::Offline(instant) = instance_info.connectivity_status
+                    {
+                        log_fields!(
+                            Level::Trace,
+                            { configuration: self.config_name, instance: instance },
+                            "update_connectivity_status - instance {} that was temporarily offline is back online",
+                            instance
+                        );
+                        self.record_instance_event(
+                            kube_interface,
+                            &instance,
+                            INSTANCE_BACK_ONLINE_REASON,
+                            format!(
+                                "Instance {} is back online after being offline for {} seconds",
+                                instance,
+                                instant.elapsed().as_secs()
+                            ),
+                        )
+                        .await;
+                    }
+                    let properties = if properties_changed {
+                        discovery_result.properties.clone()
+                    } else {
+                        instance_info.properties
+                    };
+                    let (device_specs, mounts) = if devices_changed {
+                        (
+                            discovery_result.device_specs.clone(),
+                            discovery_result.mounts.clone(),
+                        )
+                    } else {
+                        (instance_info.device_specs, instance_info.mounts)
+                    };
+                    let list_and_watch_message_sender = instance_info.list_and_watch_message_sender;
+                    let updated_instance_info = InstanceInfo {
+                        connectivity_status: instance_info
+                            .connectivity_status
+                            .transition_to(ConnectivityStatus::Online)?,
+                        list_and_watch_message_sender: list_and_watch_message_sender.clone(),
+                        properties,
+                        shared: instance_info.shared,
+                        device_specs,
+                        mounts,
+                        capacity: instance_info.capacity,
+                    };
+                    self.instance_map
+                        .lock()
+                        .await
+                        .insert(instance.clone(), updated_instance_info);
+                    list_and_watch_message_sender
+                        .send(device_plugin_service::ListAndWatchMessageKind::Continue)
+                        .unwrap();
+                }
+                log_fields!(
+                    Level::Trace,
+                    { configuration: self.config_name, instance: instance },
+                    "update_connectivity_status - instance {} still online",
+                    instance
+                );
+            } else {
+                // If the instance is not visible:
+                // // If the instance has not already been labeled offline, label it
                 // // If the instance has already been labeled offline
                 // // // shared - remove instance from map if grace period has elaspsed without the instance coming back online
                 // // // unshared - remove instance from map
@@ -463,34 +1721,84 @@ impl PeriodicDiscovery {
                     ConnectivityStatus::Online => {
                         let sender = instance_info.list_and_watch_message_sender.clone();
                         let updated_instance_info = InstanceInfo {
-                            connectivity_status: ConnectivityStatus::Offline(Instant::now()),
+                            connectivity_status: instance_info
+                                .connectivity_status
+                                .transition_to(ConnectivityStatus::Offline(Instant::now()))?,
                             list_and_watch_message_sender: instance_info
                                 .list_and_watch_message_sender,
+                            properties: instance_info.properties,
+                            shared: instance_info.shared,
+                            device_specs: instance_info.device_specs,
+                            mounts: instance_info.mounts,
+                            capacity: instance_info.capacity,
                         };
                         self.instance_map
                             .lock()
                             .await
                             .insert(instance.clone(), updated_instance_info);
-                        trace!(
+                        log_fields!(
+                            Level::Trace,
+                            { configuration: self.config_name, instance: instance },
                             "update_connectivity_status - instance {} went offline ... starting timer and forcing list_and_watch to continue",
                             instance
                         );
                         sender
                             .send(device_plugin_service::ListAndWatchMessageKind::Continue)
                             .unwrap();
+                        let message = if instance_info.shared {
+                            format!(
+                                "Instance {} went offline and will be deleted if it is not rediscovered within {} seconds",
+                                instance, SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS
+                            )
+                        } else {
+                            format!(
+                                "Instance {} went offline and will be deleted if it is not rediscovered on the next discovery",
+                                instance
+                            )
+                        };
+                        self.record_instance_event(
+                            kube_interface,
+                            &instance,
+                            INSTANCE_OFFLINE_REASON,
+                            message,
+                        )
+                        .await;
                     }
                     ConnectivityStatus::Offline(instant) => {
                         let time_offline = instant.elapsed().as_secs();
                         // If instance has been offline for longer than the grace period or it is unshared, terminate the associated device plugin
-                        if !shared || time_offline >= SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS {
-                            trace!("update_connectivity_status - instance {} has been offline too long ... terminating DevicePluginService", instance);
+                        if !instance_info.shared
+                            || time_offline >= SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS
+                        {
+                            log_fields!(
+                                Level::Trace,
+                                { configuration: self.config_name, instance: instance },
+                                "update_connectivity_status - instance {} has been offline too long ... terminating DevicePluginService",
+                                instance
+                            );
                             device_plugin_service::terminate_device_plugin_service(
                                 &instance,
                                 self.instance_map.clone(),
+                                device_plugin_path,
                             )
                             .await?;
                             try_delete_instance(kube_interface, &instance, &self.config_namespace)
                                 .await?;
+                            let deleted_reason = if instance_info.shared {
+                                INSTANCE_DELETED_GRACE_PERIOD_EXPIRED
+                            } else {
+                                INSTANCE_DELETED_LOCAL_DISAPPEARED
+                            };
+                            self.record_instance_event(
+                                kube_interface,
+                                &instance,
+                                INSTANCE_DELETED_REASON,
+                                format!(
+                                    "Deleted Instance {} after being offline for {} seconds: {}",
+                                    instance, time_offline, deleted_reason
+                                ),
+                            )
+                            .await;
                         }
                     }
                 }
@@ -502,13 +1810,56 @@ impl PeriodicDiscovery {
 
 #[cfg(test)]
 mod config_action_tests {
+    use super::super::{constants::KUBELET_SOCKET_NAME, v1beta1};
     use super::*;
-    use akri_shared::k8s::MockKubeInterface;
+    use akri_shared::{
+        akri::{
+            configuration::{
+                ConfigurationStatus, DebugEchoDevice, DebugEchoDiscoveryHandlerConfig,
+                KubeAkriConfigBuilder,
+            },
+            instance::KubeAkriInstanceList,
+        },
+        k8s::MockKubeInterface,
+        os::env_var::MockEnvVarQuery,
+    };
+    use k8s_openapi::api::core::v1::Event;
     use protocols::debug_echo::{DEBUG_ECHO_AVAILABILITY_CHECK_PATH, OFFLINE};
-    use std::{env, fs};
+    use std::{
+        env, fs,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
     use tempfile::Builder;
     use tokio::sync::broadcast;
 
+    /// Builds the debugEcho Configuration "config-a", which discovers the devices "filter1" and "filter2"
+    fn build_config_a() -> KubeAkriConfig {
+        KubeAkriConfigBuilder::new()
+            .name("config-a")
+            .namespace("config-a-namespace")
+            .uid("e9fbe880-99da-47c1-bea3-5398f21ee747")
+            .protocol(ProtocolHandler::debugEcho(
+                DebugEchoDiscoveryHandlerConfig {
+                    devices: vec!["filter1", "filter2"]
+                        .into_iter()
+                        .map(|id| DebugEchoDevice {
+                            id: id.to_string(),
+                            properties: HashMap::new(),
+                            shared: None,
+                            device_specs: Vec::new(),
+                            mounts: Vec::new(),
+                        })
+                        .collect(),
+                    shared: false,
+                    discovery_interval_seconds: 10,
+                    availability_check_path: None,
+                },
+            ))
+            .capacity(5)
+            .build()
+            .unwrap()
+    }
+
     async fn build_instance_map(
         config: &KubeAkriConfig,
         visibile_discovery_results: &mut Vec<protocols::DiscoveryResult>,
@@ -538,6 +1889,11 @@ mod config_action_tests {
                         InstanceInfo {
                             list_and_watch_message_sender,
                             connectivity_status: connectivity_status.clone(),
+                            properties: instance_info.properties.clone(),
+                            shared: instance_info.shared,
+                            device_specs: instance_info.device_specs.clone(),
+                            mounts: instance_info.mounts.clone(),
+                            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
                         },
                     )
                 })
@@ -549,9 +1905,7 @@ mod config_action_tests {
     #[tokio::test]
     async fn test_handle_config_delete() {
         let _ = env_logger::builder().is_test(true).try_init();
-        let path_to_config = "../test/json/config-a.json";
-        let dcc_json = fs::read_to_string(path_to_config).expect("Unable to read file");
-        let config: KubeAkriConfig = serde_json::from_str(&dcc_json).unwrap();
+        let config = build_config_a();
         let config_name = config.metadata.name.clone();
         let mut list_and_watch_message_receivers = Vec::new();
         let mut visible_discovery_results = Vec::new();
@@ -571,6 +1925,8 @@ mod config_action_tests {
             ConfigInfo {
                 stop_discovery_sender,
                 instance_map: instance_map.clone(),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+                config_spec: config.spec.clone(),
                 finished_discovery_sender: finished_discovery_sender.clone(),
             },
         );
@@ -608,23 +1964,265 @@ mod config_action_tests {
         assert_eq!(instance_map.lock().await.len(), 0);
     }
 
+    #[test]
+    fn test_has_kubelet_restarted() {
+        let mut last_inode = None;
+        // Kubelet is not running yet
+        assert!(!has_kubelet_restarted(&mut last_inode, None));
+        // Kubelet starting for the first time is not a restart
+        assert!(!has_kubelet_restarted(&mut last_inode, Some(1)));
+        assert!(!has_kubelet_restarted(&mut last_inode, Some(1)));
+        // Kubelet going down is not a restart until its socket is recreated
+        assert!(!has_kubelet_restarted(&mut last_inode, None));
+        assert!(has_kubelet_restarted(&mut last_inode, Some(2)));
+        assert!(!has_kubelet_restarted(&mut last_inode, Some(2)));
+    }
+
+    #[tokio::test]
+    async fn test_watch_kubelet_socket() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_temp_dir_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let kubelet_socket = device_plugin_temp_dir.path().join(KUBELET_SOCKET_NAME);
+        let registrations =
+            device_plugin_service::fake_kubelet::serve_fake_kubelet(&kubelet_socket);
+        let config = build_config_a();
+        let instance_map: InstanceMap = Arc::new(Mutex::new(HashMap::new()));
+        for instance_name in &["config-a-b494b6", "config-a-359973"] {
+            let capacity = Arc::new(AtomicI32::new(config.spec.capacity));
+            let list_and_watch_message_sender = device_plugin_service::build_device_plugin(
+                instance_name.to_string(),
+                None,
+                config.metadata.name.clone(),
+                config.metadata.uid.clone().unwrap(),
+                config.metadata.namespace.clone().unwrap(),
+                config.spec.clone(),
+                false,
+                HashMap::new(),
+                capacity.clone(),
+                Vec::new(),
+                Vec::new(),
+                instance_map.clone(),
+                device_plugin_temp_dir_path,
+            )
+            .await
+            .unwrap();
+            instance_map.lock().await.insert(
+                instance_name.to_string(),
+                InstanceInfo {
+                    list_and_watch_message_sender,
+                    connectivity_status: ConnectivityStatus::Online,
+                    properties: HashMap::new(),
+                    shared: false,
+                    device_specs: Vec::new(),
+                    mounts: Vec::new(),
+                    capacity,
+                },
+            );
+        }
+        assert_eq!(2, registrations.lock().await.len());
+        let (stop_discovery_sender, _) = mpsc::channel(2);
+        let (finished_discovery_sender, _) = broadcast::channel(2);
+        let mut map: HashMap<String, ConfigInfo> = HashMap::new();
+        map.insert(
+            config.metadata.name.clone(),
+            ConfigInfo {
+                stop_discovery_sender,
+                instance_map: instance_map.clone(),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+                config_spec: config.spec.clone(),
+                finished_discovery_sender,
+            },
+        );
+        let config_map: ConfigMap = Arc::new(Mutex::new(map));
+        let kubelet_socket_path = kubelet_socket.to_str().unwrap().to_string();
+        tokio::spawn(async move {
+            watch_kubelet_socket(config_map, &kubelet_socket_path, Duration::from_millis(100))
+                .await;
+        });
+
+        // Device plugins are left alone while kubelet keeps running
+        tokio::time::delay_for(Duration::from_millis(300)).await;
+        assert_eq!(2, registrations.lock().await.len());
+
+        // Restart kubelet, serving the new socket before replacing the old so that it gets a new inode
+        let new_kubelet_socket = device_plugin_temp_dir.path().join("kubelet.sock.new");
+        let new_registrations =
+            device_plugin_service::fake_kubelet::serve_fake_kubelet(&new_kubelet_socket);
+        fs::rename(&new_kubelet_socket, &kubelet_socket).unwrap();
+
+        // Assert every device plugin registers with the new kubelet and its Instance stays in the InstanceMap
+        let start = Instant::now();
+        while new_registrations.lock().await.len() < 2 && start.elapsed() < Duration::from_secs(5) {
+            tokio::time::delay_for(Duration::from_millis(100)).await;
+        }
+        let mut reregistered = new_registrations.lock().await.clone();
+        reregistered.sort();
+        assert_eq!(
+            vec!["akri.sh/config-a-359973", "akri.sh/config-a-b494b6"],
+            reregistered
+        );
+        assert_eq!(2, instance_map.lock().await.len());
+    }
+
     // 1: ConnectivityStatus of all instances that go offline is changed from Online to Offline
     // 2: ConnectivityStatus of shared instances that come back online in under 5 minutes is changed from Offline to Online
     // 3: ConnectivityStatus of unshared instances that come back online before next periodic discovery is changed from Offline to Online
+    fn load_config_with_node_selector(node_selector: Vec<(&str, &str)>) -> KubeAkriConfig {
+        let mut config = build_config_a();
+        config.spec.node_selector = node_selector
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        config
+    }
+
+    fn configure_find_node(mock: &mut MockKubeInterface, times: usize) {
+        mock.expect_find_node()
+            .times(times)
+            .withf(|name: &str| name == "node-a")
+            .returning(|_| {
+                let node_json = fs::read_to_string("../test/json/node-a.json").unwrap();
+                Ok(serde_json::from_str(&node_json).unwrap())
+            });
+    }
+
     #[tokio::test]
-    async fn test_update_connectivity_status() {
+    async fn test_should_discover_on_node() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+
+        // No node selector does not require looking up the node
+        let mock = MockKubeInterface::new();
+        let node_labels: NodeLabels = Arc::new(Mutex::new(None));
+        let config = load_config_with_node_selector(Vec::new());
+        assert!(should_discover_on_node(&mock, &config, &node_labels).await);
+        assert!(node_labels.lock().await.is_none());
+
+        // The node is only looked up once
+        let mut mock = MockKubeInterface::new();
+        configure_find_node(&mut mock, 1);
+        let config = load_config_with_node_selector(vec![
+            ("kubernetes.io/hostname", "node-a"),
+            ("kubernetes.io/arch", "amd64"),
+        ]);
+        assert!(should_discover_on_node(&mock, &config, &node_labels).await);
+
+        let config = load_config_with_node_selector(vec![
+            ("kubernetes.io/hostname", "node-a"),
+            ("kubernetes.io/arch", "arm64"),
+        ]);
+        assert!(!should_discover_on_node(&mock, &config, &node_labels).await);
+
+        let config = load_config_with_node_selector(vec![("usb-camera", "true")]);
+        assert!(!should_discover_on_node(&mock, &config, &node_labels).await);
+
+        // Nodes that cannot be found are not selected, and are looked up again next time
+        let mut mock = MockKubeInterface::new();
+        mock.expect_find_node()
+            .times(2)
+            .returning(|_| Err(None.ok_or("failure")?));
+        let node_labels: NodeLabels = Arc::new(Mutex::new(None));
+        let config = load_config_with_node_selector(vec![("kubernetes.io/hostname", "node-a")]);
+        assert!(!should_discover_on_node(&mock, &config, &node_labels).await);
+        assert!(!should_discover_on_node(&mock, &config, &node_labels).await);
+        assert!(node_labels.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_config_skips_unselected_node() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+        let mut mock = MockKubeInterface::new();
+        configure_find_node(&mut mock, 1);
+        let config = load_config_with_node_selector(vec![("kubernetes.io/hostname", "node-b")]);
+        let config_map: ConfigMap = Arc::new(Mutex::new(HashMap::new()));
+        let node_labels: NodeLabels = Arc::new(Mutex::new(None));
+
+        handle_config(
+            &mock,
+            WatchEvent::Added(config.clone()),
+            config_map.clone(),
+            &node_labels,
+        )
+        .await
+        .unwrap();
+        assert!(config_map.lock().await.is_empty());
+
+        // Modifying or deleting a Configuration that was never discovered is a no-op
+        handle_config(
+            &mock,
+            WatchEvent::Modified(config.clone()),
+            config_map.clone(),
+            &node_labels,
+        )
+        .await
+        .unwrap();
+        handle_config(
+            &mock,
+            WatchEvent::Deleted(config),
+            config_map.clone(),
+            &node_labels,
+        )
+        .await
+        .unwrap();
+        assert!(config_map.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_is_spec_unchanged() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let config = load_config_with_node_selector(Vec::new());
+        let config_map: ConfigMap = Arc::new(Mutex::new(HashMap::new()));
+        // Configurations that are not being discovered have no spec to compare against
+        assert!(!is_spec_unchanged(&config, &config_map).await);
+
+        let (stop_discovery_sender, _) = mpsc::channel(2);
+        let (finished_discovery_sender, _) = broadcast::channel(2);
+        config_map.lock().await.insert(
+            config.metadata.name.clone(),
+            ConfigInfo {
+                stop_discovery_sender,
+                instance_map: Arc::new(Mutex::new(HashMap::new())),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+                config_spec: config.spec.clone(),
+                finished_discovery_sender,
+            },
+        );
+        // Status updates leave the spec unchanged
+        let mut status_modified_config = config.clone();
+        status_modified_config.status = Some(ConfigurationStatus {
+            conditions: vec![ConfigurationCondition {
+                condition_type: DISCOVERY_ERROR_CONDITION.to_string(),
+                status: "True".to_string(),
+                message: "failure".to_string(),
+                last_transition_time: "2020-10-16T00:00:00Z".to_string(),
+                node_name: Some("node-a".to_string()),
+            }],
+        });
+        assert!(is_spec_unchanged(&status_modified_config, &config_map).await);
+
+        // Changing how devices are discovered changes the spec
+        let mut spec_modified_config = config.clone();
+        if let ProtocolHandler::debugEcho(debug_echo) = &mut spec_modified_config.spec.protocol {
+            debug_echo.discovery_interval_seconds += 1;
+        }
+        assert!(!is_spec_unchanged(&spec_modified_config, &config_map).await);
+
+        let mut capacity_modified_config = config;
+        capacity_modified_config.spec.capacity += 1;
+        assert!(!is_spec_unchanged(&capacity_modified_config, &config_map).await);
+    }
+
+    #[tokio::test]
+    async fn test_handle_config_modified() {
         let _ = env_logger::builder().is_test(true).try_init();
-        let path_to_config = "../test/json/config-a.json";
-        let dcc_json = fs::read_to_string(path_to_config).expect("Unable to read file");
-        let config: KubeAkriConfig = serde_json::from_str(&dcc_json).unwrap();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+        let config = load_config_with_node_selector(Vec::new());
         let config_name = config.metadata.name.clone();
         let mut list_and_watch_message_receivers = Vec::new();
         let mut visible_discovery_results = Vec::new();
-        let mock = MockKubeInterface::new();
-
-        //
-        // 1: Assert that ConnectivityStatus of instance that are no longer visible is changed to Offline
-        //
         let instance_map: InstanceMap = build_instance_map(
             &config,
             &mut visible_discovery_results,
@@ -632,32 +2230,195 @@ mod config_action_tests {
             ConnectivityStatus::Online,
         )
         .await;
-        let shared = true;
-        // discover returns an empty vector when instances are offline
-        let no_visible_instances: HashMap<String, protocols::DiscoveryResult> = HashMap::new();
-        let periodic_dicovery = PeriodicDiscovery {
-            config_name: config_name.clone(),
-            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
-            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
-            config_spec: config.spec.clone(),
-            config_protocol: config.spec.protocol.clone(),
-            instance_map: instance_map.clone(),
-        };
-        periodic_dicovery
-            .update_connectivity_status(&mock, &no_visible_instances, shared)
+        let (stop_discovery_sender, mut stop_discovery_receiver) = mpsc::channel(2);
+        let (finished_discovery_sender, _) = broadcast::channel(2);
+        let mut map: HashMap<String, ConfigInfo> = HashMap::new();
+        map.insert(
+            config_name.clone(),
+            ConfigInfo {
+                stop_discovery_sender,
+                instance_map: instance_map.clone(),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+                config_spec: config.spec.clone(),
+                finished_discovery_sender: finished_discovery_sender.clone(),
+            },
+        );
+        let config_map: ConfigMap = Arc::new(Mutex::new(map));
+
+        // Change a discovery detail. The modified Configuration also moves to another node, so that
+        // discovery is not restarted against a real cluster.
+        let mut modified_config =
+            load_config_with_node_selector(vec![("kubernetes.io/hostname", "node-b")]);
+        if let ProtocolHandler::debugEcho(debug_echo) = &mut modified_config.spec.protocol {
+            debug_echo.discovery_interval_seconds += 1;
+        }
+        let mut mock = MockKubeInterface::new();
+        mock.expect_delete_instance()
+            .times(2)
+            .returning(move |_, _| Ok(()));
+        let task_config_map = config_map.clone();
+        let modify_task = tokio::spawn(async move {
+            handle_config(
+                &mock,
+                WatchEvent::Modified(modified_config),
+                task_config_map,
+                &Arc::new(Mutex::new(None)),
+            )
             .await
             .unwrap();
-        let unwrapped_instance_map = instance_map.lock().await.clone();
-        for (_, instance_info) in unwrapped_instance_map {
-            assert_ne!(
-                instance_info.connectivity_status,
-                ConnectivityStatus::Online
-            );
-        }
+        });
 
-        //
-        // 2: Assert that ConnectivityStatus of shared instances that come back online in <5 mins is changed to Online
-        //
+        // Assert that the running discovery is told to end
+        assert!(stop_discovery_receiver.recv().await.is_some());
+        // Mimic do_periodic_discovery's response
+        finished_discovery_sender.send(()).unwrap();
+
+        // Assert list_and_watch is signaled to end for every instance discovered under the previous spec
+        for mut receiver in list_and_watch_message_receivers {
+            assert_eq!(
+                receiver.recv().await.unwrap(),
+                device_plugin_service::ListAndWatchMessageKind::End
+            );
+        }
+        modify_task.await.unwrap();
+        assert_eq!(instance_map.lock().await.len(), 0);
+        assert!(!config_map.lock().await.contains_key(&config_name));
+    }
+
+    #[tokio::test]
+    async fn test_handle_config_capacity_modified() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+        let config = load_config_with_node_selector(Vec::new());
+        let config_name = config.metadata.name.clone();
+        let mut list_and_watch_message_receivers = Vec::new();
+        let mut visible_discovery_results = Vec::new();
+        let instance_map: InstanceMap = build_instance_map(
+            &config,
+            &mut visible_discovery_results,
+            &mut list_and_watch_message_receivers,
+            ConnectivityStatus::Online,
+        )
+        .await;
+        let (stop_discovery_sender, mut stop_discovery_receiver) = mpsc::channel(2);
+        let (finished_discovery_sender, _) = broadcast::channel(2);
+        let capacity = Arc::new(AtomicI32::new(config.spec.capacity));
+        let mut map: HashMap<String, ConfigInfo> = HashMap::new();
+        map.insert(
+            config_name.clone(),
+            ConfigInfo {
+                stop_discovery_sender,
+                instance_map: instance_map.clone(),
+                capacity: capacity.clone(),
+                config_spec: config.spec.clone(),
+                finished_discovery_sender,
+            },
+        );
+        let config_map: ConfigMap = Arc::new(Mutex::new(map));
+
+        let mut modified_config = config;
+        modified_config.spec.capacity = 6;
+        let mut mock = MockKubeInterface::new();
+        mock.expect_find_instance().times(2).returning(|name, _| {
+            let instance_json = fs::read_to_string("../test/json/local-instance.json").unwrap();
+            let mut instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
+            instance.spec.device_usage = (0..5)
+                .map(|x| (format!("{}-{}", name, x), "".to_string()))
+                .collect();
+            Ok(instance)
+        });
+        mock.expect_patch_instance()
+            .times(2)
+            .withf(|patch, name, namespace| {
+                patch
+                    == &serde_json::json!([{
+                        "op": "add",
+                        "path": format!("/spec/deviceUsage/{}-5", name),
+                        "value": ""
+                    }])
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _, _| Ok(()));
+        handle_config(
+            &mock,
+            WatchEvent::Modified(modified_config),
+            config_map.clone(),
+            &Arc::new(Mutex::new(None)),
+        )
+        .await
+        .unwrap();
+
+        // Discovery keeps running and the Instances are kept, advertising their new slots
+        assert!(stop_discovery_receiver.try_recv().is_err());
+        assert_eq!(instance_map.lock().await.len(), 2);
+        for mut receiver in list_and_watch_message_receivers {
+            assert_eq!(
+                receiver.try_recv().unwrap(),
+                device_plugin_service::ListAndWatchMessageKind::Continue
+            );
+        }
+        assert_eq!(capacity.load(Ordering::SeqCst), 6);
+        assert_eq!(
+            config_map
+                .lock()
+                .await
+                .get(&config_name)
+                .unwrap()
+                .config_spec
+                .capacity,
+            6
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_connectivity_status() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let config = build_config_a();
+        let config_name = config.metadata.name.clone();
+        let mut list_and_watch_message_receivers = Vec::new();
+        let mut visible_discovery_results = Vec::new();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_create_event().returning(|_, _| Ok(()));
+
+        //
+        // 1: Assert that ConnectivityStatus of instance that are no longer visible is changed to Offline
+        //
+        let instance_map: InstanceMap = build_instance_map(
+            &config,
+            &mut visible_discovery_results,
+            &mut list_and_watch_message_receivers,
+            ConnectivityStatus::Online,
+        )
+        .await;
+        // discover returns an empty vector when instances are offline
+        let no_visible_instances: HashMap<String, protocols::DiscoveryResult> = HashMap::new();
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config_name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+        periodic_dicovery
+            .update_connectivity_status(&mock, &no_visible_instances, device_plugin_path)
+            .await
+            .unwrap();
+        let unwrapped_instance_map = instance_map.lock().await.clone();
+        for (_, instance_info) in unwrapped_instance_map {
+            assert_ne!(
+                instance_info.connectivity_status,
+                ConnectivityStatus::Online
+            );
+        }
+
+        //
+        // 2: Assert that ConnectivityStatus of shared instances that come back online in <5 mins is changed to Online
+        //
         let instance_map: InstanceMap = build_instance_map(
             &config,
             &mut visible_discovery_results,
@@ -665,68 +2426,1010 @@ mod config_action_tests {
             ConnectivityStatus::Offline(Instant::now()),
         )
         .await;
-        let shared = true;
-        let currently_visible_instances: HashMap<String, protocols::DiscoveryResult> =
-            visible_discovery_results
-                .iter()
-                .map(|instance_info| {
-                    let instance_name =
-                        get_device_instance_name(&instance_info.digest, &config_name);
-                    (instance_name, instance_info.clone())
-                })
-                .collect();
-        let periodic_dicovery = PeriodicDiscovery {
-            config_name: config_name.clone(),
-            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
-            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
-            config_spec: config.spec.clone(),
-            config_protocol: config.spec.protocol.clone(),
-            instance_map: instance_map.clone(),
-        };
-        periodic_dicovery
-            .update_connectivity_status(&mock, &currently_visible_instances, shared)
-            .await
-            .unwrap();
-        let unwrapped_instance_map = instance_map.lock().await.clone();
-        for (_, instance_info) in unwrapped_instance_map {
+        let currently_visible_instances: HashMap<String, protocols::DiscoveryResult> =
+            visible_discovery_results
+                .iter()
+                .map(|instance_info| {
+                    let instance_name =
+                        get_device_instance_name(&instance_info.digest, &config_name);
+                    (instance_name, instance_info.clone())
+                })
+                .collect();
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config_name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+        periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap();
+        let unwrapped_instance_map = instance_map.lock().await.clone();
+        for (_, instance_info) in unwrapped_instance_map {
+            assert_eq!(
+                instance_info.connectivity_status,
+                ConnectivityStatus::Online
+            );
+        }
+
+        //
+        // 3: Assert that ConnectivityStatus of unshared instances that come back online before next visibility check is changed to Online
+        //
+        let instance_map: InstanceMap = build_instance_map(
+            &config,
+            &mut visible_discovery_results,
+            &mut list_and_watch_message_receivers,
+            ConnectivityStatus::Offline(Instant::now()),
+        )
+        .await;
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config_name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+        periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap();
+        let unwrapped_instance_map = instance_map.lock().await.clone();
+        for (_, instance_info) in unwrapped_instance_map {
+            assert_eq!(
+                instance_info.connectivity_status,
+                ConnectivityStatus::Online
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_connectivity_status_uses_instance_shared() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let config = build_config_a();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_delete_instance()
+            .times(1)
+            .withf(|name: &str, namespace: &str| {
+                name == "config-a-359973" && namespace == "config-a-namespace"
+            })
+            .returning(|_, _| Ok(()));
+        mock.expect_create_event()
+            .times(1)
+            .withf(|event: &Event, namespace: &str| {
+                event.reason.as_deref() == Some(INSTANCE_DELETED_REASON)
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _| Ok(()));
+        let (list_and_watch_message_sender, mut list_and_watch_message_receiver) =
+            broadcast::channel(2);
+        let mut instances = HashMap::new();
+        for (instance_name, shared) in &[("config-a-b494b6", true), ("config-a-359973", false)] {
+            instances.insert(
+                instance_name.to_string(),
+                InstanceInfo {
+                    list_and_watch_message_sender: list_and_watch_message_sender.clone(),
+                    connectivity_status: ConnectivityStatus::Offline(Instant::now()),
+                    properties: HashMap::new(),
+                    shared: *shared,
+                    device_specs: Vec::new(),
+                    mounts: Vec::new(),
+                    capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+                },
+            );
+        }
+        let instance_map: InstanceMap = Arc::new(Mutex::new(instances));
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config.metadata.name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+
+        // The unshared instance is removed as soon as it is still offline, while the shared one
+        // of the same Configuration is given the grace period
+        periodic_dicovery
+            .update_connectivity_status(&mock, &HashMap::new(), device_plugin_path)
+            .await
+            .unwrap();
+        assert_eq!(
+            list_and_watch_message_receiver.try_recv().unwrap(),
+            device_plugin_service::ListAndWatchMessageKind::End
+        );
+        let instance_map = instance_map.lock().await;
+        assert_eq!(1, instance_map.len());
+        assert!(instance_map.contains_key("config-a-b494b6"));
+    }
+
+    #[tokio::test]
+    async fn test_update_connectivity_status_records_events() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let config = build_config_a();
+        let recorded_events: Arc<std::sync::Mutex<Vec<Event>>> =
+            Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded_events_clone = recorded_events.clone();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_delete_instance()
+            .times(2)
+            .returning(|_, _| Ok(()));
+        mock.expect_create_event()
+            .withf(|_: &Event, namespace: &str| namespace == "config-a-namespace")
+            .returning(move |event, _| {
+                recorded_events_clone.lock().unwrap().push(event.clone());
+                Ok(())
+            });
+        let (list_and_watch_message_sender, _list_and_watch_message_receiver) =
+            broadcast::channel(6);
+        let mut instances = HashMap::new();
+        for (instance_name, shared) in &[("config-a-b494b6", true), ("config-a-359973", false)] {
+            instances.insert(
+                instance_name.to_string(),
+                InstanceInfo {
+                    list_and_watch_message_sender: list_and_watch_message_sender.clone(),
+                    connectivity_status: ConnectivityStatus::Online,
+                    properties: HashMap::new(),
+                    shared: *shared,
+                    device_specs: Vec::new(),
+                    mounts: Vec::new(),
+                    capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+                },
+            );
+        }
+        let instance_map: InstanceMap = Arc::new(Mutex::new(instances));
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config.metadata.name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+        let take_events = || -> Vec<(String, String, String)> {
+            let mut events: Vec<(String, String, String)> = recorded_events
+                .lock()
+                .unwrap()
+                .drain(..)
+                .map(|event| {
+                    assert_eq!(Some("config-a".to_string()), event.involved_object.name);
+                    (
+                        event.reason.unwrap(),
+                        event.metadata.name.unwrap(),
+                        event.message.unwrap(),
+                    )
+                })
+                .collect();
+            events.sort();
+            events
+        };
+
+        // Both instances go offline
+        periodic_dicovery
+            .update_connectivity_status(&mock, &HashMap::new(), device_plugin_path)
+            .await
+            .unwrap();
+        let events = take_events();
+        assert_eq!(2, events.len());
+        for (reason, name, _) in &events {
+            assert_eq!(INSTANCE_OFFLINE_REASON, reason);
+            assert!(name.starts_with("config-a-"));
+        }
+        assert!(events[0].2.contains("config-a-359973"));
+        assert!(events[0].2.contains("next discovery"));
+        assert!(events[1].2.contains("config-a-b494b6"));
+        assert!(events[1].2.contains(&format!(
+            "{} seconds",
+            SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS
+        )));
+
+        // The unshared instance is deleted on the next discovery while the shared one is within its grace period
+        periodic_dicovery
+            .update_connectivity_status(&mock, &HashMap::new(), device_plugin_path)
+            .await
+            .unwrap();
+        let events = take_events();
+        assert_eq!(1, events.len());
+        assert_eq!(INSTANCE_DELETED_REASON, events[0].0);
+        assert!(events[0].2.contains("config-a-359973"));
+        assert!(events[0].2.ends_with(INSTANCE_DELETED_LOCAL_DISAPPEARED));
+
+        // The shared instance is deleted once its grace period expires
+        instance_map
+            .lock()
+            .await
+            .get_mut("config-a-b494b6")
+            .unwrap()
+            .connectivity_status = ConnectivityStatus::Offline(
+            Instant::now() - Duration::from_secs(SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS),
+        );
+        periodic_dicovery
+            .update_connectivity_status(&mock, &HashMap::new(), device_plugin_path)
+            .await
+            .unwrap();
+        let events = take_events();
+        assert_eq!(1, events.len());
+        assert_eq!(INSTANCE_DELETED_REASON, events[0].0);
+        assert!(events[0].2.contains("config-a-b494b6"));
+        assert!(events[0].2.ends_with(INSTANCE_DELETED_GRACE_PERIOD_EXPIRED));
+        assert!(instance_map.lock().await.is_empty());
+    }
+
+    /// Expects the properties of Instance config-a-b494b6 to be updated once, to a map containing `key`=`value`
+    fn configure_update_instance_properties(
+        mock: &mut MockKubeInterface,
+        key: &'static str,
+        value: &'static str,
+    ) {
+        mock.expect_update_instance_properties()
+            .times(1)
+            .withf(
+                move |properties: &HashMap<String, String>, name: &str, namespace: &str| {
+                    properties.get(key).map(|v| v.as_str()) == Some(value)
+                        && name == "config-a-b494b6"
+                        && namespace == "config-a-namespace"
+                },
+            )
+            .returning(|_, _, _| Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_update_connectivity_status_updates_properties() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let config = build_config_a();
+        let config_name = config.metadata.name.clone();
+        let mut mock = MockKubeInterface::new();
+        configure_update_instance_properties(&mut mock, "IP_ADDRESS", "10.0.0.2");
+        let mut properties = HashMap::new();
+        properties.insert("IP_ADDRESS".to_string(), "10.0.0.1".to_string());
+        let discovery_result = protocols::DiscoveryResult {
+            id: "device-a".to_string(),
+            digest: "b494b6".to_string(),
+            properties,
+            shared: false,
+            capacity: None,
+            device_specs: Vec::new(),
+            mounts: Vec::new(),
+        };
+        let instance_name = get_device_instance_name(&discovery_result.digest, &config_name);
+        let (list_and_watch_message_sender, mut list_and_watch_message_receiver) =
+            broadcast::channel(2);
+        let mut instances = HashMap::new();
+        instances.insert(
+            instance_name.clone(),
+            InstanceInfo {
+                list_and_watch_message_sender,
+                connectivity_status: ConnectivityStatus::Online,
+                properties: discovery_result.properties.clone(),
+                shared: discovery_result.shared,
+                device_specs: discovery_result.device_specs.clone(),
+                mounts: discovery_result.mounts.clone(),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            },
+        );
+        let instance_map: InstanceMap = Arc::new(Mutex::new(instances));
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config_name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+
+        // First round: the device is rediscovered with the same properties, so its Instance is left alone
+        let mut currently_visible_instances = HashMap::new();
+        currently_visible_instances.insert(instance_name.clone(), discovery_result.clone());
+        periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap();
+        assert!(list_and_watch_message_receiver.try_recv().is_err());
+
+        // Second round: the device keeps its id but its properties change, so its Instance is updated
+        let mut changed_discovery_result = discovery_result.clone();
+        changed_discovery_result
+            .properties
+            .insert("IP_ADDRESS".to_string(), "10.0.0.2".to_string());
+        currently_visible_instances.insert(instance_name.clone(), changed_discovery_result.clone());
+        periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap();
+        assert_eq!(
+            list_and_watch_message_receiver.try_recv().unwrap(),
+            device_plugin_service::ListAndWatchMessageKind::Continue
+        );
+        assert_eq!(
+            changed_discovery_result.properties,
+            instance_map
+                .lock()
+                .await
+                .get(&instance_name)
+                .unwrap()
+                .properties
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_connectivity_status_updates_devices() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let config = build_config_a();
+        let config_name = config.metadata.name.clone();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_patch_instance()
+            .times(1)
+            .withf(|patch: &serde_json::Value, name: &str, namespace: &str| {
+                patch[0]["path"] == "/spec/deviceSpecs"
+                    && patch[0]["value"][0]["hostPath"] == "/dev/nvidia1"
+                    && patch[1]["path"] == "/spec/mounts"
+                    && patch[1]["value"][0]["hostPath"] == "/usr/lib/nvidia"
+                    && name == "config-a-b494b6"
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _, _| Ok(()));
+        let discovery_result = protocols::DiscoveryResult {
+            id: "device-a".to_string(),
+            digest: "b494b6".to_string(),
+            properties: HashMap::new(),
+            shared: false,
+            capacity: None,
+            device_specs: vec![v1beta1::DeviceSpec {
+                container_path: "/dev/nvidia0".to_string(),
+                host_path: "/dev/nvidia0".to_string(),
+                permissions: "rw".to_string(),
+            }],
+            mounts: Vec::new(),
+        };
+        let instance_name = get_device_instance_name(&discovery_result.digest, &config_name);
+        let (list_and_watch_message_sender, mut list_and_watch_message_receiver) =
+            broadcast::channel(2);
+        let mut instances = HashMap::new();
+        instances.insert(
+            instance_name.clone(),
+            InstanceInfo {
+                list_and_watch_message_sender,
+                connectivity_status: ConnectivityStatus::Online,
+                properties: discovery_result.properties.clone(),
+                shared: discovery_result.shared,
+                device_specs: discovery_result.device_specs.clone(),
+                mounts: discovery_result.mounts.clone(),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            },
+        );
+        let instance_map: InstanceMap = Arc::new(Mutex::new(instances));
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config_name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+
+        // First round: the device is rediscovered with the same device specs and mounts, so its Instance is left alone
+        let mut currently_visible_instances = HashMap::new();
+        currently_visible_instances.insert(instance_name.clone(), discovery_result.clone());
+        periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap();
+        assert!(list_and_watch_message_receiver.try_recv().is_err());
+
+        // Second round: the device is now reported with another host device and a mount, so its Instance is patched
+        let mut changed_discovery_result = discovery_result.clone();
+        changed_discovery_result.device_specs[0].host_path = "/dev/nvidia1".to_string();
+        changed_discovery_result.mounts = vec![v1beta1::Mount {
+            container_path: "/usr/local/nvidia".to_string(),
+            host_path: "/usr/lib/nvidia".to_string(),
+            read_only: true,
+        }];
+        currently_visible_instances.insert(instance_name.clone(), changed_discovery_result.clone());
+        periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap();
+        assert_eq!(
+            list_and_watch_message_receiver.try_recv().unwrap(),
+            device_plugin_service::ListAndWatchMessageKind::Continue
+        );
+        let instance_map = instance_map.lock().await;
+        let instance_info = instance_map.get(&instance_name).unwrap();
+        assert_eq!(
+            changed_discovery_result.device_specs,
+            instance_info.device_specs
+        );
+        assert_eq!(changed_discovery_result.mounts, instance_info.mounts);
+    }
+
+    #[tokio::test]
+    async fn test_update_connectivity_status_device_health() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let config = build_config_a();
+        let config_name = config.metadata.name.clone();
+        let mut mock = MockKubeInterface::new();
+        configure_update_instance_properties(
+            &mut mock,
+            protocols::AKRI_DEVICE_HEALTH_LABEL_ID,
+            "Healthy",
+        );
+        let mut properties = HashMap::new();
+        properties.insert(
+            protocols::AKRI_DEVICE_HEALTH_LABEL_ID.to_string(),
+            "Unhealthy".to_string(),
+        );
+        let unhealthy_discovery_result = protocols::DiscoveryResult {
+            id: "device-a".to_string(),
+            digest: "b494b6".to_string(),
+            properties,
+            shared: true,
+            capacity: None,
+            device_specs: Vec::new(),
+            mounts: Vec::new(),
+        };
+        let instance_name =
+            get_device_instance_name(&unhealthy_discovery_result.digest, &config_name);
+        let (list_and_watch_message_sender, mut list_and_watch_message_receiver) =
+            broadcast::channel(2);
+        let mut instances = HashMap::new();
+        instances.insert(
+            instance_name.clone(),
+            InstanceInfo {
+                list_and_watch_message_sender,
+                connectivity_status: ConnectivityStatus::Online,
+                properties: unhealthy_discovery_result.properties.clone(),
+                shared: unhealthy_discovery_result.shared,
+                device_specs: unhealthy_discovery_result.device_specs.clone(),
+                mounts: unhealthy_discovery_result.mounts.clone(),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            },
+        );
+        let instance_map: InstanceMap = Arc::new(Mutex::new(instances));
+        let periodic_dicovery = PeriodicDiscovery {
+            config_name: config_name.clone(),
+            config_uid: config.metadata.uid.as_ref().unwrap().clone(),
+            config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec.clone(),
+            config_protocol: config.spec.protocol.clone(),
+            instance_map: instance_map.clone(),
+            event_recorder: EventRecorder::new(),
+        };
+
+        // Rediscovering the device while it is still unhealthy changes nothing
+        let mut currently_visible_instances = HashMap::new();
+        currently_visible_instances
+            .insert(instance_name.clone(), unhealthy_discovery_result.clone());
+        assert!(periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(list_and_watch_message_receiver.try_recv().is_err());
+        assert!(device_plugin_service::is_reported_unhealthy(
+            &instance_map
+                .lock()
+                .await
+                .get(&instance_name)
+                .unwrap()
+                .properties
+        ));
+
+        // The device becoming healthy under the same id updates the existing instance and nudges list_and_watch
+        let mut healthy_discovery_result = unhealthy_discovery_result.clone();
+        healthy_discovery_result.properties.insert(
+            protocols::AKRI_DEVICE_HEALTH_LABEL_ID.to_string(),
+            "Healthy".to_string(),
+        );
+        currently_visible_instances.insert(instance_name.clone(), healthy_discovery_result);
+        assert!(periodic_dicovery
+            .update_connectivity_status(&mock, &currently_visible_instances, device_plugin_path)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            list_and_watch_message_receiver.try_recv().unwrap(),
+            device_plugin_service::ListAndWatchMessageKind::Continue
+        );
+        let instance_info = instance_map
+            .lock()
+            .await
+            .get(&instance_name)
+            .unwrap()
+            .clone();
+        assert_eq!(
+            instance_info.connectivity_status,
+            ConnectivityStatus::Online
+        );
+        assert!(!device_plugin_service::is_reported_unhealthy(
+            &instance_info.properties
+        ));
+    }
+
+    fn create_shared_discovery_result(id: &str) -> protocols::DiscoveryResult {
+        protocols::DiscoveryResult {
+            id: id.to_string(),
+            digest: protocols::generate_instance_digest(id, true),
+            properties: HashMap::new(),
+            shared: true,
+            capacity: None,
+            device_specs: Vec::new(),
+            mounts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_get_currently_visible_instances_digest_collision() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        // These ids have the same 3 byte digest
+        let discovery_results = vec![
+            create_shared_discovery_result("device-6888"),
+            create_shared_discovery_result("device-2726"),
+        ];
+        assert_eq!(discovery_results[0].digest, discovery_results[1].digest);
+        let colliding_instance_name =
+            get_device_instance_name(&discovery_results[0].digest, "config-a");
+
+        // With neither device owning the name, the lower id claims it and the other is given a longer digest
+        let (currently_visible_instances, digest_collisions) =
+            get_currently_visible_instances("config-a", &discovery_results, &HashMap::new());
+        assert_eq!(2, currently_visible_instances.len());
+        assert_eq!(
+            "device-2726",
+            currently_visible_instances
+                .get(&colliding_instance_name)
+                .unwrap()
+                .id
+        );
+        let extended_instance_name = get_device_instance_name(
+            &protocols::generate_extended_instance_digest("device-6888", true, 1).unwrap(),
+            "config-a",
+        );
+        assert_eq!(
+            "device-6888",
+            currently_visible_instances
+                .get(&extended_instance_name)
+                .unwrap()
+                .id
+        );
+        // The collision is reported with both device ids and the colliding digest
+        assert_eq!(
+            vec![DigestCollision {
+                digest: discovery_results[0].digest.clone(),
+                device_id: "device-6888".to_string(),
+                other_device_id: "device-2726".to_string(),
+                extended_digest: protocols::generate_extended_instance_digest(
+                    "device-6888",
+                    true,
+                    1
+                )
+                .unwrap(),
+            }],
+            digest_collisions
+        );
+
+        // A device that already owns the name keeps it
+        let mut instance_ids = HashMap::new();
+        instance_ids.insert(colliding_instance_name.clone(), "device-6888".to_string());
+        let (currently_visible_instances, digest_collisions) =
+            get_currently_visible_instances("config-a", &discovery_results, &instance_ids);
+        assert_eq!(2, currently_visible_instances.len());
+        assert_eq!(1, digest_collisions.len());
+        assert_eq!("device-2726", digest_collisions[0].device_id);
+        assert_eq!(
+            "device-6888",
+            currently_visible_instances
+                .get(&colliding_instance_name)
+                .unwrap()
+                .id
+        );
+        let instance_ids: HashMap<String, String> = currently_visible_instances
+            .iter()
+            .map(|(name, discovery_result)| (name.clone(), discovery_result.id.clone()))
+            .collect();
+
+        // Names are stable across discovery rounds, and collisions that were already resolved are not reported again
+        let (next_currently_visible_instances, digest_collisions) =
+            get_currently_visible_instances("config-a", &discovery_results, &instance_ids);
+        assert!(digest_collisions.is_empty());
+        let mut next_instance_names: Vec<String> = next_currently_visible_instances
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let mut instance_names: Vec<String> = instance_ids.keys().cloned().collect();
+        next_instance_names.sort();
+        instance_names.sort();
+        assert_eq!(instance_names, next_instance_names);
+    }
+
+    #[tokio::test]
+    async fn test_get_known_instance_ids_from_other_node() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let config = build_config_a();
+        // Another node saw both devices and gave the colliding name to device-2726, while this node only sees device-6888
+        let discovery_results = vec![create_shared_discovery_result("device-6888")];
+        let colliding_instance_name =
+            get_device_instance_name(&discovery_results[0].digest, "config-a");
+        let other_node_instance_name = colliding_instance_name.clone();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_get_instances().times(1).returning(move || {
+            let instance_list_json =
+                fs::read_to_string("../test/json/pre-existing-instance-list.json").unwrap();
+            let mut instances: KubeAkriInstanceList =
+                serde_json::from_str(&instance_list_json).unwrap();
+            instances.items[0].metadata.name = other_node_instance_name.clone();
+            instances.items[0].spec.device_id = Some("device-2726".to_string());
+            Ok(instances)
+        });
+        let periodic_discovery =
+            build_periodic_discovery(&config, Arc::new(Mutex::new(HashMap::new())));
+        let known_instance_ids = periodic_discovery
+            .get_known_instance_ids(&mock, &discovery_results, &HashMap::new())
+            .await;
+        assert_eq!(1, known_instance_ids.len());
+        assert_eq!("device-2726", known_instance_ids[&colliding_instance_name]);
+
+        // The device is given the same extended name the other node gave it
+        let (currently_visible_instances, _) =
+            get_currently_visible_instances("config-a", &discovery_results, &known_instance_ids);
+        let extended_instance_name = get_device_instance_name(
+            &protocols::generate_extended_instance_digest("device-6888", true, 1).unwrap(),
+            "config-a",
+        );
+        assert_eq!(
+            vec![&extended_instance_name],
+            currently_visible_instances.keys().collect::<Vec<&String>>()
+        );
+
+        // Instances are not looked up once every device has been given a name by this node
+        let mut instance_ids = HashMap::new();
+        instance_ids.insert(extended_instance_name, "device-6888".to_string());
+        let mut mock = MockKubeInterface::new();
+        mock.expect_get_instances().times(0);
+        assert_eq!(
+            instance_ids,
+            periodic_discovery
+                .get_known_instance_ids(&mock, &discovery_results, &instance_ids)
+                .await
+        );
+    }
+
+    #[test]
+    fn test_discovery_circuit_breaker() {
+        let start = Instant::now();
+        let seconds = |secs: u64| start + Duration::from_secs(secs);
+        let mut circuit_breaker =
+            DiscoveryCircuitBreaker::new(3, Duration::from_secs(300), Duration::from_secs(600));
+        assert_eq!(None, circuit_breaker.remaining_pause(start));
+
+        // A success resets the count of consecutive failures
+        assert!(!circuit_breaker.record_failure(seconds(0)));
+        assert!(!circuit_breaker.record_failure(seconds(10)));
+        circuit_breaker.record_success();
+        assert!(!circuit_breaker.record_failure(seconds(20)));
+        assert!(!circuit_breaker.record_failure(seconds(30)));
+        assert_eq!(None, circuit_breaker.remaining_pause(seconds(30)));
+
+        // Failures spread out over more than the window do not open the circuit
+        assert!(!circuit_breaker.record_failure(seconds(400)));
+        assert_eq!(None, circuit_breaker.remaining_pause(seconds(400)));
+
+        // The threshold of failures within the window opens the circuit
+        assert!(!circuit_breaker.record_failure(seconds(405)));
+        assert!(circuit_breaker.record_failure(seconds(410)));
+        assert_eq!(
+            Some(Duration::from_secs(590)),
+            circuit_breaker.remaining_pause(seconds(420))
+        );
+
+        // After the reset period the circuit half-opens, and another failure opens it again
+        assert_eq!(None, circuit_breaker.remaining_pause(seconds(1010)));
+        assert!(circuit_breaker.record_failure(seconds(1010)));
+        assert_eq!(
+            Some(Duration::from_secs(600)),
+            circuit_breaker.remaining_pause(seconds(1010))
+        );
+
+        // A success while half-open closes the circuit
+        assert_eq!(None, circuit_breaker.remaining_pause(seconds(1610)));
+        circuit_breaker.record_success();
+        assert_eq!(None, circuit_breaker.remaining_pause(seconds(1620)));
+        assert!(!circuit_breaker.record_failure(seconds(1620)));
+    }
+
+    #[tokio::test]
+    async fn test_build_device_plugin_with_timeout() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        // A build that never finishes is abandoned once the timeout elapses
+        let result = build_device_plugin_with_timeout(
+            "config-a-b494b6",
+            Duration::from_millis(10),
+            futures::future::pending(),
+        )
+        .await;
+        assert!(result.is_err());
+
+        // A build that finishes in time is returned as is
+        let (list_and_watch_message_sender, _) = broadcast::channel(2);
+        let result = build_device_plugin_with_timeout(
+            "config-a-b494b6",
+            Duration::from_millis(10),
+            futures::future::ready(Ok(list_and_watch_message_sender)),
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_get_max_concurrent_device_plugin_builds() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let cases = vec![
+            (None, DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS),
+            (Some("3"), 3),
+            (Some("0"), DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS),
+            (Some("many"), DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS),
+        ];
+        for (value, expected) in cases {
+            let mut mock_query = MockEnvVarQuery::new();
+            mock_query
+                .expect_get_env_var()
+                .withf(|name: &str| name == MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS_LABEL)
+                .returning(move |_| {
+                    value
+                        .map(|v| v.to_string())
+                        .ok_or(env::VarError::NotPresent)
+                });
             assert_eq!(
-                instance_info.connectivity_status,
-                ConnectivityStatus::Online
+                expected,
+                get_max_concurrent_device_plugin_builds(&mock_query)
             );
         }
+    }
 
-        //
-        // 3: Assert that ConnectivityStatus of unshared instances that come back online before next visibility check is changed to Online
-        //
-        let instance_map: InstanceMap = build_instance_map(
-            &config,
-            &mut visible_discovery_results,
-            &mut list_and_watch_message_receivers,
-            ConnectivityStatus::Offline(Instant::now()),
+    /// Stands in for `build_device_plugin`, recording how many builds are running at once.
+    /// Every fifth build fails.
+    async fn mock_build_device_plugin(
+        build_number: usize,
+        running: Arc<AtomicUsize>,
+        max_running: Arc<AtomicUsize>,
+    ) -> Result<
+        broadcast::Sender<ListAndWatchMessageKind>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    > {
+        let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+        max_running.fetch_max(now_running, Ordering::SeqCst);
+        tokio::time::delay_for(Duration::from_millis(20)).await;
+        running.fetch_sub(1, Ordering::SeqCst);
+        if build_number % 5 == 0 {
+            return Err(format!("build {} failed", build_number).into());
+        }
+        let (list_and_watch_message_sender, _) = broadcast::channel(2);
+        Ok(list_and_watch_message_sender)
+    }
+
+    #[tokio::test]
+    async fn test_build_device_plugins() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let builds = (0..12)
+            .map(|i| {
+                (
+                    format!("config-a-{}", i),
+                    mock_build_device_plugin(i, running.clone(), max_running.clone()),
+                )
+            })
+            .collect();
+        let mut failed = build_device_plugins(builds, 3, Duration::from_secs(5)).await;
+        failed.sort();
+        assert_eq!(vec!["config-a-0", "config-a-10", "config-a-5"], failed);
+        // Builds run concurrently but never more than the limit at once
+        assert_eq!(3, max_running.load(Ordering::SeqCst));
+        assert_eq!(0, running.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_build_device_plugins_default_limit() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let builds = (1..=20)
+            .map(|i| {
+                (
+                    format!("config-a-{}", i),
+                    mock_build_device_plugin(i, running.clone(), max_running.clone()),
+                )
+            })
+            .collect();
+        let failed = build_device_plugins(
+            builds,
+            DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS,
+            Duration::from_secs(5),
         )
         .await;
-        let shared = false;
-        let periodic_dicovery = PeriodicDiscovery {
-            config_name: config_name.clone(),
+        assert_eq!(4, failed.len());
+        assert_eq!(5, max_running.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_limit_new_discovery_results() {
+        let new_discovery_results: Vec<protocols::DiscoveryResult> = (0..10)
+            .map(|x| protocols::DiscoveryResult {
+                id: format!("device-{}", x),
+                digest: format!("digest-{}", x),
+                properties: HashMap::new(),
+                shared: true,
+                capacity: None,
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+            })
+            .collect();
+        // No limit
+        assert_eq!(
+            10,
+            limit_new_discovery_results("config-a", new_discovery_results.clone(), None, 40).len()
+        );
+        // Room for all of them
+        assert_eq!(
+            10,
+            limit_new_discovery_results("config-a", new_discovery_results.clone(), Some(50), 40)
+                .len()
+        );
+        // Truncated at the limit
+        assert_eq!(
+            new_discovery_results[..5].to_vec(),
+            limit_new_discovery_results("config-a", new_discovery_results.clone(), Some(50), 45)
+        );
+        // Already at or over the limit
+        assert!(limit_new_discovery_results(
+            "config-a",
+            new_discovery_results.clone(),
+            Some(50),
+            50
+        )
+        .is_empty());
+        assert!(
+            limit_new_discovery_results("config-a", new_discovery_results, Some(50), 60).is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_instances_to_adopt() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let config = build_config_a();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_get_instances().times(1).returning(|| {
+            let instance_list_json =
+                fs::read_to_string("../test/json/pre-existing-instance-list.json").unwrap();
+            Ok(serde_json::from_str(&instance_list_json).unwrap())
+        });
+        // Adopted Instances should never be re-created
+        mock.expect_create_instance().times(0);
+        let instances_to_adopt: Vec<String> =
+            build_periodic_discovery(&config, Arc::new(Mutex::new(HashMap::new())))
+                .get_instances_to_adopt(&mock, "node-a")
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|instance| instance.metadata.name)
+                .collect();
+        assert_eq!(
+            vec!["config-a-b494b6".to_string(), "config-a-359973".to_string()],
+            instances_to_adopt
+        );
+    }
+
+    fn build_periodic_discovery(
+        config: &KubeAkriConfig,
+        instance_map: InstanceMap,
+    ) -> PeriodicDiscovery {
+        PeriodicDiscovery {
+            config_name: config.metadata.name.clone(),
             config_uid: config.metadata.uid.as_ref().unwrap().clone(),
             config_namespace: config.metadata.namespace.as_ref().unwrap().clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
             config_spec: config.spec.clone(),
             config_protocol: config.spec.protocol.clone(),
-            instance_map: instance_map.clone(),
-        };
-        periodic_dicovery
-            .update_connectivity_status(&mock, &currently_visible_instances, shared)
-            .await
-            .unwrap();
-        let unwrapped_instance_map = instance_map.lock().await.clone();
-        for (_, instance_info) in unwrapped_instance_map {
-            assert_eq!(
-                instance_info.connectivity_status,
-                ConnectivityStatus::Online
-            );
+            instance_map,
+            event_recorder: EventRecorder::new(),
         }
     }
 
+    #[tokio::test]
+    async fn test_adopt_pre_existing_instances() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+        let config = build_config_a();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_get_instances().times(1).returning(|| {
+            let instance_list_json =
+                fs::read_to_string("../test/json/pre-existing-instance-list.json").unwrap();
+            Ok(serde_json::from_str(&instance_list_json).unwrap())
+        });
+        // Adopted Instances should never be re-created
+        mock.expect_create_instance().times(0);
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_temp_dir_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let registrations = device_plugin_service::fake_kubelet::serve_fake_kubelet(
+            &device_plugin_temp_dir.path().join(KUBELET_SOCKET_NAME),
+        );
+        let instance_map: InstanceMap = Arc::new(Mutex::new(HashMap::new()));
+        build_periodic_discovery(&config, instance_map.clone())
+            .adopt_pre_existing_instances(&mock, device_plugin_temp_dir_path)
+            .await;
+
+        // Assert a device plugin was built and registered for each adopted Instance
+        let instance_map = instance_map.lock().await;
+        let mut adopted_instances: Vec<&String> = instance_map.keys().collect();
+        adopted_instances.sort();
+        assert_eq!(
+            vec!["config-a-359973", "config-a-b494b6"],
+            adopted_instances
+        );
+        assert!(instance_map
+            .values()
+            .all(|instance_info| instance_info.connectivity_status == ConnectivityStatus::Online));
+        // Device specs stored in an Instance are kept, so a changed device is detected by the next discovery
+        assert_eq!(
+            vec![v1beta1::DeviceSpec {
+                container_path: "/dev/video0".to_string(),
+                host_path: "/dev/video1".to_string(),
+                permissions: "rw".to_string(),
+            }],
+            instance_map["config-a-b494b6"].device_specs
+        );
+        assert!(instance_map["config-a-359973"].device_specs.is_empty());
+        let mut registrations = registrations.lock().await.clone();
+        registrations.sort();
+        assert_eq!(
+            vec!["akri.sh/config-a-359973", "akri.sh/config-a-b494b6"],
+            registrations
+        );
+    }
+
+    #[tokio::test]
+    async fn test_adopt_pre_existing_instances_get_instances_error() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+        let config = build_config_a();
+        let mut mock = MockKubeInterface::new();
+        mock.expect_get_instances()
+            .times(1)
+            .returning(|| Err(anyhow::format_err!("api server unavailable").into()));
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let instance_map: InstanceMap = Arc::new(Mutex::new(HashMap::new()));
+        // Failing to look up Instances leaves them to be handled as newly discovered rather than ending discovery
+        build_periodic_discovery(&config, instance_map.clone())
+            .adopt_pre_existing_instances(&mock, device_plugin_temp_dir.path().to_str().unwrap())
+            .await;
+        assert!(instance_map.lock().await.is_empty());
+    }
+
     /// Checks the termination case for when an unshared instance is still offline upon the second periodic discovery
     /// Must be run independently since writing "OFFLINE" to DEBUG_ECHO_AVAILABILITY_CHECK_PATH in order to emulate
     /// offline devices can clobber other tests run in parallel that are looking for online devices.
@@ -739,15 +3442,21 @@ mod config_action_tests {
         env::set_var("AGENT_NODE_NAME", "node-a");
         env::set_var("ENABLE_DEBUG_ECHO", "yes");
         // Make each get_instances check return an empty list of instances
-        let path_to_config = "../test/json/config-a.json";
-        let dcc_json = fs::read_to_string(path_to_config).expect("Unable to read file");
-        let config: KubeAkriConfig = serde_json::from_str(&dcc_json).unwrap();
+        let config = build_config_a();
         let config_name = config.metadata.name.clone();
         let mut visible_discovery_results = Vec::new();
         let mut list_and_watch_message_receivers = Vec::new();
         let (mut watch_periph_tx, watch_periph_rx) = mpsc::channel(2);
         let (finished_watching_tx, mut finished_watching_rx) = broadcast::channel(2);
         let mut mock = MockKubeInterface::new();
+        mock.expect_get_instances().times(1).returning(|| {
+            let instance_list_json = fs::read_to_string("../test/json/empty-list.json").unwrap();
+            Ok(serde_json::from_str(&instance_list_json).unwrap())
+        });
+        mock.expect_find_configuration()
+            .returning(|_, _| Ok(build_config_a()));
+        mock.expect_update_configuration_status()
+            .returning(|_, _, _, _| Ok(()));
 
         // Set instance count metric to ensure it is cleared
         INSTANCE_COUNT_METRIC
@@ -767,6 +3476,7 @@ mod config_action_tests {
         mock.expect_delete_instance()
             .times(2)
             .returning(move |_, _| Ok(()));
+        mock.expect_create_event().returning(|_, _| Ok(()));
         let instance_map_clone = instance_map.clone();
         // Change instances to be offline
         fs::write(DEBUG_ECHO_AVAILABILITY_CHECK_PATH, OFFLINE).unwrap();
@@ -776,8 +3486,10 @@ mod config_action_tests {
                 config_uid: config.metadata.uid.as_ref().unwrap().to_string(),
                 config_namespace: config.metadata.namespace.as_ref().unwrap().to_string(),
                 config_protocol: config.spec.protocol.clone(),
+                capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
                 config_spec: config.spec,
                 instance_map: instance_map_clone,
+                event_recorder: EventRecorder::new(),
             };
             let device_plugin_temp_dir =
                 Builder::new().prefix("device-plugins-").tempdir().unwrap();
@@ -821,4 +3533,410 @@ mod config_action_tests {
         // Reset file to be online
         fs::write(DEBUG_ECHO_AVAILABILITY_CHECK_PATH, "ONLINE").unwrap();
     }
+
+    /// Checks that a discovery error is written to the Configuration's status rather than ending discovery
+    #[cfg(feature = "udev-feat")]
+    #[tokio::test]
+    async fn test_do_periodic_discovery_reports_discovery_error() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("AGENT_NODE_NAME", "node-a");
+        let mut config = build_config_a();
+        // ACTION is not a supported udev rule field, so discovery fails
+        config.spec.protocol = ProtocolHandler::udev(
+            akri_shared::akri::configuration::UdevDiscoveryHandlerConfig {
+                udev_rules: vec!["ACTION==\"add\"".to_string()],
+            },
+        );
+        let (mut stop_discovery_sender, stop_discovery_receiver) = mpsc::channel(2);
+        let (finished_discovery_sender, mut finished_discovery_receiver) = broadcast::channel(2);
+        let (mut status_updated_sender, mut status_updated_receiver) = mpsc::channel(2);
+        let mut mock = MockKubeInterface::new();
+        mock.expect_get_instances().times(1).returning(|| {
+            let instance_list_json = fs::read_to_string("../test/json/empty-list.json").unwrap();
+            Ok(serde_json::from_str(&instance_list_json).unwrap())
+        });
+        mock.expect_find_configuration()
+            .times(1)
+            .returning(|_, _| Ok(build_config_a()));
+        mock.expect_update_configuration_status()
+            .times(1)
+            .withf(
+                |status: &ConfigurationStatus, _: &str, name: &str, namespace: &str| {
+                    let condition = match status.conditions.as_slice() {
+                        [condition] => condition,
+                        _ => return false,
+                    };
+                    condition.condition_type == DISCOVERY_ERROR_CONDITION
+                        && condition.status == "True"
+                        && condition.message.contains("unsupported field ACTION")
+                        && condition.node_name.as_deref() == Some("node-a")
+                        && name == "config-a"
+                        && namespace == "config-a-namespace"
+                },
+            )
+            .returning(move |_, _, _, _| {
+                status_updated_sender.try_send(()).unwrap();
+                Ok(())
+            });
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_temp_dir_path =
+            device_plugin_temp_dir.path().to_str().unwrap().to_string();
+        let periodic_discovery = PeriodicDiscovery {
+            config_name: config.metadata.name.clone(),
+            config_uid: config.metadata.uid.clone().unwrap(),
+            config_namespace: config.metadata.namespace.clone().unwrap(),
+            config_protocol: config.spec.protocol.clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec,
+            instance_map: Arc::new(Mutex::new(HashMap::new())),
+            event_recorder: EventRecorder::new(),
+        };
+        let discovery_task = tokio::spawn(async move {
+            periodic_discovery
+                .do_periodic_discovery(
+                    &mock,
+                    stop_discovery_receiver,
+                    finished_discovery_sender,
+                    &device_plugin_temp_dir_path,
+                )
+                .await
+                .unwrap();
+        });
+
+        // Discovery keeps running after reporting the error until told to stop
+        assert!(status_updated_receiver.recv().await.is_some());
+        stop_discovery_sender.send(()).await.unwrap();
+        assert!(finished_discovery_receiver.recv().await.is_ok());
+        discovery_task.await.unwrap();
+    }
+
+    /// Serves the Configuration's status the way the API server does, rejecting writes made at a stale
+    /// resourceVersion, so that several Agents can report their conditions on the same Configuration
+    fn configure_configuration_status(
+        mock: &mut MockKubeInterface,
+        stored_status: Arc<std::sync::Mutex<(u32, ConfigurationStatus)>>,
+    ) {
+        let find_stored_status = stored_status.clone();
+        mock.expect_find_configuration().returning(move |_, _| {
+            let (resource_version, status) = find_stored_status.lock().unwrap().clone();
+            let mut config = build_config_a();
+            config.metadata.resourceVersion = Some(resource_version.to_string());
+            config.status = Some(status);
+            Ok(config)
+        });
+        mock.expect_update_configuration_status().returning(
+            move |status: &ConfigurationStatus, resource_version: &str, _, _| {
+                let mut stored_status = stored_status.lock().unwrap();
+                if resource_version != stored_status.0.to_string() {
+                    return Err(anyhow::format_err!("conflict").into());
+                }
+                *stored_status = (stored_status.0 + 1, status.clone());
+                Ok(())
+            },
+        );
+    }
+
+    /// Checks that each node reports its own DiscoveryError condition, without overwriting those of other nodes
+    #[tokio::test]
+    async fn test_report_discovery_error_from_two_nodes() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let config = build_config_a();
+        let stored_status = Arc::new(std::sync::Mutex::new((1, ConfigurationStatus::default())));
+        let mut mock = MockKubeInterface::new();
+        configure_configuration_status(&mut mock, stored_status.clone());
+        let periodic_discovery =
+            build_periodic_discovery(&config, Arc::new(Mutex::new(HashMap::new())));
+        let mut node_a_reported = None;
+        let mut node_b_reported = None;
+        let get_conditions = || {
+            stored_status
+                .lock()
+                .unwrap()
+                .1
+                .conditions
+                .iter()
+                .map(|condition| {
+                    (
+                        condition.node_name.clone().unwrap(),
+                        condition.status.clone(),
+                        condition.message.clone(),
+                    )
+                })
+                .collect::<Vec<(String, String, String)>>()
+        };
+        let condition = |node_name: &str, status: &str, message: &str| {
+            (
+                node_name.to_string(),
+                status.to_string(),
+                message.to_string(),
+            )
+        };
+
+        // Discovery fails on node A while it succeeds on node B, which has no error to clear
+        periodic_discovery
+            .report_discovery_error(
+                &mock,
+                "node-a",
+                &mut node_a_reported,
+                Some("udev failed".to_string()),
+            )
+            .await;
+        periodic_discovery
+            .report_discovery_error(&mock, "node-b", &mut node_b_reported, None)
+            .await;
+        assert_eq!(
+            vec![condition("node-a", "True", "udev failed")],
+            get_conditions()
+        );
+
+        // Node B starts failing too, and node A's condition is kept
+        periodic_discovery
+            .report_discovery_error(
+                &mock,
+                "node-b",
+                &mut node_b_reported,
+                Some("timed out".to_string()),
+            )
+            .await;
+        assert_eq!(
+            vec![
+                condition("node-a", "True", "udev failed"),
+                condition("node-b", "True", "timed out")
+            ],
+            get_conditions()
+        );
+
+        // Node A recovering only clears its own condition
+        periodic_discovery
+            .report_discovery_error(&mock, "node-a", &mut node_a_reported, None)
+            .await;
+        assert_eq!(
+            vec![
+                condition("node-a", "False", ""),
+                condition("node-b", "True", "timed out")
+            ],
+            get_conditions()
+        );
+        assert_eq!(Some(None), node_a_reported);
+        assert_eq!(Some(Some("timed out".to_string())), node_b_reported);
+    }
+
+    #[test]
+    fn test_discovery_handler_status_metric() {
+        let protocol = "test-discovery-handler-status-metric";
+        let get_count = |connectivity_status: &str| {
+            DISCOVERY_HANDLER_COUNT_METRIC
+                .with_label_values(&[protocol, connectivity_status])
+                .get()
+        };
+        let mut status_metric = DiscoveryHandlerStatusMetric::new(protocol);
+        assert_eq!(get_count(DISCOVERY_HANDLER_ONLINE), 0);
+        assert_eq!(get_count(DISCOVERY_HANDLER_OFFLINE), 0);
+
+        status_metric.set(DISCOVERY_HANDLER_ONLINE);
+        status_metric.set(DISCOVERY_HANDLER_ONLINE);
+        assert_eq!(get_count(DISCOVERY_HANDLER_ONLINE), 1);
+        assert_eq!(get_count(DISCOVERY_HANDLER_OFFLINE), 0);
+
+        status_metric.set(DISCOVERY_HANDLER_OFFLINE);
+        assert_eq!(get_count(DISCOVERY_HANDLER_ONLINE), 0);
+        assert_eq!(get_count(DISCOVERY_HANDLER_OFFLINE), 1);
+
+        drop(status_metric);
+        assert_eq!(get_count(DISCOVERY_HANDLER_ONLINE), 0);
+        assert_eq!(get_count(DISCOVERY_HANDLER_OFFLINE), 0);
+    }
+
+    #[tokio::test]
+    async fn test_discover_counts_results() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        env::set_var("ENABLE_DEBUG_ECHO", "yes");
+        let config = build_config_a();
+        // Use a Configuration name unique to this test since the metric is global
+        let config_name = "test-discover-counts-results".to_string();
+        let periodic_discovery = PeriodicDiscovery {
+            config_name: config_name.clone(),
+            config_uid: config.metadata.uid.clone().unwrap(),
+            config_namespace: config.metadata.namespace.clone().unwrap(),
+            config_protocol: config.spec.protocol.clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec,
+            instance_map: Arc::new(Mutex::new(HashMap::new())),
+            event_recorder: EventRecorder::new(),
+        };
+        let protocol =
+            protocols::get_discovery_handler(&periodic_discovery.config_protocol).unwrap();
+        let mut expected_count = 0;
+        for _ in 0..2 {
+            let discovery_results = periodic_discovery
+                .discover(
+                    &*protocol,
+                    Duration::from_secs(DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS),
+                )
+                .await
+                .unwrap();
+            expected_count += discovery_results.len() as u64;
+        }
+        assert_eq!(
+            DISCOVERY_RESULT_COUNT_METRIC
+                .with_label_values(&[&config_name])
+                .get(),
+            expected_count
+        );
+    }
+
+    /// Metadata and fields of a span recorded by `RecordingSubscriber`
+    #[cfg(feature = "tracing-feat")]
+    type RecordedSpan = (&'static tracing::Metadata<'static>, HashMap<String, String>);
+
+    /// Subscriber that records the fields of the spans and events created while it is the default
+    #[cfg(feature = "tracing-feat")]
+    #[derive(Clone, Default)]
+    struct RecordingSubscriber {
+        spans: Arc<std::sync::Mutex<Vec<RecordedSpan>>>,
+        events: Arc<std::sync::Mutex<Vec<HashMap<String, String>>>>,
+    }
+
+    #[cfg(feature = "tracing-feat")]
+    struct FieldRecorder<'a>(&'a mut HashMap<String, String>);
+
+    #[cfg(feature = "tracing-feat")]
+    impl tracing::field::Visit for FieldRecorder<'_> {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    #[cfg(feature = "tracing-feat")]
+    impl tracing::Subscriber for RecordingSubscriber {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::Id {
+            let mut fields = HashMap::new();
+            span.record(&mut FieldRecorder(&mut fields));
+            let mut spans = self.spans.lock().unwrap();
+            spans.push((span.metadata(), fields));
+            tracing::Id::from_u64(spans.len() as u64)
+        }
+        fn record(&self, _span: &tracing::Id, _values: &tracing::span::Record<'_>) {}
+        fn record_follows_from(&self, _span: &tracing::Id, _follows: &tracing::Id) {}
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = HashMap::new();
+            event.record(&mut FieldRecorder(&mut fields));
+            self.events.lock().unwrap().push(fields);
+        }
+        fn enter(&self, _span: &tracing::Id) {}
+        fn exit(&self, _span: &tracing::Id) {}
+    }
+
+    #[cfg(feature = "tracing-feat")]
+    #[tokio::test]
+    async fn test_discover_traces_discovery() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let subscriber = RecordingSubscriber::default();
+        let _guard = tracing::subscriber::set_default(subscriber.clone());
+        env::set_var("ENABLE_DEBUG_ECHO", "yes");
+        let config = build_config_a();
+        let periodic_discovery = PeriodicDiscovery {
+            config_name: config.metadata.name.clone(),
+            config_uid: config.metadata.uid.clone().unwrap(),
+            config_namespace: config.metadata.namespace.clone().unwrap(),
+            config_protocol: config.spec.protocol.clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec,
+            instance_map: Arc::new(Mutex::new(HashMap::new())),
+            event_recorder: EventRecorder::new(),
+        };
+        let protocol =
+            protocols::get_discovery_handler(&periodic_discovery.config_protocol).unwrap();
+        let discovery_results = periodic_discovery
+            .discover(
+                &*protocol,
+                Duration::from_secs(DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS),
+            )
+            .await
+            .unwrap();
+
+        let spans = subscriber.spans.lock().unwrap();
+        let (metadata, fields) = spans
+            .iter()
+            .find(|(metadata, _)| metadata.name() == "discover")
+            .unwrap();
+        // Spans are created at trace level so that they only reach the log when asked for
+        assert_eq!(tracing::Level::TRACE, *metadata.level());
+        assert_eq!("config-a", fields["config"]);
+        assert_eq!("debugEcho", fields["protocol"]);
+        let instance_count = discovery_results.len().to_string();
+        assert!(subscriber
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|fields| fields.get("instance_count") == Some(&instance_count)));
+    }
+
+    #[test]
+    fn test_get_discovery_stall_timeout() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let default = Duration::from_secs(DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS);
+        let cases = vec![
+            (None, default),
+            (Some("60"), Duration::from_secs(60)),
+            (Some("0"), default),
+            (Some("forever"), default),
+        ];
+        for (value, expected) in cases {
+            let mut mock_query = MockEnvVarQuery::new();
+            mock_query
+                .expect_get_env_var()
+                .withf(|name: &str| name == DISCOVERY_STALL_TIMEOUT_LABEL)
+                .returning(move |_| {
+                    value
+                        .map(|v| v.to_string())
+                        .ok_or(env::VarError::NotPresent)
+                });
+            assert_eq!(expected, get_discovery_stall_timeout(&mock_query));
+        }
+    }
+
+    /// Discovery handler whose discovery never finishes, as if it had deadlocked
+    struct HungDiscoveryHandler {}
+
+    #[async_trait::async_trait]
+    impl protocols::DiscoveryHandler for HungDiscoveryHandler {
+        async fn discover(&self) -> Result<Vec<protocols::DiscoveryResult>, anyhow::Error> {
+            futures::future::pending().await
+        }
+        fn are_shared(&self) -> Result<bool, anyhow::Error> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover_times_out_hung_handler() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let config = build_config_a();
+        let periodic_discovery = PeriodicDiscovery {
+            config_name: config.metadata.name.clone(),
+            config_uid: config.metadata.uid.clone().unwrap(),
+            config_namespace: config.metadata.namespace.clone().unwrap(),
+            config_protocol: config.spec.protocol.clone(),
+            capacity: Arc::new(AtomicI32::new(config.spec.capacity)),
+            config_spec: config.spec,
+            instance_map: Arc::new(Mutex::new(HashMap::new())),
+            event_recorder: EventRecorder::new(),
+        };
+        let result = periodic_discovery
+            .discover(&HungDiscoveryHandler {}, Duration::from_millis(10))
+            .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("did not respond within"));
+    }
 }
@@ -15,17 +15,51 @@ pub const DEVICE_PLUGIN_PATH: &str = "/var/lib/kubelet/device-plugins";
 /// Path of the Kubelet registry socket
 pub const KUBELET_SOCKET: &str = "/var/lib/kubelet/device-plugins/kubelet.sock";
 
+/// Name of the Kubelet registry socket within the device plugin folder
+pub const KUBELET_SOCKET_NAME: &str = "kubelet.sock";
+
+/// Length of time to sleep between checks of whether kubelet has restarted
+pub const KUBELET_SOCKET_CHECK_DELAY_SECS: u64 = 5;
+
 /// Maximum length of time `list_and_watch` will sleep before sending kubelet another list of virtual devices
 pub const LIST_AND_WATCH_SLEEP_SECS: u64 = 60;
 
-/// Length of time to sleep between instance discovery checks
+/// Default length of time to sleep between instance discovery checks, for protocols without a configurable interval
 pub const DISCOVERY_DELAY_SECS: u64 = 10;
 
 /// Length of time a shared instance can be offline before it's `DevicePluginService` is shutdown.
 pub const SHARED_INSTANCE_OFFLINE_GRACE_PERIOD_SECS: u64 = 300;
 
+/// Length of time to wait for a `DevicePluginService` to be built before giving up until the next discovery
+pub const BUILD_DEVICE_PLUGIN_TIMEOUT_SECS: u64 = 30;
+
+/// Number of `DevicePluginServices` built at once for a Configuration's newly discovered instances,
+/// unless `AKRI_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS` is set
+pub const DEFAULT_MAX_CONCURRENT_DEVICE_PLUGIN_BUILDS: usize = 5;
+
+/// Length of time a discovery handler may take to respond to a discovery request before it is considered hung,
+/// unless `AKRI_DISCOVERY_STALL_TIMEOUT_SECS` is set
+pub const DEFAULT_DISCOVERY_STALL_TIMEOUT_SECS: u64 = 300;
+
 /// Length of time to sleep between slot reconciliation checks
 pub const SLOT_RECONCILIATION_CHECK_DELAY_SECS: u64 = 10;
 
 /// Length of time a slot can be unused before slot reconciliation relaims it
 pub const SLOT_RECONCILIATION_SLOT_GRACE_PERIOD_SECS: u64 = 300;
+
+/// Number of consecutive discovery failures, within `CIRCUIT_BREAKER_WINDOW_SECS`, after which discovery for a Configuration is paused
+pub const CIRCUIT_BREAKER_FAILURE_THRESHOLD: usize = 10;
+
+/// Length of time in which `CIRCUIT_BREAKER_FAILURE_THRESHOLD` consecutive discovery failures pause discovery
+pub const CIRCUIT_BREAKER_WINDOW_SECS: u64 = 300;
+
+/// Length of time discovery is paused before it is tried again
+pub const CIRCUIT_BREAKER_RESET_SECS: u64 = 600;
+
+/// Prefix of a Configuration broker property value that refers to a key of a Kubernetes Secret in the Configuration's
+/// namespace, as `secret://<namespace>/<name>/<key>`. The key's data is given to the broker in place of the reference
+/// when kubelet calls Allocate.
+pub const SECRET_REFERENCE_PREFIX: &str = "secret://";
+
+/// Minimum length of time between two Events of the same kind about the same Instance
+pub const INSTANCE_EVENT_RATE_LIMIT_SECS: u64 = 60;
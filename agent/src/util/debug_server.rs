@@ -0,0 +1,229 @@
+use super::{
+    config_action::{get_instance_maps, ConfigMap},
+    device_plugin_service::{ConnectivityStatus, InstanceMap},
+};
+use akri_shared::os::env_var::EnvVarQuery;
+use std::collections::BTreeMap;
+use warp::{Filter, Rejection, Reply};
+
+/// Name of the environment variable that enables the debug server when set to "true"
+pub const DEBUG_SERVER_LABEL: &str = "AKRI_DEBUG_SERVER";
+/// Name of the environment variable that sets the port of the debug server
+pub const DEBUG_SERVER_PORT_LABEL: &str = "AKRI_DEBUG_SERVER_PORT";
+/// Port the debug server listens on if `AKRI_DEBUG_SERVER_PORT` is not set.
+/// The metrics server already uses 8080.
+pub const DEFAULT_DEBUG_SERVER_PORT: u16 = 8081;
+/// Argument that makes the Agent binary print the Instances tracked by the Agent on its node instead of running
+pub const INSTANCES_SUBCOMMAND: &str = "instances";
+
+/// State of an entry in a Configuration's `InstanceMap`, as reported at /debug/instances
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct InstanceSnapshot {
+    name: String,
+    configuration: String,
+    connectivity_status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seconds_offline: Option<u64>,
+    /// Properties most recently reported by the discovery handler
+    properties: BTreeMap<String, String>,
+}
+
+/// Returns the port to serve debug information on, or None if the debug server is not enabled
+pub fn get_debug_server_port(query: &impl EnvVarQuery) -> Option<u16> {
+    match query.get_env_var(DEBUG_SERVER_LABEL) {
+        Ok(enabled) if enabled == "true" => {}
+        _ => return None,
+    }
+    match query.get_env_var(DEBUG_SERVER_PORT_LABEL) {
+        Ok(port) => match port.parse::<u16>() {
+            Ok(port) => Some(port),
+            Err(_) => {
+                error!(
+                    "get_debug_server_port - invalid {} {} ... using {}",
+                    DEBUG_SERVER_PORT_LABEL, port, DEFAULT_DEBUG_SERVER_PORT
+                );
+                Some(DEFAULT_DEBUG_SERVER_PORT)
+            }
+        },
+        Err(_) => Some(DEFAULT_DEBUG_SERVER_PORT),
+    }
+}
+
+/// Takes a snapshot of each Configuration's `InstanceMap`, sorted by Instance name
+async fn get_instance_snapshots(
+    instance_maps: Vec<(String, InstanceMap)>,
+) -> Vec<InstanceSnapshot> {
+    let mut snapshots = Vec::new();
+    for (configuration, instance_map) in instance_maps {
+        for (name, instance_info) in instance_map.lock().await.iter() {
+            let (connectivity_status, seconds_offline) = match instance_info.connectivity_status {
+                ConnectivityStatus::Online => ("Online", None),
+                ConnectivityStatus::Offline(instant) => {
+                    ("Offline", Some(instant.elapsed().as_secs()))
+                }
+            };
+            snapshots.push(InstanceSnapshot {
+                name: name.clone(),
+                configuration: configuration.clone(),
+                connectivity_status: connectivity_status.to_string(),
+                seconds_offline,
+                properties: instance_info.properties.clone().into_iter().collect(),
+            });
+        }
+    }
+    snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+    snapshots
+}
+
+async fn instances_handler(config_map: ConfigMap) -> Result<impl Reply, Rejection> {
+    let snapshots = get_instance_snapshots(get_instance_maps(&config_map).await).await;
+    Ok(warp::reply::json(&snapshots))
+}
+
+/// Serves the state of every Instance this Agent is tracking as JSON at /debug/instances.
+/// Only listens on the loopback address, as the Agent runs on the host network and Instance properties
+/// should not be served to other hosts.
+pub async fn run_debug_server(
+    config_map: ConfigMap,
+    port: u16,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    info!(
+        "starting debug server on 127.0.0.1 port {} at /debug/instances",
+        port
+    );
+    let instances_route = warp::path!("debug" / "instances")
+        .and(warp::any().map(move || config_map.clone()))
+        .and_then(instances_handler);
+    warp::serve(instances_route)
+        .run(([127, 0, 0, 1], port))
+        .await;
+    Ok(())
+}
+
+/// Prints the Instances served at /debug/instances by the Agent listening on `port` on this node.
+/// Run in the Agent's container with `agent instances`.
+pub async fn print_instances(
+    port: u16,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let uri: hyper::Uri = format!("http://127.0.0.1:{}/debug/instances", port).parse()?;
+    let response = hyper::Client::new().get(uri).await?;
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    let snapshots: serde_json::Value = serde_json::from_slice(&body)?;
+    println!("{}", serde_json::to_string_pretty(&snapshots)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::device_plugin_service::InstanceInfo;
+    use super::*;
+    use akri_shared::os::env_var::MockEnvVarQuery;
+    use std::{
+        collections::HashMap,
+        env::VarError,
+        sync::{atomic::AtomicI32, Arc},
+        time::{Duration, Instant},
+    };
+    use tokio::sync::{broadcast, Mutex};
+
+    fn configure_env_vars(
+        mock_query: &mut MockEnvVarQuery,
+        enabled: Option<&'static str>,
+        port: Option<&'static str>,
+    ) {
+        let enabled = enabled.map(|e| e.to_string());
+        let port = port.map(|p| p.to_string());
+        mock_query
+            .expect_get_env_var()
+            .returning(move |name| match name {
+                DEBUG_SERVER_LABEL => enabled.clone().ok_or(VarError::NotPresent),
+                DEBUG_SERVER_PORT_LABEL => port.clone().ok_or(VarError::NotPresent),
+                _ => Err(VarError::NotPresent),
+            });
+    }
+
+    #[test]
+    fn test_get_debug_server_port() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let cases = vec![
+            (None, None, None),
+            (Some("false"), Some("9000"), None),
+            (Some("true"), None, Some(DEFAULT_DEBUG_SERVER_PORT)),
+            (Some("true"), Some("9000"), Some(9000)),
+            (
+                Some("true"),
+                Some("not-a-port"),
+                Some(DEFAULT_DEBUG_SERVER_PORT),
+            ),
+        ];
+        for (enabled, port, expected_port) in cases {
+            let mut mock_query = MockEnvVarQuery::new();
+            configure_env_vars(&mut mock_query, enabled, port);
+            assert_eq!(expected_port, get_debug_server_port(&mock_query));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_instance_snapshots() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let (list_and_watch_message_sender, _) = broadcast::channel(2);
+        let mut instances = HashMap::new();
+        instances.insert(
+            "config-a-b494b6".to_string(),
+            InstanceInfo {
+                list_and_watch_message_sender: list_and_watch_message_sender.clone(),
+                connectivity_status: ConnectivityStatus::Online,
+                properties: vec![("IP_ADDRESS".to_string(), "10.0.0.1".to_string())]
+                    .into_iter()
+                    .collect(),
+                shared: true,
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+                capacity: Arc::new(AtomicI32::new(1)),
+            },
+        );
+        instances.insert(
+            "config-a-359973".to_string(),
+            InstanceInfo {
+                list_and_watch_message_sender,
+                connectivity_status: ConnectivityStatus::Offline(
+                    Instant::now() - Duration::from_secs(30),
+                ),
+                properties: HashMap::new(),
+                shared: true,
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+                capacity: Arc::new(AtomicI32::new(1)),
+            },
+        );
+        let instance_map: InstanceMap = Arc::new(Mutex::new(instances));
+
+        let snapshots = get_instance_snapshots(vec![("config-a".to_string(), instance_map)]).await;
+        assert_eq!(
+            vec![
+                InstanceSnapshot {
+                    name: "config-a-359973".to_string(),
+                    configuration: "config-a".to_string(),
+                    connectivity_status: "Offline".to_string(),
+                    seconds_offline: Some(30),
+                    properties: BTreeMap::new(),
+                },
+                InstanceSnapshot {
+                    name: "config-a-b494b6".to_string(),
+                    configuration: "config-a".to_string(),
+                    connectivity_status: "Online".to_string(),
+                    seconds_offline: None,
+                    properties: vec![("IP_ADDRESS".to_string(), "10.0.0.1".to_string())]
+                        .into_iter()
+                        .collect(),
+                },
+            ],
+            snapshots
+        );
+        assert_eq!(
+            r#"[{"name":"config-a-359973","configuration":"config-a","connectivityStatus":"Offline","secondsOffline":30,"properties":{}},{"name":"config-a-b494b6","configuration":"config-a","connectivityStatus":"Online","properties":{"IP_ADDRESS":"10.0.0.1"}}]"#,
+            serde_json::to_string(&snapshots).unwrap()
+        );
+    }
+}
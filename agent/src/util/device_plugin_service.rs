@@ -1,5 +1,7 @@
+use super::super::protocols::AKRI_DEVICE_HEALTH_LABEL_ID;
 use super::constants::{
-    HEALTHY, K8S_DEVICE_PLUGIN_VERSION, KUBELET_SOCKET, LIST_AND_WATCH_SLEEP_SECS, UNHEALTHY,
+    HEALTHY, K8S_DEVICE_PLUGIN_VERSION, KUBELET_SOCKET_NAME, LIST_AND_WATCH_SLEEP_SECS,
+    SECRET_REFERENCE_PREFIX, UNHEALTHY,
 };
 use super::v1beta1;
 use super::v1beta1::{
@@ -10,21 +12,27 @@ use super::v1beta1::{
 use akri_shared::{
     akri::{
         configuration::{Configuration, ProtocolHandler},
-        instance::Instance,
-        retry::{random_delay, MAX_INSTANCE_UPDATE_TRIES},
+        instance::{
+            get_add_node_patch, get_device_usage_patch, Instance, InstanceDeviceSpec, InstanceMount,
+        },
+        retry::{random_backoff_delay, MAX_INSTANCE_UPDATE_TRIES},
         AKRI_PREFIX, AKRI_SLOT_ANNOTATION_NAME,
     },
     k8s,
     k8s::KubeInterface,
+    log_fields,
 };
 use futures::stream::TryStreamExt;
-use log::{error, info, trace};
+use log::{trace, Level};
 use std::{
     collections::HashMap,
     convert::TryFrom,
     env,
     path::Path,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
@@ -48,6 +56,8 @@ pub enum ListAndWatchMessageKind {
     Continue,
     /// Stop looping
     End,
+    /// Kubelet restarted, so serve the DevicePluginService on a new socket and register it with the new kubelet
+    Reregister,
 }
 
 /// Describes the discoverability of an instance for this node
@@ -59,6 +69,55 @@ pub enum ConnectivityStatus {
     Offline(Instant),
 }
 
+impl ConnectivityStatus {
+    pub fn is_online(&self) -> bool {
+        matches!(self, ConnectivityStatus::Online)
+    }
+
+    pub fn is_offline(&self) -> bool {
+        matches!(self, ConnectivityStatus::Offline(_))
+    }
+
+    /// Returns the status to move to if `next` may follow this one. An Online instance may go Offline or stay
+    /// Online, and an Offline instance may come back Online. An Offline instance may not be marked Offline again,
+    /// as that would restart the time it has been offline for. Instances that stay offline too long are removed
+    /// rather than given a new status.
+    pub fn transition_to(
+        &self,
+        next: ConnectivityStatus,
+    ) -> Result<ConnectivityStatus, InvalidTransition> {
+        match (self, &next) {
+            (ConnectivityStatus::Online, _)
+            | (ConnectivityStatus::Offline(_), ConnectivityStatus::Online) => Ok(next),
+            (ConnectivityStatus::Offline(_), ConnectivityStatus::Offline(_)) => {
+                Err(InvalidTransition {
+                    from: self.clone(),
+                    to: next,
+                })
+            }
+        }
+    }
+}
+
+/// A change of `ConnectivityStatus` that `ConnectivityStatus::transition_to` does not allow
+#[derive(PartialEq, Debug, Clone)]
+pub struct InvalidTransition {
+    pub from: ConnectivityStatus,
+    pub to: ConnectivityStatus,
+}
+
+impl std::fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid connectivity status transition from {:?} to {:?}",
+            self.from, self.to
+        )
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
 /// Contains an Instance's state
 #[derive(Clone, Debug)]
 pub struct InstanceInfo {
@@ -66,10 +125,79 @@ pub struct InstanceInfo {
     pub list_and_watch_message_sender: broadcast::Sender<ListAndWatchMessageKind>,
     /// Instance's `ConnectivityStatus`
     pub connectivity_status: ConnectivityStatus,
+    /// Properties most recently reported for the Instance by its discovery handler
+    pub properties: HashMap<String, String>,
+    /// Whether the Instance is shared by multiple nodes, which determines how long it may stay offline
+    /// before it is removed
+    pub shared: bool,
+    /// Device specs most recently reported for the Instance by its discovery handler
+    pub device_specs: Vec<v1beta1::DeviceSpec>,
+    /// Mounts most recently reported for the Instance by its discovery handler
+    pub mounts: Vec<v1beta1::Mount>,
+    /// Number of usage slots the Instance has, shared with its DevicePluginService so that it advertises
+    /// as many virtual Devices as the Instance has slots after its Configuration's capacity changes
+    pub capacity: Arc<AtomicI32>,
+}
+
+/// Returns whether a discovery handler has reported a device as unhealthy by setting its
+/// `AKRI_DEVICE_HEALTH` property to `Unhealthy`. The device stays Online but none of its
+/// virtual Devices can be scheduled to.
+pub fn is_reported_unhealthy(properties: &HashMap<String, String>) -> bool {
+    properties
+        .get(AKRI_DEVICE_HEALTH_LABEL_ID)
+        .map_or(false, |health| health == UNHEALTHY)
 }
 
 pub type InstanceMap = Arc<Mutex<HashMap<String, InstanceInfo>>>;
 
+/// Converts device specs reported by a discovery handler to the form stored in an Instance CRD
+pub fn to_instance_device_specs(device_specs: &[v1beta1::DeviceSpec]) -> Vec<InstanceDeviceSpec> {
+    device_specs
+        .iter()
+        .map(|device_spec| InstanceDeviceSpec {
+            container_path: device_spec.container_path.clone(),
+            host_path: device_spec.host_path.clone(),
+            permissions: device_spec.permissions.clone(),
+        })
+        .collect()
+}
+
+/// Converts mounts reported by a discovery handler to the form stored in an Instance CRD
+pub fn to_instance_mounts(mounts: &[v1beta1::Mount]) -> Vec<InstanceMount> {
+    mounts
+        .iter()
+        .map(|mount| InstanceMount {
+            container_path: mount.container_path.clone(),
+            host_path: mount.host_path.clone(),
+            read_only: mount.read_only,
+        })
+        .collect()
+}
+
+/// Converts device specs stored in an Instance CRD back to the form given to kubelet
+pub fn from_instance_device_specs(device_specs: &[InstanceDeviceSpec]) -> Vec<v1beta1::DeviceSpec> {
+    device_specs
+        .iter()
+        .map(|device_spec| v1beta1::DeviceSpec {
+            container_path: device_spec.container_path.clone(),
+            host_path: device_spec.host_path.clone(),
+            permissions: device_spec.permissions.clone(),
+        })
+        .collect()
+}
+
+/// Converts mounts stored in an Instance CRD back to the form given to kubelet
+pub fn from_instance_mounts(mounts: &[InstanceMount]) -> Vec<v1beta1::Mount> {
+    mounts
+        .iter()
+        .map(|mount| v1beta1::Mount {
+            container_path: mount.container_path.clone(),
+            host_path: mount.host_path.clone(),
+            read_only: mount.read_only,
+        })
+        .collect()
+}
+
 /// Kubernetes Device-Plugin for an Instance.
 ///
 /// `DevicePluginService` implements Kubernetes Device-Plugin v1beta1 API specification
@@ -84,8 +212,14 @@ pub struct DevicePluginService {
     instance_name: String,
     /// Socket endpoint
     endpoint: String,
+    /// Id the discovery handler reported for the Instance's device, if known
+    device_id: Option<String>,
     /// Instance's Configuration
     config: Configuration,
+    /// Number of virtual Devices (usage slots) for the Instance.
+    /// Set by the discovery handler if it reports one, else the Configuration's capacity.
+    /// Shared with the Instance's `InstanceInfo`, which is updated when the Configuration's capacity changes.
+    capacity: Arc<AtomicI32>,
     /// Name of Instance's Configuration CRD
     config_name: String,
     /// UID of Instance's Configuration CRD
@@ -98,6 +232,10 @@ pub struct DevicePluginService {
     node_name: String,
     /// Information that must be communicated with broker. Stored in Instance CRD as metadata.
     instance_properties: HashMap<String, String>,
+    /// Host devices given to brokers, as reported by the discovery handler
+    device_specs: Vec<v1beta1::DeviceSpec>,
+    /// Host paths mounted into brokers, as reported by the discovery handler
+    mounts: Vec<v1beta1::Mount>,
     /// Map of all Instances that have the same Configuration CRD as this one
     instance_map: InstanceMap,
     /// Receiver for list_and_watch continue or end messages
@@ -129,14 +267,16 @@ impl DevicePlugin for DevicePluginService {
     /// Returns a stream of List of "virtual" Devices over a channel.
     /// Since Kubernetes designed Device-Plugin so that multiple consumers can use a Device,
     /// "virtual" Devices are reservation slots for using the Device or Instance in akri terms.
-    /// The number of "virtual" Devices (length of `ListAndWatchResponse`) is determined by Instance.capacity.
+    /// The number of "virtual" Devices (length of `ListAndWatchResponse`) is determined by `DevicePluginService.capacity`.
     /// Whenever Instance state changes or an Instance disapears, `list_and_watch` returns the new list.
     /// Runs until receives message to end due to Instance disappearing or Configuration being deleted.
     async fn list_and_watch(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<Self::ListAndWatchStream>, Status> {
-        info!(
+        log_fields!(
+            Level::Info,
+            { configuration: self.config_name, instance: self.instance_name },
             "list_and_watch - kubelet called list_and_watch for instance {}",
             self.instance_name
         );
@@ -155,7 +295,9 @@ impl DevicePlugin for DevicePluginService {
             #[cfg(not(test))]
             {
                 if let Err(e) = try_create_instance(dps.clone(), kube_interface.clone()).await {
-                    error!(
+                    log_fields!(
+                        Level::Error,
+                        { configuration: dps.config_name, instance: dps.instance_name },
                         "list_and_watch - ending service because could not create instance {} with error {}",
                         dps.instance_name,
                         e
@@ -166,7 +308,9 @@ impl DevicePlugin for DevicePluginService {
             }
 
             while keep_looping {
-                trace!(
+                log_fields!(
+                    Level::Trace,
+                    { configuration: dps.config_name, instance: dps.instance_name },
                     "list_and_watch - loop iteration for Instance {}",
                     dps.instance_name
                 );
@@ -174,8 +318,10 @@ impl DevicePlugin for DevicePluginService {
                 let virtual_devices: Vec<v1beta1::Device>;
                 #[cfg(test)]
                 {
-                    virtual_devices =
-                        build_unhealthy_virtual_devices(dps.config.capacity, &dps.instance_name);
+                    virtual_devices = build_unhealthy_virtual_devices(
+                        dps.capacity.load(Ordering::SeqCst),
+                        &dps.instance_name,
+                    );
                 }
                 #[cfg(not(test))]
                 {
@@ -191,7 +337,9 @@ impl DevicePlugin for DevicePluginService {
 
                 // Send virtual devices list back to kubelet
                 if let Err(e) = kubelet_update_sender.send(Ok(resp)).await {
-                    trace!(
+                    log_fields!(
+                        Level::Trace,
+                        { configuration: dps.config_name, instance: dps.instance_name },
                         "list_and_watch - for Instance {} kubelet no longer receiving with error {}",
                         dps.instance_name,
                         e
@@ -199,6 +347,10 @@ impl DevicePlugin for DevicePluginService {
                     // This means kubelet is down/has been restarted. Remove instance from instance map so
                     // do_periodic_discovery will create a new device plugin service for this instance.
                     dps.instance_map.lock().await.remove(&dps.instance_name);
+                    // Stop re-registering the device plugin service being shut down
+                    dps.list_and_watch_message_sender
+                        .send(ListAndWatchMessageKind::End)
+                        .unwrap_or(0);
                     dps.server_ender_sender.clone().send(()).await.unwrap();
                     keep_looping = false;
                 }
@@ -214,27 +366,48 @@ impl DevicePlugin for DevicePluginService {
                         // If receive message to end list_and_watch, send list of unhealthy devices
                         // and shutdown the server by sending message on server_ender_sender channel
                         if message == Ok(ListAndWatchMessageKind::End) {
-                            trace!(
+                            log_fields!(
+                                Level::Trace,
+                                { configuration: dps.config_name, instance: dps.instance_name },
                                 "list_and_watch - for Instance {} received message to end",
                                 dps.instance_name
                             );
                             let devices = build_unhealthy_virtual_devices(
-                                dps.config.capacity,
+                                dps.capacity.load(Ordering::SeqCst),
                                 &dps.instance_name,
                             );
-                            kubelet_update_sender.send(Ok(v1beta1::ListAndWatchResponse { devices }))
+                            // Kubelet may have already stopped receiving, such as when it restarted
+                            kubelet_update_sender
+                                .send(Ok(v1beta1::ListAndWatchResponse { devices }))
                                 .await
-                                .unwrap();
+                                .unwrap_or(());
                             dps.server_ender_sender.clone().send(()).await.unwrap();
                             keep_looping = false;
+                        } else if message == Ok(ListAndWatchMessageKind::Reregister) {
+                            // The kubelet this was streaming to is gone. The DevicePluginService is served again
+                            // for the new kubelet, which calls list_and_watch on the new server.
+                            log_fields!(
+                                Level::Trace,
+                                { configuration: dps.config_name, instance: dps.instance_name },
+                                "list_and_watch - for Instance {} received message to re-register ... ending",
+                                dps.instance_name
+                            );
+                            keep_looping = false;
                         }
                     }
-                    Err(_) => trace!(
+                    Err(_) => log_fields!(
+                        Level::Trace,
+                        { configuration: dps.config_name, instance: dps.instance_name },
                         "list_and_watch - for Instance {} did not receive a message for {} seconds ... continuing", dps.instance_name, LIST_AND_WATCH_SLEEP_SECS
                     ),
                 }
             }
-            trace!("list_and_watch - for Instance {} ending", dps.instance_name);
+            log_fields!(
+                Level::Trace,
+                { configuration: dps.config_name, instance: dps.instance_name },
+                "list_and_watch - for Instance {} ending",
+                dps.instance_name
+            );
         });
         Ok(Response::new(kubelet_update_receiver))
     }
@@ -246,7 +419,9 @@ impl DevicePlugin for DevicePluginService {
         &self,
         requests: Request<AllocateRequest>,
     ) -> Result<Response<AllocateResponse>, Status> {
-        info!(
+        log_fields!(
+            Level::Info,
+            { configuration: self.config_name, instance: self.instance_name },
             "allocate - kubelet called allocate for Instance {}",
             self.instance_name
         );
@@ -262,7 +437,9 @@ impl DevicePlugin for DevicePluginService {
         &self,
         _request: Request<PreStartContainerRequest>,
     ) -> Result<Response<PreStartContainerResponse>, Status> {
-        error!(
+        log_fields!(
+            Level::Error,
+            { configuration: self.config_name, instance: self.instance_name },
             "pre_start_container - kubelet called pre_start_container for Instance {}",
             self.instance_name
         );
@@ -271,6 +448,27 @@ impl DevicePlugin for DevicePluginService {
 }
 
 impl DevicePluginService {
+    /// Returns the Instance's latest properties, as updated by discovery in the InstanceMap,
+    /// falling back to the properties the Instance was created with
+    async fn get_instance_properties(&self) -> HashMap<String, String> {
+        match self.instance_map.lock().await.get(&self.instance_name) {
+            Some(instance_info) => instance_info.properties.clone(),
+            None => self.instance_properties.clone(),
+        }
+    }
+
+    /// Returns the Instance's latest device specs and mounts, as updated by discovery in the InstanceMap,
+    /// falling back to those the Instance was created with
+    async fn get_instance_devices(&self) -> (Vec<v1beta1::DeviceSpec>, Vec<v1beta1::Mount>) {
+        match self.instance_map.lock().await.get(&self.instance_name) {
+            Some(instance_info) => (
+                instance_info.device_specs.clone(),
+                instance_info.mounts.clone(),
+            ),
+            None => (self.device_specs.clone(), self.mounts.clone()),
+        }
+    }
+
     /// Called when kubelet is trying to reserve for this node a usage slot (or virtual device) of the Instance.
     /// Tries to update Instance CRD to reserve the requested slot. If cannot reserve that slot, forces `list_and_watch` to continue
     /// (sending kubelet the latest list of slots) and returns error, so kubelet will not schedule the pod to this node.
@@ -280,16 +478,39 @@ impl DevicePluginService {
         kube_interface: Arc<impl KubeInterface>,
     ) -> Result<Response<AllocateResponse>, Status> {
         let mut container_responses: Vec<v1beta1::ContainerAllocateResponse> = Vec::new();
+        // Resolve the secret references of the Configuration's broker properties before reserving any slots,
+        // so that a broker is never scheduled without the credentials it was meant to be given
+        let broker_properties = resolve_secret_references(
+            self.config.broker_properties.clone(),
+            &self.config_namespace,
+            kube_interface.as_ref(),
+        )
+        .await
+        .map_err(|e| {
+            log_fields!(
+                Level::Error,
+                { configuration: self.config_name, instance: self.instance_name },
+                "internal_allocate - for Instance {} could not resolve secret reference: {}",
+                &self.instance_name, e
+            );
+            Status::new(Code::FailedPrecondition, e)
+        })?;
+        let instance_properties = self.get_instance_properties().await;
+        let (device_specs, mounts) = self.get_instance_devices().await;
 
         for request in requests.into_inner().container_requests {
-            trace!(
+            log_fields!(
+                Level::Trace,
+                { configuration: self.config_name, instance: self.instance_name },
                 "internal_allocate - for Instance {} handling request {:?}",
                 &self.instance_name,
                 request,
             );
             let mut akri_annotations = std::collections::HashMap::new();
             for device_usage_id in request.devices_i_ds {
-                trace!(
+                log_fields!(
+                    Level::Trace,
+                    { configuration: self.config_name, instance: self.instance_name },
                     "internal_allocate - for Instance {} processing request for device usage slot id {}",
                     &self.instance_name,
                     device_usage_id
@@ -309,14 +530,21 @@ impl DevicePluginService {
                 )
                 .await
                 {
-                    trace!("internal_allocate - could not assign {} slot to {} node ... forcing list_and_watch to continue", device_usage_id, &self.node_name);
+                    log_fields!(
+                        Level::Trace,
+                        { configuration: self.config_name, instance: self.instance_name },
+                        "internal_allocate - could not assign {} slot to {} node ... forcing list_and_watch to continue",
+                        device_usage_id, &self.node_name
+                    );
                     self.list_and_watch_message_sender
                         .send(ListAndWatchMessageKind::Continue)
                         .unwrap();
                     return Err(e);
                 }
 
-                trace!(
+                log_fields!(
+                    Level::Trace,
+                    { configuration: self.config_name, instance: self.instance_name },
                     "internal_allocate - finished processing device_usage_id {}",
                     device_usage_id
                 );
@@ -325,12 +553,18 @@ impl DevicePluginService {
             // Add response to list of responses
             let response = build_container_allocate_response(
                 akri_annotations,
-                &self.instance_properties,
+                &instance_properties,
                 &self.config.protocol,
+                self.config.broker_properties_prefix.as_deref(),
+                &broker_properties,
+                &device_specs,
+                &mounts,
             );
             container_responses.push(response);
         }
-        trace!(
+        log_fields!(
+            Level::Trace,
+            { configuration: self.config_name, instance: self.instance_name },
             "internal_allocate - for Instance {} returning responses",
             &self.instance_name
         );
@@ -383,6 +617,7 @@ fn get_slot_value(
 
 /// This tries up to `MAX_INSTANCE_UPDATE_TRIES` to update the requested slot of the Instance with the appropriate value (either "" to clear slot or node_name).
 /// It cannot be assumed that this will successfully update Instance on first try since Device Plugins on other nodes may be simultaneously trying to update the Instance.
+/// Only the requested slot is patched, so the update only fails if that slot was changed since the Instance was read.
 /// This returns an error if slot does not need to be updated or `MAX_INSTANCE_UPDATE_TRIES` attempted.
 async fn try_update_instance_device_usage(
     device_usage_id: &str,
@@ -391,14 +626,13 @@ async fn try_update_instance_device_usage(
     instance_namespace: &str,
     kube_interface: Arc<impl KubeInterface>,
 ) -> Result<(), Status> {
-    let mut instance: Instance;
     for x in 0..MAX_INSTANCE_UPDATE_TRIES {
         // Grab latest instance
-        match kube_interface
+        let instance = match kube_interface
             .find_instance(&instance_name, &instance_namespace)
             .await
         {
-            Ok(instance_object) => instance = instance_object.spec,
+            Ok(instance_object) => instance_object.spec,
             Err(_) => {
                 trace!(
                     "internal_allocate - could not find Instance {}",
@@ -409,7 +643,7 @@ async fn try_update_instance_device_usage(
                     format!("Could not find Instance {}", instance_name),
                 ));
             }
-        }
+        };
 
         // at this point, `value` should either be:
         //   * `node_name`: meaning that this node is claiming this slot
@@ -418,12 +652,14 @@ async fn try_update_instance_device_usage(
         //          slot (which triggers each node to set the slot as Healthy) to
         //          allow a fair rescheduling of the workload
         let value = get_slot_value(device_usage_id, node_name, &instance)?;
-        instance
-            .device_usage
-            .insert(device_usage_id.to_string(), value.clone());
+        let patch = get_device_usage_patch(
+            device_usage_id,
+            &instance.device_usage[device_usage_id],
+            &value,
+        );
 
         match kube_interface
-            .update_instance(&instance, &instance_name, &instance_namespace)
+            .patch_instance(&patch, &instance_name, &instance_namespace)
             .await
         {
             Ok(()) => {
@@ -435,21 +671,226 @@ async fn try_update_instance_device_usage(
             }
             Err(e) => {
                 if x == (MAX_INSTANCE_UPDATE_TRIES - 1) {
-                    trace!("internal_allocate - update_instance returned error [{}] after max tries ... returning error", e);
+                    trace!("internal_allocate - patch_instance returned error [{}] after max tries ... returning error", e);
                     return Err(Status::new(Code::Unknown, "Could not update Instance"));
                 }
             }
         }
-        random_delay().await;
+        random_backoff_delay(x).await;
+    }
+    Ok(())
+}
+
+/// Builds a JSON patch that changes the number of an Instance's usage slots to `capacity`, or None if it already has
+/// that many. New slots take the lowest `<instance name>-<n>` ids not yet in use. Only free slots are removed, highest
+/// first, and each removal is preceded by a test that the slot is still free, so the patch fails rather than removing
+/// a slot that was allocated since the Instance was read. Returns an error if more than `capacity` slots are allocated.
+fn get_capacity_patch(
+    instance_name: &str,
+    device_usage: &HashMap<String, String>,
+    capacity: i32,
+) -> Result<Option<serde_json::Value>, String> {
+    let capacity = capacity.max(0) as usize;
+    let slot_count = device_usage.len();
+    let mut operations = Vec::new();
+    if capacity > slot_count {
+        let new_slots = (0..)
+            .map(|x| format!("{}-{}", instance_name, x))
+            .filter(|device_usage_id| !device_usage.contains_key(device_usage_id))
+            .take(capacity - slot_count);
+        for device_usage_id in new_slots {
+            operations.push(serde_json::json!({
+                "op": "add",
+                "path": format!("/spec/deviceUsage/{}", device_usage_id),
+                "value": ""
+            }));
+        }
+    } else if capacity < slot_count {
+        let allocated_count = device_usage
+            .values()
+            .filter(|allocated_node| !allocated_node.is_empty())
+            .count();
+        if allocated_count > capacity {
+            return Err(format!(
+                "cannot lower capacity of Instance {} to {} while {} of its slots are allocated",
+                instance_name, capacity, allocated_count
+            ));
+        }
+        let slot_prefix = format!("{}-", instance_name);
+        let mut free_slots: Vec<&String> = device_usage
+            .iter()
+            .filter(|(_, allocated_node)| allocated_node.is_empty())
+            .map(|(device_usage_id, _)| device_usage_id)
+            .collect();
+        // Remove the highest numbered slots, and any not numbered like Akri numbers them, first
+        free_slots.sort_by_key(|device_usage_id| {
+            std::cmp::Reverse(
+                device_usage_id
+                    .strip_prefix(&slot_prefix)
+                    .and_then(|x| x.parse::<usize>().ok())
+                    .unwrap_or(usize::MAX),
+            )
+        });
+        for device_usage_id in free_slots.into_iter().take(slot_count - capacity) {
+            let path = format!("/spec/deviceUsage/{}", device_usage_id);
+            operations.push(serde_json::json!({ "op": "test", "path": path, "value": "" }));
+            operations.push(serde_json::json!({ "op": "remove", "path": path }));
+        }
+    }
+    if operations.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(serde_json::Value::Array(operations)))
+    }
+}
+
+/// Changes the number of usage slots of an Instance from `previous_capacity` to `capacity` after its Configuration's
+/// capacity was edited, then stores the Instance's number of slots in its `InstanceInfo` and tells its
+/// DevicePluginService to send kubelet the new list of virtual Devices.
+/// An Instance whose number of slots was reported by its discovery handler, rather than taken from the Configuration,
+/// is left as it is. Tries up to `MAX_INSTANCE_UPDATE_TRIES` times, since the Agents of other nodes may be changing
+/// the same Instance. Returns an error without changing the Instance if more than `capacity` slots are allocated.
+pub async fn update_instance_capacity(
+    instance_name: &str,
+    instance_namespace: &str,
+    previous_capacity: i32,
+    capacity: i32,
+    instance_info: &InstanceInfo,
+    kube_interface: &impl KubeInterface,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    for x in 0..MAX_INSTANCE_UPDATE_TRIES {
+        let instance = kube_interface
+            .find_instance(instance_name, instance_namespace)
+            .await?
+            .spec;
+        let slot_count = instance.device_usage.len() as i32;
+        if slot_count != capacity && slot_count != previous_capacity {
+            trace!(
+                "update_instance_capacity - Instance {} has {} slots set by its discovery handler ... leaving them",
+                instance_name,
+                slot_count
+            );
+            return Ok(());
+        }
+        let patch = match get_capacity_patch(instance_name, &instance.device_usage, capacity)? {
+            Some(patch) => patch,
+            // Another node's Agent has already updated the Instance
+            None => break,
+        };
+        match kube_interface
+            .patch_instance(&patch, instance_name, instance_namespace)
+            .await
+        {
+            Ok(()) => {
+                trace!(
+                    "update_instance_capacity - changed Instance {} from {} to {} slots",
+                    instance_name,
+                    slot_count,
+                    capacity
+                );
+                break;
+            }
+            Err(e) => {
+                if x == (MAX_INSTANCE_UPDATE_TRIES - 1) {
+                    return Err(e);
+                }
+            }
+        }
+        random_backoff_delay(x).await;
     }
+    instance_info.capacity.store(capacity, Ordering::SeqCst);
+    // list_and_watch may not have been called by kubelet yet, in which case it will advertise the new slots once it is
+    instance_info
+        .list_and_watch_message_sender
+        .send(ListAndWatchMessageKind::Continue)
+        .unwrap_or(0);
     Ok(())
 }
 
+/// Returns the Secret key referenced by a property value of the form `secret://<namespace>/<name>/<key>`
+/// as `(namespace, name, key)`, or None if the value is not a secret reference
+fn parse_secret_reference(value: &str) -> Option<Result<(&str, &str, &str), String>> {
+    let reference = value.strip_prefix(SECRET_REFERENCE_PREFIX)?;
+    let parts: Vec<&str> = reference.splitn(3, '/').collect();
+    match parts.as_slice() {
+        [namespace, name, key] if !namespace.is_empty() && !name.is_empty() && !key.is_empty() => {
+            Some(Ok((*namespace, *name, *key)))
+        }
+        _ => Some(Err(format!(
+            "secret reference {} is not of the form {}<namespace>/<name>/<key>",
+            value, SECRET_REFERENCE_PREFIX
+        ))),
+    }
+}
+
+/// Replaces every broker property value that is a secret reference with the data stored under the referenced
+/// Secret key, leaving other properties as they are. The Configuration keeps the reference, so the
+/// secret data is only ever given to the broker's environment.
+/// Only the Configuration's broker properties are resolved, never the properties reported for a device, so that
+/// whoever controls a device cannot have the Agent read Secrets. References must also be to Secrets in the
+/// Configuration's own namespace, so a Configuration cannot read Secrets of namespaces its author may not have
+/// access to.
+/// Returns an error naming the reference if it is outside of `config_namespace` or the Secret or key cannot be found.
+async fn resolve_secret_references(
+    broker_properties: HashMap<String, String>,
+    config_namespace: &str,
+    kube_interface: &impl KubeInterface,
+) -> Result<HashMap<String, String>, String> {
+    let mut resolved_properties = HashMap::new();
+    for (property, value) in broker_properties {
+        let resolved_value = match parse_secret_reference(&value) {
+            None => value,
+            Some(Err(e)) => return Err(format!("property {}: {}", property, e)),
+            Some(Ok((namespace, name, _))) if namespace != config_namespace => {
+                return Err(format!(
+                    "property {}: Secret {} is in namespace {} but only Secrets in the Configuration's namespace {} can be referenced",
+                    property, name, namespace, config_namespace
+                ))
+            }
+            Some(Ok((namespace, name, key))) => {
+                let secret = kube_interface
+                    .find_secret(name, namespace)
+                    .await
+                    .map_err(|e| {
+                        format!(
+                            "property {}: could not get Secret {} in namespace {}: {}",
+                            property, name, namespace, e
+                        )
+                    })?;
+                let data = secret
+                    .data
+                    .and_then(|mut data| data.remove(key))
+                    .ok_or_else(|| {
+                        format!(
+                            "property {}: Secret {} in namespace {} has no key {}",
+                            property, name, namespace, key
+                        )
+                    })?;
+                String::from_utf8(data.0).map_err(|_| {
+                    format!(
+                        "property {}: key {} of Secret {} in namespace {} is not valid UTF-8",
+                        property, key, name, namespace
+                    )
+                })?
+            }
+        };
+        resolved_properties.insert(property, resolved_value);
+    }
+    Ok(resolved_properties)
+}
+
 /// This sets the volume mounts and environment variables according to the instance's protocol.
+/// The Configuration's `brokerProperties` are added too, unless an instance property has the same name.
+/// The name of each environment variable is prefixed with the Configuration's `brokerPropertiesPrefix`, if any.
+/// The device specs and mounts reported by the discovery handler are added to those required by the protocol.
 fn build_container_allocate_response(
     annotations: HashMap<String, String>,
     instance_properties: &HashMap<String, String>,
     protocol: &ProtocolHandler,
+    broker_properties_prefix: Option<&str>,
+    broker_properties: &HashMap<String, String>,
+    device_specs: &[v1beta1::DeviceSpec],
+    device_mounts: &[v1beta1::Mount],
 ) -> v1beta1::ContainerAllocateResponse {
     let mut mounts: Vec<v1beta1::Mount> = Vec::new();
 
@@ -468,13 +909,25 @@ fn build_container_allocate_response(
         }
         _ => trace!("get_volumes_and_mounts - no mounts or volumes required by this protocol"),
     }
-
-    // Create response, setting environment variables to be an instance's properties (specified by protocol)
+    mounts.extend_from_slice(device_mounts);
+
+    // Create response, setting environment variables to be the Configuration's broker properties
+    // and an instance's properties (specified by protocol), the latter winning on conflict
+    let envs = broker_properties
+        .iter()
+        .chain(instance_properties.iter())
+        .map(|(name, value)| {
+            (
+                format!("{}{}", broker_properties_prefix.unwrap_or_default(), name),
+                value.clone(),
+            )
+        })
+        .collect();
     v1beta1::ContainerAllocateResponse {
         annotations,
         mounts,
-        envs: instance_properties.clone(),
-        ..Default::default()
+        devices: device_specs.to_vec(),
+        envs,
     }
 }
 
@@ -491,16 +944,19 @@ async fn try_create_instance(
         .find_configuration(&dps.config_name, &dps.config_namespace)
         .await
     {
-        error!(
+        log_fields!(
+            Level::Error,
+            { configuration: dps.config_name, instance: dps.instance_name },
             "try_create_instance - no Configuration for device {} ... returning error",
             dps.instance_name
         );
         return Err(e);
     }
 
-    let device_usage: std::collections::HashMap<String, String> = (0..dps.config.capacity)
-        .map(|x| (format!("{}-{}", dps.instance_name, x), "".to_string()))
-        .collect();
+    let device_usage: std::collections::HashMap<String, String> =
+        (0..dps.capacity.load(Ordering::SeqCst))
+            .map(|x| (format!("{}-{}", dps.instance_name, x), "".to_string()))
+            .collect();
     let instance = Instance {
         configuration_name: dps.config_name.clone(),
         shared: dps.shared,
@@ -508,6 +964,9 @@ async fn try_create_instance(
         device_usage,
         metadata: dps.instance_properties.clone(),
         rbac: "rbac".to_string(),
+        device_id: dps.device_id.clone(),
+        device_specs: to_instance_device_specs(&dps.device_specs),
+        mounts: to_instance_mounts(&dps.mounts),
     };
 
     // Try up to MAX_INSTANCE_UPDATE_TRIES to create or update instance, breaking on success
@@ -517,25 +976,28 @@ async fn try_create_instance(
             .find_instance(&dps.instance_name, &dps.config_namespace)
             .await
         {
-            Ok(mut instance_object) => {
-                trace!(
+            Ok(instance_object) => {
+                log_fields!(
+                    Level::Trace,
+                    { configuration: dps.config_name, instance: dps.instance_name },
                     "try_create_instance - discovered Instance {} already created",
                     dps.instance_name
                 );
 
                 // Check if instance's node list already contains this node, possibly due to device plugin failure and restart
                 if !instance_object.spec.nodes.contains(&dps.node_name) {
-                    instance_object.spec.nodes.push(dps.node_name.clone());
                     match kube_interface
-                        .update_instance(
-                            &instance_object.spec,
+                        .patch_instance(
+                            &get_add_node_patch(&dps.node_name),
                             &instance_object.metadata.name,
                             &dps.config_namespace,
                         )
                         .await
                     {
                         Ok(()) => {
-                            trace!(
+                            log_fields!(
+                                Level::Trace,
+                                { configuration: dps.config_name, instance: dps.instance_name },
                                 "try_create_instance - updated Instance {} to include {}",
                                 dps.instance_name,
                                 dps.node_name
@@ -543,7 +1005,12 @@ async fn try_create_instance(
                             break;
                         }
                         Err(e) => {
-                            trace!("try_create_instance - call to update_instance returned with error {} on try # {} of {}", e, x, MAX_INSTANCE_UPDATE_TRIES);
+                            log_fields!(
+                                Level::Trace,
+                                { configuration: dps.config_name, instance: dps.instance_name },
+                                "try_create_instance - call to patch_instance returned with error {} on try # {} of {}",
+                                e, x, MAX_INSTANCE_UPDATE_TRIES
+                            );
                             if x == (MAX_INSTANCE_UPDATE_TRIES - 1) {
                                 return Err(e);
                             }
@@ -565,14 +1032,21 @@ async fn try_create_instance(
                     .await
                 {
                     Ok(()) => {
-                        trace!(
+                        log_fields!(
+                            Level::Trace,
+                            { configuration: dps.config_name, instance: dps.instance_name },
                             "try_create_instance - created Instance with name {}",
                             dps.instance_name
                         );
                         break;
                     }
                     Err(e) => {
-                        trace!("try_create_instance - couldn't create instance with error {} on try # {} of {}", e, x, MAX_INSTANCE_UPDATE_TRIES);
+                        log_fields!(
+                            Level::Trace,
+                            { configuration: dps.config_name, instance: dps.instance_name },
+                            "try_create_instance - couldn't create instance with error {} on try # {} of {}",
+                            e, x, MAX_INSTANCE_UPDATE_TRIES
+                        );
                         if x == MAX_INSTANCE_UPDATE_TRIES - 1 {
                             return Err(e);
                         }
@@ -580,7 +1054,7 @@ async fn try_create_instance(
                 }
             }
         }
-        random_delay().await;
+        random_backoff_delay(x).await;
     }
 
     // Successfully created or updated instance. Add it to instance_map.
@@ -589,6 +1063,11 @@ async fn try_create_instance(
         InstanceInfo {
             list_and_watch_message_sender: dps.list_and_watch_message_sender.clone(),
             connectivity_status: ConnectivityStatus::Online,
+            properties: dps.instance_properties.clone(),
+            shared: dps.shared,
+            device_specs: dps.device_specs.clone(),
+            mounts: dps.mounts.clone(),
+            capacity: dps.capacity.clone(),
         },
     );
 
@@ -596,12 +1075,14 @@ async fn try_create_instance(
 }
 
 /// Returns list of "virtual" Devices and their health.
-/// If the instance is offline, returns all unhealthy virtual Devices.
+/// If the instance is offline or reported unhealthy by its discovery handler, returns all unhealthy virtual Devices.
 async fn build_list_and_watch_response(
     dps: Arc<DevicePluginService>,
     kube_interface: Arc<impl KubeInterface>,
 ) -> Result<Vec<v1beta1::Device>, Box<dyn std::error::Error + Send + Sync + 'static>> {
-    info!(
+    log_fields!(
+        Level::Info,
+        { configuration: dps.config_name, instance: dps.instance_name },
         "build_list_and_watch_response -- for Instance {} entered",
         dps.instance_name
     );
@@ -613,9 +1094,14 @@ async fn build_list_and_watch_response(
         .await
         .contains_key(&dps.instance_name)
     {
-        trace!("build_list_and_watch_response - Instance {} removed from map ... returning unhealthy devices", dps.instance_name);
+        log_fields!(
+            Level::Trace,
+            { configuration: dps.config_name, instance: dps.instance_name },
+            "build_list_and_watch_response - Instance {} removed from map ... returning unhealthy devices",
+            dps.instance_name
+        );
         return Ok(build_unhealthy_virtual_devices(
-            dps.config.capacity,
+            dps.capacity.load(Ordering::SeqCst),
             &dps.instance_name,
         ));
     }
@@ -627,16 +1113,45 @@ async fn build_list_and_watch_response(
         .get(&dps.instance_name)
         .unwrap()
         .connectivity_status
-        != ConnectivityStatus::Online
+        .is_offline()
     {
-        trace!("build_list_and_watch_response - device for Instance {} is offline ... returning unhealthy devices", dps.instance_name);
+        log_fields!(
+            Level::Trace,
+            { configuration: dps.config_name, instance: dps.instance_name },
+            "build_list_and_watch_response - device for Instance {} is offline ... returning unhealthy devices",
+            dps.instance_name
+        );
         return Ok(build_unhealthy_virtual_devices(
-            dps.config.capacity,
+            dps.capacity.load(Ordering::SeqCst),
             &dps.instance_name,
         ));
     }
 
-    trace!(
+    // If the discovery handler reported the device as unhealthy, send back all unhealthy device slots
+    if dps
+        .instance_map
+        .lock()
+        .await
+        .get(&dps.instance_name)
+        .map_or(false, |instance_info| {
+            is_reported_unhealthy(&instance_info.properties)
+        })
+    {
+        log_fields!(
+            Level::Trace,
+            { configuration: dps.config_name, instance: dps.instance_name },
+            "build_list_and_watch_response - device for Instance {} is reported unhealthy ... returning unhealthy devices",
+            dps.instance_name
+        );
+        return Ok(build_unhealthy_virtual_devices(
+            dps.capacity.load(Ordering::SeqCst),
+            &dps.instance_name,
+        ));
+    }
+
+    log_fields!(
+        Level::Trace,
+        { configuration: dps.config_name, instance: dps.instance_name },
         "build_list_and_watch_response -- device for Instance {} is online",
         dps.instance_name
     );
@@ -651,9 +1166,14 @@ async fn build_list_and_watch_response(
             &dps.node_name,
         )),
         Err(_) => {
-            trace!("build_list_and_watch_response - could not find instance {} so returning unhealthy devices", dps.instance_name);
+            log_fields!(
+                Level::Trace,
+                { configuration: dps.config_name, instance: dps.instance_name },
+                "build_list_and_watch_response - could not find instance {} so returning unhealthy devices",
+                dps.instance_name
+            );
             Ok(build_unhealthy_virtual_devices(
-                dps.config.capacity,
+                dps.capacity.load(Ordering::SeqCst),
                 &dps.instance_name,
             ))
         }
@@ -719,6 +1239,7 @@ fn build_virtual_devices(
 pub async fn terminate_device_plugin_service(
     instance_name: &str,
     instance_map: InstanceMap,
+    device_plugin_path: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
     let mut instance_map = instance_map.lock().await;
     trace!(
@@ -737,38 +1258,85 @@ pub async fn terminate_device_plugin_service(
         instance_name
     );
     instance_map.remove(instance_name);
+    // The server only deletes its socket once list_and_watch ends it, which never happens if kubelet never connected
+    remove_device_plugin_sockets(device_plugin_path, instance_name);
     Ok(())
 }
 
-/// This creates a new DevicePluginService for an instance and registers it with kubelet
+/// Removes an Instance's device plugin sockets from `device_plugin_path`, including stale ones left behind by
+/// a previous run of the Agent. Sockets are named `<instance name>-<creation time in milliseconds>.sock`.
+fn remove_device_plugin_sockets(device_plugin_path: &str, instance_name: &str) {
+    let entries = match std::fs::read_dir(device_plugin_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            trace!(
+                "remove_device_plugin_sockets - could not read {} ... {}",
+                device_plugin_path,
+                e
+            );
+            return;
+        }
+    };
+    let prefix = format!("{}-", instance_name);
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name();
+        let is_instance_socket = file_name
+            .to_string_lossy()
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".sock"))
+            .map_or(false, |secs| {
+                !secs.is_empty() && secs.chars().all(|c| c.is_ascii_digit())
+            });
+        if is_instance_socket {
+            trace!(
+                "remove_device_plugin_sockets - deleting socket {:?}",
+                entry.path()
+            );
+            // Socket may already be deleted by the server shutting down
+            std::fs::remove_file(entry.path()).unwrap_or(());
+        }
+    }
+}
+
+/// This creates a new DevicePluginService for an instance and registers it with kubelet.
+/// `device_id` is the id of the instance's device, which is recorded in the Instance if it is created.
+/// `capacity` is the number of usage slots of the instance, shared with the `InstanceInfo` it is added to the
+/// `InstanceMap` with. `device_specs` and `mounts` are given to every broker allocated the instance.
+/// Returns the sender used to signal the DevicePluginService's `list_and_watch`.
 pub async fn build_device_plugin(
     instance_name: String,
+    device_id: Option<String>,
     config_name: String,
     config_uid: String,
     config_namespace: String,
     config: Configuration,
     shared: bool,
     instance_properties: HashMap<String, String>,
+    capacity: Arc<AtomicI32>,
+    device_specs: Vec<v1beta1::DeviceSpec>,
+    mounts: Vec<v1beta1::Mount>,
     instance_map: InstanceMap,
     device_plugin_path: &str,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    info!("build_device_plugin - entered for device {}", instance_name);
-    let capability_id: String = format!("{}/{}", AKRI_PREFIX, instance_name);
-    let unique_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
-    let device_endpoint: String = format!("{}-{}.sock", instance_name, unique_time.as_secs());
-    let socket_path: String = Path::new(device_plugin_path)
-        .join(device_endpoint.clone())
-        .to_str()
-        .unwrap()
-        .to_string();
+) -> Result<
+    broadcast::Sender<ListAndWatchMessageKind>,
+    Box<dyn std::error::Error + Send + Sync + 'static>,
+> {
+    log_fields!(
+        Level::Info,
+        { configuration: config_name, instance: instance_name },
+        "build_device_plugin - entered for device {}",
+        instance_name
+    );
     // Channel capacity set to 6 because 3 possible senders (allocate, update_connectivity_status, and handle_config_delete)
     // and and receiver only periodically checks channel
     let (list_and_watch_message_sender, _) = broadcast::channel(6);
-    // Channel capacity set to 2 because worst case both register and list_and_watch send messages at same time and receiver is always listening
-    let (server_ender_sender, server_ender_receiver) = mpsc::channel(2);
-    let device_plugin_service = DevicePluginService {
+    // Replaced by serve_and_register with the sender that shuts down the server it starts
+    let (server_ender_sender, _) = mpsc::channel(1);
+    let mut device_plugin_service = DevicePluginService {
         instance_name: instance_name.clone(),
-        endpoint: device_endpoint.clone(),
+        endpoint: String::new(),
+        device_id,
+        capacity,
         config,
         config_name: config_name.clone(),
         config_uid: config_uid.clone(),
@@ -776,13 +1344,52 @@ pub async fn build_device_plugin(
         shared,
         node_name: env::var("AGENT_NODE_NAME")?,
         instance_properties,
+        device_specs,
+        mounts,
         instance_map: instance_map.clone(),
         list_and_watch_message_sender: list_and_watch_message_sender.clone(),
-        server_ender_sender: server_ender_sender.clone(),
+        server_ender_sender,
     };
+    serve_and_register(&mut device_plugin_service, device_plugin_path).await?;
+
+    let list_and_watch_message_receiver = list_and_watch_message_sender.subscribe();
+    let device_plugin_path = device_plugin_path.to_string();
+    task::spawn(async move {
+        handle_reregistration(
+            device_plugin_service,
+            &device_plugin_path,
+            list_and_watch_message_receiver,
+        )
+        .await;
+    });
+
+    Ok(list_and_watch_message_sender)
+}
+
+/// Serves a DevicePluginService on a new socket in `device_plugin_path` and registers it with kubelet.
+/// `device_plugin_service` is updated with the socket it is served on and the sender that shuts down its server.
+async fn serve_and_register(
+    device_plugin_service: &mut DevicePluginService,
+    device_plugin_path: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let instance_name = device_plugin_service.instance_name.clone();
+    // Clear out sockets left behind for this Instance, such as by an Agent that crashed
+    remove_device_plugin_sockets(device_plugin_path, &instance_name);
+    let capability_id: String = format!("{}/{}", AKRI_PREFIX, instance_name);
+    let unique_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+    let device_endpoint: String = format!("{}-{}.sock", instance_name, unique_time.as_millis());
+    let socket_path: String = Path::new(device_plugin_path)
+        .join(device_endpoint.clone())
+        .to_str()
+        .unwrap()
+        .to_string();
+    // Channel capacity set to 2 because worst case both register and list_and_watch send messages at same time and receiver is always listening
+    let (server_ender_sender, server_ender_receiver) = mpsc::channel(2);
+    device_plugin_service.endpoint = device_endpoint.clone();
+    device_plugin_service.server_ender_sender = server_ender_sender.clone();
 
     serve(
-        device_plugin_service,
+        device_plugin_service.clone(),
         socket_path.clone(),
         server_ender_receiver,
     )
@@ -793,12 +1400,68 @@ pub async fn build_device_plugin(
         device_endpoint,
         &instance_name,
         server_ender_sender,
+        Path::new(device_plugin_path)
+            .join(KUBELET_SOCKET_NAME)
+            .to_str()
+            .unwrap()
+            .to_string(),
     )
     .await?;
-
     Ok(())
 }
 
+/// Serves a DevicePluginService on a new socket and registers it with kubelet again each time it is told
+/// that kubelet restarted, shutting down the server it replaces. Runs until the DevicePluginService is ended.
+async fn handle_reregistration(
+    mut device_plugin_service: DevicePluginService,
+    device_plugin_path: &str,
+    mut list_and_watch_message_receiver: broadcast::Receiver<ListAndWatchMessageKind>,
+) {
+    loop {
+        match list_and_watch_message_receiver.recv().await {
+            Ok(ListAndWatchMessageKind::Reregister) => {
+                log_fields!(
+                    Level::Info,
+                    {
+                        configuration: device_plugin_service.config_name,
+                        instance: device_plugin_service.instance_name,
+                    },
+                    "handle_reregistration - re-registering device plugin for Instance {}",
+                    device_plugin_service.instance_name
+                );
+                let mut replaced_server_ender_sender =
+                    device_plugin_service.server_ender_sender.clone();
+                match serve_and_register(&mut device_plugin_service, device_plugin_path).await {
+                    // The replaced server may have already shutdown
+                    Ok(()) => replaced_server_ender_sender.send(()).await.unwrap_or(()),
+                    Err(e) => log_fields!(
+                        Level::Error,
+                        {
+                            configuration: device_plugin_service.config_name,
+                            instance: device_plugin_service.instance_name,
+                        },
+                        "handle_reregistration - error {} re-registering device plugin for Instance {}",
+                        e, device_plugin_service.instance_name
+                    ),
+                }
+            }
+            Ok(ListAndWatchMessageKind::End) | Err(broadcast::RecvError::Closed) => {
+                log_fields!(
+                    Level::Trace,
+                    {
+                        configuration: device_plugin_service.config_name,
+                        instance: device_plugin_service.instance_name,
+                    },
+                    "handle_reregistration - for Instance {} ending",
+                    device_plugin_service.instance_name
+                );
+                return;
+            }
+            Ok(ListAndWatchMessageKind::Continue) | Err(broadcast::RecvError::Lagged(_)) => {}
+        }
+    }
+}
+
 /// This acts as a signal future to gracefully shutdown DevicePluginServer upon its completion.
 /// Ends when it receives message from `list_and_watch`.
 async fn shutdown_signal(mut server_ender_receiver: mpsc::Receiver<()>) {
@@ -816,7 +1479,9 @@ async fn serve(
     socket_path: String,
     server_ender_receiver: mpsc::Receiver<()>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    info!(
+    log_fields!(
+        Level::Info,
+        { instance: device_plugin_service.instance_name, endpoint: socket_path },
         "serve - creating a device plugin server that will listen at: {}",
         socket_path
     );
@@ -835,7 +1500,9 @@ async fn serve(
             )
             .await
             .unwrap();
-        trace!(
+        log_fields!(
+            Level::Trace,
+            { endpoint: socket_path_to_delete },
             "serve - gracefully shutdown ... deleting socket {}",
             socket_path_to_delete
         );
@@ -872,7 +1539,9 @@ async fn serve(
     }
 
     if !connected {
-        error!(
+        log_fields!(
+            Level::Error,
+            { endpoint: socket_path },
             "serve - could not connect to Device Plugin server on socket {}",
             socket_path
         );
@@ -885,14 +1554,18 @@ async fn serve(
 /// (1) name of unix socket,
 /// (2) Device-Plugin API it was built against (v1beta1),
 /// (3) resource name akri.sh/device_id.
+/// Kubelet is reached at `kubelet_socket`, which it serves in the same folder as device plugin sockets.
 /// If registration request to kubelet fails, terminates DevicePluginService.
 async fn register(
     capability_id: String,
     socket_name: String,
     instance_name: &str,
     mut server_ender_sender: mpsc::Sender<()>,
+    kubelet_socket: String,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    info!(
+    log_fields!(
+        Level::Info,
+        { instance: instance_name, endpoint: socket_name },
         "register - entered for Instance {} and socket_name: {}",
         capability_id, socket_name
     );
@@ -901,8 +1574,11 @@ async fn register(
     };
 
     // lttp://... is a fake uri that is unused (in service_fn) but necessary for uds connection
+    let kubelet_socket_to_connect = kubelet_socket.clone();
     let channel = Endpoint::try_from("lttp://[::]:50051")?
-        .connect_with_connector(service_fn(|_: Uri| UnixStream::connect(KUBELET_SOCKET)))
+        .connect_with_connector(service_fn(move |_: Uri| {
+            UnixStream::connect(kubelet_socket_to_connect.clone())
+        }))
         .await?;
     let mut registration_client = registration_client::RegistrationClient::new(channel);
 
@@ -914,7 +1590,7 @@ async fn register(
     });
     trace!(
         "register - before call to register with Kubelet at socket {}",
-        KUBELET_SOCKET
+        kubelet_socket
     );
 
     // If fail to register with kubelet, terminate device plugin
@@ -923,7 +1599,9 @@ async fn register(
         .await
         .is_err()
     {
-        trace!(
+        log_fields!(
+            Level::Trace,
+            { instance: instance_name },
             "register - failed to register Instance {} with kubelet ... terminating device plugin",
             instance_name
         );
@@ -988,6 +1666,59 @@ mod unix {
     }
 }
 
+/// Fake kubelet registration service for tests that build device plugins
+#[cfg(test)]
+pub mod fake_kubelet {
+    use super::super::v1beta1::{
+        registration_server::{Registration, RegistrationServer},
+        Empty, RegisterRequest,
+    };
+    use super::unix;
+    use futures::stream::TryStreamExt;
+    use std::{path::Path, sync::Arc};
+    use tokio::{net::UnixListener, sync::Mutex};
+    use tonic::{transport::Server, Request, Response, Status};
+
+    /// Resource names of the device plugins registered with a `FakeKubelet`, in the order they registered
+    pub type Registrations = Arc<Mutex<Vec<String>>>;
+
+    struct FakeKubelet {
+        registrations: Registrations,
+    }
+
+    #[tonic::async_trait]
+    impl Registration for FakeKubelet {
+        async fn register(
+            &self,
+            request: Request<RegisterRequest>,
+        ) -> Result<Response<Empty>, Status> {
+            self.registrations
+                .lock()
+                .await
+                .push(request.into_inner().resource_name);
+            Ok(Response::new(Empty {}))
+        }
+    }
+
+    /// Serves a fake kubelet registration socket at `kubelet_socket`, returning the registrations it receives
+    pub fn serve_fake_kubelet(kubelet_socket: &Path) -> Registrations {
+        let registrations: Registrations = Arc::new(Mutex::new(Vec::new()));
+        let mut uds =
+            UnixListener::bind(kubelet_socket).expect("Failed to bind to kubelet socket path");
+        let service = RegistrationServer::new(FakeKubelet {
+            registrations: registrations.clone(),
+        });
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(uds.incoming().map_ok(unix::UnixStream))
+                .await
+                .unwrap();
+        });
+        registrations
+    }
+}
+
 #[cfg(test)]
 mod device_plugin_service_tests {
     use super::super::v1beta1::device_plugin_client::DevicePluginClient;
@@ -997,6 +1728,7 @@ mod device_plugin_service_tests {
         akri::instance::{Instance, KubeAkriInstance},
         k8s::MockKubeInterface,
     };
+    use k8s_openapi::{api::core::v1::Secret, ByteString};
     use mockall::predicate::*;
     use std::{
         fs,
@@ -1048,6 +1780,18 @@ mod device_plugin_service_tests {
     fn create_device_plugin_service(
         connectivity_status: ConnectivityStatus,
         add_to_instance_map: bool,
+    ) -> (DevicePluginService, DevicePluginServiceReceivers) {
+        create_device_plugin_service_with_properties(
+            connectivity_status,
+            add_to_instance_map,
+            HashMap::new(),
+        )
+    }
+
+    fn create_device_plugin_service_with_properties(
+        connectivity_status: ConnectivityStatus,
+        add_to_instance_map: bool,
+        properties: HashMap<String, String>,
     ) -> (DevicePluginService, DevicePluginServiceReceivers) {
         let path_to_config = "../test/json/config-a.json";
         let kube_akri_config_json =
@@ -1065,12 +1809,18 @@ mod device_plugin_service_tests {
         let (list_and_watch_message_sender, list_and_watch_message_receiver) =
             broadcast::channel(4);
         let (server_ender_sender, server_ender_receiver) = mpsc::channel(1);
+        let capacity = Arc::new(AtomicI32::new(kube_akri_config.spec.capacity));
 
         let mut map = HashMap::new();
         if add_to_instance_map {
             let instance_info: InstanceInfo = InstanceInfo {
                 list_and_watch_message_sender: list_and_watch_message_sender.clone(),
                 connectivity_status,
+                properties: properties.clone(),
+                shared: false,
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+                capacity: capacity.clone(),
             };
             map.insert(device_instance_name.clone(), instance_info);
         }
@@ -1079,13 +1829,17 @@ mod device_plugin_service_tests {
         let dps = DevicePluginService {
             instance_name: device_instance_name,
             endpoint: device_endpoint,
+            device_id: Some("filter1".to_string()),
+            capacity,
             config: kube_akri_config.spec.clone(),
             config_name: kube_akri_config.metadata.name,
             config_uid: kube_akri_config.metadata.uid.unwrap(),
             config_namespace: kube_akri_config.metadata.namespace.unwrap(),
             shared: false,
             node_name: "node-a".to_string(),
-            instance_properties: HashMap::new(),
+            instance_properties: properties,
+            device_specs: Vec::new(),
+            mounts: Vec::new(),
             instance_map,
             list_and_watch_message_sender,
             server_ender_sender,
@@ -1128,6 +1882,34 @@ mod device_plugin_service_tests {
         );
     }
 
+    // Tests that only the allowed connectivity status transitions succeed
+    #[test]
+    fn test_connectivity_status_transition_to() {
+        let went_offline = Instant::now() - Duration::from_secs(30);
+        let online = || ConnectivityStatus::Online;
+        let offline = || ConnectivityStatus::Offline(went_offline);
+        let offline_now = || ConnectivityStatus::Offline(Instant::now());
+        let cases = vec![
+            (online(), online(), true),
+            (online(), offline_now(), true),
+            (offline(), online(), true),
+            (offline(), offline(), false),
+            (offline(), offline_now(), false),
+        ];
+        for (from, to, valid) in cases {
+            let result = from.transition_to(to.clone());
+            if valid {
+                assert_eq!(Ok(to), result);
+            } else {
+                assert_eq!(Err(InvalidTransition { from, to }), result);
+            }
+        }
+        assert!(online().is_online());
+        assert!(!online().is_offline());
+        assert!(offline().is_offline());
+        assert!(!offline().is_online());
+    }
+
     fn configure_find_configuration(
         mock: &mut MockKubeInterface,
         config_name: String,
@@ -1180,6 +1962,7 @@ mod device_plugin_service_tests {
                 namespace == config_namespace
                     && name == instance_name
                     && instance.nodes.contains(&"node-a".to_string())
+                    && instance.device_id.as_deref() == Some("filter1")
                     && owner_name == config_name
                     && owner_uid == config_uid
             })
@@ -1218,12 +2001,15 @@ mod device_plugin_service_tests {
         );
         let instance_name = device_plugin_service.instance_name.clone();
         let config_namespace = device_plugin_service.config_namespace.clone();
-        mock.expect_update_instance()
+        mock.expect_patch_instance()
             .times(1)
-            .withf(move |instance, name, namespace| {
+            .withf(move |patch, name, namespace| {
                 namespace == config_namespace
                     && name == instance_name
-                    && instance.nodes.contains(&"node-a".to_string())
+                    && patch
+                        == &serde_json::json!([
+                            { "op": "add", "path": "/spec/nodes/-", "value": "node-a" }
+                        ])
             })
             .returning(move |_, _, _| Ok(()));
 
@@ -1388,29 +2174,301 @@ mod device_plugin_service_tests {
         };
     }
 
+    // Tests that list_and_watch advertises a usage slot for each unit of capacity reported by the discovery handler
     #[tokio::test]
-    async fn test_build_virtual_devices() {
-        let mut device_usage: HashMap<String, String> = HashMap::new();
-        let mut expected_devices_nodea: HashMap<String, String> = HashMap::new();
-        let mut expected_devices_nodeb: HashMap<String, String> = HashMap::new();
-        let instance_name = "s0meH@sH";
-        for x in 0..5 {
-            if x % 2 == 0 {
-                device_usage.insert(format!("{}-{}", instance_name, x), "nodeA".to_string());
-                expected_devices_nodea
-                    .insert(format!("{}-{}", instance_name, x), HEALTHY.to_string());
-                expected_devices_nodeb
-                    .insert(format!("{}-{}", instance_name, x), UNHEALTHY.to_string());
-            } else {
-                device_usage.insert(format!("{}-{}", instance_name, x), "".to_string());
-                expected_devices_nodea
-                    .insert(format!("{}-{}", instance_name, x), HEALTHY.to_string());
-                expected_devices_nodeb
-                    .insert(format!("{}-{}", instance_name, x), HEALTHY.to_string());
-            }
-        }
-
-        // Test shared all healthy
+    async fn test_list_and_watch_capacity_override() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let (mut device_plugin_service, device_plugin_service_receivers) =
+            create_device_plugin_service(ConnectivityStatus::Online, false);
+        let expected_capacity = device_plugin_service.config.capacity + 2;
+        device_plugin_service.capacity = Arc::new(AtomicI32::new(expected_capacity));
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let socket_path: String = device_plugin_temp_dir
+            .path()
+            .join(device_plugin_service.endpoint.clone())
+            .to_str()
+            .unwrap()
+            .to_string();
+        let list_and_watch_message_sender =
+            device_plugin_service.list_and_watch_message_sender.clone();
+        let instance_name = device_plugin_service.instance_name.clone();
+        serve(
+            device_plugin_service,
+            socket_path.clone(),
+            device_plugin_service_receivers.server_ender_receiver,
+        )
+        .await
+        .unwrap();
+        let channel = Endpoint::try_from("lttp://[::]:50051")
+            .unwrap()
+            .connect_with_connector(service_fn(move |_: Uri| {
+                UnixStream::connect(socket_path.clone())
+            }))
+            .await
+            .unwrap();
+        let mut client = DevicePluginClient::new(channel);
+        let mut stream = client
+            .list_and_watch(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner();
+        list_and_watch_message_sender
+            .send(ListAndWatchMessageKind::End)
+            .unwrap();
+        let list_and_watch_response = stream.message().await.unwrap().unwrap();
+        assert_eq!(
+            expected_capacity as usize,
+            list_and_watch_response.devices.len()
+        );
+        assert_eq!(
+            format!("{}-{}", instance_name, expected_capacity - 1),
+            list_and_watch_response.devices[(expected_capacity - 1) as usize].id
+        );
+    }
+
+    // Tests that terminating a DevicePluginService deletes its socket, along with stale ones of the same Instance,
+    // even when kubelet never connected to end the server
+    #[tokio::test]
+    async fn test_terminate_device_plugin_service_deletes_sockets() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let (device_plugin_service, device_plugin_service_receivers) =
+            create_device_plugin_service(ConnectivityStatus::Online, true);
+        let device_plugin_temp_dir = Builder::new().prefix("device-plugins-").tempdir().unwrap();
+        let device_plugin_path = device_plugin_temp_dir.path().to_str().unwrap();
+        let socket_path = device_plugin_temp_dir
+            .path()
+            .join(device_plugin_service.endpoint.clone());
+        let instance_name = device_plugin_service.instance_name.clone();
+        let instance_map = device_plugin_service.instance_map.clone();
+        let stale_socket_path = device_plugin_temp_dir
+            .path()
+            .join(format!("{}-1.sock", instance_name));
+        fs::write(&stale_socket_path, "").unwrap();
+        let other_socket_path = device_plugin_temp_dir
+            .path()
+            .join(format!("{}-ab12cd-1.sock", instance_name));
+        fs::write(&other_socket_path, "").unwrap();
+        serve(
+            device_plugin_service,
+            socket_path.to_str().unwrap().to_string(),
+            device_plugin_service_receivers.server_ender_receiver,
+        )
+        .await
+        .unwrap();
+        assert!(socket_path.exists());
+
+        terminate_device_plugin_service(&instance_name, instance_map.clone(), device_plugin_path)
+            .await
+            .unwrap();
+        assert!(!socket_path.exists());
+        assert!(!stale_socket_path.exists());
+        // Sockets of other Instances whose names start with this one's are left alone
+        assert!(other_socket_path.exists());
+        assert!(!instance_map.lock().await.contains_key(&instance_name));
+    }
+
+    /// Expects config-a-b494b6 to be looked up, finding its 5 slots with the first `allocated_count` allocated to node-a
+    fn configure_find_instance_with_allocated_slots(
+        mock: &mut MockKubeInterface,
+        allocated_count: usize,
+    ) {
+        mock.expect_find_instance()
+            .times(1)
+            .withf(|name: &str, namespace: &str| {
+                name == "config-a-b494b6" && namespace == "config-a-namespace"
+            })
+            .returning(move |_, _| {
+                let instance_json = fs::read_to_string("../test/json/local-instance.json")
+                    .expect("Unable to read file");
+                let mut instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
+                for x in 0..allocated_count {
+                    instance
+                        .spec
+                        .device_usage
+                        .insert(format!("config-a-b494b6-{}", x), "node-a".to_string());
+                }
+                Ok(instance)
+            });
+    }
+
+    async fn get_instance_info(device_plugin_service: &DevicePluginService) -> InstanceInfo {
+        device_plugin_service
+            .instance_map
+            .lock()
+            .await
+            .get(&device_plugin_service.instance_name)
+            .unwrap()
+            .clone()
+    }
+
+    /// Checks that the offline Instance of `device_plugin_service` advertises `capacity` unhealthy virtual Devices
+    async fn assert_unhealthy_virtual_device_count(
+        device_plugin_service: DevicePluginService,
+        capacity: usize,
+    ) {
+        let devices = build_list_and_watch_response(
+            Arc::new(device_plugin_service),
+            Arc::new(MockKubeInterface::new()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(capacity, devices.len());
+        assert!(devices.iter().all(|device| device.health == UNHEALTHY));
+    }
+
+    #[tokio::test]
+    async fn test_update_instance_capacity_grow() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut mock = MockKubeInterface::new();
+        configure_find_instance_with_allocated_slots(&mut mock, 2);
+        mock.expect_patch_instance()
+            .times(1)
+            .withf(|patch, name, namespace| {
+                patch
+                    == &serde_json::json!([
+                        { "op": "add", "path": "/spec/deviceUsage/config-a-b494b6-5", "value": "" },
+                        { "op": "add", "path": "/spec/deviceUsage/config-a-b494b6-6", "value": "" }
+                    ])
+                    && name == "config-a-b494b6"
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _, _| Ok(()));
+        let (device_plugin_service, mut device_plugin_service_receivers) =
+            create_device_plugin_service(ConnectivityStatus::Offline(Instant::now()), true);
+        update_instance_capacity(
+            "config-a-b494b6",
+            "config-a-namespace",
+            5,
+            7,
+            &get_instance_info(&device_plugin_service).await,
+            &mock,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            device_plugin_service_receivers
+                .list_and_watch_message_receiver
+                .try_recv()
+                .unwrap(),
+            ListAndWatchMessageKind::Continue
+        );
+        assert_unhealthy_virtual_device_count(device_plugin_service, 7).await;
+    }
+
+    #[tokio::test]
+    async fn test_update_instance_capacity_shrink() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut mock = MockKubeInterface::new();
+        configure_find_instance_with_allocated_slots(&mut mock, 2);
+        // Only free slots are removed, highest first
+        mock.expect_patch_instance()
+            .times(1)
+            .withf(|patch, name, namespace| {
+                patch
+                    == &serde_json::json!([
+                        { "op": "test", "path": "/spec/deviceUsage/config-a-b494b6-4", "value": "" },
+                        { "op": "remove", "path": "/spec/deviceUsage/config-a-b494b6-4" },
+                        { "op": "test", "path": "/spec/deviceUsage/config-a-b494b6-3", "value": "" },
+                        { "op": "remove", "path": "/spec/deviceUsage/config-a-b494b6-3" }
+                    ])
+                    && name == "config-a-b494b6"
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _, _| Ok(()));
+        let (device_plugin_service, mut device_plugin_service_receivers) =
+            create_device_plugin_service(ConnectivityStatus::Offline(Instant::now()), true);
+        update_instance_capacity(
+            "config-a-b494b6",
+            "config-a-namespace",
+            5,
+            3,
+            &get_instance_info(&device_plugin_service).await,
+            &mock,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            device_plugin_service_receivers
+                .list_and_watch_message_receiver
+                .try_recv()
+                .unwrap(),
+            ListAndWatchMessageKind::Continue
+        );
+        assert_unhealthy_virtual_device_count(device_plugin_service, 3).await;
+    }
+
+    #[tokio::test]
+    async fn test_update_instance_capacity_shrink_below_allocated() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut mock = MockKubeInterface::new();
+        configure_find_instance_with_allocated_slots(&mut mock, 2);
+        mock.expect_patch_instance().times(0);
+        let (device_plugin_service, mut device_plugin_service_receivers) =
+            create_device_plugin_service(ConnectivityStatus::Offline(Instant::now()), true);
+        let e = update_instance_capacity(
+            "config-a-b494b6",
+            "config-a-namespace",
+            5,
+            1,
+            &get_instance_info(&device_plugin_service).await,
+            &mock,
+        )
+        .await
+        .unwrap_err();
+        assert!(e.to_string().contains("2 of its slots are allocated"));
+        assert!(device_plugin_service_receivers
+            .list_and_watch_message_receiver
+            .try_recv()
+            .is_err());
+        // The Instance keeps its slots, so as many virtual Devices are advertised as before
+        assert_unhealthy_virtual_device_count(device_plugin_service, 5).await;
+    }
+
+    #[tokio::test]
+    async fn test_update_instance_capacity_leaves_discovery_handler_capacity() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut mock = MockKubeInterface::new();
+        configure_find_instance_with_allocated_slots(&mut mock, 0);
+        mock.expect_patch_instance().times(0);
+        let (device_plugin_service, _device_plugin_service_receivers) =
+            create_device_plugin_service(ConnectivityStatus::Offline(Instant::now()), true);
+        // The Instance's 5 slots were not taken from the previous capacity of 2
+        update_instance_capacity(
+            "config-a-b494b6",
+            "config-a-namespace",
+            2,
+            3,
+            &get_instance_info(&device_plugin_service).await,
+            &mock,
+        )
+        .await
+        .unwrap();
+        assert_unhealthy_virtual_device_count(device_plugin_service, 5).await;
+    }
+
+    #[tokio::test]
+    async fn test_build_virtual_devices() {
+        let mut device_usage: HashMap<String, String> = HashMap::new();
+        let mut expected_devices_nodea: HashMap<String, String> = HashMap::new();
+        let mut expected_devices_nodeb: HashMap<String, String> = HashMap::new();
+        let instance_name = "s0meH@sH";
+        for x in 0..5 {
+            if x % 2 == 0 {
+                device_usage.insert(format!("{}-{}", instance_name, x), "nodeA".to_string());
+                expected_devices_nodea
+                    .insert(format!("{}-{}", instance_name, x), HEALTHY.to_string());
+                expected_devices_nodeb
+                    .insert(format!("{}-{}", instance_name, x), UNHEALTHY.to_string());
+            } else {
+                device_usage.insert(format!("{}-{}", instance_name, x), "".to_string());
+                expected_devices_nodea
+                    .insert(format!("{}-{}", instance_name, x), HEALTHY.to_string());
+                expected_devices_nodeb
+                    .insert(format!("{}-{}", instance_name, x), HEALTHY.to_string());
+            }
+        }
+
+        // Test shared all healthy
         let mut devices: Vec<v1beta1::Device> =
             build_virtual_devices(&device_usage, true, &"nodeA".to_string());
         for device in devices {
@@ -1461,6 +2519,32 @@ mod device_plugin_service_tests {
             .for_each(|device| assert!(device.health == UNHEALTHY));
     }
 
+    // Tests when the discovery handler reports an online device as unhealthy and unhealthy devices are returned
+    #[tokio::test]
+    async fn test_build_list_and_watch_response_reported_unhealthy() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut properties = HashMap::new();
+        properties.insert(
+            AKRI_DEVICE_HEALTH_LABEL_ID.to_string(),
+            UNHEALTHY.to_string(),
+        );
+        let (device_plugin_service, _device_plugin_service_receivers) =
+            create_device_plugin_service_with_properties(
+                ConnectivityStatus::Online,
+                true,
+                properties,
+            );
+        let mock = MockKubeInterface::new();
+        let devices =
+            build_list_and_watch_response(Arc::new(device_plugin_service), Arc::new(mock))
+                .await
+                .unwrap();
+        assert_eq!(devices.len(), 5);
+        devices
+            .into_iter()
+            .for_each(|device| assert!(device.health == UNHEALTHY));
+    }
+
     // Tests when instance has not yet been created for this device, all devices are returned as UNHEALTHY
     #[tokio::test]
     async fn test_build_list_and_watch_response_no_instance() {
@@ -1531,14 +2615,15 @@ mod device_plugin_service_tests {
             "",
             NodeName::ThisNode,
         );
-        mock.expect_update_instance()
+        mock.expect_patch_instance()
             .times(1)
-            .withf(move |instance_to_update: &Instance, _, _| {
-                instance_to_update
-                    .device_usage
-                    .get(&device_usage_id_slot)
-                    .unwrap()
-                    == &node_name
+            .withf(move |patch: &serde_json::Value, _, _| {
+                let path = format!("/spec/deviceUsage/{}", device_usage_id_slot);
+                patch
+                    == &serde_json::json!([
+                        { "op": "test", "path": path, "value": "" },
+                        { "op": "replace", "path": path, "value": node_name }
+                    ])
             })
             .returning(move |_, _, _| Ok(()));
         let devices_i_ds = vec![device_usage_id_slot_2];
@@ -1554,6 +2639,401 @@ mod device_plugin_service_tests {
             .is_err());
     }
 
+    // Test that the environment variables in the allocate response are the Instance's properties and the
+    // Configuration's broker properties, with their names prefixed by the Configuration's brokerPropertiesPrefix
+    // if it has one
+    #[tokio::test]
+    async fn test_internal_allocate_broker_properties_prefix() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        for (prefix, expected_name, expected_broker_property_name) in vec![
+            (
+                None,
+                "ONVIF_DEVICE_IP_ADDRESS_VALUE",
+                "INFERENCE_SERVER_URL",
+            ),
+            (
+                Some("CAMERA_1_".to_string()),
+                "CAMERA_1_ONVIF_DEVICE_IP_ADDRESS_VALUE",
+                "CAMERA_1_INFERENCE_SERVER_URL",
+            ),
+        ] {
+            let mut properties = HashMap::new();
+            properties.insert(
+                "ONVIF_DEVICE_IP_ADDRESS_VALUE".to_string(),
+                "10.0.0.1".to_string(),
+            );
+            let (mut device_plugin_service, _device_plugin_service_receivers) =
+                create_device_plugin_service_with_properties(
+                    ConnectivityStatus::Online,
+                    true,
+                    properties,
+                );
+            device_plugin_service.config.broker_properties_prefix = prefix;
+            device_plugin_service.config.broker_properties = vec![(
+                "INFERENCE_SERVER_URL".to_string(),
+                "http://inference:8080".to_string(),
+            )]
+            .into_iter()
+            .collect();
+            let device_usage_id_slot = format!("{}-0", device_plugin_service.instance_name);
+            let mut mock = MockKubeInterface::new();
+            configure_find_instance(
+                &mut mock,
+                "../test/json/local-instance.json",
+                device_plugin_service.instance_name.clone(),
+                device_plugin_service.config_namespace.clone(),
+                "",
+                NodeName::ThisNode,
+            );
+            mock.expect_patch_instance()
+                .times(1)
+                .returning(move |_, _, _| Ok(()));
+            let devices_i_ds = vec![device_usage_id_slot];
+            let container_requests = vec![v1beta1::ContainerAllocateRequest { devices_i_ds }];
+            let requests = Request::new(AllocateRequest { container_requests });
+            let response = device_plugin_service
+                .internal_allocate(requests, Arc::new(mock))
+                .await
+                .unwrap()
+                .into_inner();
+            let envs = &response.container_responses[0].envs;
+            assert_eq!(2, envs.len());
+            assert_eq!(Some(&"10.0.0.1".to_string()), envs.get(expected_name));
+            assert_eq!(
+                Some(&"http://inference:8080".to_string()),
+                envs.get(expected_broker_property_name)
+            );
+        }
+    }
+
+    // Test that the Configuration's broker properties are added to the environment variables in the
+    // allocate response, with the Instance's properties winning on conflict
+    #[tokio::test]
+    async fn test_internal_allocate_broker_properties() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut properties = HashMap::new();
+        properties.insert(
+            "ONVIF_DEVICE_IP_ADDRESS_VALUE".to_string(),
+            "10.0.0.1".to_string(),
+        );
+        let (mut device_plugin_service, _device_plugin_service_receivers) =
+            create_device_plugin_service_with_properties(
+                ConnectivityStatus::Online,
+                true,
+                properties,
+            );
+        device_plugin_service.config.broker_properties = vec![
+            ("INFERENCE_SERVER_URL", "http://inference:8080"),
+            ("ONVIF_DEVICE_IP_ADDRESS_VALUE", "10.0.0.2"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let device_usage_id_slot = format!("{}-0", device_plugin_service.instance_name);
+        let mut mock = MockKubeInterface::new();
+        configure_find_instance(
+            &mut mock,
+            "../test/json/local-instance.json",
+            device_plugin_service.instance_name.clone(),
+            device_plugin_service.config_namespace.clone(),
+            "",
+            NodeName::ThisNode,
+        );
+        mock.expect_patch_instance()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
+        let devices_i_ds = vec![device_usage_id_slot];
+        let container_requests = vec![v1beta1::ContainerAllocateRequest { devices_i_ds }];
+        let requests = Request::new(AllocateRequest { container_requests });
+        let response = device_plugin_service
+            .internal_allocate(requests, Arc::new(mock))
+            .await
+            .unwrap()
+            .into_inner();
+        let envs = &response.container_responses[0].envs;
+        assert_eq!(2, envs.len());
+        assert_eq!(
+            Some(&"http://inference:8080".to_string()),
+            envs.get("INFERENCE_SERVER_URL")
+        );
+        assert_eq!(
+            Some(&"10.0.0.1".to_string()),
+            envs.get("ONVIF_DEVICE_IP_ADDRESS_VALUE")
+        );
+    }
+
+    // Test that the device specs and mounts reported for the Instance are in the allocate response
+    #[tokio::test]
+    async fn test_internal_allocate_device_specs_and_mounts() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let (mut device_plugin_service, _device_plugin_service_receivers) =
+            create_device_plugin_service(ConnectivityStatus::Online, true);
+        let device_spec = v1beta1::DeviceSpec {
+            container_path: "/dev/nvidia0".to_string(),
+            host_path: "/dev/nvidia0".to_string(),
+            permissions: "rw".to_string(),
+        };
+        let mount = v1beta1::Mount {
+            container_path: "/usr/local/nvidia".to_string(),
+            host_path: "/usr/lib/nvidia".to_string(),
+            read_only: true,
+        };
+        // The device specs and mounts most recently reported by discovery take the place of those
+        // the Instance was created with
+        device_plugin_service.device_specs = vec![v1beta1::DeviceSpec {
+            container_path: "/dev/nvidia0".to_string(),
+            host_path: "/dev/nvidia1".to_string(),
+            permissions: "rw".to_string(),
+        }];
+        {
+            let mut instance_map = device_plugin_service.instance_map.lock().await;
+            let instance_info = instance_map
+                .get_mut(&device_plugin_service.instance_name)
+                .unwrap();
+            instance_info.device_specs = vec![device_spec.clone()];
+            instance_info.mounts = vec![mount.clone()];
+        }
+        let device_usage_id_slot = format!("{}-0", device_plugin_service.instance_name);
+        let mut mock = MockKubeInterface::new();
+        configure_find_instance(
+            &mut mock,
+            "../test/json/local-instance.json",
+            device_plugin_service.instance_name.clone(),
+            device_plugin_service.config_namespace.clone(),
+            "",
+            NodeName::ThisNode,
+        );
+        mock.expect_patch_instance()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
+        let devices_i_ds = vec![device_usage_id_slot];
+        let container_requests = vec![v1beta1::ContainerAllocateRequest { devices_i_ds }];
+        let requests = Request::new(AllocateRequest { container_requests });
+        let response = device_plugin_service
+            .internal_allocate(requests, Arc::new(mock))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(vec![device_spec], response.container_responses[0].devices);
+        assert_eq!(vec![mount], response.container_responses[0].mounts);
+    }
+
+    fn configure_find_secret(
+        mock: &mut MockKubeInterface,
+        secret_data: Option<Vec<(&'static str, &'static str)>>,
+    ) {
+        mock.expect_find_secret()
+            .times(1)
+            .withf(|name: &str, namespace: &str| {
+                name == "camera-credentials" && namespace == "config-a-namespace"
+            })
+            .returning(move |_, _| match &secret_data {
+                Some(secret_data) => Ok(Secret {
+                    data: Some(
+                        secret_data
+                            .iter()
+                            .map(|(key, value)| {
+                                (key.to_string(), ByteString(value.as_bytes().to_vec()))
+                            })
+                            .collect(),
+                    ),
+                    ..Default::default()
+                }),
+                None => Err(Error::new(ErrorKind::NotFound, "secret not found").into()),
+            });
+    }
+
+    fn create_device_plugin_service_with_secret_reference(
+        secret_reference: &str,
+    ) -> (DevicePluginService, DevicePluginServiceReceivers) {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "ONVIF_DEVICE_IP_ADDRESS_VALUE".to_string(),
+            "10.0.0.1".to_string(),
+        );
+        let (mut device_plugin_service, device_plugin_service_receivers) =
+            create_device_plugin_service_with_properties(
+                ConnectivityStatus::Online,
+                true,
+                properties,
+            );
+        device_plugin_service.config.broker_properties.insert(
+            "ONVIF_DEVICE_PASSWORD".to_string(),
+            secret_reference.to_string(),
+        );
+        (device_plugin_service, device_plugin_service_receivers)
+    }
+
+    // Test that a broker property referring to a Secret key is given to the broker as that key's data
+    #[tokio::test]
+    async fn test_internal_allocate_resolves_secret_reference() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let (device_plugin_service, _device_plugin_service_receivers) =
+            create_device_plugin_service_with_secret_reference(
+                "secret://config-a-namespace/camera-credentials/password",
+            );
+        let device_usage_id_slot = format!("{}-0", device_plugin_service.instance_name);
+        let mut mock = MockKubeInterface::new();
+        configure_find_secret(&mut mock, Some(vec![("password", "hunter2")]));
+        configure_find_instance(
+            &mut mock,
+            "../test/json/local-instance.json",
+            device_plugin_service.instance_name.clone(),
+            device_plugin_service.config_namespace.clone(),
+            "",
+            NodeName::ThisNode,
+        );
+        mock.expect_patch_instance()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
+        let devices_i_ds = vec![device_usage_id_slot];
+        let container_requests = vec![v1beta1::ContainerAllocateRequest { devices_i_ds }];
+        let requests = Request::new(AllocateRequest { container_requests });
+        let response = device_plugin_service
+            .internal_allocate(requests, Arc::new(mock))
+            .await
+            .unwrap()
+            .into_inner();
+        let envs = &response.container_responses[0].envs;
+        assert_eq!(2, envs.len());
+        assert_eq!(
+            Some(&"hunter2".to_string()),
+            envs.get("ONVIF_DEVICE_PASSWORD")
+        );
+        assert_eq!(
+            Some(&"10.0.0.1".to_string()),
+            envs.get("ONVIF_DEVICE_IP_ADDRESS_VALUE")
+        );
+        // The Configuration keeps the reference rather than the secret data
+        assert_eq!(
+            Some(&"secret://config-a-namespace/camera-credentials/password".to_string()),
+            device_plugin_service
+                .config
+                .broker_properties
+                .get("ONVIF_DEVICE_PASSWORD")
+        );
+    }
+
+    // Test that secret references reported by a device are given to the broker as they are, without reading any Secret
+    #[tokio::test]
+    async fn test_internal_allocate_ignores_device_secret_reference() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut properties = HashMap::new();
+        properties.insert(
+            "ONVIF_DEVICE_PASSWORD".to_string(),
+            "secret://config-a-namespace/camera-credentials/password".to_string(),
+        );
+        let (device_plugin_service, _device_plugin_service_receivers) =
+            create_device_plugin_service_with_properties(
+                ConnectivityStatus::Online,
+                true,
+                properties,
+            );
+        let device_usage_id_slot = format!("{}-0", device_plugin_service.instance_name);
+        let mut mock = MockKubeInterface::new();
+        mock.expect_find_secret().times(0);
+        configure_find_instance(
+            &mut mock,
+            "../test/json/local-instance.json",
+            device_plugin_service.instance_name.clone(),
+            device_plugin_service.config_namespace.clone(),
+            "",
+            NodeName::ThisNode,
+        );
+        mock.expect_patch_instance()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
+        let devices_i_ds = vec![device_usage_id_slot];
+        let container_requests = vec![v1beta1::ContainerAllocateRequest { devices_i_ds }];
+        let requests = Request::new(AllocateRequest { container_requests });
+        let response = device_plugin_service
+            .internal_allocate(requests, Arc::new(mock))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            Some(&"secret://config-a-namespace/camera-credentials/password".to_string()),
+            response.container_responses[0]
+                .envs
+                .get("ONVIF_DEVICE_PASSWORD")
+        );
+    }
+
+    // Test that allocate fails without reading the Secret when a broker property refers to another namespace
+    #[tokio::test]
+    async fn test_internal_allocate_secret_reference_outside_namespace() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let (device_plugin_service, _device_plugin_service_receivers) =
+            create_device_plugin_service_with_secret_reference(
+                "secret://kube-system/camera-credentials/password",
+            );
+        let device_usage_id_slot = format!("{}-0", device_plugin_service.instance_name);
+        let mut mock = MockKubeInterface::new();
+        mock.expect_find_secret().times(0);
+        mock.expect_find_instance().times(0);
+        mock.expect_patch_instance().times(0);
+        let devices_i_ds = vec![device_usage_id_slot];
+        let container_requests = vec![v1beta1::ContainerAllocateRequest { devices_i_ds }];
+        let requests = Request::new(AllocateRequest { container_requests });
+        let status = device_plugin_service
+            .internal_allocate(requests, Arc::new(mock))
+            .await
+            .unwrap_err();
+        assert_eq!(Code::FailedPrecondition, status.code());
+        assert!(status
+            .message()
+            .contains("only Secrets in the Configuration's namespace config-a-namespace"));
+    }
+
+    // Test that allocate fails without reserving a slot when a referenced Secret or key cannot be found
+    #[tokio::test]
+    async fn test_internal_allocate_unresolved_secret_reference() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        for (secret_data, expected_message) in vec![
+            (None, "could not get Secret camera-credentials"),
+            (Some(vec![("username", "admin")]), "has no key password"),
+        ] {
+            let (device_plugin_service, _device_plugin_service_receivers) =
+                create_device_plugin_service_with_secret_reference(
+                    "secret://config-a-namespace/camera-credentials/password",
+                );
+            let device_usage_id_slot = format!("{}-0", device_plugin_service.instance_name);
+            let mut mock = MockKubeInterface::new();
+            configure_find_secret(&mut mock, secret_data);
+            mock.expect_find_instance().times(0);
+            mock.expect_patch_instance().times(0);
+            let devices_i_ds = vec![device_usage_id_slot];
+            let container_requests = vec![v1beta1::ContainerAllocateRequest { devices_i_ds }];
+            let requests = Request::new(AllocateRequest { container_requests });
+            let status = device_plugin_service
+                .internal_allocate(requests, Arc::new(mock))
+                .await
+                .unwrap_err();
+            assert_eq!(Code::FailedPrecondition, status.code());
+            assert!(status.message().contains(expected_message));
+        }
+    }
+
+    #[test]
+    fn test_parse_secret_reference() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        assert_eq!(None, parse_secret_reference("10.0.0.1"));
+        assert_eq!(
+            Some(Ok(("camera-namespace", "camera-credentials", "password"))),
+            parse_secret_reference("secret://camera-namespace/camera-credentials/password")
+        );
+        assert!(
+            parse_secret_reference("secret://camera-namespace/camera-credentials")
+                .unwrap()
+                .is_err()
+        );
+        assert!(
+            parse_secret_reference("secret://camera-namespace//password")
+                .unwrap()
+                .is_err()
+        );
+    }
+
     // Test when device_usage[id] == self.nodeName
     // Expected behavior: internal_allocate should set device_usage[id] == "", invoke list_and_watch, and return error
     #[tokio::test]
@@ -1572,14 +3052,15 @@ mod device_plugin_service_tests {
             "node-a",
             NodeName::ThisNode,
         );
-        mock.expect_update_instance()
+        mock.expect_patch_instance()
             .times(1)
-            .withf(move |instance_to_update: &Instance, _, _| {
-                instance_to_update
-                    .device_usage
-                    .get(&device_usage_id_slot)
-                    .unwrap()
-                    == ""
+            .withf(move |patch: &serde_json::Value, _, _| {
+                let path = format!("/spec/deviceUsage/{}", device_usage_id_slot);
+                patch
+                    == &serde_json::json!([
+                        { "op": "test", "path": path, "value": "node-a" },
+                        { "op": "replace", "path": path, "value": "" }
+                    ])
             })
             .returning(move |_, _, _| Ok(()));
         let devices_i_ds = vec![device_usage_id_slot_2];
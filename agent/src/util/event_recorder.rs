@@ -0,0 +1,205 @@
+use super::constants::INSTANCE_EVENT_RATE_LIMIT_SECS;
+use akri_shared::{
+    akri::{API_NAMESPACE, API_VERSION},
+    k8s::KubeInterface,
+};
+use chrono::Utc;
+use k8s_openapi::api::core::v1::{Event, EventSource, ObjectReference};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use std::{
+    collections::HashMap,
+    env,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Reason of the Event recorded when a device plugin is created for a newly discovered Instance
+pub const INSTANCE_CREATED_REASON: &str = "InstanceCreated";
+/// Reason of the Event recorded when an Instance can no longer be discovered
+pub const INSTANCE_OFFLINE_REASON: &str = "InstanceOffline";
+/// Reason of the Event recorded when an offline Instance is discovered again
+pub const INSTANCE_BACK_ONLINE_REASON: &str = "InstanceBackOnline";
+/// Reason of the Event recorded when an Instance that stayed offline is deleted
+pub const INSTANCE_DELETED_REASON: &str = "InstanceDeleted";
+/// Reason of the Event recorded when an Instance cannot be given its Configuration's new capacity,
+/// such as when more of its slots are allocated than the new capacity
+pub const INSTANCE_CAPACITY_NOT_CHANGED_REASON: &str = "InstanceCapacityNotChanged";
+/// Reason given in an InstanceDeleted Event for an unshared Instance that was still not discovered on the next discovery
+pub const INSTANCE_DELETED_LOCAL_DISAPPEARED: &str = "local-disappeared";
+/// Reason given in an InstanceDeleted Event for a shared Instance that stayed offline for the whole grace period
+pub const INSTANCE_DELETED_GRACE_PERIOD_EXPIRED: &str = "grace-period-expired";
+/// Component the Agent's Events are reported as coming from
+const AGENT_EVENT_COMPONENT: &str = "akri-agent";
+
+/// Records Kubernetes Events about a Configuration's Instances, attached to the Configuration so that
+/// `kubectl describe akric <configuration name>` shows what happened to its devices.
+/// Each kind of Event is recorded at most once per `INSTANCE_EVENT_RATE_LIMIT_SECS` for an Instance,
+/// so that a device that keeps dropping off the network cannot flood the API server.
+pub struct EventRecorder {
+    rate_limit: Duration,
+    /// When each kind of Event was last recorded for each Instance
+    last_recorded: Mutex<HashMap<(String, &'static str), Instant>>,
+}
+
+impl EventRecorder {
+    pub fn new() -> Self {
+        EventRecorder {
+            rate_limit: Duration::from_secs(INSTANCE_EVENT_RATE_LIMIT_SECS),
+            last_recorded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns whether an Event with `reason` may be recorded for an Instance at `now`,
+    /// remembering that it was if so
+    fn should_record(&self, instance_name: &str, reason: &'static str, now: Instant) -> bool {
+        let mut last_recorded = self.last_recorded.lock().unwrap();
+        // Forget Events that no longer hold back new ones, so Instances that are gone are not remembered forever
+        let rate_limit = self.rate_limit;
+        last_recorded.retain(|_, last| now.duration_since(*last) < rate_limit);
+        let key = (instance_name.to_string(), reason);
+        match last_recorded.get(&key) {
+            Some(last) if now.duration_since(*last) < self.rate_limit => false,
+            _ => {
+                last_recorded.insert(key, now);
+                true
+            }
+        }
+    }
+
+    /// Records a Normal Event about an Instance on the Configuration that owns it.
+    /// Failing to record an Event is logged rather than returned, as Events are informational.
+    pub async fn record(
+        &self,
+        kube_interface: &impl KubeInterface,
+        configuration: &ObjectReference,
+        instance_name: &str,
+        reason: &'static str,
+        message: String,
+    ) {
+        if !self.should_record(instance_name, reason, Instant::now()) {
+            trace!(
+                "record - skipping {} Event for Instance {} recorded in the last {} seconds",
+                reason,
+                instance_name,
+                self.rate_limit.as_secs()
+            );
+            return;
+        }
+        let namespace = configuration.namespace.clone().unwrap_or_default();
+        let event = build_instance_event(configuration, instance_name, reason, message);
+        if let Err(e) = kube_interface.create_event(&event, &namespace).await {
+            error!(
+                "record - failed to record {} Event for Instance {}: {}",
+                reason, instance_name, e
+            );
+        }
+    }
+}
+
+/// Returns a reference to a Configuration for use as the object of its Instances' Events
+pub fn get_configuration_reference(name: &str, uid: &str, namespace: &str) -> ObjectReference {
+    ObjectReference {
+        api_version: Some(format!("{}/{}", API_NAMESPACE, API_VERSION)),
+        kind: Some("Configuration".to_string()),
+        name: Some(name.to_string()),
+        namespace: Some(namespace.to_string()),
+        uid: Some(uid.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Builds a Normal Event about an Instance, named like the Events kubelet records
+fn build_instance_event(
+    configuration: &ObjectReference,
+    instance_name: &str,
+    reason: &str,
+    message: String,
+) -> Event {
+    let now = Utc::now();
+    let node_name = env::var("AGENT_NODE_NAME").ok();
+    Event {
+        metadata: ObjectMeta {
+            name: Some(format!("{}.{:x}", instance_name, now.timestamp_nanos())),
+            namespace: configuration.namespace.clone(),
+            ..Default::default()
+        },
+        involved_object: configuration.clone(),
+        reason: Some(reason.to_string()),
+        message: Some(message),
+        type_: Some("Normal".to_string()),
+        source: Some(EventSource {
+            component: Some(AGENT_EVENT_COMPONENT.to_string()),
+            host: node_name.clone(),
+        }),
+        reporting_component: Some(AGENT_EVENT_COMPONENT.to_string()),
+        reporting_instance: node_name,
+        first_timestamp: Some(Time(now)),
+        last_timestamp: Some(Time(now)),
+        count: Some(1),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_record() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let event_recorder = EventRecorder::new();
+        let start = Instant::now();
+        let within_limit = start + Duration::from_secs(INSTANCE_EVENT_RATE_LIMIT_SECS - 1);
+        let after_limit = start + Duration::from_secs(INSTANCE_EVENT_RATE_LIMIT_SECS);
+        assert!(event_recorder.should_record("config-a-b494b6", INSTANCE_OFFLINE_REASON, start));
+        // The same kind of Event about the same Instance is rate limited ...
+        assert!(!event_recorder.should_record(
+            "config-a-b494b6",
+            INSTANCE_OFFLINE_REASON,
+            within_limit
+        ));
+        // ... while other kinds of Events and other Instances are not
+        assert!(event_recorder.should_record(
+            "config-a-b494b6",
+            INSTANCE_BACK_ONLINE_REASON,
+            within_limit
+        ));
+        assert!(event_recorder.should_record(
+            "config-a-359973",
+            INSTANCE_OFFLINE_REASON,
+            within_limit
+        ));
+        assert!(event_recorder.should_record(
+            "config-a-b494b6",
+            INSTANCE_OFFLINE_REASON,
+            after_limit
+        ));
+    }
+
+    #[test]
+    fn test_build_instance_event() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let configuration =
+            get_configuration_reference("config-a", "e9fbe880", "config-a-namespace");
+        let event = build_instance_event(
+            &configuration,
+            "config-a-b494b6",
+            INSTANCE_OFFLINE_REASON,
+            "went offline".to_string(),
+        );
+        assert!(event.metadata.name.unwrap().starts_with("config-a-b494b6."));
+        assert_eq!(
+            Some("config-a-namespace".to_string()),
+            event.metadata.namespace
+        );
+        assert_eq!(
+            Some("Configuration".to_string()),
+            event.involved_object.kind
+        );
+        assert_eq!(Some("config-a".to_string()), event.involved_object.name);
+        assert_eq!(Some("e9fbe880".to_string()), event.involved_object.uid);
+        assert_eq!(Some(INSTANCE_OFFLINE_REASON.to_string()), event.reason);
+        assert_eq!(Some("went offline".to_string()), event.message);
+        assert_eq!(Some("Normal".to_string()), event.type_);
+    }
+}
@@ -1,6 +1,8 @@
 pub mod config_action;
 pub mod constants;
 pub mod crictl_containers;
+pub mod debug_server;
 mod device_plugin_service;
+mod event_recorder;
 pub mod slot_reconciliation;
-mod v1beta1;
+pub mod v1beta1;
@@ -256,6 +256,9 @@ impl DevicePluginSlotReconciler {
                     shared: instance.spec.shared,
                     device_usage: modified_device_usage,
                     nodes: instance.spec.nodes.clone(),
+                    device_id: instance.spec.device_id.clone(),
+                    device_specs: instance.spec.device_specs.clone(),
+                    mounts: instance.spec.mounts.clone(),
                 };
                 trace!("reconcile - update Instance from: {:?}", &instance.spec);
                 trace!("reconcile - update Instance   to: {:?}", &modified_instance);
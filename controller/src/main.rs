@@ -2,7 +2,10 @@
 extern crate lazy_static;
 mod util;
 
-use akri_shared::akri::{metrics::run_metrics_server, API_NAMESPACE};
+use akri_shared::{
+    akri::{metrics::run_metrics_server, API_NAMESPACE},
+    os::{env_var::ActualEnvVarQuery, logging::init_logger},
+};
 use async_std::sync::Mutex;
 use prometheus::IntGaugeVec;
 use std::sync::Arc;
@@ -25,7 +28,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>
         "{} KUBERNETES_PORT found ... env_logger::init",
         API_NAMESPACE
     );
-    env_logger::try_init()?;
+    init_logger(&ActualEnvVarQuery {})?;
     println!(
         "{} KUBERNETES_PORT found ... env_logger::init finished",
         API_NAMESPACE
@@ -35,6 +38,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>
 
     let synchronization = Arc::new(Mutex::new(()));
     let instance_watch_synchronization = synchronization.clone();
+    let broker_backoff_synchronization = synchronization.clone();
     let mut tasks = Vec::new();
 
     // Start server for prometheus metrics
@@ -56,6 +60,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>
                 .unwrap();
         }
     }));
+    // Recreate broker Pods once they have finished backing off
+    tasks.push(tokio::spawn({
+        async move {
+            instance_action::handle_expired_broker_backoffs(broker_backoff_synchronization)
+                .await
+                .unwrap();
+        }
+    }));
     // Watch for node disappearance
     tasks.push(tokio::spawn({
         async move {
@@ -0,0 +1,352 @@
+use akri_shared::akri::configuration::BrokerRestartPolicy;
+use log::error;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Instance annotation that records how often each Node's broker Pod has been recreated,
+/// so that a Configuration's broker restart policy holds across controller restarts
+pub const BROKER_RESTARTS_ANNOTATION: &str = "akri.sh/broker-restarts";
+
+/// Instance annotation that records how often the Instance's broker Job has been recreated after failing
+pub const BROKER_JOB_RESTARTS_ANNOTATION: &str = "akri.sh/broker-job-restarts";
+
+/// Instance annotation that records the name of the Instance's broker Job once it has completed
+/// successfully, so that the Job is not rerun once it has been cleaned up
+pub const BROKER_JOB_COMPLETED_ANNOTATION: &str = "akri.sh/broker-job-completed";
+
+/// Restart policy for the broker Jobs of Configurations without a broker restart policy
+pub const DEFAULT_BROKER_JOB_RESTART_POLICY: BrokerRestartPolicy = BrokerRestartPolicy {
+    max_restarts: 3,
+    window_seconds: 600,
+    backoff_seconds: 300,
+};
+
+/// The broker Pod restarts recorded for a Node
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeBrokerRestarts {
+    /// When the broker Pod was recreated, in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restart_times: Vec<i64>,
+    /// UID of the last broker Pod that was recreated, so that a Pod seen again before
+    /// it is gone is not counted twice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_restarted_pod_uid: Option<String>,
+    /// When the broker Pod may be recreated again, in seconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_until: Option<i64>,
+}
+
+impl NodeBrokerRestarts {
+    /// Returns whether a backoff was recorded that has passed by `now`
+    pub fn has_expired_backoff(&self, now: i64) -> bool {
+        self.backoff_until.map_or(false, |until| until <= now)
+    }
+}
+
+/// The broker Pod restarts recorded for each Node of an Instance
+pub type BrokerRestarts = BTreeMap<String, NodeBrokerRestarts>;
+
+/// What to do with a broker Pod that needs to be recreated
+#[derive(Clone, Debug, PartialEq)]
+pub enum RestartDecision {
+    /// Recreate the broker Pod
+    Restart,
+    /// The broker Pod has used up its restarts and must not be recreated until `until`
+    BackOff { until: i64 },
+    /// The broker Pod is already backing off until `until`
+    BackingOff { until: i64 },
+}
+
+/// Reads the broker Pod restarts recorded in an Instance's annotations. A malformed
+/// annotation is logged and treated as no restarts.
+pub fn get_broker_restarts(annotations: &BTreeMap<String, String>) -> BrokerRestarts {
+    get_annotation(annotations, BROKER_RESTARTS_ANNOTATION)
+}
+
+/// Reads the broker Job restarts recorded in an Instance's annotations. A malformed
+/// annotation is logged and treated as no restarts.
+pub fn get_broker_job_restarts(annotations: &BTreeMap<String, String>) -> NodeBrokerRestarts {
+    get_annotation(annotations, BROKER_JOB_RESTARTS_ANNOTATION)
+}
+
+fn get_annotation<T: serde::de::DeserializeOwned + Default>(
+    annotations: &BTreeMap<String, String>,
+    annotation: &str,
+) -> T {
+    match annotations.get(annotation) {
+        Some(value) => serde_json::from_str(value).unwrap_or_else(|e| {
+            error!(
+                "get_annotation - ignoring malformed {} annotation {}: {}",
+                annotation, value, e
+            );
+            T::default()
+        }),
+        None => T::default(),
+    }
+}
+
+/// Decides whether a Node's broker Pod, with UID `pod_uid`, may be recreated at `now` under
+/// `policy`, recording the restart or backoff in `node_restarts`. Once a backoff has passed,
+/// the broker Pod is given a fresh set of restarts.
+pub fn decide_restart(
+    node_restarts: &mut NodeBrokerRestarts,
+    policy: &BrokerRestartPolicy,
+    pod_uid: Option<&str>,
+    now: i64,
+) -> RestartDecision {
+    if pod_uid.is_some() && node_restarts.last_restarted_pod_uid.as_deref() == pod_uid {
+        return RestartDecision::Restart;
+    }
+    if let Some(until) = node_restarts.backoff_until {
+        if now < until {
+            return RestartDecision::BackingOff { until };
+        }
+        node_restarts.backoff_until = None;
+        node_restarts.restart_times.clear();
+    }
+    let window_start = now - policy.window_seconds as i64;
+    node_restarts
+        .restart_times
+        .retain(|restart_time| *restart_time > window_start);
+    if node_restarts.restart_times.len() >= policy.max_restarts as usize {
+        let until = now + policy.backoff_seconds as i64;
+        node_restarts.backoff_until = Some(until);
+        return RestartDecision::BackOff { until };
+    }
+    node_restarts.restart_times.push(now);
+    node_restarts.last_restarted_pod_uid = pod_uid.map(|uid| uid.to_string());
+    RestartDecision::Restart
+}
+
+/// Returns whether any Node's broker Pod has finished backing off by `now`
+pub fn has_expired_backoff(restarts: &BrokerRestarts, now: i64) -> bool {
+    restarts
+        .values()
+        .any(|node_restarts| node_restarts.has_expired_backoff(now))
+}
+
+/// Creates a JSON patch that records `restarts` in an Instance with `annotations`
+pub fn create_broker_restarts_patch(
+    annotations: &BTreeMap<String, String>,
+    restarts: &BrokerRestarts,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Ok(create_annotation_patch(
+        annotations,
+        BROKER_RESTARTS_ANNOTATION,
+        serde_json::to_string(restarts)?,
+    ))
+}
+
+/// Creates a JSON patch that records broker Job `restarts` in an Instance with `annotations`
+pub fn create_broker_job_restarts_patch(
+    annotations: &BTreeMap<String, String>,
+    restarts: &NodeBrokerRestarts,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Ok(create_annotation_patch(
+        annotations,
+        BROKER_JOB_RESTARTS_ANNOTATION,
+        serde_json::to_string(restarts)?,
+    ))
+}
+
+/// Creates a JSON patch that sets `annotation` to `value` in an Instance with `annotations`
+pub fn create_annotation_patch(
+    annotations: &BTreeMap<String, String>,
+    annotation: &str,
+    value: String,
+) -> serde_json::Value {
+    // Adding the whole map would replace any other annotations, so only do so when there are none
+    if annotations.is_empty() {
+        serde_json::json!([{
+            "op": "add",
+            "path": "/metadata/annotations",
+            "value": { annotation: value }
+        }])
+    } else {
+        serde_json::json!([{
+            "op": "add",
+            "path": format!(
+                "/metadata/annotations/{}",
+                annotation.replace("~", "~0").replace("/", "~1")
+            ),
+            "value": value
+        }])
+    }
+}
+
+#[cfg(test)]
+mod broker_restart_tests {
+    use super::*;
+
+    fn policy() -> BrokerRestartPolicy {
+        BrokerRestartPolicy {
+            max_restarts: 3,
+            window_seconds: 600,
+            backoff_seconds: 300,
+        }
+    }
+
+    #[test]
+    fn test_decide_restart_cadence() {
+        let policy = policy();
+        let mut node_restarts = NodeBrokerRestarts::default();
+
+        // A broker that keeps failing is recreated max_restarts times ...
+        for (i, now) in [1000, 1010, 1020].iter().enumerate() {
+            assert_eq!(
+                RestartDecision::Restart,
+                decide_restart(
+                    &mut node_restarts,
+                    &policy,
+                    Some(&format!("pod-{}", i)),
+                    *now
+                )
+            );
+        }
+        assert_eq!(vec![1000, 1010, 1020], node_restarts.restart_times);
+
+        // ... then backs off ...
+        assert_eq!(
+            RestartDecision::BackOff { until: 1330 },
+            decide_restart(&mut node_restarts, &policy, Some("pod-3"), 1030)
+        );
+        assert_eq!(
+            RestartDecision::BackingOff { until: 1330 },
+            decide_restart(&mut node_restarts, &policy, Some("pod-3"), 1329)
+        );
+
+        // ... and is given a fresh set of restarts once the backoff has passed
+        assert_eq!(
+            RestartDecision::Restart,
+            decide_restart(&mut node_restarts, &policy, Some("pod-3"), 1330)
+        );
+        assert_eq!(vec![1330], node_restarts.restart_times);
+        assert_eq!(None, node_restarts.backoff_until);
+    }
+
+    #[test]
+    fn test_decide_restart_window() {
+        let policy = policy();
+        let mut node_restarts = NodeBrokerRestarts::default();
+        // Failures spread out wider than the window never back off
+        for (i, now) in [0, 400, 800, 1200, 1600].iter().enumerate() {
+            assert_eq!(
+                RestartDecision::Restart,
+                decide_restart(
+                    &mut node_restarts,
+                    &policy,
+                    Some(&format!("pod-{}", i)),
+                    *now
+                )
+            );
+        }
+        assert_eq!(vec![1200, 1600], node_restarts.restart_times);
+    }
+
+    #[test]
+    fn test_decide_restart_same_pod() {
+        let policy = BrokerRestartPolicy {
+            max_restarts: 1,
+            ..policy()
+        };
+        let mut node_restarts = NodeBrokerRestarts::default();
+        assert_eq!(
+            RestartDecision::Restart,
+            decide_restart(&mut node_restarts, &policy, Some("pod-0"), 0)
+        );
+        // The same failed Pod seen again is not another restart
+        assert_eq!(
+            RestartDecision::Restart,
+            decide_restart(&mut node_restarts, &policy, Some("pod-0"), 5)
+        );
+        assert_eq!(vec![0], node_restarts.restart_times);
+        assert_eq!(
+            RestartDecision::BackOff { until: 310 },
+            decide_restart(&mut node_restarts, &policy, Some("pod-1"), 10)
+        );
+    }
+
+    #[test]
+    fn test_get_broker_restarts() {
+        let mut annotations = BTreeMap::new();
+        assert!(get_broker_restarts(&annotations).is_empty());
+
+        annotations.insert(
+            BROKER_RESTARTS_ANNOTATION.to_string(),
+            r#"{"node-a":{"restartTimes":[1000],"lastRestartedPodUid":"pod-0","backoffUntil":1300}}"#
+                .to_string(),
+        );
+        let restarts = get_broker_restarts(&annotations);
+        assert_eq!(
+            &NodeBrokerRestarts {
+                restart_times: vec![1000],
+                last_restarted_pod_uid: Some("pod-0".to_string()),
+                backoff_until: Some(1300),
+            },
+            restarts.get("node-a").unwrap()
+        );
+        assert!(!has_expired_backoff(&restarts, 1299));
+        assert!(has_expired_backoff(&restarts, 1300));
+
+        annotations.insert(BROKER_RESTARTS_ANNOTATION.to_string(), "{".to_string());
+        assert!(get_broker_restarts(&annotations).is_empty());
+    }
+
+    #[test]
+    fn test_get_broker_job_restarts() {
+        let mut annotations = BTreeMap::new();
+        assert_eq!(
+            NodeBrokerRestarts::default(),
+            get_broker_job_restarts(&annotations)
+        );
+
+        annotations.insert(
+            BROKER_JOB_RESTARTS_ANNOTATION.to_string(),
+            r#"{"restartTimes":[1000],"backoffUntil":1300}"#.to_string(),
+        );
+        let restarts = get_broker_job_restarts(&annotations);
+        assert_eq!(vec![1000], restarts.restart_times);
+        assert!(!restarts.has_expired_backoff(1299));
+        assert!(restarts.has_expired_backoff(1300));
+
+        annotations.insert(BROKER_JOB_RESTARTS_ANNOTATION.to_string(), "{".to_string());
+        assert_eq!(
+            NodeBrokerRestarts::default(),
+            get_broker_job_restarts(&annotations)
+        );
+    }
+
+    #[test]
+    fn test_create_broker_restarts_patch() {
+        let mut restarts = BrokerRestarts::new();
+        restarts.insert(
+            "node-a".to_string(),
+            NodeBrokerRestarts {
+                restart_times: vec![1000],
+                ..Default::default()
+            },
+        );
+        let value = r#"{"node-a":{"restartTimes":[1000]}}"#;
+
+        let mut annotations = BTreeMap::new();
+        assert_eq!(
+            serde_json::json!([{
+                "op": "add",
+                "path": "/metadata/annotations",
+                "value": { "akri.sh/broker-restarts": value }
+            }]),
+            create_broker_restarts_patch(&annotations, &restarts).unwrap()
+        );
+
+        annotations.insert("other".to_string(), "annotation".to_string());
+        assert_eq!(
+            serde_json::json!([{
+                "op": "add",
+                "path": "/metadata/annotations/akri.sh~1broker-restarts",
+                "value": value
+            }]),
+            create_broker_restarts_patch(&annotations, &restarts).unwrap()
+        );
+    }
+}
@@ -1,20 +1,29 @@
 use super::super::BROKER_POD_COUNT_METRIC;
-use super::{pod_action::PodAction, pod_action::PodActionInfo};
+use super::{
+    broker_restart, broker_restart::RestartDecision, pod_action::PodAction,
+    pod_action::PodActionInfo,
+};
 use akri_shared::{
     akri::{
-        configuration::KubeAkriConfig, instance::KubeAkriInstance, AKRI_PREFIX, API_INSTANCES,
-        API_NAMESPACE, API_VERSION,
+        configuration::{BrokerRestartPolicy, KubeAkriConfig},
+        instance::KubeAkriInstance,
+        AKRI_PREFIX, API_INSTANCES, API_NAMESPACE, API_VERSION,
     },
     k8s,
     k8s::{
+        job,
+        job::{JOB_COMPLETE_CONDITION, JOB_FAILED_CONDITION},
         pod,
         pod::{AKRI_INSTANCE_LABEL_NAME, AKRI_TARGET_NODE_LABEL_NAME},
         KubeInterface, OwnershipInfo, OwnershipType,
     },
 };
 use async_std::sync::Mutex;
+use chrono::Utc;
 use futures::StreamExt;
-use k8s_openapi::api::core::v1::{PodSpec, PodStatus};
+use k8s_openapi::api::batch::v1::JobSpec;
+use k8s_openapi::api::core::v1::{Event, EventSource, ObjectReference, PodSpec, PodStatus};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
 use kube::api::{Informer, Object, RawApi, WatchEvent};
 use log::{error, info, trace};
 use std::collections::HashMap;
@@ -24,6 +33,14 @@ use std::sync::Arc;
 pub const PENDING_POD_GRACE_PERIOD_MINUTES: i64 = 5;
 /// Length of time a Pod can be in an error state before we retry
 pub const FAILED_POD_GRACE_PERIOD_MINUTES: i64 = 0;
+/// Reason of the Event recorded when an Instance's broker Pod spec refers to properties it does not have
+pub const BROKER_POD_TEMPLATE_ERROR_REASON: &str = "BrokerPodTemplateError";
+/// Reason of the Event recorded when a broker Pod has been recreated too often and is backing off
+pub const BROKER_RESTART_BACKOFF_REASON: &str = "BrokerRestartBackOff";
+/// Length of time to sleep between checks for broker Pods that have finished backing off
+pub const BROKER_BACKOFF_CHECK_DELAY_SECS: u64 = 30;
+/// Component the controller's Events are reported as coming from
+const CONTROLLER_EVENT_COMPONENT: &str = "akri-controller";
 
 /// Instance action types
 ///
@@ -54,6 +71,45 @@ pub async fn do_instance_watch(
     internal_do_instance_watch(&synchronization, &k8s::create_kube_interface()).await
 }
 
+/// This periodically rechecks Instances whose broker Pods or Jobs have finished backing off,
+/// so that they are recreated without waiting for the Instance to change
+pub async fn handle_expired_broker_backoffs(
+    synchronization: Arc<Mutex<()>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let kube_interface = k8s::create_kube_interface();
+    loop {
+        tokio::time::delay_for(std::time::Duration::from_secs(
+            BROKER_BACKOFF_CHECK_DELAY_SECS,
+        ))
+        .await;
+        let _lock = synchronization.lock().await;
+        trace!("handle_expired_broker_backoffs - aquired sync lock");
+        internal_handle_expired_broker_backoffs(&kube_interface).await?;
+    }
+}
+
+/// This handles each Instance with a broker Pod or Job that has finished backing off
+async fn internal_handle_expired_broker_backoffs(
+    kube_interface: &impl KubeInterface,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let now = Utc::now().timestamp();
+    let instances = kube_interface.get_instances().await?;
+    for instance in instances.items {
+        let restarts = broker_restart::get_broker_restarts(&instance.metadata.annotations);
+        let job_restarts = broker_restart::get_broker_job_restarts(&instance.metadata.annotations);
+        if broker_restart::has_expired_backoff(&restarts, now)
+            || job_restarts.has_expired_backoff(now)
+        {
+            trace!(
+                "internal_handle_expired_broker_backoffs - backoff passed for Instance {}",
+                instance.metadata.name
+            );
+            handle_instance_change(&instance, &InstanceAction::Update, kube_interface).await?;
+        }
+    }
+    Ok(())
+}
+
 /// This invokes an internal method that watches for Instance events
 async fn internal_handle_existing_instances(
     kube_interface: &impl KubeInterface,
@@ -151,11 +207,13 @@ async fn handle_instance(
 /// * the node is described by node_name
 /// * the protocol (or capability) is described by instance_name and namespace
 /// * what to do with the broker Pod is described by action
+/// * the existing broker Pod, if any, is described by pod_uid
 #[derive(Clone, Debug, PartialEq)]
 struct PodContext {
     node_name: Option<String>,
     namespace: Option<String>,
     action: PodAction,
+    pod_uid: Option<String>,
 }
 
 /// This finds what to do with a given broker Pod based on its current state and
@@ -222,6 +280,7 @@ fn determine_action_for_pod(
         node_name: Some(node_to_run_pod_on.to_string()),
         namespace: k8s_pod.metadata.namespace.clone(),
         action: PodAction::NoAction,
+        pod_uid: k8s_pod.metadata.uid.clone(),
     };
 
     // Early exits above ensure unwrap will not panic
@@ -310,6 +369,7 @@ mod handle_deletion_work_tests {
             node_name: None,
             namespace: Some("namespace".into()),
             action: PodAction::NoAction,
+            pod_uid: None,
         };
 
         assert!(handle_deletion_work(
@@ -332,6 +392,7 @@ mod handle_deletion_work_tests {
             node_name: Some("node-a".into()),
             namespace: None,
             action: PodAction::NoAction,
+            pod_uid: None,
         };
 
         assert!(handle_deletion_work(
@@ -347,14 +408,66 @@ mod handle_deletion_work_tests {
     }
 }
 
+/// Records a Warning Event on an Instance. Failing to record the Event is logged rather than returned.
+async fn record_instance_warning(
+    instance_name: &str,
+    instance_uid: &str,
+    instance_namespace: &str,
+    reason: &str,
+    message: String,
+    kube_interface: &impl KubeInterface,
+) {
+    let now = Utc::now();
+    let event = Event {
+        metadata: ObjectMeta {
+            name: Some(format!("{}.{:x}", instance_name, now.timestamp_nanos())),
+            namespace: Some(instance_namespace.to_string()),
+            ..Default::default()
+        },
+        involved_object: ObjectReference {
+            api_version: Some(format!("{}/{}", API_NAMESPACE, API_VERSION)),
+            kind: Some("Instance".to_string()),
+            name: Some(instance_name.to_string()),
+            namespace: Some(instance_namespace.to_string()),
+            uid: Some(instance_uid.to_string()),
+            ..Default::default()
+        },
+        reason: Some(reason.to_string()),
+        message: Some(message),
+        type_: Some("Warning".to_string()),
+        source: Some(EventSource {
+            component: Some(CONTROLLER_EVENT_COMPONENT.to_string()),
+            host: None,
+        }),
+        reporting_component: Some(CONTROLLER_EVENT_COMPONENT.to_string()),
+        first_timestamp: Some(Time(now)),
+        last_timestamp: Some(Time(now)),
+        count: Some(1),
+        ..Default::default()
+    };
+    if let Err(e) = kube_interface
+        .create_event(&event, instance_namespace)
+        .await
+    {
+        error!(
+            "record_instance_warning - failed to record {} Event for Instance {}: {}",
+            reason, instance_name, e
+        );
+    }
+}
+
 /// This handles Instance addition event by creating the
 /// broker Pod, the broker Service, and the capability Service.
+/// `{{PROPERTY_NAME}}` placeholders in the broker Pod spec are filled in from the Instance's properties.
+/// If the spec refers to a property the Instance does not have, no Pod is created and a Warning Event
+/// is recorded on the Instance instead.
 async fn handle_addition_work(
     instance_name: &str,
     instance_uid: &str,
     instance_namespace: &str,
     instance_class_name: &str,
     instance_shared: bool,
+    instance_properties: &HashMap<String, String>,
     new_node: &str,
     instance_configuration: &KubeAkriConfig,
     kube_interface: &impl KubeInterface,
@@ -365,6 +478,26 @@ async fn handle_addition_work(
     );
 
     if let Some(broker_pod_spec) = &instance_configuration.spec.broker_pod_spec {
+        let broker_pod_spec =
+            match pod::render_broker_pod_spec(broker_pod_spec, instance_properties) {
+                Ok(broker_pod_spec) => broker_pod_spec,
+                Err(e) => {
+                    error!(
+                        "handle_addition_work - not creating Pod for Instance {} on Node {}: {}",
+                        instance_name, new_node, e
+                    );
+                    record_instance_warning(
+                        instance_name,
+                        instance_uid,
+                        instance_namespace,
+                        BROKER_POD_TEMPLATE_ERROR_REASON,
+                        format!("Could not create broker Pod for Node {}: {}", new_node, e),
+                        kube_interface,
+                    )
+                    .await;
+                    return Ok(());
+                }
+            };
         let capability_id = format!("{}/{}", AKRI_PREFIX, instance_name);
         let new_pod = pod::create_new_pod_from_spec(
             &instance_namespace,
@@ -395,6 +528,374 @@ async fn handle_addition_work(
     Ok(())
 }
 
+/// This handles an Instance of a Configuration with a broker Job spec, which is
+/// serviced by a single Job rather than a broker Pod on each Node. A missing Job
+/// is created. A Job that completed successfully is not rerun; its completion is
+/// recorded on the Instance and it is released from the Instance, so that it is kept
+/// for its `ttlSecondsAfterFinished` even once the Instance is gone. A failed Job is
+/// replaced as the broker restart policy allows, or the default Job restart policy
+/// if the Configuration has none.
+async fn handle_job_work(
+    instance: &KubeAkriInstance,
+    instance_uid: &str,
+    instance_namespace: &str,
+    broker_job_spec: &JobSpec,
+    broker_restart_policy: Option<&BrokerRestartPolicy>,
+    kube_interface: &impl KubeInterface,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let instance_name = &instance.metadata.name;
+    trace!("handle_job_work - enter for Instance {}", instance_name);
+    let instance_jobs = kube_interface
+        .find_jobs_with_label(&format!("{}={}", AKRI_INSTANCE_LABEL_NAME, instance_name))
+        .await?;
+    let mut completed = instance
+        .metadata
+        .annotations
+        .contains_key(broker_restart::BROKER_JOB_COMPLETED_ANNOTATION);
+    let mut job_exists = false;
+    let mut failed_job = None;
+    for existing_job in &instance_jobs.items {
+        let job_namespace = existing_job
+            .metadata
+            .namespace
+            .as_deref()
+            .unwrap_or(instance_namespace);
+        if job::has_job_condition(existing_job.status.as_ref(), JOB_COMPLETE_CONDITION) {
+            if !completed {
+                record_job_completion(
+                    instance,
+                    instance_namespace,
+                    &existing_job.metadata.name,
+                    kube_interface,
+                )
+                .await;
+                completed = true;
+            }
+            if !existing_job.metadata.ownerReferences.is_empty() {
+                trace!(
+                    "handle_job_work - releasing completed Job {}",
+                    existing_job.metadata.name
+                );
+                kube_interface
+                    .release_job(&existing_job.metadata.name, job_namespace)
+                    .await?;
+            }
+        } else if job::has_job_condition(existing_job.status.as_ref(), JOB_FAILED_CONDITION) {
+            failed_job = Some((existing_job, job_namespace));
+        } else {
+            job_exists = true;
+        }
+    }
+    if completed {
+        trace!("handle_job_work - exit, Job already completed");
+        return Ok(());
+    }
+    if job_exists {
+        trace!("handle_job_work - exit, Job already exists");
+        return Ok(());
+    }
+    if let Some((failed_job, job_namespace)) = failed_job {
+        let broker_restart_policy =
+            broker_restart_policy.unwrap_or(&broker_restart::DEFAULT_BROKER_JOB_RESTART_POLICY);
+        if !apply_broker_job_restart_policy(
+            instance,
+            instance_uid,
+            instance_namespace,
+            failed_job.metadata.uid.as_deref(),
+            broker_restart_policy,
+            kube_interface,
+        )
+        .await
+        {
+            return Ok(());
+        }
+        trace!(
+            "handle_job_work - removing failed Job {}",
+            failed_job.metadata.name
+        );
+        kube_interface
+            .remove_job(&failed_job.metadata.name, job_namespace)
+            .await?;
+    }
+
+    let mut broker_job_spec = broker_job_spec.clone();
+    if let Some(pod_spec) = &broker_job_spec.template.spec {
+        match pod::render_broker_pod_spec(pod_spec, &instance.spec.metadata) {
+            Ok(rendered) => broker_job_spec.template.spec = Some(rendered),
+            Err(e) => {
+                error!(
+                    "handle_job_work - not creating Job for Instance {}: {}",
+                    instance_name, e
+                );
+                record_instance_warning(
+                    instance_name,
+                    instance_uid,
+                    instance_namespace,
+                    BROKER_POD_TEMPLATE_ERROR_REASON,
+                    format!("Could not create broker Job: {}", e),
+                    kube_interface,
+                )
+                .await;
+                return Ok(());
+            }
+        }
+    }
+    let capability_id = format!("{}/{}", AKRI_PREFIX, instance_name);
+    let new_job = job::create_new_job_from_spec(
+        &instance_namespace,
+        &instance_name,
+        &instance.spec.configuration_name,
+        OwnershipInfo::new(
+            OwnershipType::Instance,
+            instance_name.to_string(),
+            instance_uid.to_string(),
+        ),
+        &capability_id,
+        &broker_job_spec,
+    )?;
+    trace!("handle_job_work - New job spec={:?}", new_job);
+    kube_interface
+        .create_job(&new_job, &instance_namespace)
+        .await?;
+    trace!("handle_job_work - job::create_job succeeded");
+    Ok(())
+}
+
+/// This records on an Instance that its broker Job `job_name` completed successfully.
+/// Failing to record it is logged, and it is recorded again on the next Instance event
+/// if the Job is still around.
+async fn record_job_completion(
+    instance: &KubeAkriInstance,
+    instance_namespace: &str,
+    job_name: &str,
+    kube_interface: &impl KubeInterface,
+) {
+    let instance_name = &instance.metadata.name;
+    info!(
+        "record_job_completion - broker Job {} for Instance {} completed",
+        job_name, instance_name
+    );
+    let patch = broker_restart::create_annotation_patch(
+        &instance.metadata.annotations,
+        broker_restart::BROKER_JOB_COMPLETED_ANNOTATION,
+        job_name.to_string(),
+    );
+    if let Err(e) = kube_interface
+        .patch_instance(&patch, instance_name, instance_namespace)
+        .await
+    {
+        error!(
+            "record_job_completion - failed to record completion of Job {} for Instance {}: {}",
+            job_name, instance_name, e
+        );
+    }
+}
+
+/// This applies a broker restart policy to an Instance's failed broker Job, with UID `job_uid`,
+/// returning whether it may be recreated. A Job that has been recreated too often is left as it
+/// is until its backoff has passed, and a Warning Event is recorded on the Instance when the
+/// backoff starts. The restarts are recorded in an Instance annotation.
+async fn apply_broker_job_restart_policy(
+    instance: &KubeAkriInstance,
+    instance_uid: &str,
+    instance_namespace: &str,
+    job_uid: Option<&str>,
+    broker_restart_policy: &BrokerRestartPolicy,
+    kube_interface: &impl KubeInterface,
+) -> bool {
+    let instance_name = &instance.metadata.name;
+    let now = Utc::now().timestamp();
+    let previous_restarts = broker_restart::get_broker_job_restarts(&instance.metadata.annotations);
+    let mut restarts = previous_restarts.clone();
+    let restart = match broker_restart::decide_restart(
+        &mut restarts,
+        broker_restart_policy,
+        job_uid,
+        now,
+    ) {
+        RestartDecision::Restart => true,
+        RestartDecision::BackingOff { until } => {
+            trace!(
+                "apply_broker_job_restart_policy - broker Job for Instance {} is backing off for another {} seconds",
+                instance_name,
+                until - now
+            );
+            false
+        }
+        RestartDecision::BackOff { until } => {
+            info!(
+                "apply_broker_job_restart_policy - broker Job for Instance {} has been recreated {} times, backing off for {} seconds",
+                instance_name,
+                broker_restart_policy.max_restarts,
+                until - now
+            );
+            record_instance_warning(
+                instance_name,
+                instance_uid,
+                instance_namespace,
+                BROKER_RESTART_BACKOFF_REASON,
+                format!(
+                    "Broker Job was recreated {} times within {} seconds, not recreating it for {} seconds",
+                    broker_restart_policy.max_restarts,
+                    broker_restart_policy.window_seconds,
+                    broker_restart_policy.backoff_seconds
+                ),
+                kube_interface,
+            )
+            .await;
+            false
+        }
+    };
+    if restarts == previous_restarts {
+        return restart;
+    }
+    let result =
+        broker_restart::create_broker_job_restarts_patch(&instance.metadata.annotations, &restarts);
+    let result = match result {
+        Ok(patch) => {
+            kube_interface
+                .patch_instance(&patch, instance_name, instance_namespace)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!(
+            "apply_broker_job_restart_policy - failed to record broker Job restarts for Instance {}: {}",
+            instance_name, e
+        );
+    }
+    restart
+}
+
+/// This handles Instance deletion for broker Jobs. Jobs that have not completed
+/// successfully are deleted along with their pods, while completed Jobs are
+/// released from the Instance and left for their `ttlSecondsAfterFinished`.
+async fn handle_job_deletion_work(
+    instance_name: &str,
+    instance_namespace: &str,
+    kube_interface: &impl KubeInterface,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let instance_jobs = kube_interface
+        .find_jobs_with_label(&format!("{}={}", AKRI_INSTANCE_LABEL_NAME, instance_name))
+        .await?;
+    for existing_job in &instance_jobs.items {
+        let job_namespace = existing_job
+            .metadata
+            .namespace
+            .as_deref()
+            .unwrap_or(instance_namespace);
+        if job::has_job_condition(existing_job.status.as_ref(), JOB_COMPLETE_CONDITION) {
+            if !existing_job.metadata.ownerReferences.is_empty() {
+                kube_interface
+                    .release_job(&existing_job.metadata.name, job_namespace)
+                    .await?;
+            }
+        } else {
+            kube_interface
+                .remove_job(&existing_job.metadata.name, job_namespace)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// This applies a Configuration's broker restart policy to the broker Pods that are about to be
+/// recreated. A Pod that has been recreated too often is left as it is until its backoff has
+/// passed, and a Warning Event is recorded on the Instance when the backoff starts. The restarts
+/// are recorded in an Instance annotation, so the policy holds across controller restarts.
+async fn apply_broker_restart_policy(
+    instance: &KubeAkriInstance,
+    instance_uid: &str,
+    instance_namespace: &str,
+    broker_restart_policy: &BrokerRestartPolicy,
+    nodes_to_act_on: &mut HashMap<String, PodContext>,
+    kube_interface: &impl KubeInterface,
+) {
+    let instance_name = &instance.metadata.name;
+    let now = Utc::now().timestamp();
+    let previous_restarts = broker_restart::get_broker_restarts(&instance.metadata.annotations);
+    let mut restarts = previous_restarts.clone();
+    for (node, context) in nodes_to_act_on
+        .iter_mut()
+        .filter(|(_, context)| context.action == PodAction::RemoveAndAdd)
+    {
+        let node_restarts = restarts.entry(node.to_string()).or_default();
+        match broker_restart::decide_restart(
+            node_restarts,
+            broker_restart_policy,
+            context.pod_uid.as_deref(),
+            now,
+        ) {
+            RestartDecision::Restart => {}
+            RestartDecision::BackingOff { until } => {
+                trace!(
+                    "apply_broker_restart_policy - broker Pod for Instance {} on Node {} is backing off for another {} seconds",
+                    instance_name,
+                    node,
+                    until - now
+                );
+                context.action = PodAction::NoAction;
+            }
+            RestartDecision::BackOff { until } => {
+                info!(
+                    "apply_broker_restart_policy - broker Pod for Instance {} on Node {} has been recreated {} times, backing off for {} seconds",
+                    instance_name,
+                    node,
+                    broker_restart_policy.max_restarts,
+                    until - now
+                );
+                context.action = PodAction::NoAction;
+                record_instance_warning(
+                    instance_name,
+                    instance_uid,
+                    instance_namespace,
+                    BROKER_RESTART_BACKOFF_REASON,
+                    format!(
+                        "Broker Pod for Node {} was recreated {} times within {} seconds, not recreating it for {} seconds",
+                        node,
+                        broker_restart_policy.max_restarts,
+                        broker_restart_policy.window_seconds,
+                        broker_restart_policy.backoff_seconds
+                    ),
+                    kube_interface,
+                )
+                .await;
+            }
+        }
+    }
+    // Forget Nodes the Instance is no longer seen by and backoffs that passed without the
+    // broker Pod needing to be recreated
+    let restarts: broker_restart::BrokerRestarts = restarts
+        .into_iter()
+        .filter(|(node, node_restarts)| {
+            instance.spec.nodes.contains(node)
+                && node_restarts
+                    .backoff_until
+                    .map_or(true, |until| until > now)
+        })
+        .collect();
+    if restarts == previous_restarts {
+        return;
+    }
+    let result =
+        broker_restart::create_broker_restarts_patch(&instance.metadata.annotations, &restarts);
+    let result = match result {
+        Ok(patch) => {
+            kube_interface
+                .patch_instance(&patch, instance_name, instance_namespace)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!(
+            "apply_broker_restart_policy - failed to record broker restarts for Instance {}: {}",
+            instance_name, e
+        );
+    }
+}
+
 /// Handle Instance change by watching for node
 /// disappearances, starting broker Pods/Services that are missing,
 /// and stopping Pods/Services that are no longer needed.
@@ -416,6 +917,12 @@ pub async fn handle_instance_change(
         .as_ref()
         .ok_or(format!("UID not found for instance: {}", &instance_name))?;
 
+    // Jobs are only run for Instances of Configurations with a broker Job spec, but by the time
+    // an Instance is removed its Configuration may be gone, so look for Jobs regardless
+    if action == &InstanceAction::Remove {
+        handle_job_deletion_work(&instance_name, &instance_namespace, kube_interface).await?;
+    }
+
     // If InstanceAction::Remove, assume all nodes require PodAction::NoAction (reflect that there is no running Pod unless we find one)
     // Otherwise, assume all nodes require PodAction::Add (reflect that there is no running Pod, unless we find one)
     let default_action = match action {
@@ -433,6 +940,7 @@ pub async fn handle_instance_change(
                     node_name: None,
                     namespace: None,
                     action: default_action,
+                    pod_uid: None,
                 },
             )
         })
@@ -468,6 +976,56 @@ pub async fn handle_instance_change(
         nodes_to_act_on
     );
 
+    let needs_configuration = nodes_to_act_on.values().any(|context| {
+        ((context.action) == PodAction::Add) | ((context.action) == PodAction::RemoveAndAdd)
+    });
+    let instance_configuration_option = if needs_configuration {
+        // Only retrieve Config if needed
+        trace!(
+            "handle_instance_change - find configuration for {:?}",
+            &instance.spec.configuration_name
+        );
+        match kube_interface
+            .find_configuration(&instance.spec.configuration_name, &instance_namespace)
+            .await
+        {
+            Ok(config) => {
+                trace!(
+                    "handle_instance_change - found configuration for {:?}",
+                    &config.metadata.name
+                );
+                Some(config)
+            }
+            _ => {
+                // In this scenario, a configuration has been deleted without a Akri Agent deleting the associated Instances.
+                // Furthermore, Akri Agent is still modifying the Instances. This should not happen beacuse Agent
+                // is designed to shutdown when it's Configuration watcher fails.
+                error!(
+                    "handle_instance_change - no configuration found for {} yet instance {} exists - check that device plugin is running propertly",
+                    &instance.spec.configuration_name, &instance.metadata.name
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Some(broker_restart_policy) = instance_configuration_option
+        .as_ref()
+        .and_then(|config| config.spec.broker_restart_policy.as_ref())
+    {
+        apply_broker_restart_policy(
+            instance,
+            &instance_uid,
+            &instance_namespace,
+            broker_restart_policy,
+            &mut nodes_to_act_on,
+            kube_interface,
+        )
+        .await;
+    }
+
     // Iterate over nodes_to_act_on where value == (PodAction::Remove | PodAction::RemoveAndAdd)
     for (node_to_delete_pod, context) in nodes_to_act_on.iter().filter(|&(_, v)| {
         ((v.action) == PodAction::Remove) | ((v.action) == PodAction::RemoveAndAdd)
@@ -483,6 +1041,10 @@ pub async fn handle_instance_change(
         .await?
     }
 
+    if needs_configuration && instance_configuration_option.is_none() {
+        return Ok(());
+    }
+
     let nodes_to_add = nodes_to_act_on
         .iter()
         .filter_map(|(node, context)| {
@@ -495,36 +1057,25 @@ pub async fn handle_instance_change(
         })
         .collect::<Vec<String>>();
 
-    let instance_configuration_option = if !nodes_to_add.is_empty() {
-        // Only retrieve Config if needed
-        trace!(
-            "handle_instance_change - find configuration for {:?}",
-            &instance.spec.configuration_name
-        );
-        let instance_configuration = match kube_interface
-            .find_configuration(&instance.spec.configuration_name, &instance_namespace)
-            .await
-        {
-            Ok(config) => config,
-            _ => {
-                // In this scenario, a configuration has been deleted without a Akri Agent deleting the associated Instances.
-                // Furthermore, Akri Agent is still modifying the Instances. This should not happen beacuse Agent
-                // is designed to shutdown when it's Configuration watcher fails.
-                error!(
-                    "handle_instance_change - no configuration found for {} yet instance {} exists - check that device plugin is running propertly",
-                    &instance.spec.configuration_name, &instance.metadata.name
-                );
-                return Ok(());
-            }
-        };
-        trace!(
-            "handle_instance_change - found configuration for {:?}",
-            &instance_configuration.metadata.name
-        );
-        Some(instance_configuration)
-    } else {
-        None
-    };
+    // An Instance of a Configuration with a broker Job spec has no broker Pods, so every Node
+    // it is seen by is left to add. One Job services the Instance on whichever of them it is
+    // scheduled to.
+    if let Some(config) = instance_configuration_option
+        .as_ref()
+        .filter(|config| config.spec.broker_job_spec.is_some())
+    {
+        handle_job_work(
+            instance,
+            &instance_uid,
+            &instance_namespace,
+            config.spec.broker_job_spec.as_ref().unwrap(),
+            config.spec.broker_restart_policy.as_ref(),
+            kube_interface,
+        )
+        .await?;
+        trace!("handle_instance_change - exit");
+        return Ok(());
+    }
 
     // Iterate over nodes_to_act_on where value == (PodAction::Add | PodAction::RemoveAndAdd)
     for new_node in nodes_to_add {
@@ -534,6 +1085,7 @@ pub async fn handle_instance_change(
             &instance_namespace,
             &instance.spec.configuration_name,
             instance.spec.shared,
+            &instance.spec.metadata,
             &new_node,
             &instance_configuration_option.as_ref().unwrap(),
             kube_interface,
@@ -549,7 +1101,7 @@ pub async fn handle_instance_change(
 #[cfg(test)]
 mod handle_instance_tests {
     use super::super::shared_test_utils::config_for_tests;
-    use super::super::shared_test_utils::config_for_tests::PodList;
+    use super::super::shared_test_utils::config_for_tests::{JobList, PodList};
     use super::*;
     use akri_shared::{
         akri::instance::KubeAkriInstance,
@@ -790,6 +1342,17 @@ mod handle_instance_tests {
         trace!("run_handle_instance_change_test enter");
         let instance_json = file::read_file_to_string(instance_file);
         let instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
+        // Removing any Instance checks for broker Jobs to clean up
+        if action == &InstanceAction::Remove {
+            let job_selector = format!("{}={}", AKRI_INSTANCE_LABEL_NAME, instance.metadata.name);
+            mock.expect_find_jobs_with_label()
+                .times(1)
+                .withf(move |selector| selector == job_selector)
+                .returning(|_| {
+                    let jobs_json = file::read_file_to_string("../test/json/empty-list.json");
+                    Ok(serde_json::from_str(&jobs_json).unwrap())
+                });
+        }
         handle_instance(
             match action {
                 InstanceAction::Add => WatchEvent::Added(instance),
@@ -991,11 +1554,587 @@ mod handle_instance_tests {
         run_handle_instance_change_test(&mut mock, &instance_file, &InstanceAction::Update).await;
     }
 
+    /// Loads config-a with its broker container given `args`
+    fn load_config_a_with_broker_args(args: Vec<&str>) -> KubeAkriConfig {
+        let config_json = file::read_file_to_string("../test/json/config-a.json");
+        let mut config: KubeAkriConfig = serde_json::from_str(&config_json).unwrap();
+        config.spec.broker_pod_spec.as_mut().unwrap().containers[0].args =
+            Some(args.into_iter().map(|arg| arg.to_string()).collect());
+        config
+    }
+
+    #[tokio::test]
+    async fn test_handle_addition_work_renders_broker_pod_spec() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let config = load_config_a_with_broker_args(vec!["--url={{RTSP_URL}}"]);
+        let mut properties = HashMap::new();
+        properties.insert("RTSP_URL".to_string(), "rtsp://10.0.0.1/stream".to_string());
+        let mut mock = MockKubeInterface::new();
+        mock.expect_create_pod()
+            .times(1)
+            .withf(|pod_to_create, namespace| {
+                pod_to_create.spec.as_ref().unwrap().containers[0].args
+                    == Some(vec!["--url=rtsp://10.0.0.1/stream".to_string()])
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _| Ok(()));
+        mock.expect_create_event().times(0);
+        handle_addition_work(
+            "config-a-b494b6",
+            "abcdegfh-ijkl-mnop-qrst-uvwxyz012345",
+            "config-a-namespace",
+            "config-a",
+            false,
+            &properties,
+            "node-template",
+            &config,
+            &mock,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handle_addition_work_missing_broker_property() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let config = load_config_a_with_broker_args(vec!["--url={{RTSP_URL}}"]);
+        let mut mock = MockKubeInterface::new();
+        mock.expect_create_pod().times(0);
+        mock.expect_create_event()
+            .times(1)
+            .withf(|event: &Event, namespace: &str| {
+                event.reason.as_deref() == Some(BROKER_POD_TEMPLATE_ERROR_REASON)
+                    && event.type_.as_deref() == Some("Warning")
+                    && event.involved_object.kind.as_deref() == Some("Instance")
+                    && event.involved_object.name.as_deref() == Some("config-a-b494b6")
+                    && event
+                        .message
+                        .as_ref()
+                        .map_or(false, |message| message.contains("RTSP_URL"))
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _| Ok(()));
+        // The missing property is reported on the Instance rather than failing the Instance watch
+        handle_addition_work(
+            "config-a-b494b6",
+            "abcdegfh-ijkl-mnop-qrst-uvwxyz012345",
+            "config-a-namespace",
+            "config-a",
+            false,
+            &HashMap::new(),
+            "node-template",
+            &config,
+            &mock,
+        )
+        .await
+        .unwrap();
+    }
+
+    /// Expects the Jobs of config-a-b494b6 to be looked up, finding its Job with
+    /// `condition` set or, without one, still running
+    fn configure_find_config_a_b494b6_job(
+        mock: &mut MockKubeInterface,
+        condition: Option<&'static str>,
+    ) {
+        mock.expect_find_jobs_with_label()
+            .times(1)
+            .withf(|selector| selector == "akri.sh/instance=config-a-b494b6")
+            .returning(move |_| {
+                let jobs_json = file::read_file_to_string(
+                    "../test/json/running-job-list-for-config-a-local.json",
+                );
+                let status_adjusted_json = match condition {
+                    Some(condition) => jobs_json.replace(
+                        "\"active\": 1,",
+                        &format!(
+                            "\"conditions\": [{{\"type\": \"{}\", \"status\": \"True\"}}],",
+                            condition
+                        ),
+                    ),
+                    None => jobs_json,
+                };
+                let jobs: JobList = serde_json::from_str(&status_adjusted_json).unwrap();
+                Ok(jobs)
+            });
+    }
+
+    /// Expects the Instance's Pods to be looked up, finding none, and config-a with a broker Job spec to be found
+    fn configure_find_pods_and_job_config(mock: &mut MockKubeInterface) {
+        config_for_tests::configure_find_pods(
+            mock,
+            "akri.sh/instance=config-a-b494b6",
+            "../test/json/empty-list.json",
+            false,
+        );
+        config_for_tests::configure_find_config(
+            mock,
+            "config-a",
+            "config-a-namespace",
+            "../test/json/config-a-job.json",
+            false,
+        );
+    }
+
+    async fn run_handle_job_instance_change_test(
+        mock: &mut MockKubeInterface,
+        action: &'static InstanceAction,
+    ) {
+        run_handle_job_instance_change_test_with_annotation(mock, action, None).await;
+    }
+
+    /// Runs `action` on config-a-b494b6 with `annotation` set on it
+    async fn run_handle_job_instance_change_test_with_annotation(
+        mock: &mut MockKubeInterface,
+        action: &'static InstanceAction,
+        annotation: Option<(&str, String)>,
+    ) {
+        let instance_json = file::read_file_to_string("../test/json/local-instance.json");
+        let mut instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
+        if let Some((annotation, value)) = annotation {
+            instance
+                .metadata
+                .annotations
+                .insert(annotation.to_string(), value);
+        }
+        handle_instance_change(&instance, action, mock)
+            .await
+            .unwrap();
+    }
+
+    /// Expects the broker Job restarts recorded in config-a-b494b6 to be patched to ones that satisfy `check`
+    fn configure_patch_broker_job_restarts(
+        mock: &mut MockKubeInterface,
+        check: impl Fn(&broker_restart::NodeBrokerRestarts) -> bool + Send + 'static,
+    ) {
+        mock.expect_patch_instance()
+            .times(1)
+            .withf(move |patch, name, namespace| {
+                let restarts_json = patch[0]["value"]
+                    [broker_restart::BROKER_JOB_RESTARTS_ANNOTATION]
+                    .as_str()
+                    .unwrap();
+                let restarts: broker_restart::NodeBrokerRestarts =
+                    serde_json::from_str(restarts_json).unwrap();
+                patch[0]["path"] == "/metadata/annotations"
+                    && check(&restarts)
+                    && name == "config-a-b494b6"
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _, _| Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_for_add_job_instance() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_pods_and_job_config(&mut mock);
+        config_for_tests::configure_find_jobs(
+            &mut mock,
+            "akri.sh/instance=config-a-b494b6",
+            "../test/json/empty-list.json",
+            false,
+        );
+        mock.expect_create_job()
+            .times(1)
+            .withf(|job_to_create, namespace| {
+                let metadata = job_to_create.metadata.as_ref().unwrap();
+                let limits = job_to_create
+                    .spec
+                    .as_ref()
+                    .unwrap()
+                    .template
+                    .spec
+                    .as_ref()
+                    .unwrap()
+                    .containers[0]
+                    .resources
+                    .as_ref()
+                    .unwrap()
+                    .limits
+                    .as_ref()
+                    .unwrap();
+                metadata.name.as_deref() == Some("config-a-b494b6-job")
+                    && metadata.owner_references.as_ref().unwrap()[0].uid
+                        == "abcdegfh-ijkl-mnop-qrst-uvwxyz012345"
+                    && limits.contains_key("akri.sh/config-a-b494b6")
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _| Ok(()));
+        // No broker Pods are created for an Instance serviced by a Job
+        mock.expect_create_pod().times(0);
+        run_handle_job_instance_change_test(&mut mock, &InstanceAction::Add).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_for_update_running_job_instance() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_pods_and_job_config(&mut mock);
+        configure_find_config_a_b494b6_job(&mut mock, None);
+        mock.expect_create_job().times(0);
+        mock.expect_remove_job().times(0);
+        mock.expect_release_job().times(0);
+        run_handle_job_instance_change_test(&mut mock, &InstanceAction::Update).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_for_update_completed_job_instance() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_pods_and_job_config(&mut mock);
+        configure_find_config_a_b494b6_job(&mut mock, Some(job::JOB_COMPLETE_CONDITION));
+        // A Job that completed successfully is kept rather than rerun, and its completion is recorded
+        config_for_tests::configure_release_job(
+            &mut mock,
+            "config-a-b494b6-job",
+            "config-a-namespace",
+        );
+        config_for_tests::configure_patch_instance(
+            &mut mock,
+            serde_json::json!([{
+                "op": "add",
+                "path": "/metadata/annotations",
+                "value": { "akri.sh/broker-job-completed": "config-a-b494b6-job" }
+            }]),
+            "config-a-b494b6",
+            "config-a-namespace",
+            false,
+        );
+        mock.expect_create_job().times(0);
+        mock.expect_remove_job().times(0);
+        run_handle_job_instance_change_test(&mut mock, &InstanceAction::Update).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_for_update_cleaned_up_completed_job_instance() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_pods_and_job_config(&mut mock);
+        // The completed Job has been deleted after its ttlSecondsAfterFinished
+        config_for_tests::configure_find_jobs(
+            &mut mock,
+            "akri.sh/instance=config-a-b494b6",
+            "../test/json/empty-list.json",
+            false,
+        );
+        mock.expect_create_job().times(0);
+        mock.expect_patch_instance().times(0);
+        run_handle_job_instance_change_test_with_annotation(
+            &mut mock,
+            &InstanceAction::Update,
+            Some((
+                broker_restart::BROKER_JOB_COMPLETED_ANNOTATION,
+                "config-a-b494b6-job".to_string(),
+            )),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_for_update_failed_job_instance() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_pods_and_job_config(&mut mock);
+        configure_find_config_a_b494b6_job(&mut mock, Some(job::JOB_FAILED_CONDITION));
+        config_for_tests::configure_remove_job(
+            &mut mock,
+            "config-a-b494b6-job",
+            "config-a-namespace",
+        );
+        config_for_tests::configure_add_job(
+            &mut mock,
+            "config-a-b494b6-job",
+            "config-a-namespace",
+            AKRI_INSTANCE_LABEL_NAME,
+            "config-a-b494b6",
+        );
+        // The restart is counted against the default Job restart policy
+        configure_patch_broker_job_restarts(&mut mock, |restarts| {
+            restarts.restart_times.len() == 1 && restarts.backoff_until.is_none()
+        });
+        run_handle_job_instance_change_test(&mut mock, &InstanceAction::Update).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_backs_off_failing_job() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_pods_and_job_config(&mut mock);
+        configure_find_config_a_b494b6_job(&mut mock, Some(job::JOB_FAILED_CONDITION));
+        // The failed Job is left in place rather than recreated
+        mock.expect_remove_job().times(0);
+        mock.expect_create_job().times(0);
+        mock.expect_create_event()
+            .times(1)
+            .withf(|event: &Event, namespace: &str| {
+                event.reason.as_deref() == Some(BROKER_RESTART_BACKOFF_REASON)
+                    && event.involved_object.name.as_deref() == Some("config-a-b494b6")
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _| Ok(()));
+        let now = Utc::now().timestamp();
+        configure_patch_broker_job_restarts(&mut mock, move |restarts| {
+            restarts
+                .backoff_until
+                .map_or(false, |until| until >= now + 300)
+        });
+        // The Job has already been recreated the maximum number of times within the window
+        run_handle_job_instance_change_test_with_annotation(
+            &mut mock,
+            &InstanceAction::Update,
+            Some((
+                broker_restart::BROKER_JOB_RESTARTS_ANNOTATION,
+                format!(
+                    r#"{{"restartTimes":[{},{},{}]}}"#,
+                    now - 30,
+                    now - 20,
+                    now - 10
+                ),
+            )),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_for_remove_running_job_instance() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_config_a_b494b6_job(&mut mock, None);
+        config_for_tests::configure_find_pods(
+            &mut mock,
+            "akri.sh/instance=config-a-b494b6",
+            "../test/json/empty-list.json",
+            false,
+        );
+        config_for_tests::configure_remove_job(
+            &mut mock,
+            "config-a-b494b6-job",
+            "config-a-namespace",
+        );
+        mock.expect_release_job().times(0);
+        run_handle_job_instance_change_test(&mut mock, &InstanceAction::Remove).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_for_remove_completed_job_instance() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_config_a_b494b6_job(&mut mock, Some(job::JOB_COMPLETE_CONDITION));
+        config_for_tests::configure_find_pods(
+            &mut mock,
+            "akri.sh/instance=config-a-b494b6",
+            "../test/json/empty-list.json",
+            false,
+        );
+        // Completed Jobs are left for their ttlSecondsAfterFinished
+        config_for_tests::configure_release_job(
+            &mut mock,
+            "config-a-b494b6-job",
+            "config-a-namespace",
+        );
+        mock.expect_remove_job().times(0);
+        run_handle_job_instance_change_test(&mut mock, &InstanceAction::Remove).await;
+    }
+
     /// Checks that the BROKER_POD_COUNT_METRIC is appropriately incremented
     /// and decremented when an instance is added and deleted (and pods are
     /// created and deleted). Cannot be run in parallel with other tests
     /// due to the metric being a global variable and modified unpredictably by
     /// other tests.
+    /// Expects config-a-b494b6's broker Pod to be found Failed and config-a with a broker restart
+    /// policy of 2 restarts within 600 seconds and a 300 second backoff to be found
+    fn configure_find_failed_pod_and_restart_policy_config(mock: &mut MockKubeInterface) {
+        mock.expect_find_pods_with_label()
+            .times(1)
+            .withf(|selector| selector == "akri.sh/instance=config-a-b494b6")
+            .returning(|_| {
+                let pods_json = file::read_file_to_string(
+                    "../test/json/running-pod-list-for-config-a-local.json",
+                )
+                .replace("\"phase\": \"Running\"", "\"phase\": \"Failed\"")
+                .replace(
+                    "\"name\": \"config-a-b494b6-pod\",",
+                    "\"name\": \"config-a-b494b6-pod\", \"uid\": \"failed-pod-uid\",",
+                );
+                let pods: PodList = serde_json::from_str(&pods_json).unwrap();
+                Ok(pods)
+            });
+        config_for_tests::configure_find_config(
+            mock,
+            "config-a",
+            "config-a-namespace",
+            "../test/json/config-a-restart-policy.json",
+            false,
+        );
+    }
+
+    /// Expects the broker restarts of node-a recorded in config-a-b494b6 to be patched to ones
+    /// that satisfy `check`
+    fn configure_patch_broker_restarts(
+        mock: &mut MockKubeInterface,
+        check: impl Fn(&broker_restart::NodeBrokerRestarts) -> bool + Send + 'static,
+    ) {
+        mock.expect_patch_instance()
+            .times(1)
+            .withf(move |patch, name, namespace| {
+                let restarts_json = patch[0]["value"][broker_restart::BROKER_RESTARTS_ANNOTATION]
+                    .as_str()
+                    .unwrap();
+                let restarts: broker_restart::BrokerRestarts =
+                    serde_json::from_str(restarts_json).unwrap();
+                patch[0]["path"] == "/metadata/annotations"
+                    && check(restarts.get("node-a").unwrap())
+                    && name == "config-a-b494b6"
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _, _| Ok(()));
+    }
+
+    /// Runs an Update of config-a-b494b6 with `broker_restarts` recorded in its annotations
+    async fn run_broker_restart_policy_test(
+        mock: &mut MockKubeInterface,
+        broker_restarts: Option<String>,
+    ) {
+        let instance_json = file::read_file_to_string("../test/json/local-instance.json");
+        let mut instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
+        if let Some(broker_restarts) = broker_restarts {
+            instance.metadata.annotations.insert(
+                broker_restart::BROKER_RESTARTS_ANNOTATION.to_string(),
+                broker_restarts,
+            );
+        }
+        handle_instance_change(&instance, &InstanceAction::Update, mock)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_restarts_failed_broker() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_failed_pod_and_restart_policy_config(&mut mock);
+        config_for_tests::configure_remove_pod(
+            &mut mock,
+            "config-a-b494b6-pod",
+            "config-a-namespace",
+        );
+        config_for_tests::configure_add_pod(
+            &mut mock,
+            "config-a-b494b6-pod",
+            "config-a-namespace",
+            AKRI_INSTANCE_LABEL_NAME,
+            "config-a-b494b6",
+        );
+        configure_patch_broker_restarts(&mut mock, |node_restarts| {
+            node_restarts.restart_times.len() == 1
+                && node_restarts.last_restarted_pod_uid.as_deref() == Some("failed-pod-uid")
+                && node_restarts.backoff_until.is_none()
+        });
+        mock.expect_create_event().times(0);
+        run_broker_restart_policy_test(&mut mock, None).await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_backs_off_crashlooping_broker() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_failed_pod_and_restart_policy_config(&mut mock);
+        mock.expect_remove_pod().times(0);
+        mock.expect_create_pod().times(0);
+        mock.expect_create_event()
+            .times(1)
+            .withf(|event: &Event, namespace: &str| {
+                event.reason.as_deref() == Some(BROKER_RESTART_BACKOFF_REASON)
+                    && event.type_.as_deref() == Some("Warning")
+                    && event.involved_object.name.as_deref() == Some("config-a-b494b6")
+                    && namespace == "config-a-namespace"
+            })
+            .returning(|_, _| Ok(()));
+        let now = Utc::now().timestamp();
+        configure_patch_broker_restarts(&mut mock, move |node_restarts| {
+            node_restarts
+                .backoff_until
+                .map_or(false, |until| until >= now + 300)
+        });
+        // The broker Pod has already been recreated the maximum number of times within the window
+        run_broker_restart_policy_test(
+            &mut mock,
+            Some(format!(
+                r#"{{"node-a":{{"restartTimes":[{},{}],"lastRestartedPodUid":"previous-pod-uid"}}}}"#,
+                now - 20,
+                now - 10
+            )),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_while_broker_backing_off() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_failed_pod_and_restart_policy_config(&mut mock);
+        mock.expect_remove_pod().times(0);
+        mock.expect_create_pod().times(0);
+        mock.expect_create_event().times(0);
+        mock.expect_patch_instance().times(0);
+        let now = Utc::now().timestamp();
+        run_broker_restart_policy_test(
+            &mut mock,
+            Some(format!(
+                r#"{{"node-a":{{"restartTimes":[{},{}],"backoffUntil":{}}}}}"#,
+                now - 20,
+                now - 10,
+                now + 100
+            )),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_handle_instance_change_restarts_broker_after_backoff() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockKubeInterface::new();
+        configure_find_failed_pod_and_restart_policy_config(&mut mock);
+        config_for_tests::configure_remove_pod(
+            &mut mock,
+            "config-a-b494b6-pod",
+            "config-a-namespace",
+        );
+        config_for_tests::configure_add_pod(
+            &mut mock,
+            "config-a-b494b6-pod",
+            "config-a-namespace",
+            AKRI_INSTANCE_LABEL_NAME,
+            "config-a-b494b6",
+        );
+        configure_patch_broker_restarts(&mut mock, |node_restarts| {
+            node_restarts.restart_times.len() == 1 && node_restarts.backoff_until.is_none()
+        });
+        let now = Utc::now().timestamp();
+        run_broker_restart_policy_test(
+            &mut mock,
+            Some(format!(
+                r#"{{"node-a":{{"restartTimes":[{},{}],"backoffUntil":{}}}}}"#,
+                now - 320,
+                now - 310,
+                now - 1
+            )),
+        )
+        .await;
+    }
+
     /// Run with: cargo test -- test_broker_pod_count_metric --ignored
     #[tokio::test]
     #[ignore]
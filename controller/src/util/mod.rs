@@ -1,3 +1,4 @@
+mod broker_restart;
 mod pod_action;
 mod shared_test_utils;
 
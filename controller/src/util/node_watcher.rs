@@ -1,7 +1,7 @@
 use akri_shared::{
     akri::{
-        instance::{Instance, KubeAkriInstance},
-        retry::{random_delay, MAX_INSTANCE_UPDATE_TRIES},
+        instance::{get_remove_node_patch, KubeAkriInstance},
+        retry::{random_backoff_delay, MAX_INSTANCE_UPDATE_TRIES},
     },
     k8s,
     k8s::KubeInterface,
@@ -188,8 +188,8 @@ impl NodeWatcher {
     }
 
     /// This handles when a node disappears by clearing nodes from
-    /// the nodes list and deviceUsage map and then trying 5 times to
-    /// update the Instance.
+    /// the nodes list and deviceUsage map and then trying 5 times,
+    /// backing off between tries, to patch the Instance.
     async fn handle_node_disappearance(
         &self,
         vanished_node_name: &str,
@@ -246,7 +246,7 @@ impl NodeWatcher {
                         if x == (MAX_INSTANCE_UPDATE_TRIES - 1) {
                             return Err(e);
                         }
-                        random_delay().await;
+                        random_backoff_delay(x).await;
                     }
                 }
             }
@@ -257,8 +257,8 @@ impl NodeWatcher {
     }
 
     /// This attempts to remove nodes from the nodes list and deviceUsage
-    /// map in an Instance.  An attempt is made to update
-    /// the instance in etcd, any failure is returned.
+    /// map in an Instance.  Only the entries referencing the vanished node
+    /// are patched, and any failure is returned.
     async fn try_remove_nodes_from_instance(
         &self,
         vanished_node_name: &str,
@@ -271,49 +271,27 @@ impl NodeWatcher {
             "try_remove_nodes_from_instance - vanished_node_name: {:?}",
             &vanished_node_name
         );
-        let modified_nodes = instance
-            .spec
-            .nodes
-            .iter()
-            .filter(|node| &vanished_node_name != node)
-            .map(|node| node.into())
-            .collect::<Vec<String>>();
-        // Remove nodes from instance.deviceusage
-        let modified_device_usage = instance
-            .spec
-            .device_usage
-            .iter()
-            .map(|(slot, node)| {
-                (
-                    slot.to_string(),
-                    if vanished_node_name == node {
-                        "".into()
-                    } else {
-                        node.into()
-                    },
-                )
-            })
-            .collect::<HashMap<String, String>>();
-
-        // Save the instance
-        let modified_instance = Instance {
-            configuration_name: instance.spec.configuration_name.clone(),
-            metadata: instance.spec.metadata.clone(),
-            rbac: instance.spec.rbac.clone(),
-            shared: instance.spec.shared,
-            device_usage: modified_device_usage,
-            nodes: modified_nodes,
+        let patch = match get_remove_node_patch(&instance.spec, vanished_node_name) {
+            Some(patch) => patch,
+            None => {
+                trace!(
+                    "try_remove_nodes_from_instance - {} does not reference {}",
+                    &instance_name,
+                    &vanished_node_name
+                );
+                return Ok(());
+            }
         };
 
         trace!(
-            "handle_node_disappearance - kube_interface.update_instance name: {}, namespace: {}, {:?}",
+            "handle_node_disappearance - kube_interface.patch_instance name: {}, namespace: {}, {}",
             &instance_name,
             &instance_namespace,
-            &modified_instance
+            &patch
         );
 
         kube_interface
-            .update_instance(&modified_instance, &instance_name, &instance_namespace)
+            .patch_instance(&patch, &instance_name, &instance_namespace)
             .await
     }
 }
@@ -325,8 +303,8 @@ mod tests {
     use akri_shared::{akri::instance::KubeAkriInstanceList, k8s::MockKubeInterface, os::file};
 
     #[derive(Clone)]
-    struct UpdateInstance {
-        instance_to_update: Instance,
+    struct PatchInstance {
+        patch: serde_json::Value,
         instance_name: &'static str,
         instance_namespace: &'static str,
     }
@@ -335,7 +313,7 @@ mod tests {
     struct HandleNodeDisappearance {
         get_instances_result_file: &'static str,
         get_instances_result_listify: bool,
-        update_instance: Option<UpdateInstance>,
+        patch_instance: Option<PatchInstance>,
     }
 
     fn configure_for_handle_node_disappearance(
@@ -348,17 +326,27 @@ mod tests {
             work.get_instances_result_listify,
         );
 
-        if let Some(update_instance) = &work.update_instance {
-            config_for_tests::configure_update_instance(
+        if let Some(patch_instance) = &work.patch_instance {
+            config_for_tests::configure_patch_instance(
                 mock,
-                update_instance.instance_to_update.clone(),
-                update_instance.instance_name,
-                update_instance.instance_namespace,
+                patch_instance.patch.clone(),
+                patch_instance.instance_name,
+                patch_instance.instance_namespace,
                 false,
             );
         }
     }
 
+    /// The patch that removes node-b from shared-instance-update.json
+    fn remove_node_b_patch() -> serde_json::Value {
+        serde_json::json!([
+            { "op": "test", "path": "/spec/nodes/0", "value": "node-b" },
+            { "op": "remove", "path": "/spec/nodes/0" },
+            { "op": "test", "path": "/spec/deviceUsage/config-a-359973-2", "value": "node-b" },
+            { "op": "replace", "path": "/spec/deviceUsage/config-a-359973-2", "value": "" }
+        ])
+    }
+
     #[tokio::test]
     async fn test_handle_node_added_unready() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -406,23 +394,14 @@ mod tests {
         let node: NodeObject = serde_json::from_str(&node_json).unwrap();
         let mut node_watcher = NodeWatcher::new();
 
-        let instance_file = "../test/json/shared-instance-update.json";
-        let instance_json = file::read_file_to_string(instance_file);
-        let kube_object_instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
-        let mut instance = kube_object_instance.spec;
-        instance.nodes.clear();
-        instance
-            .device_usage
-            .insert("config-a-359973-2".to_string(), "".to_string());
-
         let mut mock = MockKubeInterface::new();
         configure_for_handle_node_disappearance(
             &mut mock,
             &HandleNodeDisappearance {
                 get_instances_result_file: "../test/json/shared-instance-update.json",
                 get_instances_result_listify: true,
-                update_instance: Some(UpdateInstance {
-                    instance_to_update: instance,
+                patch_instance: Some(PatchInstance {
+                    patch: remove_node_b_patch(),
                     instance_name: "config-a-359973",
                     instance_namespace: "config-a-namespace",
                 }),
@@ -472,23 +451,14 @@ mod tests {
         let node: NodeObject = serde_json::from_str(&node_json).unwrap();
         let mut node_watcher = NodeWatcher::new();
 
-        let instance_file = "../test/json/shared-instance-update.json";
-        let instance_json = file::read_file_to_string(instance_file);
-        let kube_object_instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
-        let mut instance = kube_object_instance.spec;
-        instance.nodes.clear();
-        instance
-            .device_usage
-            .insert("config-a-359973-2".to_string(), "".to_string());
-
         let mut mock = MockKubeInterface::new();
         configure_for_handle_node_disappearance(
             &mut mock,
             &HandleNodeDisappearance {
                 get_instances_result_file: "../test/json/shared-instance-update.json",
                 get_instances_result_listify: true,
-                update_instance: Some(UpdateInstance {
-                    instance_to_update: instance,
+                patch_instance: Some(PatchInstance {
+                    patch: remove_node_b_patch(),
                     instance_name: "config-a-359973",
                     instance_namespace: "config-a-namespace",
                 }),
@@ -536,9 +506,9 @@ mod tests {
             let list: KubeAkriInstanceList = serde_json::from_str(&instance_list_json).unwrap();
             Ok(list)
         });
-        mock.expect_update_instance()
+        mock.expect_patch_instance()
             .times(MAX_INSTANCE_UPDATE_TRIES as usize)
-            .withf(move |_instance, n, ns| n == "config-a-359973" && ns == "config-a-namespace")
+            .withf(move |_patch, n, ns| n == "config-a-359973" && ns == "config-a-namespace")
             .returning(move |_, _, _| Err(None.ok_or("failure")?));
         mock.expect_find_instance()
             .times((MAX_INSTANCE_UPDATE_TRIES - 1) as usize)
@@ -552,7 +522,7 @@ mod tests {
 
         let node_watcher = NodeWatcher::new();
         assert!(node_watcher
-            .handle_node_disappearance(&"foo-a", &mock,)
+            .handle_node_disappearance(&"node-b", &mock,)
             .await
             .is_err());
     }
@@ -565,28 +535,15 @@ mod tests {
         let instance_json = file::read_file_to_string(instance_file);
         let kube_object_instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
 
+        // Only node-b's entry in nodes and the slot it holds are patched
         let mut mock = MockKubeInterface::new();
-        mock.expect_update_instance()
-            .times(1)
-            .withf(move |ins, n, ns| {
-                n == "config-a"
-                    && ns == "config-a-namespace"
-                    && !ins.nodes.contains(&"node-b".to_string())
-                    && ins
-                        .device_usage
-                        .iter()
-                        .filter_map(|(_slot, value)| {
-                            if value == &"node-b".to_string() {
-                                Some(value.to_string())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<String>>()
-                        .first()
-                        .is_none()
-            })
-            .returning(move |_, _, _| Ok(()));
+        config_for_tests::configure_patch_instance(
+            &mut mock,
+            remove_node_b_patch(),
+            "config-a",
+            "config-a-namespace",
+            false,
+        );
 
         let node_watcher = NodeWatcher::new();
         assert!(node_watcher
@@ -601,6 +558,28 @@ mod tests {
             .is_ok());
     }
 
+    #[tokio::test]
+    async fn test_try_remove_nodes_from_instance_unreferenced_node() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let instance_file = "../test/json/shared-instance-update.json";
+        let instance_json = file::read_file_to_string(instance_file);
+        let kube_object_instance: KubeAkriInstance = serde_json::from_str(&instance_json).unwrap();
+
+        // An Instance that does not reference the vanished node is not patched
+        let node_watcher = NodeWatcher::new();
+        assert!(node_watcher
+            .try_remove_nodes_from_instance(
+                "node-c",
+                "config-a",
+                "config-a-namespace",
+                &kube_object_instance,
+                &MockKubeInterface::new(),
+            )
+            .await
+            .is_ok());
+    }
+
     #[test]
     fn test_is_node_ready_ready() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -3,11 +3,12 @@ pub mod config_for_tests {
     use akri_shared::{
         akri::{
             configuration::KubeAkriConfig,
-            instance::{Instance, KubeAkriInstance, KubeAkriInstanceList},
+            instance::{KubeAkriInstance, KubeAkriInstanceList},
         },
         k8s::MockKubeInterface,
         os::file,
     };
+    use k8s_openapi::api::batch::v1::{JobSpec, JobStatus};
     use k8s_openapi::api::core::v1::{PodSpec, PodStatus, ServiceSpec, ServiceStatus};
     use kube::api::{Object, ObjectList};
     use log::trace;
@@ -16,6 +17,8 @@ pub mod config_for_tests {
     pub type PodList = ObjectList<PodObject>;
     pub type ServiceObject = Object<ServiceSpec, ServiceStatus>;
     pub type ServiceList = ObjectList<ServiceObject>;
+    pub type JobObject = Object<JobSpec, JobStatus>;
+    pub type JobList = ObjectList<JobObject>;
 
     pub fn configure_find_instance(
         mock: &mut MockKubeInterface,
@@ -73,26 +76,23 @@ pub mod config_for_tests {
         });
     }
 
-    pub fn configure_update_instance(
+    pub fn configure_patch_instance(
         mock: &mut MockKubeInterface,
-        instance_to_update: Instance,
+        patch: serde_json::Value,
         instance_name: &'static str,
         instance_namespace: &'static str,
         result_error: bool,
     ) {
         trace!(
-            "mock.expect_update_instance name:{} namespace:{} error:{}",
+            "mock.expect_patch_instance name:{} namespace:{} error:{}",
             instance_name,
             instance_namespace,
             result_error
         );
-        mock.expect_update_instance()
+        mock.expect_patch_instance()
             .times(1)
-            .withf(move |instance, name, namespace| {
-                name == instance_name
-                    && namespace == instance_namespace
-                    && instance.nodes == instance_to_update.nodes
-                    && instance.device_usage == instance_to_update.device_usage
+            .withf(move |actual_patch, name, namespace| {
+                name == instance_name && namespace == instance_namespace && actual_patch == &patch
             })
             .returning(move |_, _, _| {
                 if result_error {
@@ -301,4 +301,83 @@ pub mod config_for_tests {
             })
             .returning(move |_, _| Ok(()));
     }
+
+    pub fn configure_find_jobs(
+        mock: &mut MockKubeInterface,
+        job_selector: &'static str,
+        result_file: &'static str,
+        result_error: bool,
+    ) {
+        trace!(
+            "mock.expect_find_jobs_with_label job_selector:{}",
+            job_selector
+        );
+        mock.expect_find_jobs_with_label()
+            .times(1)
+            .withf(move |selector| selector == job_selector)
+            .returning(move |_| {
+                if result_error {
+                    Err(None.ok_or("failure")?)
+                } else {
+                    let jobs_json = file::read_file_to_string(result_file);
+                    let jobs: JobList = serde_json::from_str(&jobs_json).unwrap();
+                    Ok(jobs)
+                }
+            });
+    }
+
+    pub fn configure_add_job(
+        mock: &mut MockKubeInterface,
+        job_name: &'static str,
+        job_namespace: &'static str,
+        label_id: &'static str,
+        label_value: &'static str,
+    ) {
+        trace!("mock.expect_create_job job_name:{}", job_name);
+        mock.expect_create_job()
+            .times(1)
+            .withf(move |job_to_create, namespace| {
+                let metadata = job_to_create.metadata.as_ref().unwrap();
+                metadata.name.as_ref().unwrap() == job_name
+                    && metadata.labels.as_ref().unwrap().get(label_id).unwrap() == label_value
+                    && namespace == job_namespace
+            })
+            .returning(move |_, _| Ok(()));
+    }
+
+    pub fn configure_remove_job(
+        mock: &mut MockKubeInterface,
+        job_name: &'static str,
+        job_namespace: &'static str,
+    ) {
+        trace!(
+            "mock.expect_remove_job job_name:{} job_namespace:{}",
+            job_name,
+            job_namespace
+        );
+        mock.expect_remove_job()
+            .times(1)
+            .withf(move |job_to_remove, namespace| {
+                job_to_remove == job_name && namespace == job_namespace
+            })
+            .returning(move |_, _| Ok(()));
+    }
+
+    pub fn configure_release_job(
+        mock: &mut MockKubeInterface,
+        job_name: &'static str,
+        job_namespace: &'static str,
+    ) {
+        trace!(
+            "mock.expect_release_job job_name:{} job_namespace:{}",
+            job_name,
+            job_namespace
+        );
+        mock.expect_release_job()
+            .times(1)
+            .withf(move |job_to_release, namespace| {
+                job_to_release == job_name && namespace == job_namespace
+            })
+            .returning(move |_, _| Ok(()));
+    }
 }
@@ -8,16 +8,20 @@
 use super::API_CONFIGURATIONS;
 use super::API_NAMESPACE;
 use super::API_VERSION;
+use k8s_openapi::api::batch::v1::JobSpec;
 use k8s_openapi::api::core::v1::PodSpec;
 use k8s_openapi::api::core::v1::ServiceSpec;
 use kube::{
-    api::{ListParams, Object, ObjectList, RawApi, Void},
+    api::{ListParams, Object, ObjectList, ObjectMeta, PatchParams, RawApi, TypeMeta},
     client::APIClient,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
-pub type KubeAkriConfig = Object<Configuration, Void>;
-pub type KubeAkriConfigList = ObjectList<Object<Configuration, Void>>;
+pub type KubeAkriConfig = Object<Configuration, ConfigurationStatus>;
+pub type KubeAkriConfigList = ObjectList<Object<Configuration, ConfigurationStatus>>;
+
+/// Type of the condition that reports whether a Configuration's discovery handler is failing
+pub const DISCOVERY_ERROR_CONDITION: &str = "DiscoveryError";
 
 /// This defines the supported types of protocols
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -27,6 +31,10 @@ pub enum ProtocolHandler {
     udev(UdevDiscoveryHandlerConfig),
     opcua(OpcuaDiscoveryHandlerConfig),
     debugEcho(DebugEchoDiscoveryHandlerConfig),
+    ble(BleDiscoveryHandlerConfig),
+    snmp(SnmpDiscoveryHandlerConfig),
+    mqtt(MqttDiscoveryHandlerConfig),
+    serial(SerialDiscoveryHandlerConfig),
 }
 
 /// This defines the types of supported filters
@@ -45,13 +53,49 @@ fn default_action() -> FilterType {
     FilterType::Include
 }
 
+/// This defines how the items of a filter list are compared
+/// against what is discovered
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum MatchType {
+    /// Items are compared the way each protocol always has: ONVIF
+    /// accepts values containing an item, other protocols accept
+    /// values equal to an item
+    Literal,
+    /// Items must equal the whole value
+    Exact,
+    /// Items are regular expressions, which match any part of the
+    /// value unless anchored with `^` and `$`
+    Regex,
+    /// Items are glob patterns that must match the whole value, where
+    /// `*` matches any characters, `?` matches a single character and
+    /// `[...]` matches one of a set of characters
+    Glob,
+}
+
+/// The default match type is `Literal`
+fn default_match_type() -> MatchType {
+    MatchType::Literal
+}
+
+fn is_literal_match_type(match_type: &MatchType) -> bool {
+    *match_type == MatchType::Literal
+}
+
 /// This defines a filter list.
 ///
 /// The items list can either define the only acceptable
 /// items (Include) or can define the only unacceptable items
 /// (Exclude)
+///
+/// Regex and Glob items are checked when the filter list is
+/// deserialized, so that a Configuration with an invalid pattern
+/// is rejected rather than silently matching nothing.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
+#[serde(
+    rename_all = "camelCase",
+    deny_unknown_fields,
+    try_from = "FilterListEntry"
+)]
 pub struct FilterList {
     /// This defines a list of items that will be evaluated as part
     /// of the filtering process
@@ -61,28 +105,127 @@ pub struct FilterList {
     /// is `Include`
     #[serde(default = "default_action")]
     pub action: FilterType,
+    /// This defines how items are compared.  The default is `Literal`
+    #[serde(
+        default = "default_match_type",
+        skip_serializing_if = "is_literal_match_type"
+    )]
+    pub match_type: MatchType,
 }
 
-/// This tests whether an item should be included according to the `FilterList`
-pub fn should_include(filter_list: Option<&FilterList>, item: &str) -> bool {
-    if filter_list.is_none() {
-        return true;
+/// A filter list as written in a Configuration, before its patterns are checked
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct FilterListEntry {
+    items: Vec<String>,
+    #[serde(default = "default_action")]
+    action: FilterType,
+    #[serde(default = "default_match_type")]
+    match_type: MatchType,
+}
+
+impl std::convert::TryFrom<FilterListEntry> for FilterList {
+    type Error = String;
+
+    fn try_from(entry: FilterListEntry) -> Result<Self, Self::Error> {
+        for item in &entry.items {
+            match entry.match_type {
+                MatchType::Regex => {
+                    regex::Regex::new(item)
+                        .map_err(|e| format!("invalid regex filter item {}: {}", item, e))?;
+                }
+                MatchType::Glob => {
+                    glob::Pattern::new(item)
+                        .map_err(|e| format!("invalid glob filter item {}: {}", item, e))?;
+                }
+                MatchType::Literal | MatchType::Exact => {}
+            }
+        }
+        Ok(FilterList {
+            items: entry.items,
+            action: entry.action,
+            match_type: entry.match_type,
+        })
     }
-    let item_contained = filter_list.unwrap().items.contains(&item.to_string());
-    if filter_list.as_ref().unwrap().action == FilterType::Include {
-        item_contained
-    } else {
-        !item_contained
+}
+
+impl FilterList {
+    /// This tests whether `value` matches the filter list item `item`.
+    /// Literal items match values for which `literal_match(item, value)` holds
+    pub fn item_matches(
+        &self,
+        item: &str,
+        value: &str,
+        literal_match: impl Fn(&str, &str) -> bool,
+    ) -> bool {
+        match self.match_type {
+            MatchType::Literal => literal_match(item, value),
+            MatchType::Exact => item == value,
+            MatchType::Regex => match regex::Regex::new(item) {
+                Ok(regex) => regex.is_match(value),
+                Err(e) => {
+                    log::error!(
+                        "item_matches - invalid regex {} ... ignoring it: {}",
+                        item,
+                        e
+                    );
+                    false
+                }
+            },
+            MatchType::Glob => match glob::Pattern::new(item) {
+                Ok(pattern) => pattern.matches(value),
+                Err(e) => {
+                    log::error!(
+                        "item_matches - invalid glob pattern {} ... ignoring it: {}",
+                        item,
+                        e
+                    );
+                    false
+                }
+            },
+        }
+    }
+}
+
+/// This tests whether something described by `values` should be included according to the `FilterList`.
+/// With no `FilterList` everything is included. An Include list includes only what matches at least one
+/// of its items, so an empty Include list includes nothing. An Exclude list excludes anything that matches
+/// at least one of its items, so an empty Exclude list excludes nothing.
+/// Literal items match values for which `literal_match(item, value)` holds.
+pub fn should_include_values(
+    filter_list: Option<&FilterList>,
+    values: &[String],
+    literal_match: impl Fn(&str, &str) -> bool,
+) -> bool {
+    let filter_list = match filter_list {
+        Some(filter_list) => filter_list,
+        None => return true,
+    };
+    let any_item_matches = filter_list.items.iter().any(|item| {
+        values
+            .iter()
+            .any(|value| filter_list.item_matches(item, value, &literal_match))
+    });
+    match filter_list.action {
+        FilterType::Include => any_item_matches,
+        FilterType::Exclude => !any_item_matches,
     }
 }
 
+/// This tests whether an item should be included according to the `FilterList`
+pub fn should_include(filter_list: Option<&FilterList>, item: &str) -> bool {
+    should_include_values(filter_list, &[item.to_string()], |filter_item, item| {
+        filter_item == item
+    })
+}
+
 /// This defines the ONVIF data stored in the Configuration
 /// CRD
 ///
 /// The ONVIF discovery handler is structured to store a filter list for
 /// ip addresses, mac addresses, and ONVIF scopes.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct OnvifDiscoveryHandlerConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub ip_addresses: Option<FilterList>,
@@ -92,16 +235,76 @@ pub struct OnvifDiscoveryHandlerConfig {
     pub scopes: Option<FilterList>,
     #[serde(default = "default_discovery_timeout_seconds")]
     pub discovery_timeout_seconds: i32,
+    #[serde(default = "default_discovery_interval_seconds")]
+    pub discovery_interval_seconds: i32,
+    /// How long responses from a camera are reused before the camera is queried again
+    #[serde(default = "default_query_cache_ttl_seconds")]
+    pub query_cache_ttl_seconds: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<OnvifCredentials>,
+    /// Whether to query each camera for the stream uri of its first media profile
+    #[serde(default)]
+    pub include_stream_uri: bool,
+    /// Whether to query each camera for its manufacturer, model and firmware version
+    #[serde(default)]
+    pub include_device_info: bool,
+    /// Maximum number of cameras to collect from WS-Discovery responses each discovery cycle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cameras: Option<usize>,
+    /// How long to wait before sending the WS-Discovery probe each discovery cycle
+    #[serde(default)]
+    pub probe_delay_ms: u64,
+    /// Name or IPv4 address of the network interface to send the WS-Discovery probe from.
+    /// The operating system picks the interface if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_interface: Option<String>,
+    /// Time to live of the WS-Discovery probe, limiting how many routers it may cross.
+    /// The operating system default, usually 1, is used if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multicast_ttl: Option<u32>,
+    /// IPv4 networks, in CIDR notation, whose addresses are preferred when a camera answers
+    /// WS-Discovery on more than one address
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preferred_networks: Vec<String>,
+    /// Prefix of the ONVIF scope that groups cameras, such as `onvif://www.onvif.org/group/`.
+    /// When set, cameras with the same value after the prefix are reported as a single device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_by_scope: Option<String>,
+}
+
+/// This defines the credentials used to authenticate to ONVIF cameras
+///
+/// When present, requests made to cameras are signed with a WS-Security
+/// UsernameToken.  The password can either be provided directly or read
+/// from a file, such as a Kubernetes Secret mounted into the Agent.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct OnvifCredentials {
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_file: Option<String>,
 }
 
 fn default_discovery_timeout_seconds() -> i32 {
     1
 }
 
+/// The default number of seconds to wait between discovery attempts
+fn default_discovery_interval_seconds() -> i32 {
+    10
+}
+
+/// This defines the default ONVIF query cache TTL
+fn default_query_cache_ttl_seconds() -> i32 {
+    60
+}
+
 /// This defines the UDEV data stored in the Configuration
 /// CRD
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct UdevDiscoveryHandlerConfig {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub udev_rules: Vec<String>,
@@ -114,11 +317,28 @@ pub struct UdevDiscoveryHandlerConfig {
 /// for discovering OPC UA servers and stores a filter list for
 /// application names.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct OpcuaDiscoveryHandlerConfig {
     pub opcua_discovery_method: OpcuaDiscoveryMethod,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub application_names: Option<FilterList>,
+    #[serde(default = "default_discovery_interval_seconds")]
+    pub discovery_interval_seconds: i32,
+    /// Number of concurrent sessions supported by specific Servers, keyed by DiscoveryURL.
+    /// Used instead of the Configuration's capacity for those Servers' Instances.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub server_capacities: HashMap<String, i32>,
+    /// Path to the DER certificate the discovery client authenticates with.
+    /// Discovery is done without security unless both this and `private_key_path` are set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_path: Option<String>,
+    /// Path to the PEM private key for `certificate_path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key_path: Option<String>,
+    /// Whether to read each discovered Server's ServerStatus and add its build information
+    /// to the Server's properties. Off by default, as it takes another round-trip per Server.
+    #[serde(default)]
+    pub include_server_status: bool,
 }
 
 /// Methods for discovering OPC UA Servers
@@ -133,10 +353,13 @@ pub enum OpcuaDiscoveryMethod {
 /// If the DiscoveryURL is for a LocalDiscoveryServer, it will discover all Servers
 /// that have registered with that LocalDiscoveryServer.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct StandardOpcuaDiscovery {
     #[serde(default = "lds_discovery_url", skip_serializing_if = "Vec::is_empty")]
     pub discovery_urls: Vec<String>,
+    /// How long to wait for each DiscoveryURL to respond before skipping it
+    #[serde(default = "default_discovery_url_timeout_seconds")]
+    pub discovery_url_timeout_seconds: i32,
 }
 
 /// If no DiscoveryURLs are specified, uses the OPC UA default DiscoveryURL
@@ -145,16 +368,322 @@ fn lds_discovery_url() -> Vec<String> {
     vec!["opc.tcp://localhost:4840/".to_string()]
 }
 
+/// This defines the default timeout for querying a single OPC UA DiscoveryURL
+fn default_discovery_url_timeout_seconds() -> i32 {
+    5
+}
+
+/// This defines the BLE data stored in the Configuration
+/// CRD
+///
+/// The BLE discovery handler scans for advertising Bluetooth Low Energy
+/// peripherals, optionally filtering them by advertised GATT service
+/// and by local name.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct BleDiscoveryHandlerConfig {
+    /// GATT service UUIDs, either 128-bit or 16-bit short forms such as `180d`.
+    /// Only peripherals advertising at least one of them are discovered if any are listed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service_uuids: Vec<String>,
+    /// Only peripherals whose local name starts with this prefix are discovered if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_prefix: Option<String>,
+    /// How long to scan for advertising peripherals each discovery cycle
+    #[serde(default = "default_ble_scan_duration_secs")]
+    pub scan_duration_secs: u64,
+    #[serde(default = "default_discovery_interval_seconds")]
+    pub discovery_interval_seconds: i32,
+}
+
+/// This defines the default number of seconds to scan for BLE peripherals
+fn default_ble_scan_duration_secs() -> u64 {
+    5
+}
+
+/// This defines the SNMP data stored in the Configuration
+/// CRD
+///
+/// The SNMP discovery handler queries each address of the listed
+/// subnets for the system group of an SNMP agent, optionally filtering
+/// agents by their sysObjectID.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SnmpDiscoveryHandlerConfig {
+    /// IP addresses and subnets in CIDR notation, such as `10.0.0.0/24`, to look for SNMP agents in
+    pub subnets: Vec<String>,
+    #[serde(default = "default_snmp_version")]
+    pub version: SnmpVersion,
+    /// Only agents whose sysObjectID is this OID or under it, such as `1.3.6.1.4.1.9` for Cisco devices,
+    /// are discovered if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oid_filter: Option<String>,
+    #[serde(default = "default_snmp_port")]
+    pub port: u16,
+    /// How long to wait for each agent to respond to a request
+    #[serde(default = "default_snmp_request_timeout_seconds")]
+    pub request_timeout_seconds: u64,
+    #[serde(default = "default_discovery_interval_seconds")]
+    pub discovery_interval_seconds: i32,
+}
+
+/// SNMP versions agents are queried with, along with their security settings
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SnmpVersion {
+    v2c(SnmpV2cSecurity),
+    // TODO: add v3 once the Agent has an SNMP client that implements USM authentication and privacy
+}
+
+/// Community based security of SNMP v2c
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SnmpV2cSecurity {
+    #[serde(default = "default_snmp_community")]
+    pub community: String,
+}
+
+/// SNMP v2c with the `public` community is used unless a version is specified
+fn default_snmp_version() -> SnmpVersion {
+    SnmpVersion::v2c(SnmpV2cSecurity {
+        community: default_snmp_community(),
+    })
+}
+
+fn default_snmp_community() -> String {
+    "public".to_string()
+}
+
+/// This defines the port SNMP agents listen on by default
+fn default_snmp_port() -> u16 {
+    161
+}
+
+/// This defines the default timeout for a single SNMP request
+fn default_snmp_request_timeout_seconds() -> u64 {
+    1
+}
+
+/// This defines the MQTT data stored in the Configuration
+/// CRD
+///
+/// The MQTT discovery handler subscribes to the topics devices announce
+/// themselves on, such as the retained discovery messages of Home Assistant,
+/// and discovers a device for each topic with a current announcement.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct MqttDiscoveryHandlerConfig {
+    /// URL of the broker, such as `mqtt://mosquitto.default:1883`
+    pub broker_url: String,
+    /// Topic filter that announcements are published to, which may contain the `+` and `#` wildcards
+    pub topic_pattern: String,
+    /// Prefix of the client id each Agent connects with. The node name is appended to it, as a broker
+    /// disconnects a client when another client connects with the same id.
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default = "default_mqtt_qos")]
+    pub qos: u8,
+    /// JSON path, such as `$.device.id`, of the device id in an announcement
+    #[serde(default = "default_mqtt_id_path")]
+    pub id_path: String,
+    /// JSON path of the object in an announcement whose fields become the Instance's properties
+    #[serde(default = "default_mqtt_properties_path")]
+    pub properties_path: String,
+    /// Payload that marks the device announced on a topic as gone, such as the payload of its last will.
+    /// An empty payload, which clears a retained announcement, always does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offline_payload: Option<String>,
+    #[serde(default = "default_discovery_interval_seconds")]
+    pub discovery_interval_seconds: i32,
+}
+
+fn default_mqtt_client_id() -> String {
+    "akri".to_string()
+}
+
+/// Announcements are received at least once by default
+fn default_mqtt_qos() -> u8 {
+    1
+}
+
+fn default_mqtt_id_path() -> String {
+    "$.id".to_string()
+}
+
+fn default_mqtt_properties_path() -> String {
+    "$.properties".to_string()
+}
+
+/// This defines the Serial data stored in the Configuration
+/// CRD
+///
+/// The Serial discovery handler looks for the serial ports matching a
+/// glob pattern, optionally probing each one for a device that answers
+/// a command.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SerialDiscoveryHandlerConfig {
+    /// Glob pattern of the ports to discover, such as `/dev/ttyUSB*`
+    pub port_pattern: String,
+    /// Baud rates to probe with, in order. A port is discovered with the first rate that gets a response.
+    #[serde(default = "default_serial_baud_rates")]
+    pub baud_rates: Vec<u32>,
+    /// Command to write to each port, such as `"*IDN?\r\n"`. Ports are discovered without being probed if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_command: Option<String>,
+    /// Text that the response to the probe command must contain. Any response will do if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_contains: Option<String>,
+    /// How long to wait for a port to respond to the probe command
+    #[serde(default = "default_serial_probe_timeout_ms")]
+    pub probe_timeout_ms: u64,
+    #[serde(default = "default_discovery_interval_seconds")]
+    pub discovery_interval_seconds: i32,
+}
+
+fn default_serial_baud_rates() -> Vec<u32> {
+    vec![9600]
+}
+
+fn default_serial_probe_timeout_ms() -> u64 {
+    500
+}
+
 /// This defines the DebugEcho data stored in the Configuration
 /// CRD
 ///
 /// DebugEcho is used for testing Akri.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct DebugEchoDiscoveryHandlerConfig {
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub descriptions: Vec<String>,
+    /// Mock devices to discover. Also accepts the older `descriptions` list of ids.
+    #[serde(alias = "descriptions", skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<DebugEchoDevice>,
+    #[serde(default)]
     pub shared: bool,
+    #[serde(default = "default_discovery_interval_seconds")]
+    pub discovery_interval_seconds: i32,
+    /// File checked for "OFFLINE" to mock the instances going offline.
+    /// Defaults to `/tmp/debug-echo-availability.txt` so that Configurations
+    /// can be toggled independently by giving each its own path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability_check_path: Option<String>,
+}
+
+/// This defines a mock device discovered by the DebugEcho
+/// discovery handler
+///
+/// A device may also be given as a plain string, which is used as its id.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "DebugEchoDeviceEntry")]
+pub struct DebugEchoDevice {
+    pub id: String,
+    /// Properties added to the Instance of this device
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub properties: HashMap<String, String>,
+    /// Whether this device is shared, overriding the DebugEcho `shared` setting when set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared: Option<bool>,
+    /// Host devices given to brokers of this device, such as `/dev/nvidia0`
+    #[serde(rename = "deviceSpecs", skip_serializing_if = "Vec::is_empty")]
+    pub device_specs: Vec<DebugEchoDeviceSpec>,
+    /// Host paths mounted into brokers of this device
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<DebugEchoMount>,
+}
+
+/// This defines a host device given to the brokers of a DebugEcho device
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DebugEchoDeviceSpec {
+    pub container_path: String,
+    pub host_path: String,
+    /// Cgroup permissions of the device, any of `r`, `w` and `m`
+    #[serde(default = "default_device_spec_permissions")]
+    pub permissions: String,
+}
+
+fn default_device_spec_permissions() -> String {
+    "rw".to_string()
+}
+
+/// This defines a host path mounted into the brokers of a DebugEcho device
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DebugEchoMount {
+    pub container_path: String,
+    pub host_path: String,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Either form a DebugEcho device can take in a Configuration
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DebugEchoDeviceEntry {
+    Description(String),
+    Device {
+        id: String,
+        #[serde(default)]
+        properties: HashMap<String, String>,
+        #[serde(default)]
+        shared: Option<bool>,
+        #[serde(default, rename = "deviceSpecs")]
+        device_specs: Vec<DebugEchoDeviceSpec>,
+        #[serde(default)]
+        mounts: Vec<DebugEchoMount>,
+    },
+}
+
+impl From<DebugEchoDeviceEntry> for DebugEchoDevice {
+    fn from(entry: DebugEchoDeviceEntry) -> Self {
+        match entry {
+            DebugEchoDeviceEntry::Description(id) => DebugEchoDevice {
+                id,
+                properties: HashMap::new(),
+                shared: None,
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+            },
+            DebugEchoDeviceEntry::Device {
+                id,
+                properties,
+                shared,
+                device_specs,
+                mounts,
+            } => DebugEchoDevice {
+                id,
+                properties,
+                shared,
+                device_specs,
+                mounts,
+            },
+        }
+    }
+}
+
+/// This defines how the controller recreates broker Pods that keep failing.
+/// A broker Pod that has been recreated `max_restarts` times within
+/// `window_seconds` is not recreated again until `backoff_seconds` have passed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct BrokerRestartPolicy {
+    /// Number of times a broker Pod may be recreated within the window
+    pub max_restarts: u32,
+    /// Length of the window that restarts are counted over, in seconds
+    #[serde(default = "default_restart_window_seconds")]
+    pub window_seconds: u64,
+    /// Length of time to wait before recreating a broker Pod that has
+    /// used up its restarts, in seconds
+    pub backoff_seconds: u64,
+}
+
+fn default_restart_window_seconds() -> u64 {
+    600
 }
 
 /// Defines the information in the Akri Configuration CRD
@@ -183,6 +712,19 @@ pub struct Configuration {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub broker_pod_spec: Option<PodSpec>,
 
+    /// This defines a Job that should be run once for each
+    /// capability found that is described by this configuration,
+    /// for one-shot tasks such as provisioning a device. It
+    /// cannot be set along with broker_pod_spec
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broker_job_spec: Option<JobSpec>,
+
+    /// This limits how often the controller recreates a failing
+    /// broker Pod. If unset, failed broker Pods are always
+    /// recreated right away
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broker_restart_policy: Option<BrokerRestartPolicy>,
+
     /// This defines a service that should be created to access
     /// any specific capability found that is described by this
     /// configuration. For each Configuration, several Instances
@@ -202,6 +744,91 @@ pub struct Configuration {
     /// any Instance
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub properties: HashMap<String, String>,
+
+    /// This restricts discovery to nodes whose labels include
+    /// all of these key-value pairs. If empty, every node
+    /// discovers the capabilities described by this configuration
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub node_selector: HashMap<String, String>,
+
+    /// This limits how many Instances each Agent tracks for this
+    /// configuration. Capabilities found beyond the limit are
+    /// ignored until others disappear. If unset, there is no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_instances: Option<usize>,
+
+    /// This prefixes the name of every environment variable a broker
+    /// is given for a capability's properties and for the broker
+    /// properties below, so that brokers using
+    /// capabilities of several configurations can tell them apart.
+    /// It must be usable as the start of an environment variable name
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_broker_properties_prefix"
+    )]
+    pub broker_properties_prefix: Option<String>,
+
+    /// This defines environment variables that are given to every
+    /// broker of this configuration, such as the address of a service
+    /// the brokers report to. A capability's properties take precedence
+    /// over broker properties of the same name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub broker_properties: HashMap<String, String>,
+}
+
+/// This defines the observed state of a Configuration, as reported by the Agents
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigurationStatus {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<ConfigurationCondition>,
+}
+
+/// This defines a condition of a Configuration, following the Kubernetes conventions for conditions
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigurationCondition {
+    /// Type of the condition, such as `DiscoveryError`
+    #[serde(rename = "type")]
+    pub condition_type: String,
+    /// Whether the condition applies: "True", "False", or "Unknown"
+    pub status: String,
+    /// Human readable details about the condition
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    /// RFC 3339 time at which the condition last changed status
+    pub last_transition_time: String,
+    /// Node whose Agent reported the condition, for conditions that each node reports on its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+}
+
+impl ConfigurationStatus {
+    /// Sets the condition reported by `condition`'s node for `condition`'s type, leaving the conditions reported
+    /// by other nodes alone. The transition time of a condition whose status and message are unchanged is kept,
+    /// and a node that has not reported a condition has nothing to clear, so a `False` condition is only written
+    /// over one the node reported before. Returns whether the status changed.
+    pub fn set_node_condition(&mut self, condition: ConfigurationCondition) -> bool {
+        match self.conditions.iter_mut().find(|existing| {
+            existing.condition_type == condition.condition_type
+                && existing.node_name == condition.node_name
+        }) {
+            Some(existing) => {
+                if existing.status == condition.status && existing.message == condition.message {
+                    return false;
+                }
+                *existing = condition;
+            }
+            None => {
+                if condition.status == "False" {
+                    return false;
+                }
+                self.conditions.push(condition);
+            }
+        }
+        true
+    }
 }
 
 /// Get Configurations for a given namespace
@@ -307,63 +934,481 @@ pub async fn find_configuration(
     }
 }
 
-fn default_capacity() -> i32 {
-    1
-}
-fn default_units() -> String {
-    "pod".to_string()
+/// Update the status of the Configuration with a given name and namespace. The status is only written if the
+/// Configuration is still at `resource_version`, so that status read by one Agent and updated by another in the
+/// meantime is not overwritten; the update fails with a conflict instead.
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::akri::configuration::{self, ConfigurationStatus};
+/// use kube::client::APIClient;
+/// use kube::config;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// configuration::update_configuration_status(
+///     &ConfigurationStatus::default(),
+///     "12345",
+///     "dcc-1",
+///     "default",
+///     &api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn update_configuration_status(
+    status: &ConfigurationStatus,
+    resource_version: &str,
+    name: &str,
+    namespace: &str,
+    kube_client: &APIClient,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    log::trace!("update_configuration_status enter");
+    let akri_config_type = RawApi::customResource(API_CONFIGURATIONS)
+        .group(API_NAMESPACE)
+        .version(API_VERSION)
+        .within(&namespace);
+
+    let status_patch = serde_json::to_vec(&serde_json::json!({
+        "metadata": { "resourceVersion": resource_version },
+        "status": status
+    }))?;
+    let patch_request =
+        akri_config_type.patch_status(name, &PatchParams::default(), status_patch)?;
+    log::trace!("update_configuration_status kube_client.request::<KubeAkriConfig>(akri_config_type.patch_status(...)?).await?");
+    match kube_client.request::<KubeAkriConfig>(patch_request).await {
+        Ok(_config_modified) => {
+            log::trace!("update_configuration_status return");
+            Ok(())
+        }
+        Err(kube::Error::Api(ae)) => {
+            log::trace!(
+                "update_configuration_status kube_client.request returned kube error: {:?}",
+                ae
+            );
+            Err(ae.into())
+        }
+        Err(e) => {
+            log::trace!(
+                "update_configuration_status kube_client.request error: {:?}",
+                e
+            );
+            Err(e.into())
+        }
+    }
 }
 
-#[cfg(test)]
-mod crd_serializeation_tests {
-    use super::super::super::os::file;
-    use super::*;
-    use env_logger;
+/// A problem found when validating a Configuration, naming the offending field
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub field: String,
+    pub message: String,
+}
 
-    #[derive(Serialize, Deserialize, Clone, Debug)]
-    #[serde(rename_all = "camelCase")]
-    struct ConfigurationCRD {
-        api_version: String,
-        kind: String,
-        metadata: HashMap<String, String>,
-        spec: Configuration,
+impl ValidationError {
+    fn new(field: &str, message: String) -> Self {
+        ValidationError {
+            field: field.to_string(),
+            message,
+        }
     }
+}
 
-    #[test]
-    fn test_config_defaults_with_serialization() {
-        let _ = env_logger::builder().is_test(true).try_init();
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
 
-        {
-            if serde_json::from_str::<Configuration>(r#"{}"#).is_ok() {
-                panic!("protocol is required");
-            }
-            if serde_json::from_str::<Configuration>(r#"{"protocol":{"onvif":{}}}"#).is_err() {
-                panic!("onvif protocol doesn't require anything");
-            }
-            if serde_json::from_str::<Configuration>(r#"{"protocol":{"udev":{}}}"#).is_ok() {
-                panic!("udev protocol requires udevRules");
-            }
-            if serde_json::from_str::<Configuration>(r#"{"protocol":{"opcua":{}}}"#).is_ok() {
-                panic!("opcua protocol requires one opcua discovery method");
-            }
+/// Checks a Configuration for values that would otherwise only fail once the Agent acts on it:
+/// a capacity of at least 1, a positive discovery interval, a name that is a valid DNS subdomain,
+/// a namespace that is a valid DNS label, at most one of a broker Pod spec and a broker Job spec,
+/// a broker restart policy with a positive window and backoff and no Include filter lists without
+/// items, which would discover nothing. All problems found are returned.
+///
+/// Example:
+///
+/// ```
+/// use akri_shared::akri::configuration::{validate_configuration, KubeAkriConfig};
+///
+/// let config: KubeAkriConfig = serde_json::from_str(r#"{
+///     "apiVersion": "akri.sh/v0",
+///     "kind": "Configuration",
+///     "metadata": { "name": "akri-debug-echo-foo", "namespace": "default" },
+///     "spec": { "protocol": { "debugEcho": { "descriptions": ["foo0"] } }, "capacity": 0 }
+/// }"#).unwrap();
+/// let errors = validate_configuration(&config).unwrap_err();
+/// assert_eq!("spec.capacity", errors[0].field);
+/// ```
+pub fn validate_configuration(config: &KubeAkriConfig) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    if let Some(message) = check_dns_name(&config.metadata.name, 253, true) {
+        errors.push(ValidationError::new("metadata.name", message));
+    }
+    if let Some(namespace) = &config.metadata.namespace {
+        if let Some(message) = check_dns_name(namespace, 63, false) {
+            errors.push(ValidationError::new("metadata.namespace", message));
         }
-
-        let json = r#"{"protocol":{"onvif":{}}}"#;
-        let deserialized: Configuration = serde_json::from_str(json).unwrap();
-        match deserialized.protocol {
+    }
+    if config.spec.capacity < 1 {
+        errors.push(ValidationError::new(
+            "spec.capacity",
+            format!("must be at least 1 but is {}", config.spec.capacity),
+        ));
+    }
+    if config.spec.broker_pod_spec.is_some() && config.spec.broker_job_spec.is_some() {
+        errors.push(ValidationError::new(
+            "spec.brokerJobSpec",
+            "cannot be set along with spec.brokerPodSpec".to_string(),
+        ));
+    }
+    if let Some(broker_restart_policy) = &config.spec.broker_restart_policy {
+        if broker_restart_policy.window_seconds < 1 {
+            errors.push(ValidationError::new(
+                "spec.brokerRestartPolicy.windowSeconds",
+                "must be at least 1 but is 0".to_string(),
+            ));
+        }
+        if broker_restart_policy.backoff_seconds < 1 {
+            errors.push(ValidationError::new(
+                "spec.brokerRestartPolicy.backoffSeconds",
+                "must be at least 1 but is 0".to_string(),
+            ));
+        }
+    }
+    let discovery_interval_seconds = match &config.spec.protocol {
+        ProtocolHandler::onvif(onvif) => Some(onvif.discovery_interval_seconds),
+        ProtocolHandler::opcua(opcua) => Some(opcua.discovery_interval_seconds),
+        ProtocolHandler::debugEcho(debug_echo) => Some(debug_echo.discovery_interval_seconds),
+        ProtocolHandler::ble(ble) => Some(ble.discovery_interval_seconds),
+        ProtocolHandler::snmp(snmp) => Some(snmp.discovery_interval_seconds),
+        ProtocolHandler::mqtt(mqtt) => Some(mqtt.discovery_interval_seconds),
+        ProtocolHandler::serial(serial) => Some(serial.discovery_interval_seconds),
+        ProtocolHandler::udev(_) => None,
+    };
+    if let Some(discovery_interval_seconds) = discovery_interval_seconds {
+        if discovery_interval_seconds < 1 {
+            errors.push(ValidationError::new(
+                "spec.protocol.discoveryIntervalSeconds",
+                format!("must be at least 1 but is {}", discovery_interval_seconds),
+            ));
+        }
+    }
+    if let ProtocolHandler::mqtt(mqtt) = &config.spec.protocol {
+        if mqtt.qos > 2 {
+            errors.push(ValidationError::new(
+                "spec.protocol.mqtt.qos",
+                format!("must be 0, 1 or 2 but is {}", mqtt.qos),
+            ));
+        }
+    }
+    if let ProtocolHandler::serial(serial) = &config.spec.protocol {
+        if serial.baud_rates.is_empty() {
+            errors.push(ValidationError::new(
+                "spec.protocol.serial.baudRates",
+                "must list at least one baud rate".to_string(),
+            ));
+        }
+    }
+    let filter_lists = match &config.spec.protocol {
+        ProtocolHandler::onvif(onvif) => vec![
+            (
+                "spec.protocol.onvif.ipAddresses",
+                onvif.ip_addresses.as_ref(),
+            ),
+            (
+                "spec.protocol.onvif.macAddresses",
+                onvif.mac_addresses.as_ref(),
+            ),
+            ("spec.protocol.onvif.scopes", onvif.scopes.as_ref()),
+        ],
+        ProtocolHandler::opcua(opcua) => vec![(
+            "spec.protocol.opcua.applicationNames",
+            opcua.application_names.as_ref(),
+        )],
+        _ => Vec::new(),
+    };
+    for (field, filter_list) in filter_lists {
+        if let Some(filter_list) = filter_list {
+            if filter_list.action == FilterType::Include && filter_list.items.is_empty() {
+                errors.push(ValidationError::new(
+                    field,
+                    "an Include filter with no items includes nothing; list the items to include or remove the filter".to_string(),
+                ));
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks a name against the RFC 1123 rules Kubernetes applies to DNS subdomains (which may contain dots)
+/// and DNS labels (which may not), returning a description of the first problem found
+fn check_dns_name(name: &str, max_length: usize, allow_dots: bool) -> Option<String> {
+    if name.is_empty() {
+        return Some("must not be empty".to_string());
+    }
+    if name.len() > max_length {
+        return Some(format!(
+            "must be no more than {} characters but {} has {}",
+            max_length,
+            name,
+            name.len()
+        ));
+    }
+    let is_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    if !name
+        .chars()
+        .all(|c| is_alphanumeric(c) || c == '-' || (allow_dots && c == '.'))
+    {
+        return Some(format!(
+            "{} must consist of lower case alphanumeric characters{}",
+            name,
+            if allow_dots {
+                ", '-' or '.'"
+            } else {
+                " or '-'"
+            }
+        ));
+    }
+    let segments: Vec<&str> = if allow_dots {
+        name.split('.').collect()
+    } else {
+        vec![name]
+    };
+    if segments
+        .iter()
+        .any(|segment| !segment.starts_with(is_alphanumeric) || !segment.ends_with(is_alphanumeric))
+    {
+        return Some(format!(
+            "{} must start and end with an alphanumeric character",
+            name
+        ));
+    }
+    None
+}
+
+/// A field that `KubeAkriConfigBuilder::build` requires but was not given
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuilderError {
+    MissingName,
+    MissingNamespace,
+    MissingProtocol,
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = match self {
+            BuilderError::MissingName => "metadata.name",
+            BuilderError::MissingNamespace => "metadata.namespace",
+            BuilderError::MissingProtocol => "spec.protocol",
+        };
+        write!(f, "{} is required to build a Configuration", field)
+    }
+}
+
+impl std::error::Error for BuilderError {}
+
+/// Builds a `KubeAkriConfig` in code rather than from JSON, which keeps test setup next to the test.
+/// A name, namespace and protocol are required; every other field takes the value
+/// it would get if left out of the Configuration's JSON.
+///
+/// Example:
+///
+/// ```
+/// use akri_shared::akri::configuration::{
+///     DebugEchoDiscoveryHandlerConfig, KubeAkriConfigBuilder, ProtocolHandler,
+/// };
+///
+/// let config = KubeAkriConfigBuilder::new()
+///     .name("akri-debug-echo-foo")
+///     .namespace("default")
+///     .protocol(ProtocolHandler::debugEcho(DebugEchoDiscoveryHandlerConfig {
+///         devices: Vec::new(),
+///         shared: false,
+///         discovery_interval_seconds: 10,
+///         availability_check_path: None,
+///     }))
+///     .capacity(2)
+///     .build()
+///     .unwrap();
+/// assert_eq!("akri-debug-echo-foo", config.metadata.name);
+/// assert_eq!(2, config.spec.capacity);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KubeAkriConfigBuilder {
+    name: Option<String>,
+    namespace: Option<String>,
+    uid: Option<String>,
+    protocol: Option<ProtocolHandler>,
+    capacity: Option<i32>,
+    properties: HashMap<String, String>,
+    node_selector: HashMap<String, String>,
+    max_instances: Option<usize>,
+}
+
+impl KubeAkriConfigBuilder {
+    pub fn new() -> Self {
+        KubeAkriConfigBuilder::default()
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    pub fn uid(mut self, uid: &str) -> Self {
+        self.uid = Some(uid.to_string());
+        self
+    }
+
+    pub fn protocol(mut self, protocol: ProtocolHandler) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    pub fn capacity(mut self, capacity: i32) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    pub fn properties(mut self, properties: HashMap<String, String>) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    pub fn node_selector(mut self, node_selector: HashMap<String, String>) -> Self {
+        self.node_selector = node_selector;
+        self
+    }
+
+    pub fn max_instances(mut self, max_instances: usize) -> Self {
+        self.max_instances = Some(max_instances);
+        self
+    }
+
+    pub fn build(self) -> Result<KubeAkriConfig, BuilderError> {
+        let name = self.name.ok_or(BuilderError::MissingName)?;
+        let namespace = self.namespace.ok_or(BuilderError::MissingNamespace)?;
+        let protocol = self.protocol.ok_or(BuilderError::MissingProtocol)?;
+        Ok(KubeAkriConfig {
+            metadata: ObjectMeta {
+                name,
+                namespace: Some(namespace),
+                uid: self.uid,
+                ..Default::default()
+            },
+            spec: Configuration {
+                protocol,
+                capacity: self.capacity.unwrap_or_else(default_capacity),
+                units: default_units(),
+                broker_pod_spec: None,
+                broker_job_spec: None,
+                broker_restart_policy: None,
+                instance_service_spec: None,
+                configuration_service_spec: None,
+                properties: self.properties,
+                node_selector: self.node_selector,
+                max_instances: self.max_instances,
+                broker_properties_prefix: None,
+                broker_properties: HashMap::new(),
+            },
+            status: None,
+            types: TypeMeta {
+                apiVersion: Some(format!("{}/{}", API_NAMESPACE, API_VERSION)),
+                kind: Some("Configuration".to_string()),
+            },
+        })
+    }
+}
+
+/// Deserializes a `brokerPropertiesPrefix`, rejecting prefixes that cannot start an environment variable name
+fn deserialize_broker_properties_prefix<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let prefix = <Option<String> as serde::Deserialize>::deserialize(deserializer)?;
+    if let Some(prefix) = &prefix {
+        let starts_name = |c: char| c.is_ascii_alphabetic() || c == '_';
+        if !prefix.starts_with(starts_name)
+            || !prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(serde::de::Error::custom(format!(
+                "brokerPropertiesPrefix {} must start with a letter or '_' and consist of alphanumeric characters or '_'",
+                prefix
+            )));
+        }
+    }
+    Ok(prefix)
+}
+
+fn default_capacity() -> i32 {
+    1
+}
+fn default_units() -> String {
+    "pod".to_string()
+}
+
+#[cfg(test)]
+mod crd_serializeation_tests {
+    use super::super::super::os::file;
+    use super::*;
+    use env_logger;
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    struct ConfigurationCRD {
+        api_version: String,
+        kind: String,
+        metadata: HashMap<String, String>,
+        spec: Configuration,
+    }
+
+    #[test]
+    fn test_config_defaults_with_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        {
+            if serde_json::from_str::<Configuration>(r#"{}"#).is_ok() {
+                panic!("protocol is required");
+            }
+            if serde_json::from_str::<Configuration>(r#"{"protocol":{"onvif":{}}}"#).is_err() {
+                panic!("onvif protocol doesn't require anything");
+            }
+            if serde_json::from_str::<Configuration>(r#"{"protocol":{"udev":{}}}"#).is_ok() {
+                panic!("udev protocol requires udevRules");
+            }
+            if serde_json::from_str::<Configuration>(r#"{"protocol":{"opcua":{}}}"#).is_ok() {
+                panic!("opcua protocol requires one opcua discovery method");
+            }
+        }
+
+        let json = r#"{"protocol":{"onvif":{}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match deserialized.protocol {
             ProtocolHandler::onvif(_) => {}
             _ => panic!("protocol should be Onvif"),
         }
         assert_eq!(default_capacity(), deserialized.capacity);
         assert_eq!(default_units(), deserialized.units);
         assert_eq!(None, deserialized.broker_pod_spec);
+        assert_eq!(None, deserialized.broker_job_spec);
         assert_eq!(None, deserialized.instance_service_spec);
         assert_eq!(None, deserialized.configuration_service_spec);
         assert_eq!(0, deserialized.properties.len());
 
         let serialized = serde_json::to_string(&deserialized).unwrap();
-        let expected_deserialized =
-            r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":1}},"capacity":1,"units":"pod"}"#;
+        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":1,"discoveryIntervalSeconds":10,"queryCacheTtlSeconds":60,"includeStreamUri":false,"includeDeviceInfo":false,"probeDelayMs":0}},"capacity":1,"units":"pod"}"#;
         assert_eq!(expected_deserialized, serialized);
     }
 
@@ -371,11 +1416,12 @@ mod crd_serializeation_tests {
     fn test_config_serialization() {
         let _ = env_logger::builder().is_test(true).try_init();
 
-        let json = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":5}}, "capacity":4, "units":"slaphappies"}"#;
+        let json = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":5,"discoveryIntervalSeconds":30}}, "capacity":4, "units":"slaphappies"}"#;
         let deserialized: Configuration = serde_json::from_str(json).unwrap();
         match &deserialized.protocol {
             ProtocolHandler::onvif(discovery_handler_config) => {
                 assert_eq!(discovery_handler_config.discovery_timeout_seconds, 5);
+                assert_eq!(discovery_handler_config.discovery_interval_seconds, 30);
             }
             _ => panic!("protocol should be Onvif"),
         }
@@ -385,9 +1431,176 @@ mod crd_serializeation_tests {
         assert_eq!(None, deserialized.instance_service_spec);
         assert_eq!(None, deserialized.configuration_service_spec);
         assert_eq!(0, deserialized.properties.len());
+        assert_eq!(0, deserialized.node_selector.len());
+        assert_eq!(None, deserialized.max_instances);
+
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":5,"discoveryIntervalSeconds":30,"queryCacheTtlSeconds":60,"includeStreamUri":false,"includeDeviceInfo":false,"probeDelayMs":0}},"capacity":4,"units":"slaphappies"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test node selector
+        let json = r#"{"protocol":{"debugEcho":{"descriptions":["foo0"]}},"nodeSelector":{"kubernetes.io/hostname":"node-a"}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            Some(&"node-a".to_string()),
+            deserialized.node_selector.get("kubernetes.io/hostname")
+        );
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0"}],"shared":false,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod","nodeSelector":{"kubernetes.io/hostname":"node-a"}}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test max instances
+        let json = r#"{"protocol":{"debugEcho":{"descriptions":["foo0"]}},"maxInstances":50}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        assert_eq!(Some(50), deserialized.max_instances);
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0"}],"shared":false,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod","maxInstances":50}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test broker properties prefix
+        let json = r#"{"protocol":{"debugEcho":{"descriptions":["foo0"]}},"brokerPropertiesPrefix":"CAMERA_1_"}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            Some("CAMERA_1_".to_string()),
+            deserialized.broker_properties_prefix
+        );
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0"}],"shared":false,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod","brokerPropertiesPrefix":"CAMERA_1_"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test broker properties
+        let json = r#"{"protocol":{"debugEcho":{"descriptions":["foo0"]}},"brokerProperties":{"INFERENCE_SERVER_URL":"http://inference:8080"}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            Some(&"http://inference:8080".to_string()),
+            deserialized.broker_properties.get("INFERENCE_SERVER_URL")
+        );
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0"}],"shared":false,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod","brokerProperties":{"INFERENCE_SERVER_URL":"http://inference:8080"}}"#;
+        assert_eq!(expected_deserialized, serialized);
+        for invalid_prefix in &["", "1CAMERA_", "CAMERA-1_", "CAMERA 1"] {
+            let json = format!(
+                r#"{{"protocol":{{"debugEcho":{{"descriptions":["foo0"]}}}},"brokerPropertiesPrefix":"{}"}}"#,
+                invalid_prefix
+            );
+            assert!(serde_json::from_str::<Configuration>(&json).is_err());
+        }
+    }
+
+    #[test]
+    fn test_broker_job_spec_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0"}]}},"brokerJobSpec":{"backoffLimit":2,"template":{"spec":{"containers":[{"image":"provisioner","name":"provisioner"}],"restartPolicy":"Never"}},"ttlSecondsAfterFinished":600}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        assert_eq!(None, deserialized.broker_pod_spec);
+        let job_spec = deserialized.broker_job_spec.as_ref().unwrap();
+        assert_eq!(Some(2), job_spec.backoff_limit);
+        assert_eq!(Some(600), job_spec.ttl_seconds_after_finished);
+        assert_eq!(
+            Some("Never".to_string()),
+            job_spec.template.spec.as_ref().unwrap().restart_policy
+        );
+
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        assert!(serialized.contains(r#""brokerJobSpec":{"backoffLimit":2,"#));
+    }
+
+    #[test]
+    fn test_broker_restart_policy_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"protocol":{"onvif":{}},"brokerRestartPolicy":{"maxRestarts":3,"backoffSeconds":300}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            Some(BrokerRestartPolicy {
+                max_restarts: 3,
+                window_seconds: 600,
+                backoff_seconds: 300,
+            }),
+            deserialized.broker_restart_policy
+        );
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        assert!(serialized.contains(
+            r#""brokerRestartPolicy":{"maxRestarts":3,"windowSeconds":600,"backoffSeconds":300}"#
+        ));
+
+        assert!(serde_json::from_str::<Configuration>(
+            r#"{"protocol":{"onvif":{}},"brokerRestartPolicy":{"maxRestarts":3}}"#
+        )
+        .is_err());
+        assert!(serde_json::from_str::<Configuration>(
+            r#"{"protocol":{"onvif":{}},"brokerRestartPolicy":{"maxRestarts":3,"backoffSeconds":300,"backoff":1}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_onvif_credentials_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"protocol":{"onvif":{"credentials":{"username":"admin","passwordFile":"/etc/akri/onvif/password"}}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::onvif(discovery_handler_config) => {
+                let credentials = discovery_handler_config.credentials.as_ref().unwrap();
+                assert_eq!("admin", credentials.username);
+                assert_eq!(None, credentials.password);
+                assert_eq!(
+                    Some("/etc/akri/onvif/password".to_string()),
+                    credentials.password_file
+                );
+            }
+            _ => panic!("protocol should be Onvif"),
+        }
+
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":1,"discoveryIntervalSeconds":10,"queryCacheTtlSeconds":60,"credentials":{"username":"admin","passwordFile":"/etc/akri/onvif/password"},"includeStreamUri":false,"includeDeviceInfo":false,"probeDelayMs":0}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test pacing of WS-Discovery
+        let json = r#"{"protocol":{"onvif":{"maxCameras":100,"probeDelayMs":500}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::onvif(discovery_handler_config) => {
+                assert_eq!(Some(100), discovery_handler_config.max_cameras);
+                assert_eq!(500, discovery_handler_config.probe_delay_ms);
+            }
+            _ => panic!("protocol should be Onvif"),
+        }
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":1,"discoveryIntervalSeconds":10,"queryCacheTtlSeconds":60,"includeStreamUri":false,"includeDeviceInfo":false,"maxCameras":100,"probeDelayMs":500}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test WS-Discovery probe interface and time to live
+        let json = r#"{"protocol":{"onvif":{"networkInterface":"eth1","multicastTtl":4}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::onvif(discovery_handler_config) => {
+                assert_eq!(
+                    Some("eth1".to_string()),
+                    discovery_handler_config.network_interface
+                );
+                assert_eq!(Some(4), discovery_handler_config.multicast_ttl);
+            }
+            _ => panic!("protocol should be Onvif"),
+        }
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":1,"discoveryIntervalSeconds":10,"queryCacheTtlSeconds":60,"includeStreamUri":false,"includeDeviceInfo":false,"probeDelayMs":0,"networkInterface":"eth1","multicastTtl":4}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
 
+        // test preferred networks
+        let json = r#"{"protocol":{"onvif":{"preferredNetworks":["10.0.0.0/8"]}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
         let serialized = serde_json::to_string(&deserialized).unwrap();
-        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":5}},"capacity":4,"units":"slaphappies"}"#;
+        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":1,"discoveryIntervalSeconds":10,"queryCacheTtlSeconds":60,"includeStreamUri":false,"includeDeviceInfo":false,"probeDelayMs":0,"preferredNetworks":["10.0.0.0/8"]}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test group by scope
+        let json = r#"{"protocol":{"onvif":{"groupByScope":"onvif://www.onvif.org/group/"}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"onvif":{"discoveryTimeoutSeconds":1,"discoveryIntervalSeconds":10,"queryCacheTtlSeconds":60,"includeStreamUri":false,"includeDeviceInfo":false,"probeDelayMs":0,"groupByScope":"onvif://www.onvif.org/group/"}},"capacity":1,"units":"pod"}"#;
         assert_eq!(expected_deserialized, serialized);
     }
 
@@ -396,7 +1609,7 @@ mod crd_serializeation_tests {
     fn test_opcua_config_serialization() {
         let _ = env_logger::builder().is_test(true).try_init();
         // test standard discovery method
-        let standard_discovery_json = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls": ["opc.tcp://127.0.0.1:4855/"]}}, "applicationNames": { "action": "Exclude", "items": ["Some application name"]}}}, "capacity":4, "units":"slaphappies"}"#;
+        let standard_discovery_json = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls": ["opc.tcp://127.0.0.1:4855/"]}}, "applicationNames": { "action": "Exclude", "items": ["Some application name"]}, "discoveryIntervalSeconds": 30}}, "capacity":4, "units":"slaphappies"}"#;
         let deserialized: Configuration = serde_json::from_str(standard_discovery_json).unwrap();
         match &deserialized.protocol {
             ProtocolHandler::opcua(discovery_handler_config) => {
@@ -422,7 +1635,7 @@ mod crd_serializeation_tests {
         assert_eq!(0, deserialized.properties.len());
 
         let serialized = serde_json::to_string(&deserialized).unwrap();
-        let expected_deserialized = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls":["opc.tcp://127.0.0.1:4855/"]}},"applicationNames":{"items":["Some application name"],"action":"Exclude"}}},"capacity":4,"units":"slaphappies"}"#;
+        let expected_deserialized = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls":["opc.tcp://127.0.0.1:4855/"],"discoveryUrlTimeoutSeconds":5}},"applicationNames":{"items":["Some application name"],"action":"Exclude"},"discoveryIntervalSeconds":30,"includeServerStatus":false}},"capacity":4,"units":"slaphappies"}"#;
         assert_eq!(expected_deserialized, serialized);
 
         // test standard discovery method with default of LDS DiscoveryURL
@@ -444,8 +1657,318 @@ mod crd_serializeation_tests {
         }
 
         let serialized = serde_json::to_string(&deserialized).unwrap();
-        let expected_deserialized = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls":["opc.tcp://localhost:4840/"]}}}},"capacity":4,"units":"slaphappies"}"#;
+        let expected_deserialized = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls":["opc.tcp://localhost:4840/"],"discoveryUrlTimeoutSeconds":5}},"discoveryIntervalSeconds":10,"includeServerStatus":false}},"capacity":4,"units":"slaphappies"}"#;
         assert_eq!(expected_deserialized, serialized);
+
+        // test per-server capacities
+        let server_capacities_json = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{}},"serverCapacities":{"opc.tcp://10.0.0.1:4840/":8}}}}"#;
+        let deserialized: Configuration = serde_json::from_str(server_capacities_json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::opcua(discovery_handler_config) => {
+                assert_eq!(
+                    Some(&8),
+                    discovery_handler_config
+                        .server_capacities
+                        .get("opc.tcp://10.0.0.1:4840/")
+                );
+            }
+            _ => panic!("protocol should be opcua"),
+        }
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls":["opc.tcp://localhost:4840/"],"discoveryUrlTimeoutSeconds":5}},"discoveryIntervalSeconds":10,"serverCapacities":{"opc.tcp://10.0.0.1:4840/":8},"includeServerStatus":false}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test client credentials
+        let credentials_json = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{}},"certificatePath":"/etc/opcua/cert.der","privateKeyPath":"/etc/opcua/private.pem"}}}"#;
+        let deserialized: Configuration = serde_json::from_str(credentials_json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::opcua(discovery_handler_config) => {
+                assert_eq!(
+                    Some("/etc/opcua/cert.der"),
+                    discovery_handler_config.certificate_path.as_deref()
+                );
+                assert_eq!(
+                    Some("/etc/opcua/private.pem"),
+                    discovery_handler_config.private_key_path.as_deref()
+                );
+            }
+            _ => panic!("protocol should be opcua"),
+        }
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{"discoveryUrls":["opc.tcp://localhost:4840/"],"discoveryUrlTimeoutSeconds":5}},"discoveryIntervalSeconds":10,"certificatePath":"/etc/opcua/cert.der","privateKeyPath":"/etc/opcua/private.pem","includeServerStatus":false}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        // test including server status
+        let server_status_json = r#"{"protocol":{"opcua":{"opcuaDiscoveryMethod":{"standard":{}},"includeServerStatus":true}}}"#;
+        let deserialized: Configuration = serde_json::from_str(server_status_json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::opcua(discovery_handler_config) => {
+                assert!(discovery_handler_config.include_server_status);
+            }
+            _ => panic!("protocol should be opcua"),
+        }
+    }
+
+    #[test]
+    fn test_debug_echo_config_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"protocol":{"debugEcho":{"descriptions":["foo0"],"shared":true}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0"}],"shared":true,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        let json = r#"{"protocol":{"debugEcho":{"descriptions":["foo0"],"shared":true,"discoveryIntervalSeconds":2}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::debugEcho(discovery_handler_config) => {
+                assert_eq!(discovery_handler_config.discovery_interval_seconds, 2);
+            }
+            _ => panic!("protocol should be debugEcho"),
+        }
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0"}],"shared":true,"discoveryIntervalSeconds":2}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+    }
+
+    #[test]
+    fn test_debug_echo_device_properties_round_trip() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0","properties":{"VENDOR":"acme"},"shared":true},"foo1",{"id":"foo2","shared":false}]}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        let expected_devices = vec![
+            DebugEchoDevice {
+                id: "foo0".to_string(),
+                properties: vec![("VENDOR".to_string(), "acme".to_string())]
+                    .into_iter()
+                    .collect(),
+                shared: Some(true),
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+            },
+            DebugEchoDevice {
+                id: "foo1".to_string(),
+                properties: HashMap::new(),
+                shared: None,
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+            },
+            DebugEchoDevice {
+                id: "foo2".to_string(),
+                properties: HashMap::new(),
+                shared: Some(false),
+                device_specs: Vec::new(),
+                mounts: Vec::new(),
+            },
+        ];
+        match &deserialized.protocol {
+            ProtocolHandler::debugEcho(discovery_handler_config) => {
+                assert_eq!(expected_devices, discovery_handler_config.devices);
+            }
+            _ => panic!("protocol should be debugEcho"),
+        }
+
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_serialized = r#"{"protocol":{"debugEcho":{"devices":[{"id":"foo0","properties":{"VENDOR":"acme"},"shared":true},{"id":"foo1"},{"id":"foo2","shared":false}],"shared":false,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_serialized, serialized);
+        let round_tripped: Configuration = serde_json::from_str(&serialized).unwrap();
+        match &round_tripped.protocol {
+            ProtocolHandler::debugEcho(discovery_handler_config) => {
+                assert_eq!(expected_devices, discovery_handler_config.devices);
+            }
+            _ => panic!("protocol should be debugEcho"),
+        }
+    }
+
+    #[test]
+    fn test_ble_config_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"protocol":{"ble":{}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"ble":{"scanDurationSecs":5,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        let json = r#"{"protocol":{"ble":{"serviceUuids":["180d"],"namePrefix":"Polar","scanDurationSecs":2}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::ble(discovery_handler_config) => {
+                assert_eq!(vec!["180d"], discovery_handler_config.service_uuids);
+                assert_eq!(
+                    Some("Polar"),
+                    discovery_handler_config.name_prefix.as_deref()
+                );
+                assert_eq!(2, discovery_handler_config.scan_duration_secs);
+            }
+            _ => panic!("protocol should be ble"),
+        }
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"ble":{"serviceUuids":["180d"],"namePrefix":"Polar","scanDurationSecs":2,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+    }
+
+    #[test]
+    fn test_snmp_config_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        if serde_json::from_str::<Configuration>(r#"{"protocol":{"snmp":{}}}"#).is_ok() {
+            panic!("snmp protocol requires subnets");
+        }
+
+        let json = r#"{"protocol":{"snmp":{"subnets":["10.0.0.0/24"]}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"snmp":{"subnets":["10.0.0.0/24"],"version":{"v2c":{"community":"public"}},"port":161,"requestTimeoutSeconds":1,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        let json = r#"{"protocol":{"snmp":{"subnets":["10.0.0.1"],"version":{"v2c":{"community":"private"}},"oidFilter":"1.3.6.1.4.1.9","port":1161}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::snmp(discovery_handler_config) => {
+                assert_eq!(
+                    SnmpVersion::v2c(SnmpV2cSecurity {
+                        community: "private".to_string()
+                    }),
+                    discovery_handler_config.version
+                );
+                assert_eq!(
+                    Some("1.3.6.1.4.1.9"),
+                    discovery_handler_config.oid_filter.as_deref()
+                );
+                assert_eq!(1161, discovery_handler_config.port);
+            }
+            _ => panic!("protocol should be snmp"),
+        }
+
+        let json = r#"{"protocol":{"snmp":{"subnets":["10.0.0.1"],"version":{"v3":{}}}}}"#;
+        assert!(serde_json::from_str::<Configuration>(json).is_err());
+    }
+
+    #[test]
+    fn test_mqtt_config_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        if serde_json::from_str::<Configuration>(r#"{"protocol":{"mqtt":{"topicPattern":"a/#"}}}"#)
+            .is_ok()
+        {
+            panic!("mqtt protocol requires brokerUrl");
+        }
+
+        let json = r#"{"protocol":{"mqtt":{"brokerUrl":"mqtt://broker:1883","topicPattern":"devices/+/announce"}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"mqtt":{"brokerUrl":"mqtt://broker:1883","topicPattern":"devices/+/announce","clientId":"akri","qos":1,"idPath":"$.id","propertiesPath":"$.properties","discoveryIntervalSeconds":10}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        let json = r#"{"protocol":{"mqtt":{"brokerUrl":"mqtt://broker","topicPattern":"homeassistant/#","username":"akri","password":"secret","qos":0,"idPath":"$.device.identifiers[0]","offlinePayload":"offline"}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::mqtt(discovery_handler_config) => {
+                assert_eq!(Some("akri"), discovery_handler_config.username.as_deref());
+                assert_eq!(0, discovery_handler_config.qos);
+                assert_eq!("$.device.identifiers[0]", discovery_handler_config.id_path);
+                assert_eq!(
+                    Some("offline"),
+                    discovery_handler_config.offline_payload.as_deref()
+                );
+            }
+            _ => panic!("protocol should be mqtt"),
+        }
+    }
+
+    #[test]
+    fn test_serial_config_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        if serde_json::from_str::<Configuration>(r#"{"protocol":{"serial":{}}}"#).is_ok() {
+            panic!("serial protocol requires portPattern");
+        }
+
+        let json = r#"{"protocol":{"serial":{"portPattern":"/dev/ttyUSB*"}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        let expected_deserialized = r#"{"protocol":{"serial":{"portPattern":"/dev/ttyUSB*","baudRates":[9600],"probeTimeoutMs":500,"discoveryIntervalSeconds":10}},"capacity":1,"units":"pod"}"#;
+        assert_eq!(expected_deserialized, serialized);
+
+        let json = r#"{"protocol":{"serial":{"portPattern":"/dev/ttyS*","baudRates":[115200,9600],"probeCommand":"*IDN?\r\n","responseContains":"ACME"}}}"#;
+        let deserialized: Configuration = serde_json::from_str(json).unwrap();
+        match &deserialized.protocol {
+            ProtocolHandler::serial(discovery_handler_config) => {
+                assert_eq!(vec![115200, 9600], discovery_handler_config.baud_rates);
+                assert_eq!(
+                    Some("*IDN?\r\n"),
+                    discovery_handler_config.probe_command.as_deref()
+                );
+                assert_eq!(
+                    Some("ACME"),
+                    discovery_handler_config.response_contains.as_deref()
+                );
+            }
+            _ => panic!("protocol should be serial"),
+        }
+    }
+
+    #[test]
+    fn test_configuration_status_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"apiVersion":"akri.sh/v0","kind":"Configuration","metadata":{"name":"config-a"},"spec":{"protocol":{"debugEcho":{"descriptions":["foo0"]}}},"status":{"conditions":[{"type":"DiscoveryError","status":"True","message":"invalid argument","lastTransitionTime":"2020-01-01T00:00:00Z","nodeName":"node-a"}]}}"#;
+        let config: KubeAkriConfig = serde_json::from_str(json).unwrap();
+        let status = config.status.unwrap();
+        assert_eq!(
+            vec![ConfigurationCondition {
+                condition_type: DISCOVERY_ERROR_CONDITION.to_string(),
+                status: "True".to_string(),
+                message: "invalid argument".to_string(),
+                last_transition_time: "2020-01-01T00:00:00Z".to_string(),
+                node_name: Some("node-a".to_string()),
+            }],
+            status.conditions
+        );
+        assert_eq!(
+            r#"{"conditions":[{"type":"DiscoveryError","status":"True","message":"invalid argument","lastTransitionTime":"2020-01-01T00:00:00Z","nodeName":"node-a"}]}"#,
+            serde_json::to_string(&status).unwrap()
+        );
+
+        // Configurations without a status still deserialize
+        let json = r#"{"apiVersion":"akri.sh/v0","kind":"Configuration","metadata":{"name":"config-a"},"spec":{"protocol":{"debugEcho":{"descriptions":["foo0"]}}}}"#;
+        let config: KubeAkriConfig = serde_json::from_str(json).unwrap();
+        assert!(config.status.is_none());
+    }
+
+    #[test]
+    fn test_set_node_condition() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let condition =
+            |node_name: &str, status: &str, message: &str, time: &str| ConfigurationCondition {
+                condition_type: DISCOVERY_ERROR_CONDITION.to_string(),
+                status: status.to_string(),
+                message: message.to_string(),
+                last_transition_time: time.to_string(),
+                node_name: Some(node_name.to_string()),
+            };
+        let mut status = ConfigurationStatus::default();
+        // A node without a condition has nothing to clear
+        assert!(!status.set_node_condition(condition("node-a", "False", "", "t0")));
+        assert!(status.conditions.is_empty());
+
+        assert!(status.set_node_condition(condition("node-a", "True", "failed", "t1")));
+        assert!(status.set_node_condition(condition("node-b", "True", "failed", "t1")));
+        // Reporting the same error again keeps its transition time
+        assert!(!status.set_node_condition(condition("node-a", "True", "failed", "t2")));
+        // A node recovering only clears its own condition
+        assert!(status.set_node_condition(condition("node-b", "False", "", "t3")));
+        assert_eq!(
+            vec![
+                condition("node-a", "True", "failed", "t1"),
+                condition("node-b", "False", "", "t3")
+            ],
+            status.conditions
+        );
     }
 
     #[test]
@@ -455,6 +1978,7 @@ mod crd_serializeation_tests {
         let files = [
             "../test/yaml/akri-onvif-video.yaml",
             "../test/yaml/akri-debug-echo-foo.yaml",
+            "../test/yaml/akri-debug-echo-gpu.yaml",
             "../test/yaml/akri-udev-video.yaml",
             "../test/yaml/akri-opcua.yaml",
         ];
@@ -547,6 +2071,7 @@ mod crd_serializeation_tests {
         let exclude_filter_list = Some(FilterList {
             items: exclude_items,
             action: FilterType::Exclude,
+            match_type: MatchType::Literal,
         });
         assert_eq!(should_include(exclude_filter_list.as_ref(), "beep"), false);
         assert_eq!(should_include(exclude_filter_list.as_ref(), "bop"), false);
@@ -557,6 +2082,7 @@ mod crd_serializeation_tests {
         let empty_exclude_filter_list = Some(FilterList {
             items: empty_exclude_items,
             action: FilterType::Exclude,
+            match_type: MatchType::Literal,
         });
         assert_eq!(
             should_include(empty_exclude_filter_list.as_ref(), "beep"),
@@ -568,6 +2094,7 @@ mod crd_serializeation_tests {
         let include_filter_list = Some(FilterList {
             items: include_items,
             action: FilterType::Include,
+            match_type: MatchType::Literal,
         });
         assert_eq!(should_include(include_filter_list.as_ref(), "beep"), true);
         assert_eq!(should_include(include_filter_list.as_ref(), "bop"), true);
@@ -578,6 +2105,7 @@ mod crd_serializeation_tests {
         let empty_include_filter_list = Some(FilterList {
             items: empty_include_items,
             action: FilterType::Include,
+            match_type: MatchType::Literal,
         });
         assert_eq!(
             should_include(empty_include_filter_list.as_ref(), "beep"),
@@ -586,5 +2114,387 @@ mod crd_serializeation_tests {
 
         // Test when None
         assert_eq!(should_include(None, "beep"), true);
+
+        // Test glob patterns, which must match the whole item
+        let glob_filter_list = Some(FilterList {
+            items: vec!["b??p".to_string(), "bo*".to_string(), "[xy]ip".to_string()],
+            action: FilterType::Include,
+            match_type: MatchType::Glob,
+        });
+        assert_eq!(should_include(glob_filter_list.as_ref(), "beep"), true);
+        assert_eq!(should_include(glob_filter_list.as_ref(), "bop"), true);
+        assert_eq!(should_include(glob_filter_list.as_ref(), "yip"), true);
+        assert_eq!(should_include(glob_filter_list.as_ref(), "zip"), false);
+        assert_eq!(should_include(glob_filter_list.as_ref(), "a-bop"), false);
+
+        // Test exact matches, which must equal the whole item
+        let exact_filter_list = Some(FilterList {
+            items: vec!["beep".to_string()],
+            action: FilterType::Exclude,
+            match_type: MatchType::Exact,
+        });
+        assert_eq!(should_include(exact_filter_list.as_ref(), "beep"), false);
+        assert_eq!(should_include(exact_filter_list.as_ref(), "beeps"), true);
+
+        // Test regexes, which match any part of the item unless anchored
+        let regex_filter_list = Some(FilterList {
+            items: vec!["e{2}".to_string(), "^bo+p$".to_string()],
+            action: FilterType::Include,
+            match_type: MatchType::Regex,
+        });
+        assert_eq!(should_include(regex_filter_list.as_ref(), "beep"), true);
+        assert_eq!(should_include(regex_filter_list.as_ref(), "boooop"), true);
+        assert_eq!(should_include(regex_filter_list.as_ref(), "boop!"), false);
+
+        // Test that an invalid glob pattern matches nothing
+        let invalid_glob_filter_list = Some(FilterList {
+            items: vec!["[beep".to_string()],
+            action: FilterType::Exclude,
+            match_type: MatchType::Glob,
+        });
+        assert_eq!(
+            should_include(invalid_glob_filter_list.as_ref(), "[beep"),
+            true
+        );
+    }
+
+    #[test]
+    fn test_should_include_values() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let filter_list = |action: FilterType, items: &[&str]| FilterList {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            action,
+            match_type: MatchType::Literal,
+        };
+        let values = vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        // (filter list, values, expected for substring literal matches)
+        let cases = vec![
+            (None, values.clone(), true),
+            (None, Vec::new(), true),
+            (
+                Some(filter_list(FilterType::Include, &[])),
+                values.clone(),
+                false,
+            ),
+            (
+                Some(filter_list(FilterType::Include, &[])),
+                Vec::new(),
+                false,
+            ),
+            (
+                Some(filter_list(FilterType::Exclude, &[])),
+                values.clone(),
+                true,
+            ),
+            (
+                Some(filter_list(FilterType::Exclude, &[])),
+                Vec::new(),
+                true,
+            ),
+            (
+                Some(filter_list(FilterType::Include, &["0.0.2"])),
+                values.clone(),
+                true,
+            ),
+            (
+                Some(filter_list(FilterType::Include, &["0.0.3"])),
+                values.clone(),
+                false,
+            ),
+            (
+                Some(filter_list(FilterType::Include, &["0.0.2"])),
+                Vec::new(),
+                false,
+            ),
+            (
+                Some(filter_list(FilterType::Exclude, &["0.0.2"])),
+                values.clone(),
+                false,
+            ),
+            (
+                Some(filter_list(FilterType::Exclude, &["0.0.3"])),
+                values.clone(),
+                true,
+            ),
+            (
+                Some(filter_list(FilterType::Exclude, &["0.0.2"])),
+                Vec::new(),
+                true,
+            ),
+        ];
+        for (filter_list, values, expected) in cases {
+            assert_eq!(
+                expected,
+                should_include_values(filter_list.as_ref(), &values, |item, value| value
+                    .contains(item)),
+                "filter list {:?} with values {:?}",
+                filter_list,
+                values
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_list_match_type_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"items":["192.168.*.10"],"action":"Include","matchType":"Glob"}"#;
+        let deserialized: FilterList = serde_json::from_str(json).unwrap();
+        assert_eq!(MatchType::Glob, deserialized.match_type);
+        assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+
+        // Literal matching is the default and is not serialized
+        let json = r#"{"items":["10.0.0.1"],"action":"Exclude"}"#;
+        let deserialized: FilterList = serde_json::from_str(json).unwrap();
+        assert_eq!(MatchType::Literal, deserialized.match_type);
+        assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+
+        let json = r#"{"items":["^192\\.168\\.1\\.10$"],"action":"Include","matchType":"Regex"}"#;
+        let deserialized: FilterList = serde_json::from_str(json).unwrap();
+        assert_eq!(MatchType::Regex, deserialized.match_type);
+        assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+
+        // Invalid patterns are rejected, as are unknown fields
+        assert!(serde_json::from_str::<FilterList>(
+            r#"{"items":["192.168.(1"],"matchType":"Regex"}"#
+        )
+        .is_err());
+        assert!(
+            serde_json::from_str::<FilterList>(r#"{"items":["[beep"],"matchType":"Glob"}"#)
+                .is_err()
+        );
+        assert!(
+            serde_json::from_str::<FilterList>(r#"{"items":["beep"],"matchType":"Fuzzy"}"#)
+                .is_err()
+        );
+        assert!(serde_json::from_str::<FilterList>(r#"{"items":["beep"],"other":true}"#).is_err());
+        // Patterns are not checked by literal or exact filter lists
+        assert!(
+            serde_json::from_str::<FilterList>(r#"{"items":["[beep"],"matchType":"Exact"}"#)
+                .is_ok()
+        );
+    }
+
+    fn load_config_a() -> KubeAkriConfig {
+        let json = file::read_file_to_string("../test/json/config-a.json");
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_validate_configuration() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        assert!(validate_configuration(&load_config_a()).is_ok());
+
+        let mut config = load_config_a();
+        config.metadata.name = "akri.debug-echo.foo".to_string();
+        assert!(validate_configuration(&config).is_ok());
+
+        let mut config = load_config_a();
+        config.metadata.name = "Config_A".to_string();
+        config.metadata.namespace = Some("config.a".to_string());
+        config.spec.capacity = 0;
+        if let ProtocolHandler::debugEcho(debug_echo) = &mut config.spec.protocol {
+            debug_echo.discovery_interval_seconds = 0;
+        }
+        let fields: Vec<String> = validate_configuration(&config)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(
+            vec![
+                "metadata.name",
+                "metadata.namespace",
+                "spec.capacity",
+                "spec.protocol.discoveryIntervalSeconds"
+            ],
+            fields
+        );
+
+        // Include filter lists without items are rejected, Exclude filter lists without items are not
+        let empty_filter_list = |action: FilterType| FilterList {
+            items: Vec::new(),
+            action,
+            match_type: MatchType::Literal,
+        };
+        let mut config = load_config_a();
+        config.spec.protocol = ProtocolHandler::onvif(OnvifDiscoveryHandlerConfig {
+            ip_addresses: Some(empty_filter_list(FilterType::Include)),
+            mac_addresses: Some(empty_filter_list(FilterType::Exclude)),
+            scopes: Some(empty_filter_list(FilterType::Include)),
+            ..serde_json::from_str("{}").unwrap()
+        });
+        let fields: Vec<String> = validate_configuration(&config)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(
+            vec![
+                "spec.protocol.onvif.ipAddresses",
+                "spec.protocol.onvif.scopes"
+            ],
+            fields
+        );
+
+        let mut config = load_config_a();
+        config.spec.protocol = ProtocolHandler::opcua(OpcuaDiscoveryHandlerConfig {
+            application_names: Some(empty_filter_list(FilterType::Include)),
+            ..serde_json::from_str(r#"{"opcuaDiscoveryMethod":{"standard":{}}}"#).unwrap()
+        });
+        let fields: Vec<String> = validate_configuration(&config)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(vec!["spec.protocol.opcua.applicationNames"], fields);
+
+        let mut config = load_config_a();
+        config.spec.protocol = ProtocolHandler::mqtt(MqttDiscoveryHandlerConfig {
+            qos: 3,
+            ..serde_json::from_str(r#"{"brokerUrl":"mqtt://broker","topicPattern":"a/#"}"#).unwrap()
+        });
+        let fields: Vec<String> = validate_configuration(&config)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(vec!["spec.protocol.mqtt.qos"], fields);
+
+        let mut config = load_config_a();
+        config.spec.protocol = ProtocolHandler::serial(SerialDiscoveryHandlerConfig {
+            baud_rates: Vec::new(),
+            ..serde_json::from_str(r#"{"portPattern":"/dev/ttyUSB*"}"#).unwrap()
+        });
+        let fields: Vec<String> = validate_configuration(&config)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(vec!["spec.protocol.serial.baudRates"], fields);
+
+        // A Configuration can have a broker Job spec or a broker Pod spec but not both
+        let mut config = load_config_a();
+        assert!(config.spec.broker_pod_spec.is_some());
+        config.spec.broker_job_spec = Some(JobSpec::default());
+        let fields: Vec<String> = validate_configuration(&config)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(vec!["spec.brokerJobSpec"], fields);
+        config.spec.broker_pod_spec = None;
+        assert!(validate_configuration(&config).is_ok());
+
+        let mut config = load_config_a();
+        config.spec.broker_restart_policy = Some(BrokerRestartPolicy {
+            max_restarts: 0,
+            window_seconds: 0,
+            backoff_seconds: 0,
+        });
+        let fields: Vec<String> = validate_configuration(&config)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(
+            vec![
+                "spec.brokerRestartPolicy.windowSeconds",
+                "spec.brokerRestartPolicy.backoffSeconds"
+            ],
+            fields
+        );
+    }
+
+    #[test]
+    fn test_check_dns_name() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        assert_eq!(None, check_dns_name("config-a", 253, true));
+        assert_eq!(None, check_dns_name("config-a.akri.sh", 253, true));
+        assert_eq!(None, check_dns_name("0config", 63, false));
+        assert!(check_dns_name("", 253, true).is_some());
+        assert!(check_dns_name(&"a".repeat(254), 253, true).is_some());
+        assert!(check_dns_name(&"a".repeat(64), 63, false).is_some());
+        assert!(check_dns_name("config.a", 63, false).is_some());
+        assert!(check_dns_name("-config-a", 253, true).is_some());
+        assert!(check_dns_name("config-a-", 253, true).is_some());
+        assert!(check_dns_name("config..a", 253, true).is_some());
+        assert!(check_dns_name("config_a", 253, true).is_some());
+    }
+
+    #[test]
+    fn test_kube_akri_config_builder() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let debug_echo = || {
+            ProtocolHandler::debugEcho(DebugEchoDiscoveryHandlerConfig {
+                devices: Vec::new(),
+                shared: true,
+                discovery_interval_seconds: 10,
+                availability_check_path: None,
+            })
+        };
+
+        let config = KubeAkriConfigBuilder::new()
+            .name("config-a")
+            .namespace("config-a-namespace")
+            .uid("e9fbe880-99da-47c1-bea3-5398f21ee747")
+            .protocol(debug_echo())
+            .capacity(5)
+            .max_instances(2)
+            .build()
+            .unwrap();
+        assert_eq!("config-a", config.metadata.name);
+        assert_eq!(
+            Some("config-a-namespace".to_string()),
+            config.metadata.namespace
+        );
+        assert_eq!(
+            Some("e9fbe880-99da-47c1-bea3-5398f21ee747".to_string()),
+            config.metadata.uid
+        );
+        assert_eq!(5, config.spec.capacity);
+        assert_eq!(Some(2), config.spec.max_instances);
+        assert!(validate_configuration(&config).is_ok());
+
+        // Fields left unset take the defaults they would have in JSON
+        let config = KubeAkriConfigBuilder::new()
+            .name("config-a")
+            .namespace("default")
+            .protocol(debug_echo())
+            .build()
+            .unwrap();
+        assert_eq!(1, config.spec.capacity);
+        assert_eq!("pod", config.spec.units);
+        assert_eq!(None, config.metadata.uid);
+        assert_eq!(Some("akri.sh/v0".to_string()), config.types.apiVersion);
+        assert_eq!(Some("Configuration".to_string()), config.types.kind);
+
+        assert_eq!(
+            BuilderError::MissingName,
+            KubeAkriConfigBuilder::new()
+                .namespace("default")
+                .protocol(debug_echo())
+                .build()
+                .unwrap_err()
+        );
+        assert_eq!(
+            BuilderError::MissingNamespace,
+            KubeAkriConfigBuilder::new()
+                .name("config-a")
+                .protocol(debug_echo())
+                .build()
+                .unwrap_err()
+        );
+        assert_eq!(
+            BuilderError::MissingProtocol,
+            KubeAkriConfigBuilder::new()
+                .name("config-a")
+                .namespace("default")
+                .build()
+                .unwrap_err()
+        );
     }
 }
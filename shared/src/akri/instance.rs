@@ -2,7 +2,7 @@ use super::{API_INSTANCES, API_NAMESPACE, API_VERSION};
 use kube::{
     api::{
         DeleteParams, ListParams, Object, ObjectList, ObjectMeta, OwnerReference, PatchParams,
-        PostParams, RawApi, TypeMeta, Void,
+        PatchStrategy, PostParams, RawApi, TypeMeta, Void,
     },
     client::APIClient,
 };
@@ -47,6 +47,41 @@ pub struct Instance {
     /// This is a placeholder for eventual RBAC support
     #[serde(default = "default_rbac")]
     pub rbac: String,
+
+    /// This is the id the discovery handler reported for the device, which
+    /// lets every node agree on which device owns this Instance's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+
+    /// This contains the host devices the discovery handler reported for the device,
+    /// which are given to every broker allocated this Instance
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_specs: Vec<InstanceDeviceSpec>,
+
+    /// This contains the host paths the discovery handler reported for the device,
+    /// which are mounted into every broker allocated this Instance
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<InstanceMount>,
+}
+
+/// This defines a host device given to the brokers of an Instance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDeviceSpec {
+    pub container_path: String,
+    pub host_path: String,
+    /// Cgroup permissions of the device, any of `r`, `w` and `m`
+    pub permissions: String,
+}
+
+/// This defines a host path mounted into the brokers of an Instance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMount {
+    pub container_path: String,
+    pub host_path: String,
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 /// Get Instances for a given namespace
@@ -175,6 +210,9 @@ pub async fn find_instance(
 ///         device_usage: std::collections::HashMap::new(),
 ///         metadata: std::collections::HashMap::new(),
 ///         rbac: "".to_string(),
+///         device_id: None,
+///         device_specs: Vec::new(),
+///         mounts: Vec::new(),
 ///     },
 ///     "instance-1",
 ///     "default",
@@ -321,6 +359,9 @@ pub async fn delete_instance(
 ///         device_usage: std::collections::HashMap::new(),
 ///         metadata: std::collections::HashMap::new(),
 ///         rbac: "".to_string(),
+///         device_id: None,
+///         device_specs: Vec::new(),
+///         mounts: Vec::new(),
 ///     },
 ///     "instance-1",
 ///     "default",
@@ -377,6 +418,232 @@ pub async fn update_instance(
     }
 }
 
+/// Builds a JSON merge patch that sets an Instance's `metadata` to `properties`, nulling out any
+/// existing property that is no longer present so that the merge removes it
+fn get_properties_patch(
+    existing_properties: &HashMap<String, String>,
+    properties: &HashMap<String, String>,
+) -> serde_json::Value {
+    let mut metadata = serde_json::Map::new();
+    for key in existing_properties.keys() {
+        if !properties.contains_key(key) {
+            metadata.insert(key.clone(), serde_json::Value::Null);
+        }
+    }
+    for (key, value) in properties {
+        metadata.insert(key.clone(), serde_json::Value::String(value.clone()));
+    }
+    serde_json::json!({ "spec": { "metadata": metadata } })
+}
+
+/// Update the properties (`metadata`) of an Instance, leaving the rest of it untouched
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::akri::instance;
+/// use kube::client::APIClient;
+/// use kube::config;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// let mut properties = std::collections::HashMap::new();
+/// properties.insert("IP_ADDRESS".to_string(), "10.0.0.2".to_string());
+/// instance::update_instance_properties(
+///     &properties,
+///     "instance-1",
+///     "default",
+///     &api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn update_instance_properties(
+    properties: &HashMap<String, String>,
+    name: &str,
+    namespace: &str,
+    kube_client: &APIClient,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    log::trace!("update_instance_properties enter");
+    let akri_instance_type = RawApi::customResource(API_INSTANCES)
+        .group(API_NAMESPACE)
+        .version(API_VERSION)
+        .within(&namespace);
+
+    let existing_kube_akri_instance_type = find_instance(name, namespace, kube_client).await?;
+    let properties_patch = serde_json::to_vec(&get_properties_patch(
+        &existing_kube_akri_instance_type.spec.metadata,
+        properties,
+    ))?;
+    let patch_request =
+        akri_instance_type.patch(name, &PatchParams::default(), properties_patch)?;
+    log::trace!("update_instance_properties kube_client.request::<KubeAkriInstance>(akri_instance_type.patch(...)?).await?");
+    match kube_client.request::<KubeAkriInstance>(patch_request).await {
+        Ok(_instance_modified) => {
+            log::trace!("update_instance_properties return");
+            Ok(())
+        }
+        Err(kube::Error::Api(ae)) => {
+            log::trace!(
+                "update_instance_properties kube_client.request returned kube error: {:?}",
+                ae
+            );
+            Err(ae.into())
+        }
+        Err(e) => {
+            log::trace!(
+                "update_instance_properties kube_client.request error: {:?}",
+                e
+            );
+            Err(e.into())
+        }
+    }
+}
+
+/// Escapes a key for use as a segment of a JSON pointer (RFC 6901)
+fn escape_json_pointer_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// JSON patch operations that set the `device_usage_id` slot to `value`, provided it still holds `existing_value`
+fn get_device_usage_operations(
+    device_usage_id: &str,
+    existing_value: &str,
+    value: &str,
+) -> Vec<serde_json::Value> {
+    let path = format!(
+        "/spec/deviceUsage/{}",
+        escape_json_pointer_segment(device_usage_id)
+    );
+    vec![
+        serde_json::json!({ "op": "test", "path": path, "value": existing_value }),
+        serde_json::json!({ "op": "replace", "path": path, "value": value }),
+    ]
+}
+
+/// Builds a JSON patch that sets the `device_usage_id` slot of an Instance to `value`. The patch fails
+/// if the slot no longer holds `existing_value`, so two nodes cannot both claim a slot, while changes
+/// to other slots or fields of the Instance do not conflict with it.
+pub fn get_device_usage_patch(
+    device_usage_id: &str,
+    existing_value: &str,
+    value: &str,
+) -> serde_json::Value {
+    serde_json::Value::Array(get_device_usage_operations(
+        device_usage_id,
+        existing_value,
+        value,
+    ))
+}
+
+/// Builds a JSON patch that sets an Instance's `deviceSpecs` and `mounts`, replacing any it already has
+pub fn get_devices_patch(
+    device_specs: &[InstanceDeviceSpec],
+    mounts: &[InstanceMount],
+) -> serde_json::Value {
+    serde_json::json!([
+        { "op": "add", "path": "/spec/deviceSpecs", "value": device_specs },
+        { "op": "add", "path": "/spec/mounts", "value": mounts }
+    ])
+}
+
+/// Builds a JSON patch that appends `node_name` to an Instance's `nodes`
+pub fn get_add_node_patch(node_name: &str) -> serde_json::Value {
+    serde_json::json!([{ "op": "add", "path": "/spec/nodes/-", "value": node_name }])
+}
+
+/// Builds a JSON patch that removes `node_name` from an Instance's `nodes` and frees the slots it holds,
+/// or None if the Instance does not reference `node_name`. Each operation is preceded by a test of the
+/// value it changes, so the patch fails rather than changing the wrong entry if the Instance has changed
+/// since it was read.
+pub fn get_remove_node_patch(instance: &Instance, node_name: &str) -> Option<serde_json::Value> {
+    let mut operations = Vec::new();
+    // Remove from the back so that the indices of the remaining nodes stay valid
+    for (index, _) in instance
+        .nodes
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, node)| *node == node_name)
+    {
+        let path = format!("/spec/nodes/{}", index);
+        operations.push(serde_json::json!({ "op": "test", "path": path, "value": node_name }));
+        operations.push(serde_json::json!({ "op": "remove", "path": path }));
+    }
+    let mut slots: Vec<&String> = instance
+        .device_usage
+        .iter()
+        .filter(|(_, node)| *node == node_name)
+        .map(|(slot, _)| slot)
+        .collect();
+    slots.sort();
+    for slot in slots {
+        operations.extend(get_device_usage_operations(slot, node_name, ""));
+    }
+    if operations.is_empty() {
+        None
+    } else {
+        Some(serde_json::Value::Array(operations))
+    }
+}
+
+/// Apply a JSON patch (RFC 6902) to an Instance, changing only the fields the patch names
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::akri::instance;
+/// use kube::client::APIClient;
+/// use kube::config;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// instance::patch_instance(
+///     &instance::get_device_usage_patch("instance-1-0", "", "node-a"),
+///     "instance-1",
+///     "default",
+///     &api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn patch_instance(
+    patch: &serde_json::Value,
+    name: &str,
+    namespace: &str,
+    kube_client: &APIClient,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    log::trace!("patch_instance enter");
+    let akri_instance_type = RawApi::customResource(API_INSTANCES)
+        .group(API_NAMESPACE)
+        .version(API_VERSION)
+        .within(&namespace);
+
+    log::trace!("patch_instance patch: {}", patch);
+    let instance_patch_params = PatchParams {
+        patch_strategy: PatchStrategy::JSON,
+        ..Default::default()
+    };
+    let patch_request =
+        akri_instance_type.patch(name, &instance_patch_params, serde_json::to_vec(patch)?)?;
+    log::trace!("patch_instance kube_client.request::<KubeAkriInstance>(akri_instance_type.patch(...)?).await?");
+    match kube_client.request::<KubeAkriInstance>(patch_request).await {
+        Ok(_instance_modified) => {
+            log::trace!("patch_instance return");
+            Ok(())
+        }
+        Err(kube::Error::Api(ae)) => {
+            log::trace!(
+                "patch_instance kube_client.request returned kube error: {:?}",
+                ae
+            );
+            Err(ae.into())
+        }
+        Err(e) => {
+            log::trace!("patch_instance kube_client.request error: {:?}", e);
+            Err(e.into())
+        }
+    }
+}
+
 fn default_shared() -> bool {
     false
 }
@@ -476,4 +743,138 @@ mod crd_serializeation_tests {
             let _ = serde_json::to_string(&deserialized).unwrap();
         }
     }
+
+    #[test]
+    fn test_get_properties_patch() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut existing_properties = HashMap::new();
+        existing_properties.insert("IP_ADDRESS".to_string(), "10.0.0.1".to_string());
+        existing_properties.insert("SERIAL".to_string(), "abc".to_string());
+        let mut properties = HashMap::new();
+        properties.insert("IP_ADDRESS".to_string(), "10.0.0.2".to_string());
+        properties.insert("MODEL".to_string(), "cam".to_string());
+        assert_eq!(
+            serde_json::json!({
+                "spec": {
+                    "metadata": {
+                        "IP_ADDRESS": "10.0.0.2",
+                        "MODEL": "cam",
+                        "SERIAL": null
+                    }
+                }
+            }),
+            get_properties_patch(&existing_properties, &properties)
+        );
+    }
+
+    #[test]
+    fn test_get_device_usage_patch() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        assert_eq!(
+            serde_json::json!([
+                { "op": "test", "path": "/spec/deviceUsage/config-a-359973-2", "value": "" },
+                { "op": "replace", "path": "/spec/deviceUsage/config-a-359973-2", "value": "node-a" }
+            ]),
+            get_device_usage_patch("config-a-359973-2", "", "node-a")
+        );
+        assert_eq!(
+            serde_json::json!([
+                { "op": "test", "path": "/spec/deviceUsage/a~1b~0c", "value": "node-a" },
+                { "op": "replace", "path": "/spec/deviceUsage/a~1b~0c", "value": "" }
+            ]),
+            get_device_usage_patch("a/b~c", "node-a", "")
+        );
+    }
+
+    #[test]
+    fn test_instance_devices_serialization() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = r#"{"configurationName":"foo","deviceSpecs":[{"containerPath":"/dev/nvidia0","hostPath":"/dev/nvidia0","permissions":"rw"}],"mounts":[{"containerPath":"/usr/local/nvidia","hostPath":"/usr/lib/nvidia"}]}"#;
+        let deserialized: Instance = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            vec![InstanceDeviceSpec {
+                container_path: "/dev/nvidia0".to_string(),
+                host_path: "/dev/nvidia0".to_string(),
+                permissions: "rw".to_string(),
+            }],
+            deserialized.device_specs
+        );
+        assert_eq!(
+            vec![InstanceMount {
+                container_path: "/usr/local/nvidia".to_string(),
+                host_path: "/usr/lib/nvidia".to_string(),
+                read_only: false,
+            }],
+            deserialized.mounts
+        );
+        let serialized = serde_json::to_string(&deserialized).unwrap();
+        assert!(serialized.contains(r#""deviceSpecs":[{"containerPath":"/dev/nvidia0""#));
+        assert!(serialized.contains(r#""mounts":[{"containerPath":"/usr/local/nvidia""#));
+    }
+
+    #[test]
+    fn test_get_devices_patch() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let device_specs = vec![InstanceDeviceSpec {
+            container_path: "/dev/nvidia0".to_string(),
+            host_path: "/dev/nvidia1".to_string(),
+            permissions: "rw".to_string(),
+        }];
+        assert_eq!(
+            serde_json::json!([
+                {
+                    "op": "add",
+                    "path": "/spec/deviceSpecs",
+                    "value": [{ "containerPath": "/dev/nvidia0", "hostPath": "/dev/nvidia1", "permissions": "rw" }]
+                },
+                { "op": "add", "path": "/spec/mounts", "value": [] }
+            ]),
+            get_devices_patch(&device_specs, &[])
+        );
+    }
+
+    #[test]
+    fn test_get_add_node_patch() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        assert_eq!(
+            serde_json::json!([{ "op": "add", "path": "/spec/nodes/-", "value": "node-a" }]),
+            get_add_node_patch("node-a")
+        );
+    }
+
+    #[test]
+    fn test_get_remove_node_patch() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let json = file::read_file_to_string("../test/json/shared-instance-update.json");
+        let mut instance: KubeAkriInstance = serde_json::from_str(&json).unwrap();
+        instance.spec.nodes = vec!["node-a".to_string(), "node-b".to_string()];
+        instance
+            .spec
+            .device_usage
+            .insert("config-a-359973-0".to_string(), "node-a".to_string());
+        instance
+            .spec
+            .device_usage
+            .insert("config-a-359973-4".to_string(), "node-b".to_string());
+
+        // Only the vanished node's entry in nodes and its slots are patched
+        assert_eq!(
+            Some(serde_json::json!([
+                { "op": "test", "path": "/spec/nodes/1", "value": "node-b" },
+                { "op": "remove", "path": "/spec/nodes/1" },
+                { "op": "test", "path": "/spec/deviceUsage/config-a-359973-2", "value": "node-b" },
+                { "op": "replace", "path": "/spec/deviceUsage/config-a-359973-2", "value": "" },
+                { "op": "test", "path": "/spec/deviceUsage/config-a-359973-4", "value": "node-b" },
+                { "op": "replace", "path": "/spec/deviceUsage/config-a-359973-4", "value": "" }
+            ])),
+            get_remove_node_patch(&instance.spec, "node-b")
+        );
+        assert_eq!(None, get_remove_node_patch(&instance.spec, "node-c"));
+    }
 }
@@ -32,4 +32,17 @@ pub mod retry {
         let random_delay_0_to_200: u64 = (200_f32 * random_decimal) as u64;
         time::delay_for(Duration::from_millis(random_delay_0_to_200)).await;
     }
+
+    /// This method will delay a random percentage of up to 200ms, doubled for each failed `attempt`
+    ///
+    /// Used between retries of requests that conflicted with concurrent changes, so that
+    /// repeatedly conflicting requests back off rather than retrying in lockstep
+    pub async fn random_backoff_delay(attempt: i8) {
+        let random_decimal: f32 = random::<f32>();
+        let max_delay_ms = 200_f32 * 2_f32.powi(attempt.into());
+        time::delay_for(Duration::from_millis(
+            (max_delay_ms * random_decimal) as u64,
+        ))
+        .await;
+    }
 }
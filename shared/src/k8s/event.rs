@@ -0,0 +1,56 @@
+use k8s_openapi::api::core::v1::Event;
+use kube::{
+    api::{PostParams, RawApi},
+    client::APIClient,
+};
+use log::{error, trace};
+
+/// Create Kubernetes Event
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::event;
+/// use kube::client::APIClient;
+/// use kube::config;
+/// use k8s_openapi::api::core::v1::Event;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// event::create_event(&Event::default(), "default", api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn create_event(
+    event_to_create: &Event,
+    namespace: &str,
+    kube_client: APIClient,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    trace!("create_event enter");
+    let events = RawApi::v1Event().within(&namespace);
+    let event_as_u8 = serde_json::to_vec(&event_to_create)?;
+    trace!("create_event kube_client.request::<Event>(events.create(...)?).await?");
+    match kube_client
+        .request::<Event>(events.create(&PostParams::default(), event_as_u8)?)
+        .await
+    {
+        Ok(_) => {
+            trace!("create_event return");
+            Ok(())
+        }
+        Err(kube::Error::Api(ae)) => {
+            error!(
+                "create_event events.create [{:?}] returned kube error: {:?}",
+                event_to_create.reason, ae
+            );
+            Err(ae.into())
+        }
+        Err(e) => {
+            error!(
+                "create_event events.create [{:?}] error: {:?}",
+                event_to_create.reason, e
+            );
+            Err(e.into())
+        }
+    }
+}
@@ -0,0 +1,473 @@
+use super::{
+    super::akri::API_NAMESPACE,
+    pod::{
+        replace_resource_placeholders, AKRI_CONFIGURATION_LABEL_NAME, AKRI_INSTANCE_LABEL_NAME,
+        APP_LABEL_ID, CONTROLLER_LABEL_ID,
+    },
+    OwnershipInfo, ERROR_CONFLICT, ERROR_NOT_FOUND,
+};
+use either::Either;
+use k8s_openapi::api::batch::v1::{Job, JobSpec, JobStatus};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use kube::{
+    api::{
+        Api, DeleteParams, ListParams, Object, ObjectList, PatchParams, PatchStrategy, PostParams,
+        PropagationPolicy,
+    },
+    client::APIClient,
+};
+use log::{error, info, trace};
+use std::collections::BTreeMap;
+
+/// Job condition type set once a Job's pods have completed successfully
+pub const JOB_COMPLETE_CONDITION: &str = "Complete";
+/// Job condition type set once a Job has given up on its pods
+pub const JOB_FAILED_CONDITION: &str = "Failed";
+
+/// Get Kubernetes Jobs with a given label selector
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::job;
+/// use kube::client::APIClient;
+/// use kube::config;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let selector = "akri.sh/instance=akri-onvif-8120fe";
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// for job in job::find_jobs_with_selector(&selector, api_client).await.unwrap() {
+///     println!("found job: {}", job.metadata.name)
+/// }
+/// # }
+/// ```
+pub async fn find_jobs_with_selector(
+    selector: &str,
+    kube_client: APIClient,
+) -> Result<
+    ObjectList<Object<JobSpec, JobStatus>>,
+    Box<dyn std::error::Error + Send + Sync + 'static>,
+> {
+    trace!("find_jobs_with_selector with selector={:?}", &selector);
+    let jobs = Api::v1Job(kube_client);
+    let job_list_params = ListParams {
+        label_selector: Some(selector.to_string()),
+        ..Default::default()
+    };
+    trace!("find_jobs_with_selector PRE jobs.list(...).await?");
+    let result = jobs.list(&job_list_params).await;
+    trace!("find_jobs_with_selector return");
+    Ok(result?)
+}
+
+/// Create name for Kubernetes Job.
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::job;
+///
+/// let job_name = job::create_job_app_name("capability_instance");
+/// ```
+pub fn create_job_app_name(instance_name: &str) -> String {
+    format!("{}-job", instance_name.replace(".", "-"))
+}
+
+/// Returns whether a Job has a condition of type `condition_type` that is "True"
+///
+/// Example:
+///
+/// ```
+/// use akri_shared::k8s::job;
+///
+/// assert!(!job::has_job_condition(None, job::JOB_COMPLETE_CONDITION));
+/// ```
+pub fn has_job_condition(job_status: Option<&JobStatus>, condition_type: &str) -> bool {
+    job_status
+        .and_then(|status| status.conditions.as_ref())
+        .map_or(false, |conditions| {
+            conditions
+                .iter()
+                .any(|condition| condition.type_ == condition_type && condition.status == "True")
+        })
+}
+
+/// Create Kubernetes Job based on Device Capabililty Instance & Config.
+///
+/// The Job's pods are not labeled as broker Pods, so the controller's broker Pod handling
+/// leaves them to the Job.
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::{
+///     job,
+///     OwnershipInfo,
+///     OwnershipType,
+/// };
+/// use k8s_openapi::api::batch::v1::JobSpec;
+///
+/// let job = job::create_new_job_from_spec(
+///     "job_namespace",
+///     "capability_instance",
+///     "capability_config",
+///     OwnershipInfo::new(
+///         OwnershipType::Instance,
+///         "capability_instance".to_string(),
+///         "instance_uid".to_string()
+///     ),
+///     "akri.sh/capability_name",
+///     &JobSpec::default()).unwrap();
+/// ```
+pub fn create_new_job_from_spec(
+    job_namespace: &str,
+    instance_name: &str,
+    configuration_name: &str,
+    ownership: OwnershipInfo,
+    resource_limit_name: &str,
+    job_spec: &JobSpec,
+) -> Result<Job, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    trace!("create_new_job_from_spec enter");
+
+    let app_name = create_job_app_name(instance_name);
+    let mut labels: BTreeMap<String, String> = BTreeMap::new();
+    labels.insert(APP_LABEL_ID.to_string(), app_name.clone());
+    labels.insert(CONTROLLER_LABEL_ID.to_string(), API_NAMESPACE.to_string());
+    labels.insert(
+        AKRI_CONFIGURATION_LABEL_NAME.to_string(),
+        configuration_name.to_string(),
+    );
+    labels.insert(
+        AKRI_INSTANCE_LABEL_NAME.to_string(),
+        instance_name.to_string(),
+    );
+
+    let owner_references: Vec<OwnerReference> = vec![OwnerReference {
+        api_version: ownership.get_api_version(),
+        kind: ownership.get_kind(),
+        controller: Some(ownership.get_controller()),
+        block_owner_deletion: Some(ownership.get_block_owner_deletion()),
+        name: ownership.get_name(),
+        uid: ownership.get_uid(),
+    }];
+
+    let mut modified_job_spec = job_spec.clone();
+    if let Some(pod_spec) = modified_job_spec.template.spec.as_mut() {
+        replace_resource_placeholders(pod_spec, resource_limit_name);
+    }
+
+    let result = Job {
+        spec: Some(modified_job_spec),
+        metadata: Some(ObjectMeta {
+            name: Some(app_name),
+            namespace: Some(job_namespace.to_string()),
+            labels: Some(labels),
+            owner_references: Some(owner_references),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    trace!("create_new_job_from_spec return");
+    Ok(result)
+}
+
+/// Create Kubernetes Job
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::job;
+/// use kube::client::APIClient;
+/// use kube::config;
+/// use k8s_openapi::api::batch::v1::Job;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// job::create_job(&Job::default(), "job_namespace", api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn create_job(
+    job_to_create: &Job,
+    namespace: &str,
+    kube_client: APIClient,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    trace!("create_job enter");
+    let jobs = Api::v1Job(kube_client).within(&namespace);
+    let job_as_u8 = serde_json::to_vec(&job_to_create)?;
+    info!("create_job jobs.create(...).await?:");
+    match jobs.create(&PostParams::default(), job_as_u8).await {
+        Ok(created_job) => {
+            info!(
+                "create_job jobs.create return: {:?}",
+                created_job.metadata.name
+            );
+            Ok(())
+        }
+        Err(kube::Error::Api(ae)) => {
+            if ae.code == ERROR_CONFLICT {
+                trace!("create_job - job already exists");
+                Ok(())
+            } else {
+                error!(
+                    "create_job jobs.create [{:?}] returned kube error: {:?}",
+                    serde_json::to_string(&job_to_create),
+                    ae
+                );
+                Err(ae.into())
+            }
+        }
+        Err(e) => {
+            error!(
+                "create_job jobs.create [{:?}] error: {:?}",
+                serde_json::to_string(&job_to_create),
+                e
+            );
+            Err(e.into())
+        }
+    }
+}
+
+/// Remove Kubernetes Job, along with its pods
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::job;
+/// use kube::client::APIClient;
+/// use kube::config;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// job::remove_job("job_to_remove", "job_namespace", api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn remove_job(
+    job_to_remove: &str,
+    namespace: &str,
+    kube_client: APIClient,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    trace!("remove_job enter");
+    let jobs = Api::v1Job(kube_client).within(&namespace);
+    // Jobs orphan their pods unless told otherwise
+    let job_delete_params = DeleteParams {
+        propagation_policy: Some(PropagationPolicy::Background),
+        ..Default::default()
+    };
+    info!("remove_job jobs.delete(...).await?:");
+    match jobs.delete(job_to_remove, &job_delete_params).await {
+        Ok(deleted_job) => match deleted_job {
+            Either::Left(spec) => {
+                info!("remove_job jobs.delete return: {:?}", &spec.metadata.name);
+                Ok(())
+            }
+            Either::Right(status) => {
+                info!("remove_job jobs.delete return: {:?}", &status.status);
+                Ok(())
+            }
+        },
+        Err(kube::Error::Api(ae)) => {
+            if ae.code == ERROR_NOT_FOUND {
+                trace!("remove_job - job already removed");
+                Ok(())
+            } else {
+                error!(
+                    "remove_job jobs.delete [{:?}] returned kube error: {:?}",
+                    &job_to_remove, ae
+                );
+                Err(ae.into())
+            }
+        }
+        Err(e) => {
+            error!(
+                "remove_job jobs.delete [{:?}] error: {:?}",
+                &job_to_remove, e
+            );
+            Err(e.into())
+        }
+    }
+}
+
+/// Remove the owner references of a Kubernetes Job, so that it is not garbage collected
+/// along with its owner. A released Job is left to its `ttlSecondsAfterFinished`.
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::job;
+/// use kube::client::APIClient;
+/// use kube::config;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// job::release_job("job_to_release", "job_namespace", api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn release_job(
+    job_to_release: &str,
+    namespace: &str,
+    kube_client: APIClient,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    trace!("release_job enter");
+    let jobs = Api::v1Job(kube_client).within(&namespace);
+    let patch = serde_json::json!([{ "op": "remove", "path": "/metadata/ownerReferences" }]);
+    let job_patch_params = PatchParams {
+        patch_strategy: PatchStrategy::JSON,
+        ..Default::default()
+    };
+    info!("release_job jobs.patch(...).await?:");
+    match jobs
+        .patch(
+            job_to_release,
+            &job_patch_params,
+            serde_json::to_vec(&patch)?,
+        )
+        .await
+    {
+        Ok(released_job) => {
+            info!(
+                "release_job jobs.patch return: {:?}",
+                released_job.metadata.name
+            );
+            Ok(())
+        }
+        Err(kube::Error::Api(ae)) => {
+            error!(
+                "release_job jobs.patch [{:?}] returned kube error: {:?}",
+                &job_to_release, ae
+            );
+            Err(ae.into())
+        }
+        Err(e) => {
+            error!(
+                "release_job jobs.patch [{:?}] error: {:?}",
+                &job_to_release, e
+            );
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod broker_jobspec_tests {
+    use super::super::super::akri::API_VERSION;
+    use super::super::{OwnershipType, RESOURCE_REQUIREMENTS_KEY};
+    use super::*;
+    use k8s_openapi::api::batch::v1::JobCondition;
+    use k8s_openapi::api::core::v1::{Container, PodSpec, PodTemplateSpec, ResourceRequirements};
+
+    #[test]
+    fn test_create_job_app_name() {
+        assert_eq!("instance-name-job", create_job_app_name("instance.name"));
+        assert_eq!("1-0-0-1-job", create_job_app_name("1-0-0-1"));
+    }
+
+    #[test]
+    fn test_has_job_condition() {
+        let status = JobStatus {
+            conditions: Some(vec![
+                JobCondition {
+                    type_: JOB_FAILED_CONDITION.to_string(),
+                    status: "False".to_string(),
+                    ..Default::default()
+                },
+                JobCondition {
+                    type_: JOB_COMPLETE_CONDITION.to_string(),
+                    status: "True".to_string(),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+        assert!(has_job_condition(Some(&status), JOB_COMPLETE_CONDITION));
+        assert!(!has_job_condition(Some(&status), JOB_FAILED_CONDITION));
+        assert!(!has_job_condition(
+            Some(&JobStatus::default()),
+            JOB_COMPLETE_CONDITION
+        ));
+        assert!(!has_job_condition(None, JOB_COMPLETE_CONDITION));
+    }
+
+    #[test]
+    fn test_job_spec_creation() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut placeholder_limits = BTreeMap::new();
+        placeholder_limits.insert(RESOURCE_REQUIREMENTS_KEY.to_string(), Default::default());
+        let job_spec = JobSpec {
+            ttl_seconds_after_finished: Some(600),
+            template: PodTemplateSpec {
+                spec: Some(PodSpec {
+                    containers: vec![Container {
+                        image: Some("image".to_string()),
+                        resources: Some(ResourceRequirements {
+                            limits: Some(placeholder_limits),
+                            requests: None,
+                        }),
+                        ..Default::default()
+                    }],
+                    restart_policy: Some("Never".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let job = create_new_job_from_spec(
+            "job_namespace",
+            "instance.name",
+            "config_name",
+            OwnershipInfo::new(
+                OwnershipType::Instance,
+                "instance.name".to_string(),
+                "instance_uid".to_string(),
+            ),
+            "akri.sh/instance-name",
+            &job_spec,
+        )
+        .unwrap();
+
+        let metadata = job.metadata.as_ref().unwrap();
+        assert_eq!("instance-name-job", metadata.name.as_ref().unwrap());
+        assert_eq!("job_namespace", metadata.namespace.as_ref().unwrap());
+        let labels = metadata.labels.as_ref().unwrap();
+        assert_eq!("instance-name-job", labels.get(APP_LABEL_ID).unwrap());
+        assert_eq!(API_NAMESPACE, labels.get(CONTROLLER_LABEL_ID).unwrap());
+        assert_eq!(
+            "config_name",
+            labels.get(AKRI_CONFIGURATION_LABEL_NAME).unwrap()
+        );
+        assert_eq!(
+            "instance.name",
+            labels.get(AKRI_INSTANCE_LABEL_NAME).unwrap()
+        );
+        let owner_references = metadata.owner_references.as_ref().unwrap();
+        assert_eq!(1, owner_references.len());
+        assert_eq!(
+            format!("{}/{}", API_NAMESPACE, API_VERSION),
+            owner_references[0].api_version
+        );
+        assert_eq!("Instance", owner_references[0].kind);
+        assert_eq!("instance.name", owner_references[0].name);
+        assert_eq!("instance_uid", owner_references[0].uid);
+
+        let spec = job.spec.as_ref().unwrap();
+        assert_eq!(Some(600), spec.ttl_seconds_after_finished);
+        // The Job's pods must not look like broker Pods
+        assert!(spec.template.metadata.is_none());
+        let limits = spec.template.spec.as_ref().unwrap().containers[0]
+            .resources
+            .as_ref()
+            .unwrap()
+            .limits
+            .as_ref()
+            .unwrap();
+        assert!(limits.contains_key("akri.sh/instance-name"));
+        assert!(!limits.contains_key(RESOURCE_REQUIREMENTS_KEY));
+    }
+}
@@ -1,14 +1,16 @@
 use super::akri::{
     configuration,
-    configuration::{KubeAkriConfig, KubeAkriConfigList},
+    configuration::{ConfigurationStatus, KubeAkriConfig, KubeAkriConfigList},
     instance,
     instance::{Instance, KubeAkriInstance, KubeAkriInstanceList},
     API_NAMESPACE, API_VERSION,
 };
 use async_trait::async_trait;
 use futures::executor::block_on;
+use k8s_openapi::api::batch::v1::{Job, JobSpec, JobStatus};
 use k8s_openapi::api::core::v1::{
-    NodeSpec, NodeStatus, Pod, PodSpec, PodStatus, Service, ServiceSpec, ServiceStatus,
+    Event, NodeSpec, NodeStatus, Pod, PodSpec, PodStatus, Secret, Service, ServiceSpec,
+    ServiceStatus,
 };
 use kube::{
     api::{Object, ObjectList},
@@ -16,9 +18,13 @@ use kube::{
     config,
 };
 use mockall::{automock, predicate::*};
+use std::collections::HashMap;
 
+pub mod event;
+pub mod job;
 pub mod node;
 pub mod pod;
+pub mod secret;
 pub mod service;
 
 pub const NODE_SELECTOR_OP_IN: &str = "In";
@@ -101,6 +107,18 @@ pub trait KubeInterface: Send + Sync {
         name: &str,
     ) -> Result<Object<NodeSpec, NodeStatus>, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
+    async fn find_secret(
+        &self,
+        name: &str,
+        namespace: &str,
+    ) -> Result<Secret, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    async fn create_event(
+        &self,
+        event_to_create: &Event,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+
     async fn find_pods_with_label(
         &self,
         selector: &str,
@@ -126,6 +144,29 @@ pub trait KubeInterface: Send + Sync {
         namespace: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
 
+    async fn find_jobs_with_label(
+        &self,
+        selector: &str,
+    ) -> Result<
+        ObjectList<Object<JobSpec, JobStatus>>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    >;
+    async fn create_job(
+        &self,
+        job_to_create: &Job,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn remove_job(
+        &self,
+        job_to_remove: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn release_job(
+        &self,
+        job_to_release: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+
     async fn find_services(
         &self,
         selector: &str,
@@ -158,6 +199,13 @@ pub trait KubeInterface: Send + Sync {
     async fn get_configurations(
         &self,
     ) -> Result<KubeAkriConfigList, Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn update_configuration_status(
+        &self,
+        status: &ConfigurationStatus,
+        resource_version: &str,
+        name: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
 
     async fn find_instance(
         &self,
@@ -186,6 +234,18 @@ pub trait KubeInterface: Send + Sync {
         name: &str,
         namespace: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn update_instance_properties(
+        &self,
+        properties: &HashMap<String, String>,
+        name: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn patch_instance(
+        &self,
+        patch: &serde_json::Value,
+        name: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
 }
 
 /// Create new KubeInetrace implementation
@@ -245,6 +305,51 @@ impl KubeInterface for KubeImpl {
         node::find_node(name, self.get_kube_client()).await
     }
 
+    /// Get Kubernetes Secret with a given name and namespace
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// let secret = kube.find_secret("camera-credentials", "default").await.unwrap();
+    /// # }
+    /// ```
+    async fn find_secret(
+        &self,
+        name: &str,
+        namespace: &str,
+    ) -> Result<Secret, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        secret::find_secret(name, namespace, self.get_kube_client()).await
+    }
+
+    /// Create Kubernetes Event
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    /// use k8s_openapi::api::core::v1::Event;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// kube.create_event(&Event::default(), "default").await.unwrap();
+    /// # }
+    /// ```
+    async fn create_event(
+        &self,
+        event_to_create: &Event,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        event::create_event(event_to_create, namespace, self.get_kube_client()).await
+    }
+
     /// Get Kuberenetes pods with specified label selector
     ///
     /// Example:
@@ -335,6 +440,94 @@ impl KubeInterface for KubeImpl {
         pod::remove_pod(pod_to_remove, namespace, self.get_kube_client()).await
     }
 
+    /// Get Kuberenetes jobs with specified label selector
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// let interesting_jobs = kube.find_jobs_with_label("label=interesting").await.unwrap();
+    /// # }
+    /// ```
+    async fn find_jobs_with_label(
+        &self,
+        selector: &str,
+    ) -> Result<
+        ObjectList<Object<JobSpec, JobStatus>>,
+        Box<dyn std::error::Error + Send + Sync + 'static>,
+    > {
+        job::find_jobs_with_selector(selector, self.get_kube_client()).await
+    }
+    /// Create Kuberenetes job
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    /// use k8s_openapi::api::batch::v1::Job;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// kube.create_job(&Job::default(), "job_namespace").await.unwrap();
+    /// # }
+    /// ```
+    async fn create_job(
+        &self,
+        job_to_create: &Job,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        job::create_job(job_to_create, namespace, self.get_kube_client()).await
+    }
+    /// Remove Kubernetes job and its pods
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// kube.remove_job("job_to_remove", "job_namespace").await.unwrap();
+    /// # }
+    /// ```
+    async fn remove_job(
+        &self,
+        job_to_remove: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        job::remove_job(job_to_remove, namespace, self.get_kube_client()).await
+    }
+    /// Remove the owner references of Kubernetes job, so that it outlives its owner
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// kube.release_job("job_to_release", "job_namespace").await.unwrap();
+    /// # }
+    /// ```
+    async fn release_job(
+        &self,
+        job_to_release: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        job::release_job(job_to_release, namespace, self.get_kube_client()).await
+    }
+
     /// Get Kuberenetes services with specified label selector
     ///
     /// Example:
@@ -473,6 +666,42 @@ impl KubeInterface for KubeImpl {
     ) -> Result<KubeAkriConfigList, Box<dyn std::error::Error + Send + Sync + 'static>> {
         configuration::get_configurations(&self.get_kube_client()).await
     }
+    // Update the status of Akri Configuration with given name and namespace, if it is still at `resource_version`
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    /// use akri_shared::akri::configuration::ConfigurationStatus;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// kube.update_configuration_status(
+    ///     &ConfigurationStatus::default(),
+    ///     "12345",
+    ///     "dcc-1",
+    ///     "dcc-namespace"
+    /// ).await.unwrap();
+    /// # }
+    /// ```
+    async fn update_configuration_status(
+        &self,
+        status: &ConfigurationStatus,
+        resource_version: &str,
+        name: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        configuration::update_configuration_status(
+            status,
+            resource_version,
+            name,
+            namespace,
+            &self.get_kube_client(),
+        )
+        .await
+    }
 
     // Get Akri Instance with given name and namespace
     ///
@@ -534,6 +763,9 @@ impl KubeInterface for KubeImpl {
     ///         device_usage: std::collections::HashMap::new(),
     ///         metadata: std::collections::HashMap::new(),
     ///         rbac: "".to_string(),
+    ///         device_id: None,
+    ///         device_specs: Vec::new(),
+    ///         mounts: Vec::new(),
     ///     },
     ///     "instance-1",
     ///     "instance-namespace",
@@ -604,6 +836,9 @@ impl KubeInterface for KubeImpl {
     ///         device_usage: std::collections::HashMap::new(),
     ///         metadata: std::collections::HashMap::new(),
     ///         rbac: "".to_string(),
+    ///         device_id: None,
+    ///         device_specs: Vec::new(),
+    ///         mounts: Vec::new(),
     ///     },
     ///     "instance-1",
     ///     "instance-namespace"
@@ -619,6 +854,62 @@ impl KubeInterface for KubeImpl {
         instance::update_instance(instance_to_update, name, namespace, &self.get_kube_client())
             .await
     }
+    /// Update the properties of an Akri Instance
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// let mut properties = std::collections::HashMap::new();
+    /// properties.insert("IP_ADDRESS".to_string(), "10.0.0.2".to_string());
+    /// kube.update_instance_properties(
+    ///     &properties,
+    ///     "instance-1",
+    ///     "instance-namespace"
+    /// ).await.unwrap();
+    /// # }
+    /// ```
+    async fn update_instance_properties(
+        &self,
+        properties: &HashMap<String, String>,
+        name: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        instance::update_instance_properties(properties, name, namespace, &self.get_kube_client())
+            .await
+    }
+    /// Apply a JSON patch to an Akri Instance, changing only the fields the patch names
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use akri_shared::akri::instance;
+    /// use akri_shared::k8s;
+    /// use akri_shared::k8s::KubeInterface;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let kube = k8s::create_kube_interface();
+    /// kube.patch_instance(
+    ///     &instance::get_add_node_patch("node-a"),
+    ///     "instance-1",
+    ///     "instance-namespace"
+    /// ).await.unwrap();
+    /// # }
+    /// ```
+    async fn patch_instance(
+        &self,
+        patch: &serde_json::Value,
+        name: &str,
+        namespace: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        instance::patch_instance(patch, name, namespace, &self.get_kube_client()).await
+    }
 }
 
 #[cfg(test)]
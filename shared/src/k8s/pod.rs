@@ -4,8 +4,8 @@ use super::{
 };
 use either::Either;
 use k8s_openapi::api::core::v1::{
-    Affinity, NodeAffinity, NodeSelector, NodeSelectorRequirement, NodeSelectorTerm, Pod, PodSpec,
-    PodStatus, ResourceRequirements,
+    Affinity, Container, NodeAffinity, NodeSelector, NodeSelectorRequirement, NodeSelectorTerm,
+    Pod, PodSpec, PodStatus, ResourceRequirements,
 };
 use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
@@ -14,7 +14,7 @@ use kube::{
     client::APIClient,
 };
 use log::{error, info, trace};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 pub const APP_LABEL_ID: &str = "app";
 pub const CONTROLLER_LABEL_ID: &str = "controller";
@@ -115,8 +115,173 @@ pub fn create_pod_app_name(
     }
 }
 
+/// Returns whether `name` can be referred to by a `{{name}}` placeholder in a broker Pod spec
+fn is_placeholder_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Replaces each `{{PROPERTY_NAME}}` placeholder in `template` with the property's value, adding the names of
+/// properties that are missing to `missing_properties`. `\{{` is kept as literal `{{`, as are braces that do not
+/// enclose a property name.
+fn render_template(
+    template: &str,
+    properties: &HashMap<String, String>,
+    missing_properties: &mut Vec<String>,
+) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let (before, after) = (&rest[..start], &rest[start + 2..]);
+        if let Some(before) = before.strip_suffix('\\') {
+            rendered.push_str(before);
+            rendered.push_str("{{");
+            rest = after;
+            continue;
+        }
+        rendered.push_str(before);
+        match after
+            .find("}}")
+            .map(|end| &after[..end])
+            .filter(|name| is_placeholder_name(name))
+        {
+            Some(name) => {
+                match properties.get(name) {
+                    Some(value) => rendered.push_str(value),
+                    None => missing_properties.push(name.to_string()),
+                }
+                rest = &after[name.len() + 2..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Renders the placeholders in the command, args and env values of a Container
+fn render_container(
+    container: &mut Container,
+    properties: &HashMap<String, String>,
+    missing_properties: &mut Vec<String>,
+) {
+    for values in container
+        .command
+        .iter_mut()
+        .chain(container.args.iter_mut())
+    {
+        for value in values.iter_mut() {
+            *value = render_template(value, properties, missing_properties);
+        }
+    }
+    for env_var in container.env.iter_mut().flatten() {
+        if let Some(value) = env_var.value.as_mut() {
+            *value = render_template(value, properties, missing_properties);
+        }
+    }
+}
+
+/// Substitutes `{{PROPERTY_NAME}}` placeholders in the command, args and env values of a broker Pod spec's
+/// containers and init containers with the Instance's property of that name. A placeholder can be
+/// escaped as `\{{PROPERTY_NAME}}` to keep it as is. Returns an error naming every referenced
+/// property the Instance does not have.
+///
+/// Example:
+///
+/// ```
+/// use akri_shared::k8s::pod;
+/// use k8s_openapi::api::core::v1::{Container, PodSpec};
+/// use std::collections::HashMap;
+///
+/// let pod_spec = PodSpec {
+///     containers: vec![Container {
+///         args: Some(vec!["--url={{ONVIF_DEVICE_RTSP_URI}}".to_string()]),
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+/// let mut properties = HashMap::new();
+/// properties.insert("ONVIF_DEVICE_RTSP_URI".to_string(), "rtsp://10.0.0.1/stream".to_string());
+/// let rendered = pod::render_broker_pod_spec(&pod_spec, &properties).unwrap();
+/// ```
+pub fn render_broker_pod_spec(
+    pod_spec: &PodSpec,
+    properties: &HashMap<String, String>,
+) -> Result<PodSpec, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mut rendered = pod_spec.clone();
+    let mut missing_properties = Vec::new();
+    for container in rendered
+        .containers
+        .iter_mut()
+        .chain(rendered.init_containers.iter_mut().flatten())
+    {
+        render_container(container, properties, &mut missing_properties);
+    }
+    if !missing_properties.is_empty() {
+        missing_properties.sort();
+        missing_properties.dedup();
+        return Err(format!(
+            "broker pod spec refers to properties the Instance does not have: {}",
+            missing_properties.join(", ")
+        )
+        .into());
+    }
+    Ok(rendered)
+}
+
 type ResourceQuantityType = BTreeMap<String, Quantity>;
 
+/// Replaces the `{{PLACEHOLDER}}` resource limit and request of each container in `pod_spec`
+/// with `resource_limit_name`, so that the container is allocated the Instance's capacity
+pub(crate) fn replace_resource_placeholders(pod_spec: &mut PodSpec, resource_limit_name: &str) {
+    for container in &mut pod_spec.containers {
+        let mut incoming_limits: Option<ResourceQuantityType> = None;
+        let mut incoming_requests: Option<ResourceQuantityType> = None;
+
+        if let Some(resources) = container.resources.as_ref() {
+            if let Some(limits) = resources.limits.as_ref() {
+                let mut modified_limits = limits.clone();
+                if modified_limits.contains_key(RESOURCE_REQUIREMENTS_KEY) {
+                    let placeholder_value = modified_limits
+                        .get(RESOURCE_REQUIREMENTS_KEY)
+                        .unwrap()
+                        .clone();
+                    modified_limits.insert(resource_limit_name.to_string(), placeholder_value);
+                    modified_limits.remove(RESOURCE_REQUIREMENTS_KEY);
+                }
+
+                incoming_limits = Some(modified_limits);
+            }
+            if let Some(requests) = resources.requests.as_ref() {
+                let mut modified_requests = requests.clone();
+                if modified_requests.contains_key(RESOURCE_REQUIREMENTS_KEY) {
+                    let placeholder_value = modified_requests
+                        .get(RESOURCE_REQUIREMENTS_KEY)
+                        .unwrap()
+                        .clone();
+                    modified_requests.insert(resource_limit_name.to_string(), placeholder_value);
+                    modified_requests.remove(RESOURCE_REQUIREMENTS_KEY);
+                }
+
+                incoming_requests = Some(modified_requests);
+            }
+        };
+
+        container.resources = Some(ResourceRequirements {
+            limits: incoming_limits,
+            requests: incoming_requests,
+        });
+    }
+}
+
 /// Create Kubernetes Pod based on Device Capabililty Instance & Config.
 ///
 /// Example:
@@ -190,45 +355,7 @@ pub fn create_new_pod_from_spec(
     }];
 
     let mut modified_pod_spec = pod_spec.clone();
-
-    for container in &mut modified_pod_spec.containers {
-        let mut incoming_limits: Option<ResourceQuantityType> = None;
-        let mut incoming_requests: Option<ResourceQuantityType> = None;
-
-        if let Some(resources) = container.resources.as_ref() {
-            if let Some(limits) = resources.limits.as_ref() {
-                let mut modified_limits = limits.clone();
-                if modified_limits.contains_key(RESOURCE_REQUIREMENTS_KEY) {
-                    let placeholder_value = modified_limits
-                        .get(RESOURCE_REQUIREMENTS_KEY)
-                        .unwrap()
-                        .clone();
-                    modified_limits.insert(resource_limit_name.to_string(), placeholder_value);
-                    modified_limits.remove(RESOURCE_REQUIREMENTS_KEY);
-                }
-
-                incoming_limits = Some(modified_limits);
-            }
-            if let Some(requests) = resources.requests.as_ref() {
-                let mut modified_requests = requests.clone();
-                if modified_requests.contains_key(RESOURCE_REQUIREMENTS_KEY) {
-                    let placeholder_value = modified_requests
-                        .get(RESOURCE_REQUIREMENTS_KEY)
-                        .unwrap()
-                        .clone();
-                    modified_requests.insert(resource_limit_name.to_string(), placeholder_value);
-                    modified_requests.remove(RESOURCE_REQUIREMENTS_KEY);
-                }
-
-                incoming_requests = Some(modified_requests);
-            }
-        };
-
-        container.resources = Some(ResourceRequirements {
-            limits: incoming_limits,
-            requests: incoming_requests,
-        });
-    }
+    replace_resource_placeholders(&mut modified_pod_spec, resource_limit_name);
 
     // Ensure that the modified PodSpec has the required Affinity settings
     modified_pod_spec
@@ -274,7 +401,7 @@ mod broker_podspec_tests {
     use super::super::OwnershipType;
     use super::*;
     use env_logger;
-    use k8s_openapi::api::core::v1::Container;
+    use k8s_openapi::api::core::v1::EnvVar;
 
     #[test]
     fn test_create_pod_app_name() {
@@ -391,6 +518,99 @@ mod broker_podspec_tests {
         );
     }
 
+    #[test]
+    fn test_render_broker_pod_spec() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut properties = HashMap::new();
+        properties.insert(
+            "ONVIF_DEVICE_RTSP_URI".to_string(),
+            "rtsp://10.0.0.1/stream".to_string(),
+        );
+        properties.insert("DEVICE_ID".to_string(), "camera-1".to_string());
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                command: Some(vec!["/broker".to_string(), "{{DEVICE_ID}}".to_string()]),
+                args: Some(vec![
+                    "--url={{ONVIF_DEVICE_RTSP_URI}}".to_string(),
+                    "--template=\\{{DEVICE_ID}}".to_string(),
+                    r#"--json={"a":{"b":1}}"#.to_string(),
+                    "{{ not a property }}".to_string(),
+                ]),
+                env: Some(vec![
+                    EnvVar {
+                        name: "LABEL".to_string(),
+                        value: Some("{{DEVICE_ID}}-{{DEVICE_ID}}".to_string()),
+                        ..Default::default()
+                    },
+                    EnvVar {
+                        name: "FROM_SECRET".to_string(),
+                        value: None,
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            }],
+            init_containers: Some(vec![Container {
+                args: Some(vec!["{{DEVICE_ID}}".to_string()]),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let rendered = render_broker_pod_spec(&pod_spec, &properties).unwrap();
+        let container = &rendered.containers[0];
+        assert_eq!(
+            &vec!["/broker".to_string(), "camera-1".to_string()],
+            container.command.as_ref().unwrap()
+        );
+        assert_eq!(
+            &vec![
+                "--url=rtsp://10.0.0.1/stream".to_string(),
+                "--template={{DEVICE_ID}}".to_string(),
+                r#"--json={"a":{"b":1}}"#.to_string(),
+                "{{ not a property }}".to_string(),
+            ],
+            container.args.as_ref().unwrap()
+        );
+        let env = container.env.as_ref().unwrap();
+        assert_eq!(Some("camera-1-camera-1".to_string()), env[0].value);
+        assert_eq!(None, env[1].value);
+        assert_eq!(
+            &vec!["camera-1".to_string()],
+            rendered.init_containers.as_ref().unwrap()[0]
+                .args
+                .as_ref()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_broker_pod_spec_missing_properties() {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let mut properties = HashMap::new();
+        properties.insert("DEVICE_ID".to_string(), "camera-1".to_string());
+        let pod_spec = PodSpec {
+            containers: vec![Container {
+                args: Some(vec![
+                    "{{DEVICE_ID}}".to_string(),
+                    "--url={{ONVIF_DEVICE_RTSP_URI}}".to_string(),
+                ]),
+                env: Some(vec![EnvVar {
+                    name: "PASSWORD".to_string(),
+                    value: Some("{{PASSWORD}}{{ONVIF_DEVICE_RTSP_URI}}".to_string()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let error = render_broker_pod_spec(&pod_spec, &properties)
+            .unwrap_err()
+            .to_string();
+        assert!(error.ends_with(": ONVIF_DEVICE_RTSP_URI, PASSWORD"));
+    }
+
     fn do_pod_spec_creation_test(image_names: Vec<String>, container_specs: Vec<Container>) {
         let _ = env_logger::builder().is_test(true).try_init();
 
@@ -0,0 +1,35 @@
+use k8s_openapi::api::core::v1::Secret;
+use kube::{api::RawApi, client::APIClient};
+use log::trace;
+
+/// Get Kubernetes Secret with a given name and namespace
+///
+/// Example:
+///
+/// ```no_run
+/// use akri_shared::k8s::secret;
+/// use kube::client::APIClient;
+/// use kube::config;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let api_client = APIClient::new(config::incluster_config().unwrap());
+/// let secret = secret::find_secret("camera-credentials", "default", api_client).await.unwrap();
+/// # }
+/// ```
+pub async fn find_secret(
+    name: &str,
+    namespace: &str,
+    kube_client: APIClient,
+) -> Result<Secret, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    trace!(
+        "find_secret with name={:?} namespace={:?}",
+        &name,
+        &namespace
+    );
+    let secrets = RawApi::v1Secret().within(&namespace);
+    trace!("find_secret PRE kube_client.request::<Secret>(secrets.get(...)?).await?");
+    let result = kube_client.request::<Secret>(secrets.get(&name)?).await;
+    trace!("find_secret return");
+    Ok(result?)
+}
@@ -1,22 +1,40 @@
 pub mod device_info {
     use async_trait::async_trait;
+    use chrono::{SecondsFormat, Utc};
     use futures_util::stream::TryStreamExt;
-    use hyper::Request;
+    use hyper::{
+        header::{HeaderMap, AUTHORIZATION, WWW_AUTHENTICATE},
+        Request, StatusCode,
+    };
     use log::trace;
     use mockall::{automock, predicate::*};
-    use std::io::{Error, ErrorKind};
+    use sha1::{Digest, Sha1};
+    use std::{
+        collections::HashMap,
+        fmt,
+        io::{Error, ErrorKind},
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
     use sxd_document::{parser, Package};
     use sxd_xpath::Value;
 
     pub const ONVIF_DEVICE_SERVICE_URL_LABEL_ID: &str = "ONVIF_DEVICE_SERVICE_URL";
     pub const ONVIF_DEVICE_IP_ADDRESS_LABEL_ID: &str = "ONVIF_DEVICE_IP_ADDRESS";
     pub const ONVIF_DEVICE_MAC_ADDRESS_LABEL_ID: &str = "ONVIF_DEVICE_MAC_ADDRESS";
+    pub const ONVIF_DEVICE_PTZ_SUPPORT_LABEL_ID: &str = "ONVIF_DEVICE_PTZ_SUPPORT";
+    pub const ONVIF_DEVICE_RTSP_URI_LABEL_ID: &str = "ONVIF_DEVICE_RTSP_URI";
+    pub const ONVIF_DEVICE_MANUFACTURER_LABEL_ID: &str = "ONVIF_DEVICE_MANUFACTURER";
+    pub const ONVIF_DEVICE_MODEL_LABEL_ID: &str = "ONVIF_DEVICE_MODEL";
+    pub const ONVIF_DEVICE_FIRMWARE_VERSION_LABEL_ID: &str = "ONVIF_DEVICE_FIRMWARE_VERSION";
+    pub const ONVIF_DEVICE_GROUP_LABEL_ID: &str = "ONVIF_DEVICE_GROUP";
     pub const MEDIA_WSDL: &str = "http://www.onvif.org/ver10/media/wsdl";
     pub const DEVICE_WSDL: &str = "http://www.onvif.org/ver10/device/wsdl";
 
     /// OnvifQuery can access ONVIF properties given an ONVIF camera's device service url.
     ///
-    /// An implementation of an onvif query can retrieve the camera's ip/mac address, scopes, profiles and streaming uri.
+    /// An implementation of an onvif query can retrieve the camera's ip/mac address, scopes, capabilities, device information,
+    /// profiles and streaming uri.
     #[automock]
     #[async_trait]
     pub trait OnvifQuery {
@@ -25,6 +43,14 @@ pub mod device_info {
             service_url: &str,
         ) -> Result<(String, String), anyhow::Error>;
         async fn get_device_scopes(&self, url: &str) -> Result<Vec<String>, anyhow::Error>;
+        async fn get_device_capabilities(
+            &self,
+            url: &str,
+        ) -> Result<DeviceCapabilities, anyhow::Error>;
+        async fn get_device_information(
+            &self,
+            service_url: &str,
+        ) -> Result<DeviceInformation, anyhow::Error>;
         async fn get_device_service_uri(
             &self,
             url: &str,
@@ -38,7 +64,63 @@ pub mod device_info {
         ) -> Result<String, anyhow::Error>;
     }
 
-    pub struct OnvifQueryImpl {}
+    /// Capabilities reported by an ONVIF camera's GetCapabilities endpoint
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct DeviceCapabilities {
+        /// Whether the camera supports Pan-Tilt-Zoom
+        pub ptz: bool,
+    }
+
+    /// Information reported by an ONVIF camera's GetDeviceInformation endpoint
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct DeviceInformation {
+        pub manufacturer: String,
+        pub model: String,
+        pub firmware_version: String,
+        pub serial_number: String,
+    }
+
+    /// Username and password used to sign requests to an ONVIF camera with a WS-Security UsernameToken.
+    /// They are also sent as HTTP Basic authorization, which only base64-encodes the password, but only
+    /// to cameras that answer an unauthorized request with a `WWW-Authenticate: Basic` challenge.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Credentials {
+        pub username: String,
+        pub password: String,
+    }
+
+    /// Error returned when an ONVIF camera rejects a request as not authorized,
+    /// either because credentials are required or because the supplied credentials are invalid
+    #[derive(Debug)]
+    pub struct NotAuthorizedError {
+        pub url: String,
+    }
+
+    impl fmt::Display for NotAuthorizedError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "ONVIF camera at {} rejected the request as not authorized",
+                self.url
+            )
+        }
+    }
+
+    impl std::error::Error for NotAuthorizedError {}
+
+    #[derive(Default)]
+    pub struct OnvifQueryImpl {
+        /// Optional credentials used to authenticate to cameras
+        pub credentials: Option<Credentials>,
+    }
+
+    impl OnvifQueryImpl {
+        fn http(&self) -> HttpRequest {
+            HttpRequest {
+                credentials: self.credentials.clone(),
+            }
+        }
+    }
 
     #[async_trait]
     impl OnvifQuery for OnvifQueryImpl {
@@ -47,29 +129,47 @@ pub mod device_info {
             &self,
             service_url: &str,
         ) -> Result<(String, String), anyhow::Error> {
-            let http = HttpRequest {};
+            let http = self.http();
             inner_get_device_ip_and_mac_address(service_url, &http).await
         }
 
         /// Gets the list of scopes for a given ONVIF camera
         async fn get_device_scopes(&self, url: &str) -> Result<Vec<String>, anyhow::Error> {
-            let http = HttpRequest {};
+            let http = self.http();
             inner_get_device_scopes(url, &http).await
         }
 
+        /// Gets the capabilities of a given ONVIF camera
+        async fn get_device_capabilities(
+            &self,
+            url: &str,
+        ) -> Result<DeviceCapabilities, anyhow::Error> {
+            let http = self.http();
+            inner_get_device_capabilities(url, &http).await
+        }
+
+        /// Gets the manufacturer, model, firmware version and serial number of a given ONVIF camera
+        async fn get_device_information(
+            &self,
+            service_url: &str,
+        ) -> Result<DeviceInformation, anyhow::Error> {
+            let http = self.http();
+            inner_get_device_information(service_url, &http).await
+        }
+
         /// Gets specific service, like media, from a given ONVIF camera
         async fn get_device_service_uri(
             &self,
             url: &str,
             service: &str,
         ) -> Result<String, anyhow::Error> {
-            let http = HttpRequest {};
+            let http = self.http();
             inner_get_device_service_uri(url, service, &http).await
         }
 
         /// Gets the list of streaming profiles for a given ONVIF camera
         async fn get_device_profiles(&self, url: &str) -> Result<Vec<String>, anyhow::Error> {
-            let http = HttpRequest {};
+            let http = self.http();
             inner_get_device_profiles(url, &http).await
         }
 
@@ -79,11 +179,160 @@ pub mod device_info {
             url: &str,
             profile_token: &str,
         ) -> Result<String, anyhow::Error> {
-            let http = HttpRequest {};
+            let http = self.http();
             inner_get_device_profile_streaming_uri(url, profile_token, &http).await
         }
     }
 
+    /// Responses to a single kind of ONVIF query, keyed by device service url and
+    /// stamped with when they were received
+    #[derive(Debug, Default)]
+    struct CachedResponses<T> {
+        responses: Mutex<HashMap<String, (T, Instant)>>,
+    }
+
+    impl<T: Clone> CachedResponses<T> {
+        fn get(&self, url: &str, ttl: Duration) -> Option<T> {
+            match self.responses.lock().unwrap().get(url) {
+                Some((response, received)) if received.elapsed() < ttl => Some(response.clone()),
+                _ => None,
+            }
+        }
+
+        fn insert(&self, url: &str, response: T) {
+            self.responses
+                .lock()
+                .unwrap()
+                .insert(url.to_string(), (response, Instant::now()));
+        }
+    }
+
+    /// Responses from ONVIF cameras that are kept for `ttl` so that repeated discovery
+    /// does not query every camera on every iteration. Failed queries are not cached.
+    #[derive(Debug)]
+    pub struct OnvifQueryCache {
+        ttl: Duration,
+        ip_and_mac_addresses: CachedResponses<(String, String)>,
+        scopes: CachedResponses<Vec<String>>,
+        capabilities: CachedResponses<DeviceCapabilities>,
+        device_information: CachedResponses<DeviceInformation>,
+    }
+
+    impl OnvifQueryCache {
+        pub fn new(ttl: Duration) -> Self {
+            OnvifQueryCache {
+                ttl,
+                ip_and_mac_addresses: CachedResponses::default(),
+                scopes: CachedResponses::default(),
+                capabilities: CachedResponses::default(),
+                device_information: CachedResponses::default(),
+            }
+        }
+    }
+
+    /// CachingOnvifQuery answers the queries made during discovery from an `OnvifQueryCache`,
+    /// delegating to the wrapped `OnvifQuery` on a cache miss. All other queries are delegated directly.
+    pub struct CachingOnvifQuery<'a, Q: OnvifQuery> {
+        inner: Q,
+        cache: &'a OnvifQueryCache,
+    }
+
+    impl<'a, Q: OnvifQuery> CachingOnvifQuery<'a, Q> {
+        pub fn new(inner: Q, cache: &'a OnvifQueryCache) -> Self {
+            CachingOnvifQuery { inner, cache }
+        }
+    }
+
+    #[async_trait]
+    impl<'a, Q: OnvifQuery + Sync + Send> OnvifQuery for CachingOnvifQuery<'a, Q> {
+        async fn get_device_ip_and_mac_address(
+            &self,
+            service_url: &str,
+        ) -> Result<(String, String), anyhow::Error> {
+            let cached = &self.cache.ip_and_mac_addresses;
+            if let Some(ip_and_mac) = cached.get(service_url, self.cache.ttl) {
+                trace!(
+                    "get_device_ip_and_mac_address - using cached response for {}",
+                    service_url
+                );
+                return Ok(ip_and_mac);
+            }
+            let ip_and_mac = self
+                .inner
+                .get_device_ip_and_mac_address(service_url)
+                .await?;
+            cached.insert(service_url, ip_and_mac.clone());
+            Ok(ip_and_mac)
+        }
+
+        async fn get_device_scopes(&self, url: &str) -> Result<Vec<String>, anyhow::Error> {
+            let cached = &self.cache.scopes;
+            if let Some(scopes) = cached.get(url, self.cache.ttl) {
+                trace!("get_device_scopes - using cached response for {}", url);
+                return Ok(scopes);
+            }
+            let scopes = self.inner.get_device_scopes(url).await?;
+            cached.insert(url, scopes.clone());
+            Ok(scopes)
+        }
+
+        async fn get_device_capabilities(
+            &self,
+            url: &str,
+        ) -> Result<DeviceCapabilities, anyhow::Error> {
+            let cached = &self.cache.capabilities;
+            if let Some(capabilities) = cached.get(url, self.cache.ttl) {
+                trace!(
+                    "get_device_capabilities - using cached response for {}",
+                    url
+                );
+                return Ok(capabilities);
+            }
+            let capabilities = self.inner.get_device_capabilities(url).await?;
+            cached.insert(url, capabilities.clone());
+            Ok(capabilities)
+        }
+
+        async fn get_device_information(
+            &self,
+            service_url: &str,
+        ) -> Result<DeviceInformation, anyhow::Error> {
+            let cached = &self.cache.device_information;
+            if let Some(device_information) = cached.get(service_url, self.cache.ttl) {
+                trace!(
+                    "get_device_information - using cached response for {}",
+                    service_url
+                );
+                return Ok(device_information);
+            }
+            let device_information = self.inner.get_device_information(service_url).await?;
+            cached.insert(service_url, device_information.clone());
+            Ok(device_information)
+        }
+
+        async fn get_device_service_uri(
+            &self,
+            url: &str,
+            service: &str,
+        ) -> Result<String, anyhow::Error> {
+            self.inner.get_device_service_uri(url, service).await
+        }
+
+        async fn get_device_profiles(&self, url: &str) -> Result<Vec<String>, anyhow::Error> {
+            self.inner.get_device_profiles(url).await
+        }
+
+        async fn get_device_profile_streaming_uri(
+            &self,
+            url: &str,
+            profile_token: &str,
+        ) -> Result<String, anyhow::Error> {
+            self.inner
+                .get_device_profile_streaming_uri(url, profile_token)
+                .await
+        }
+    }
+
     /// Http can send an HTTP::Post.
     ///
     /// An implementation of http can send an HTTP::Post.
@@ -98,9 +347,42 @@ pub mod device_info {
         ) -> Result<Package, anyhow::Error>;
     }
 
-    struct HttpRequest {}
+    struct HttpRequest {
+        credentials: Option<Credentials>,
+    }
 
     impl HttpRequest {
+        /// This sends an HTTP::Post with an optional `Authorization` header, returning the response's
+        /// status, headers and body
+        async fn send(
+            url: &str,
+            full_mime: &str,
+            body: String,
+            authorization: Option<String>,
+        ) -> Result<(StatusCode, HeaderMap, String), anyhow::Error> {
+            let mut request_builder = Request::post(url).header("CONTENT-TYPE", full_mime);
+            if let Some(authorization) = authorization {
+                request_builder = request_builder.header(AUTHORIZATION, authorization);
+            }
+            let request = request_builder.body(body.into()).expect("infallible");
+            let response = hyper::Client::new().request(request).await.unwrap();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let response_body = response
+                .into_body()
+                .try_fold(bytes::BytesMut::new(), |mut acc, chunk| async {
+                    acc.extend(chunk);
+                    Ok(acc)
+                })
+                .await?
+                .freeze();
+            Ok((
+                status,
+                headers,
+                std::str::from_utf8(&response_body)?.to_string(),
+            ))
+        }
+
         /// This converts an http response body into an sxd_document::Package
         fn handle_request_body(body: &str) -> Result<Package, anyhow::Error> {
             let xml_as_tree = match parser::parse(&body) {
@@ -135,23 +417,47 @@ pub mod device_info {
                 "{}; {}; {};",
                 "application/soap+xml", "charset=utf-8", mime_action
             );
-            let request = Request::post(url)
-                .header("CONTENT-TYPE", full_mime)
-                .body(msg.to_string().into())
-                .expect("infallible");
-            let response = hyper::Client::new().request(request).await.unwrap();
-            if response.status() != 200 {
+            let (mut status, mut headers, mut response_body_str) = HttpRequest::send(
+                url,
+                &full_mime,
+                sign_message(msg, self.credentials.as_ref()),
+                None,
+            )
+            .await?;
+            // Some cameras authenticate every request at the HTTP layer as well. HTTP Basic authorization
+            // exposes the password to anyone who can read the traffic, so it is only sent to cameras that
+            // explicitly ask for it.
+            if let Some(credentials) = &self.credentials {
+                if status == StatusCode::UNAUTHORIZED && has_basic_challenge(&headers) {
+                    trace!(
+                        "post - {} asked for HTTP Basic authorization ... sending request again with it",
+                        url
+                    );
+                    let (retried_status, retried_headers, retried_body_str) = HttpRequest::send(
+                        url,
+                        &full_mime,
+                        sign_message(msg, Some(credentials)),
+                        Some(get_basic_authorization(credentials)),
+                    )
+                    .await?;
+                    status = retried_status;
+                    headers = retried_headers;
+                    response_body_str = retried_body_str;
+                }
+            }
+            trace!("post - response headers: {:?}", headers);
+            if status != StatusCode::OK {
+                // Cameras report rejected credentials either as an HTTP 401 or as a SOAP fault
+                // with a `NotAuthorized` subcode
+                if status == StatusCode::UNAUTHORIZED || response_body_str.contains("NotAuthorized")
+                {
+                    return Err(NotAuthorizedError {
+                        url: url.to_string(),
+                    }
+                    .into());
+                }
                 return Err(anyhow::format_err!("failure"));
             }
-            let response_body = response
-                .into_body()
-                .try_fold(bytes::BytesMut::new(), |mut acc, chunk| async {
-                    acc.extend(chunk);
-                    Ok(acc)
-                })
-                .await?
-                .freeze();
-            let response_body_str = std::str::from_utf8(&response_body)?;
             match HttpRequest::handle_request_body(&response_body_str) {
                 Ok(dom) => Ok(dom),
                 Err(e) => {
@@ -170,6 +476,86 @@ pub mod device_info {
         format!("action=\"{}/{}\"", wsdl, function)
     }
 
+    /// Returns whether a response's `WWW-Authenticate` headers offer the HTTP Basic scheme
+    fn has_basic_challenge(headers: &HeaderMap) -> bool {
+        headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|challenges| challenges.split(','))
+            .filter_map(|challenge| challenge.split_whitespace().next())
+            .any(|scheme| scheme.eq_ignore_ascii_case("basic"))
+    }
+
+    /// Creates the value of an HTTP Basic `Authorization` header for the given credentials
+    fn get_basic_authorization(credentials: &Credentials) -> String {
+        format!(
+            "Basic {}",
+            base64::encode(format!("{}:{}", credentials.username, credentials.password))
+        )
+    }
+
+    /// Computes a WS-Security UsernameToken password digest: Base64(SHA-1(nonce + created + password))
+    pub fn get_password_digest(nonce: &[u8], created: &str, password: &str) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(nonce);
+        hasher.update(created.as_bytes());
+        hasher.update(password.as_bytes());
+        base64::encode(hasher.finalize())
+    }
+
+    /// Escapes the characters that are not allowed to appear unescaped in XML text or attribute values
+    fn xml_escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// Creates the WS-Security header that carries a UsernameToken for the given credentials
+    fn get_security_header(credentials: &Credentials, nonce: &[u8], created: &str) -> String {
+        format!(
+            r#"<wsse:Security xmlns:wsse="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd" xmlns:wsu="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd">
+                <wsse:UsernameToken>
+                    <wsse:Username>{}</wsse:Username>
+                    <wsse:Password Type="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordDigest">{}</wsse:Password>
+                    <wsse:Nonce EncodingType="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary">{}</wsse:Nonce>
+                    <wsu:Created>{}</wsu:Created>
+                </wsse:UsernameToken>
+            </wsse:Security>"#,
+            xml_escape(&credentials.username),
+            get_password_digest(nonce, created, &credentials.password),
+            base64::encode(nonce),
+            created
+        )
+    }
+
+    /// Signs a SOAP request body by inserting a WS-Security header if credentials are provided.
+    /// Without credentials, the message is returned unchanged.
+    fn sign_message(msg: &str, credentials: Option<&Credentials>) -> String {
+        match credentials {
+            Some(credentials) => {
+                let nonce: [u8; 16] = rand::random();
+                let created = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+                let security_header = get_security_header(credentials, &nonce, &created);
+                msg.replacen(
+                    "<soap:Header/>",
+                    &format!("<soap:Header>{}</soap:Header>", security_header),
+                    1,
+                )
+            }
+            None => msg.to_string(),
+        }
+    }
+
     /// Gets the ip and mac address for a given ONVIF camera
     async fn inner_get_device_ip_and_mac_address(
         service_url: &str,
@@ -184,12 +570,7 @@ pub mod device_info {
             .await
         {
             Ok(xml) => xml,
-            Err(e) => {
-                return Err(anyhow::format_err!(
-                    "failed to get network interfaces from device: {:?}",
-                    e
-                ))
-            }
+            Err(e) => return Err(e.context("failed to get network interfaces from device")),
         };
         let network_interfaces_doc = network_interfaces_xml.as_document();
         let ip_address = match sxd_xpath::evaluate_xpath(
@@ -243,12 +624,7 @@ pub mod device_info {
             .await
         {
             Ok(xml) => xml,
-            Err(e) => {
-                return Err(anyhow::format_err!(
-                    "failed to get scopes from device: {:?}",
-                    e
-                ))
-            }
+            Err(e) => return Err(e.context("failed to get scopes from device")),
         };
         let scopes_doc = scopes_xml.as_document();
         let scopes_query = sxd_xpath::evaluate_xpath(
@@ -287,6 +663,97 @@ pub mod device_info {
             </soap:Body>
         </soap:Envelope>"#;
 
+    /// Gets the capabilities of a given ONVIF camera
+    async fn inner_get_device_capabilities(
+        url: &str,
+        http: &impl Http,
+    ) -> Result<DeviceCapabilities, anyhow::Error> {
+        let capabilities_xml = match http
+            .post(
+                &url,
+                &get_action(DEVICE_WSDL, "GetCapabilities"),
+                &GET_CAPABILITIES_TEMPLATE.to_string(),
+            )
+            .await
+        {
+            Ok(xml) => xml,
+            Err(e) => return Err(e.context("failed to get capabilities from device")),
+        };
+        let capabilities_doc = capabilities_xml.as_document();
+        // A camera only advertises a PTZ service address if it supports PTZ
+        let ptz = match sxd_xpath::evaluate_xpath(
+            &capabilities_doc,
+            "//*[local-name()='GetCapabilitiesResponse']/*[local-name()='Capabilities']/*[local-name()='PTZ']/*[local-name()='XAddr']/text()"
+        ) {
+            Ok(xaddr) => !xaddr.string().trim().is_empty(),
+            Err(e) => return Err(anyhow::format_err!("Failed to get ONVIF capabilities: {}", e)),
+        };
+        trace!("inner_get_device_capabilities - ptz: {}", ptz);
+        Ok(DeviceCapabilities { ptz })
+    }
+
+    /// SOAP request body for getting the capabilities of an ONVIF camera
+    const GET_CAPABILITIES_TEMPLATE: &str = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope" xmlns:wsdl="http://www.onvif.org/ver10/device/wsdl" xmlns:sch="http://www.onvif.org/ver10/schema">
+        <soap:Header/>
+            <soap:Body>
+                <wsdl:GetCapabilities>
+                    <wsdl:Category>All</wsdl:Category>
+                </wsdl:GetCapabilities>
+            </soap:Body>
+        </soap:Envelope>"#;
+
+    /// Gets the manufacturer, model, firmware version and serial number of a given ONVIF camera
+    async fn inner_get_device_information(
+        service_url: &str,
+        http: &impl Http,
+    ) -> Result<DeviceInformation, anyhow::Error> {
+        let device_information_xml = match http
+            .post(
+                service_url,
+                &get_action(DEVICE_WSDL, "GetDeviceInformation"),
+                &GET_DEVICE_INFORMATION_TEMPLATE.to_string(),
+            )
+            .await
+        {
+            Ok(xml) => xml,
+            Err(e) => return Err(e.context("failed to get device information from device")),
+        };
+        let device_information_doc = device_information_xml.as_document();
+        let get_field = |field: &str| match sxd_xpath::evaluate_xpath(
+            &device_information_doc,
+            &format!(
+                "//*[local-name()='GetDeviceInformationResponse']/*[local-name()='{}']/text()",
+                field
+            ),
+        ) {
+            Ok(value) => Ok(value.string().trim().to_string()),
+            Err(e) => Err(anyhow::format_err!(
+                "Failed to get ONVIF device information {}: {}",
+                field,
+                e
+            )),
+        };
+        let device_information = DeviceInformation {
+            manufacturer: get_field("Manufacturer")?,
+            model: get_field("Model")?,
+            firmware_version: get_field("FirmwareVersion")?,
+            serial_number: get_field("SerialNumber")?,
+        };
+        trace!(
+            "inner_get_device_information - device information: {:?}",
+            device_information
+        );
+        Ok(device_information)
+    }
+
+    /// SOAP request body for getting the manufacturer, model, firmware version and serial number of an ONVIF camera
+    const GET_DEVICE_INFORMATION_TEMPLATE: &str = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope" xmlns:wsdl="http://www.onvif.org/ver10/device/wsdl">
+        <soap:Header/>
+            <soap:Body>
+                <wsdl:GetDeviceInformation/>
+            </soap:Body>
+        </soap:Envelope>"#;
+
     /// Gets a specific service (like media) uri from an ONVIF camera
     async fn inner_get_device_service_uri(
         url: &str,
@@ -302,12 +769,7 @@ pub mod device_info {
             .await
         {
             Ok(xml) => xml,
-            Err(e) => {
-                return Err(anyhow::format_err!(
-                    "failed to get services from device: {:?}",
-                    e
-                ))
-            }
+            Err(e) => return Err(e.context("failed to get services from device")),
         };
         let services_doc = services_xml.as_document();
         let service_xpath_query = format!(
@@ -349,12 +811,7 @@ pub mod device_info {
         let message = GET_PROFILES_TEMPLATE.to_string();
         let profiles_xml = match http.post(&url, &action, &message).await {
             Ok(xml) => xml,
-            Err(e) => {
-                return Err(anyhow::format_err!(
-                    "failed to get profiles from device: {:?}",
-                    e
-                ))
-            }
+            Err(e) => return Err(e.context("failed to get profiles from device")),
         };
         let profiles_doc = profiles_xml.as_document();
         let profiles_query = sxd_xpath::evaluate_xpath(
@@ -389,12 +846,7 @@ pub mod device_info {
             .await
         {
             Ok(xml) => xml,
-            Err(e) => {
-                return Err(anyhow::format_err!(
-                    "failed to get streaming uri from device: {:?}",
-                    e
-                ))
-            }
+            Err(e) => return Err(e.context("failed to get streaming uri from device")),
         };
         let stream_uri_doc = stream_uri_xml.as_document();
         let stream_uri = match sxd_xpath::evaluate_xpath(
@@ -441,13 +893,6 @@ pub mod device_info {
             </soap:Body>
         </soap:Envelope>"#;
 
-    //  const GET_DEVICE_INFORMATION_TEMPLATE: &str = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope" xmlns:wsdl="http://www.onvif.org/ver10/device/wsdl">
-    //     <soap:Header/>
-    //         <soap:Body>
-    //             <wsdl:GetDeviceInformation/>
-    //         </soap:Body>
-    //     </soap:Envelope>"#;
-
     //  const GET_HOSTNAME_TEMPLATE: &str = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope" xmlns:wsdl="http://www.onvif.org/ver10/device/wsdl">
     //     <soap:Header/>
     //         <soap:Body>
@@ -476,6 +921,69 @@ pub mod device_info {
                 });
         }
 
+        #[tokio::test]
+        async fn test_caching_onvif_query_reuses_responses() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            let mut mock = MockOnvifQuery::new();
+            mock.expect_get_device_ip_and_mac_address()
+                .times(1)
+                .withf(|url| url == "camera-a")
+                .returning(|_| Ok(("10.0.0.1".to_string(), "00:11:22:33:44:55".to_string())));
+            mock.expect_get_device_scopes()
+                .times(1)
+                .withf(|url| url == "camera-a")
+                .returning(|_| Ok(vec!["onvif://www.onvif.org/name/camera-a".to_string()]));
+            let cache = OnvifQueryCache::new(Duration::from_secs(60));
+            let query = CachingOnvifQuery::new(mock, &cache);
+            for _ in 0..2 {
+                assert_eq!(
+                    ("10.0.0.1".to_string(), "00:11:22:33:44:55".to_string()),
+                    query
+                        .get_device_ip_and_mac_address("camera-a")
+                        .await
+                        .unwrap()
+                );
+                assert_eq!(
+                    vec!["onvif://www.onvif.org/name/camera-a".to_string()],
+                    query.get_device_scopes("camera-a").await.unwrap()
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn test_caching_onvif_query_expires_and_skips_errors() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            // Expired responses are fetched again
+            let mut mock = MockOnvifQuery::new();
+            mock.expect_get_device_scopes()
+                .times(2)
+                .returning(|_| Ok(Vec::new()));
+            let cache = OnvifQueryCache::new(Duration::from_secs(0));
+            let query = CachingOnvifQuery::new(mock, &cache);
+            assert!(query
+                .get_device_scopes("camera-a")
+                .await
+                .unwrap()
+                .is_empty());
+            assert!(query
+                .get_device_scopes("camera-a")
+                .await
+                .unwrap()
+                .is_empty());
+
+            // Errors are not cached
+            let mut mock = MockOnvifQuery::new();
+            mock.expect_get_device_scopes()
+                .times(2)
+                .returning(|_| Err(anyhow::format_err!("camera unreachable")));
+            let cache = OnvifQueryCache::new(Duration::from_secs(60));
+            let query = CachingOnvifQuery::new(mock, &cache);
+            assert!(query.get_device_scopes("camera-a").await.is_err());
+            assert!(query.get_device_scopes("camera-a").await.is_err());
+        }
+
         #[tokio::test]
         async fn test_inner_get_device_ip_and_mac_address_ip_in_manual() {
             let _ = env_logger::builder().is_test(true).try_init();
@@ -653,6 +1161,258 @@ pub mod device_info {
             }
         }
 
+        #[test]
+        fn test_get_password_digest() {
+            // Example from the ONVIF Application Programmer's Guide
+            let nonce = base64::decode("LKqI6G/AikKCQrN0zqZFlg==").unwrap();
+            assert_eq!(
+                "tuOSpGlFlIXsozq4HFNeeGeFLEI=",
+                get_password_digest(&nonce, "2010-09-16T07:50:45Z", "userpassword")
+            );
+        }
+
+        #[tokio::test]
+        async fn test_inner_get_device_capabilities() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            let with_ptz = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tt=\"http://www.onvif.org/ver10/schema\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\"><SOAP-ENV:Header></SOAP-ENV:Header><SOAP-ENV:Body><tds:GetCapabilitiesResponse><tds:Capabilities><tt:Device><tt:XAddr>http://192.168.1.36:8000/onvif/device_service</tt:XAddr></tt:Device><tt:Media><tt:XAddr>http://192.168.1.36:8000/onvif/media_service</tt:XAddr></tt:Media><tt:PTZ><tt:XAddr>http://192.168.1.36:8000/onvif/ptz_service</tt:XAddr></tt:PTZ></tds:Capabilities></tds:GetCapabilitiesResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>";
+            let mut mock = MockHttp::new();
+            configure_post(
+                &mut mock,
+                &"test_inner_get_device_capabilities-url".to_string(),
+                &get_action(DEVICE_WSDL, "GetCapabilities"),
+                &GET_CAPABILITIES_TEMPLATE.to_string(),
+                &with_ptz.to_string(),
+            );
+            assert_eq!(
+                DeviceCapabilities { ptz: true },
+                inner_get_device_capabilities(
+                    &"test_inner_get_device_capabilities-url".to_string(),
+                    &mock
+                )
+                .await
+                .unwrap()
+            );
+
+            let without_ptz = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tt=\"http://www.onvif.org/ver10/schema\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\"><SOAP-ENV:Header></SOAP-ENV:Header><SOAP-ENV:Body><tds:GetCapabilitiesResponse><tds:Capabilities><tt:Device><tt:XAddr>http://192.168.1.36:8000/onvif/device_service</tt:XAddr></tt:Device><tt:Media><tt:XAddr>http://192.168.1.36:8000/onvif/media_service</tt:XAddr></tt:Media></tds:Capabilities></tds:GetCapabilitiesResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>";
+            let mut mock = MockHttp::new();
+            configure_post(
+                &mut mock,
+                &"test_inner_get_device_capabilities-url".to_string(),
+                &get_action(DEVICE_WSDL, "GetCapabilities"),
+                &GET_CAPABILITIES_TEMPLATE.to_string(),
+                &without_ptz.to_string(),
+            );
+            assert_eq!(
+                DeviceCapabilities { ptz: false },
+                inner_get_device_capabilities(
+                    &"test_inner_get_device_capabilities-url".to_string(),
+                    &mock
+                )
+                .await
+                .unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_inner_get_device_information() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            let response = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\"><SOAP-ENV:Header></SOAP-ENV:Header><SOAP-ENV:Body><tds:GetDeviceInformationResponse><tds:Manufacturer>Contoso</tds:Manufacturer><tds:Model>IPC-100</tds:Model><tds:FirmwareVersion>2.4.1</tds:FirmwareVersion><tds:SerialNumber>SN0001</tds:SerialNumber><tds:HardwareId>1.0</tds:HardwareId></tds:GetDeviceInformationResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>";
+            let mut mock = MockHttp::new();
+            configure_post(
+                &mut mock,
+                &"test_inner_get_device_information-url".to_string(),
+                &get_action(DEVICE_WSDL, "GetDeviceInformation"),
+                &GET_DEVICE_INFORMATION_TEMPLATE.to_string(),
+                &response.to_string(),
+            );
+            assert_eq!(
+                DeviceInformation {
+                    manufacturer: "Contoso".to_string(),
+                    model: "IPC-100".to_string(),
+                    firmware_version: "2.4.1".to_string(),
+                    serial_number: "SN0001".to_string(),
+                },
+                inner_get_device_information(
+                    &"test_inner_get_device_information-url".to_string(),
+                    &mock
+                )
+                .await
+                .unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_inner_get_device_scopes_not_authorized() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            let mut mock = MockHttp::new();
+            mock.expect_post().times(1).returning(|url, _, _| {
+                Err(NotAuthorizedError {
+                    url: url.to_string(),
+                }
+                .into())
+            });
+            let e = inner_get_device_scopes(&"test_inner_get_device_scopes-url".to_string(), &mock)
+                .await
+                .unwrap_err();
+            assert!(e.downcast_ref::<NotAuthorizedError>().is_some());
+        }
+
+        #[test]
+        fn test_sign_message() {
+            // Without credentials, requests are sent exactly as before
+            assert_eq!(
+                GET_SCOPES_TEMPLATE.to_string(),
+                sign_message(GET_SCOPES_TEMPLATE, None)
+            );
+
+            let credentials = Credentials {
+                username: "admin".to_string(),
+                password: "userpassword".to_string(),
+            };
+            let signed = sign_message(GET_SCOPES_TEMPLATE, Some(&credentials));
+            assert!(!signed.contains("<soap:Header/>"));
+            assert!(signed.contains("<wsse:Username>admin</wsse:Username>"));
+            assert!(signed.contains("#PasswordDigest"));
+            assert!(!signed.contains("userpassword"));
+            assert!(signed.contains("<wsdl:GetScopes/>"));
+        }
+
+        #[test]
+        fn test_get_security_header_escapes_username() {
+            let credentials = Credentials {
+                username: "a<b&c>\"d'".to_string(),
+                password: "userpassword".to_string(),
+            };
+            let security_header =
+                get_security_header(&credentials, &[0; 16], "2021-01-01T00:00:00Z");
+            assert!(security_header
+                .contains("<wsse:Username>a&lt;b&amp;c&gt;&quot;d&apos;</wsse:Username>"));
+            // The signed message is still well-formed XML
+            let signed = sign_message(GET_SCOPES_TEMPLATE, Some(&credentials));
+            assert!(HttpRequest::handle_request_body(&signed).is_ok());
+        }
+
+        #[test]
+        fn test_get_basic_authorization() {
+            let credentials = Credentials {
+                username: "admin".to_string(),
+                password: "userpassword".to_string(),
+            };
+            assert_eq!(
+                "Basic YWRtaW46dXNlcnBhc3N3b3Jk",
+                get_basic_authorization(&credentials)
+            );
+        }
+
+        #[test]
+        fn test_has_basic_challenge() {
+            let challenges = |values: Vec<&'static str>| {
+                let mut headers = HeaderMap::new();
+                for value in values {
+                    headers.append(WWW_AUTHENTICATE, value.parse().unwrap());
+                }
+                headers
+            };
+            assert!(has_basic_challenge(&challenges(vec![
+                "Basic realm=\"camera\""
+            ])));
+            assert!(has_basic_challenge(&challenges(vec![
+                "Digest realm=\"camera\", nonce=\"abc\"",
+                "basic realm=\"camera\""
+            ])));
+            assert!(has_basic_challenge(&challenges(vec![
+                "Digest realm=\"camera\", Basic realm=\"camera\""
+            ])));
+            assert!(!has_basic_challenge(&challenges(vec![
+                "Digest realm=\"Basic\", nonce=\"abc\""
+            ])));
+            assert!(!has_basic_challenge(&challenges(Vec::new())));
+        }
+
+        /// Starts a camera that answers GetScopes with a single scope, but only for requests
+        /// with HTTP Basic authorization for admin:userpassword, returning its device service url.
+        /// Unauthorized requests are answered with the given `WWW-Authenticate` challenge.
+        fn start_camera_requiring_basic_auth(challenge: &'static str) -> String {
+            use hyper::service::{make_service_fn, service_fn};
+            use hyper::{Body, Response, Server};
+            let make_service = make_service_fn(move |_| async move {
+                Ok::<_, hyper::Error>(service_fn(move |request: Request<Body>| async move {
+                    let authorized = request
+                        .headers()
+                        .get(AUTHORIZATION)
+                        .map_or(false, |value| value == "Basic YWRtaW46dXNlcnBhc3N3b3Jk");
+                    let response = if authorized {
+                        Response::new(Body::from(
+                            r#"<?xml version="1.0" encoding="UTF-8"?>
+<SOAP-ENV:Envelope xmlns:SOAP-ENV="http://www.w3.org/2003/05/soap-envelope" xmlns:tt="http://www.onvif.org/ver10/schema" xmlns:tds="http://www.onvif.org/ver10/device/wsdl"><SOAP-ENV:Body><tds:GetScopesResponse><tds:Scopes><tt:ScopeDef>Fixed</tt:ScopeDef><tt:ScopeItem>onvif://www.onvif.org/name/NVT</tt:ScopeItem></tds:Scopes></tds:GetScopesResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>"#,
+                        ))
+                    } else {
+                        Response::builder()
+                            .status(StatusCode::UNAUTHORIZED)
+                            .header(WWW_AUTHENTICATE, challenge)
+                            .body(Body::empty())
+                            .unwrap()
+                    };
+                    Ok::<_, hyper::Error>(response)
+                }))
+            });
+            let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+            let device_service_url = format!("http://{}/onvif/device_service", server.local_addr());
+            tokio::spawn(server);
+            device_service_url
+        }
+
+        #[tokio::test]
+        async fn test_onvif_query_impl_basic_authorization() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            let device_service_url = start_camera_requiring_basic_auth("Basic realm=\"camera\"");
+
+            let e = OnvifQueryImpl::default()
+                .get_device_scopes(&device_service_url)
+                .await
+                .unwrap_err();
+            assert!(e.downcast_ref::<NotAuthorizedError>().is_some());
+
+            let onvif_query = OnvifQueryImpl {
+                credentials: Some(Credentials {
+                    username: "admin".to_string(),
+                    password: "userpassword".to_string(),
+                }),
+            };
+            assert_eq!(
+                vec!["onvif://www.onvif.org/name/NVT".to_string()],
+                onvif_query
+                    .get_device_scopes(&device_service_url)
+                    .await
+                    .unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_onvif_query_impl_no_basic_authorization_without_challenge() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            // The camera would accept HTTP Basic authorization but does not ask for it,
+            // so the password is never sent in the clear
+            let device_service_url =
+                start_camera_requiring_basic_auth("Digest realm=\"camera\", nonce=\"abc\"");
+            let onvif_query = OnvifQueryImpl {
+                credentials: Some(Credentials {
+                    username: "admin".to_string(),
+                    password: "userpassword".to_string(),
+                }),
+            };
+            let e = onvif_query
+                .get_device_scopes(&device_service_url)
+                .await
+                .unwrap_err();
+            assert!(e.downcast_ref::<NotAuthorizedError>().is_some());
+        }
+
         #[test]
         fn test_http_handle_request_body_no_panic() {
             assert!(HttpRequest::handle_request_body("\r\n").is_err());
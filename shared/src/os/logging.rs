@@ -0,0 +1,186 @@
+use super::env_var::EnvVarQuery;
+use chrono::{SecondsFormat, Utc};
+use log::Record;
+use std::{cell::RefCell, io::Write};
+
+/// Name of the environment variable that selects the log format. Logs are written as JSON,
+/// one object per line, if it is `json`, and as env_logger's plain text otherwise.
+pub const LOG_FORMAT_LABEL: &str = "AKRI_LOG_FORMAT";
+
+/// Value of `AKRI_LOG_FORMAT` that selects JSON logs
+pub const JSON_LOG_FORMAT: &str = "json";
+
+thread_local! {
+    /// Fields attached by `log_fields!` to the records being logged on this thread
+    static LOG_FIELDS: RefCell<Vec<(&'static str, String)>> = RefCell::new(Vec::new());
+}
+
+/// Logs like `log::log!`, attaching `key: value` fields, such as the Configuration, protocol, endpoint or
+/// Instance that the message is about, to the record. JSON logs carry each field as its own key, while
+/// plain text logs only show the message. Field values are only formatted if the level is enabled.
+///
+/// ```
+/// use akri_shared::log_fields;
+/// log_fields!(
+///     log::Level::Trace,
+///     { configuration: "config-a", instance: "config-a-b494b6" },
+///     "update_connectivity_status - instance {} still online",
+///     "config-a-b494b6"
+/// );
+/// ```
+#[macro_export]
+macro_rules! log_fields {
+    ($lvl:expr, { $($key:ident: $value:expr),+ $(,)? }, $($arg:tt)+) => {
+        if ::log::log_enabled!($lvl) {
+            $crate::os::logging::with_fields(
+                vec![$((stringify!($key), $value.to_string())),+],
+                || ::log::log!($lvl, $($arg)+),
+            );
+        }
+    };
+}
+
+/// Runs `log` with `fields` attached to every record it logs on this thread. env_logger formats records
+/// on the thread that logs them, so `format_json_record` sees the fields. Used through `log_fields!`.
+pub fn with_fields<R>(fields: Vec<(&'static str, String)>, log: impl FnOnce() -> R) -> R {
+    let previous = LOG_FIELDS.with(|current| current.replace(fields));
+    let result = log();
+    LOG_FIELDS.with(|current| current.replace(previous));
+    result
+}
+
+/// Initializes env_logger, filtered by `RUST_LOG` as usual, in the format selected by `AKRI_LOG_FORMAT`
+pub fn init_logger(query: &impl EnvVarQuery) -> Result<(), log::SetLoggerError> {
+    match query.get_env_var(LOG_FORMAT_LABEL) {
+        Ok(format) if format.eq_ignore_ascii_case(JSON_LOG_FORMAT) => {
+            env_logger::Builder::from_default_env()
+                .format(|buf, record| writeln!(buf, "{}", format_json_record(record)))
+                .try_init()
+        }
+        _ => env_logger::try_init(),
+    }
+}
+
+/// Formats a log record as a JSON object. Messages that follow the `<function> - <message>` convention
+/// used across Akri have the function split out into its own field, and fields attached with `log_fields!`
+/// are added as keys of their own.
+pub fn format_json_record(record: &Record) -> serde_json::Value {
+    let message = record.args().to_string();
+    let mut json = serde_json::json!({
+        "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "level": record.level().to_string(),
+        "target": record.target(),
+    });
+    let message = match message.find(" - ") {
+        Some(index) if is_function_name(&message[..index]) => {
+            json["function"] = serde_json::Value::String(message[..index].to_string());
+            message[index + 3..].to_string()
+        }
+        _ => message,
+    };
+    json["message"] = serde_json::Value::String(message);
+    if let Some(file) = record.file() {
+        json["file"] = serde_json::Value::String(file.to_string());
+    }
+    if let Some(line) = record.line() {
+        json["line"] = serde_json::Value::from(line);
+    }
+    LOG_FIELDS.with(|fields| {
+        for (key, value) in fields.borrow().iter() {
+            // Fields never replace the standard keys
+            if json.get(*key).is_none() {
+                json[*key] = serde_json::Value::String(value.clone());
+            }
+        }
+    });
+    json
+}
+
+fn is_function_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_format_json_record() {
+        let line = format_json_record(
+            &Record::builder()
+                .args(format_args!(
+                    "do_periodic_discovery - discovery for config {} failed",
+                    "config-a"
+                ))
+                .level(Level::Error)
+                .target("agent::util::config_action")
+                .file(Some("agent/src/util/config_action.rs"))
+                .line(Some(42))
+                .build(),
+        )
+        .to_string();
+        // Each record is emitted as a single line that parses as JSON
+        assert!(!line.contains('\n'));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!("ERROR", json["level"]);
+        assert_eq!("agent::util::config_action", json["target"]);
+        assert_eq!("do_periodic_discovery", json["function"]);
+        assert_eq!("discovery for config config-a failed", json["message"]);
+        assert_eq!("agent/src/util/config_action.rs", json["file"]);
+        assert_eq!(42, json["line"]);
+        assert!(json["timestamp"].is_string());
+
+        // Messages without a function prefix are kept whole
+        let json = format_json_record(
+            &Record::builder()
+                .args(format_args!("akri.sh Agent end - bye"))
+                .level(Level::Info)
+                .build(),
+        );
+        assert_eq!("akri.sh Agent end - bye", json["message"]);
+        assert!(json.get("function").is_none());
+        assert!(json.get("configuration").is_none());
+    }
+
+    #[test]
+    fn test_format_json_record_with_fields() {
+        let record = |message| {
+            format_json_record(
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(Level::Trace)
+                    .build(),
+            )
+        };
+        let line = with_fields(
+            vec![
+                ("configuration", "config-a".to_string()),
+                ("protocol", "onvif".to_string()),
+                (
+                    "endpoint",
+                    "http://10.0.0.1/onvif/device_service".to_string(),
+                ),
+                ("instance", "config-a-b494b6".to_string()),
+                ("message", "ignored".to_string()),
+            ],
+            || record("update_connectivity_status - instance config-a-b494b6 still online"),
+        )
+        .to_string();
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!("config-a", json["configuration"]);
+        assert_eq!("onvif", json["protocol"]);
+        assert_eq!("http://10.0.0.1/onvif/device_service", json["endpoint"]);
+        assert_eq!("config-a-b494b6", json["instance"]);
+        assert_eq!("update_connectivity_status", json["function"]);
+        assert_eq!("instance config-a-b494b6 still online", json["message"]);
+
+        // Fields are only attached to records logged within `with_fields`
+        assert!(record("do_config_watch - enter")
+            .get("configuration")
+            .is_none());
+    }
+}
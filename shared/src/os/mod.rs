@@ -1,4 +1,5 @@
 pub mod env_var;
+pub mod logging;
 pub mod signal;
 
 /// Provide file operations
@@ -1,5 +1,10 @@
 use actix_web::{post, web, App, HttpResponse, HttpServer, Responder};
-use akri_shared::akri::configuration::KubeAkriConfig;
+use akri_shared::akri::configuration::{
+    validate_configuration as validate_spec, BleDiscoveryHandlerConfig,
+    DebugEchoDiscoveryHandlerConfig, KubeAkriConfig, MqttDiscoveryHandlerConfig,
+    OnvifDiscoveryHandlerConfig, OpcuaDiscoveryHandlerConfig, SerialDiscoveryHandlerConfig,
+    SnmpDiscoveryHandlerConfig, UdevDiscoveryHandlerConfig,
+};
 use clap::Arg;
 use k8s_openapi::apimachinery::pkg::runtime::RawExtension;
 use openapi::models::{
@@ -107,10 +112,92 @@ fn filter_configuration(mut v: Value) -> Value {
 
     v
 }
+/// Strictly deserializes a protocol handler's config, returning the path of the first unknown or invalid field
+fn check_protocol_handler<T: serde::de::DeserializeOwned>(
+    name: &str,
+    handler_config: &Value,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    match serde_path_to_error::deserialize::<_, T>(handler_config.clone()) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let path = e.path().to_string();
+            let field = if path == "." {
+                format!("spec.protocol.{}", name)
+            } else {
+                format!("spec.protocol.{}.{}", name, path)
+            };
+            Err(None.ok_or(format!("{}: {}", field, e.into_inner()))?)
+        }
+    }
+}
+
+/// Checks the config of each bundled protocol handler in a Configuration's `spec.protocol`
+/// for unknown fields, such as a misspelled filter, that would otherwise be silently ignored.
+/// Protocols that are not bundled with Akri are not checked.
+fn validate_protocol(
+    protocol: &Value,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    if let Value::Object(handlers) = protocol {
+        for (name, handler_config) in handlers {
+            match name.as_str() {
+                "onvif" => {
+                    check_protocol_handler::<OnvifDiscoveryHandlerConfig>(name, handler_config)?
+                }
+                "udev" => {
+                    check_protocol_handler::<UdevDiscoveryHandlerConfig>(name, handler_config)?
+                }
+                "opcua" => {
+                    check_protocol_handler::<OpcuaDiscoveryHandlerConfig>(name, handler_config)?
+                }
+                "debugEcho" => {
+                    check_protocol_handler::<DebugEchoDiscoveryHandlerConfig>(name, handler_config)?
+                }
+                "ble" => check_protocol_handler::<BleDiscoveryHandlerConfig>(name, handler_config)?,
+                "snmp" => {
+                    check_protocol_handler::<SnmpDiscoveryHandlerConfig>(name, handler_config)?
+                }
+                "mqtt" => {
+                    check_protocol_handler::<MqttDiscoveryHandlerConfig>(name, handler_config)?
+                }
+                "serial" => {
+                    check_protocol_handler::<SerialDiscoveryHandlerConfig>(name, handler_config)?
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn deny(rqst: &AdmissionRequest, message: String) -> AdmissionResponse {
+    AdmissionResponse {
+        allowed: false,
+        audit_annotations: None,
+        patch: None,
+        patch_type: None,
+        status: Some(Status {
+            api_version: None,
+            code: None,
+            details: None,
+            kind: None,
+            message: Some(message),
+            metadata: None,
+            reason: None,
+            status: None,
+        }),
+        uid: rqst.uid.to_owned(),
+        warnings: None,
+    }
+}
+
 fn validate_configuration(rqst: &AdmissionRequest) -> AdmissionResponse {
     println!("Validating Configuration");
     match &rqst.object {
         Some(raw) => {
+            // Check the protocol handler's config first, since a Configuration with unknown fields in it cannot be parsed
+            if let Err(e) = validate_protocol(&raw["spec"]["protocol"]) {
+                return deny(rqst, e.to_string());
+            }
             let x: RawExtension = serde_json::from_value(raw.clone())
                 .expect("Could not parse as Kubernetes RawExtension");
             let y = serde_json::to_string(&x).unwrap();
@@ -121,47 +208,23 @@ fn validate_configuration(rqst: &AdmissionRequest) -> AdmissionResponse {
 
             let v: Value = filter_configuration(raw.clone());
 
-            // Do they match?
-            match check(&v, &deserialized) {
+            // Do they match and are the values themselves valid?
+            let validated = check(&v, &deserialized).and_then(|_| {
+                validate_spec(&c).map_err(|errors| {
+                    errors
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                        .into()
+                })
+            });
+            match validated {
                 Ok(_) => AdmissionResponse::new(true, rqst.uid.to_owned()),
-                Err(e) => AdmissionResponse {
-                    allowed: false,
-                    audit_annotations: None,
-                    patch: None,
-                    patch_type: None,
-                    status: Some(Status {
-                        api_version: None,
-                        code: None,
-                        details: None,
-                        kind: None,
-                        message: Some(e.to_string()),
-                        metadata: None,
-                        reason: None,
-                        status: None,
-                    }),
-                    uid: rqst.uid.to_owned(),
-                    warnings: None,
-                },
+                Err(e) => deny(rqst, e.to_string()),
             }
         }
-        None => AdmissionResponse {
-            allowed: false,
-            audit_annotations: None,
-            patch: None,
-            patch_type: None,
-            status: Some(Status {
-                api_version: None,
-                code: None,
-                details: None,
-                kind: None,
-                message: Some("AdmissionRequest object contains no data".to_owned()),
-                metadata: None,
-                reason: None,
-                status: None,
-            }),
-            uid: rqst.uid.to_owned(),
-            warnings: None,
-        },
+        None => deny(rqst, "AdmissionRequest object contains no data".to_owned()),
     }
 }
 
@@ -289,7 +352,7 @@ mod tests {
                 "spec": {
                     "protocol": {
                         "debugEcho": {
-                            "descriptions": ["foo","bar"],
+                            "devices": [{"id": "foo"},{"id": "bar"}],
                             "shared": true
                         }
                     },
@@ -379,7 +442,7 @@ mod tests {
                 "spec": {
                     "protocol": {
                         "debugEcho": {
-                            "descriptions": ["foo","bar"],
+                            "devices": [{"id": "foo"},{"id": "bar"}],
                             "shared": true
                         }
                     },
@@ -465,7 +528,7 @@ mod tests {
                 "spec": {
                     "protocol": {
                         "debugEcho": {
-                            "descriptions": ["foo","bar"],
+                            "devices": [{"id": "foo"},{"id": "bar"}],
                             "shared": true
                         }
                     },
@@ -731,6 +794,22 @@ mod tests {
         assert_eq!(resp.allowed, false);
     }
 
+    #[test]
+    fn test_validate_configuration_invalid_capacity() {
+        let invalid_capacity = VALID.replace(r#""capacity": 1"#, r#""capacity": 0"#);
+        let invalid: AdmissionReview =
+            serde_json::from_str(&invalid_capacity).expect("v1.AdmissionReview JSON");
+        let rqst = invalid.request.expect("v1.AdmissionRequest JSON");
+        let resp = validate_configuration(&rqst);
+        assert_eq!(resp.allowed, false);
+        assert!(resp
+            .status
+            .unwrap()
+            .message
+            .unwrap()
+            .contains("spec.capacity"));
+    }
+
     #[test]
     fn test_validate_configuration_extended() {
         let valid: AdmissionReview =
@@ -740,6 +819,89 @@ mod tests {
         assert_eq!(resp.allowed, true);
     }
 
+    #[test]
+    fn test_validate_configuration_broker_job_spec_and_broker_pod_spec() {
+        let with_job = VALID.replace(
+            r#""capacity": 1"#,
+            r#""capacity": 1,
+                    "brokerJobSpec": {
+                        "template": {
+                            "spec": {
+                                "containers": [{"image": "image", "name": "name"}],
+                                "restartPolicy": "Never"
+                            }
+                        }
+                    }"#,
+        );
+        let invalid: AdmissionReview =
+            serde_json::from_str(&with_job).expect("v1.AdmissionReview JSON");
+        let rqst = invalid.request.expect("v1.AdmissionRequest JSON");
+        let resp = validate_configuration(&rqst);
+        assert_eq!(resp.allowed, false);
+        assert!(resp
+            .status
+            .unwrap()
+            .message
+            .unwrap()
+            .contains("spec.brokerJobSpec"));
+    }
+
+    const DEBUG_ECHO_PROTOCOL: &str = r#""debugEcho": {
+                            "devices": [{"id": "foo"},{"id": "bar"}],
+                            "shared": true
+                        }"#;
+
+    fn validate_with_protocol(protocol: &str) -> AdmissionResponse {
+        assert!(VALID.contains(DEBUG_ECHO_PROTOCOL));
+        let review = VALID.replace(DEBUG_ECHO_PROTOCOL, protocol);
+        let review: AdmissionReview =
+            serde_json::from_str(&review).expect("v1.AdmissionReview JSON");
+        let rqst = review.request.expect("v1.AdmissionRequest JSON");
+        validate_configuration(&rqst)
+    }
+
+    #[test]
+    fn test_validate_configuration_onvif() {
+        let resp = validate_with_protocol(
+            r#""onvif": {
+                "ipAddresses": {"action": "Exclude", "items": ["10.0.0.1"]},
+                "discoveryTimeoutSeconds": 2
+            }"#,
+        );
+        assert_eq!(resp.allowed, true);
+    }
+
+    #[test]
+    fn test_validate_configuration_onvif_unknown_field() {
+        let resp = validate_with_protocol(
+            r#""onvif": {
+                "ipAddressess": {"action": "Exclude", "items": ["10.0.0.1"]}
+            }"#,
+        );
+        assert_eq!(resp.allowed, false);
+        let message = resp.status.unwrap().message.unwrap();
+        assert!(message.starts_with("spec.protocol.onvif: unknown field `ipAddressess`"));
+    }
+
+    #[test]
+    fn test_validate_configuration_onvif_unknown_nested_field() {
+        let resp = validate_with_protocol(
+            r#""onvif": {
+                "ipAddresses": {"action": "Exclude", "itemz": ["10.0.0.1"]}
+            }"#,
+        );
+        assert_eq!(resp.allowed, false);
+        let message = resp.status.unwrap().message.unwrap();
+        assert!(message.starts_with("spec.protocol.onvif.ipAddresses: unknown field `itemz`"));
+    }
+
+    #[test]
+    fn test_validate_protocol_unknown_protocol() {
+        let protocol: Value =
+            serde_json::from_str(r#"{"zeroconf": {"kind": "_http._tcp"}}"#).unwrap();
+        assert!(validate_protocol(&protocol).is_ok());
+    }
+
     #[actix_rt::test]
     async fn test_validate_valid() {
         let mut app = test::init_service(App::new().service(validate)).await;